@@ -0,0 +1,83 @@
+// Binary cell commands. Result sets only carry a preview of binary values
+// (see `drivers::cells`); these read or replace one full value, addressed by
+// a `CellRef` (table, column and row key).
+
+use base64::Engine;
+use tauri::State;
+
+use crate::drivers::cells;
+use crate::models::CellRef;
+use crate::services::connection::ConnectionManager;
+use crate::{AppError, AppResult};
+
+fn profile_of(session_id: &str) -> &str {
+    session_id.split(':').next().unwrap_or("")
+}
+
+/// Fetch the full value of one cell as base64 (`null` for SQL NULL).
+#[tauri::command]
+pub async fn db_fetch_cell(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    cell: CellRef,
+    timeout_ms: Option<u64>,
+) -> AppResult<Option<String>> {
+    let pid = profile_of(&session_id);
+    let drivers = cm.get_driver(pid).await?;
+    let driver = drivers
+        .get(pid)
+        .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+    let (sql, params) = cells::select_cell(driver.db_type(), &cell)?;
+    let call = || driver.fetch_bytes(&session_id, &sql, &params, timeout_ms);
+    let (bytes, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    Ok(bytes.map(|b| base64::engine::general_purpose::STANDARD.encode(b)))
+}
+
+/// Write the full value of one cell to `path`. Returns the bytes written.
+#[tauri::command]
+pub async fn db_save_cell(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    cell: CellRef,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> AppResult<u64> {
+    let pid = profile_of(&session_id);
+    let drivers = cm.get_driver(pid).await?;
+    let driver = drivers
+        .get(pid)
+        .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+    let (sql, params) = cells::select_cell(driver.db_type(), &cell)?;
+    let call = || driver.fetch_bytes(&session_id, &sql, &params, timeout_ms);
+    let (bytes, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    let bytes = bytes.ok_or_else(|| AppError::validation(format!("{} is NULL; nothing to save", cell.column)))?;
+    tokio::fs::write(&path, &bytes)
+        .await
+        .map_err(|e| AppError::io(format!("Failed to write {}: {}", path, e)))?;
+    Ok(bytes.len() as u64)
+}
+
+/// Replace one cell with the contents of the file at `path`. Returns the
+/// number of rows updated.
+#[tauri::command]
+pub async fn db_load_cell(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    cell: CellRef,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> AppResult<u64> {
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| AppError::io(format!("Failed to read {}: {}", path, e)))?;
+    let pid = profile_of(&session_id);
+    let drivers = cm.get_driver(pid).await?;
+    let driver = drivers
+        .get(pid)
+        .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+    let (sql, params) = cells::update_cell(driver.db_type(), &cell, &bytes)?;
+    cm.guard_sql(&session_id, driver.db_type(), &sql, None).await?;
+    let call = || driver.query_params(&session_id, &sql, &params, timeout_ms);
+    let (results, _) = cm.with_recovery(driver.as_ref(), &session_id, Some(&sql), call).await?;
+    Ok(results.first().map(|r| r.affected_rows).unwrap_or(0))
+}
@@ -2,7 +2,7 @@
 
 use tauri::State;
 
-use crate::models::{ConnectParams, ConnectionHandle};
+use crate::models::{ConnectParams, ConnectionHandle, OpenTransactionPolicy, PoolStats};
 use crate::services::connection::ConnectionManager;
 use crate::AppResult;
 
@@ -18,12 +18,15 @@ pub async fn db_connect(
 }
 
 /// Disconnect from a database. Ends sessions, drops the pool, tears down SSH.
+/// Fails when a session has an open transaction unless `on_open_transaction`
+/// is `rollback`.
 #[tauri::command]
 pub async fn db_disconnect(
     cm: State<'_, ConnectionManager>,
     profile_id: String,
+    on_open_transaction: Option<OpenTransactionPolicy>,
 ) -> AppResult<()> {
-    cm.disconnect(&profile_id).await
+    cm.disconnect(&profile_id, on_open_transaction.unwrap_or_default()).await
 }
 
 /// Request cancellation of an in-progress connect.
@@ -54,3 +57,13 @@ pub async fn db_ping(
         .ok_or_else(|| crate::AppError::state(format!("No connection for profile {}", profile_id)))?;
     driver.ping(&profile_id).await
 }
+
+/// Live pool statistics (open, in use, idle, waiting) of one connected
+/// profile, or of every connected profile when none is given.
+#[tauri::command]
+pub async fn db_pool_stats(
+    cm: State<'_, ConnectionManager>,
+    profile_id: Option<String>,
+) -> AppResult<Vec<PoolStats>> {
+    cm.pool_stats(profile_id.as_deref()).await
+}
@@ -0,0 +1,165 @@
+// Export commands: stream a query's rows (or a whole table) from a cursor to
+// a file, reporting progress as `export://progress` events, and cancel an
+// export in progress.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::drivers::cells::BinaryValues;
+use crate::drivers::{DbDriver, DbType};
+use crate::models::{ExportOptions, ExportProgress, ExportSource, ExportSummary};
+use crate::services::connection::ConnectionManager;
+use crate::services::export::{Encoder, ExportService, ExportWriter, EXPORT_PAGE_ROWS, PROGRESS_EVENT};
+use crate::{AppError, AppResult};
+
+/// Export `source` on a session to `path`. `export_id` is chosen by the
+/// caller and names the export in progress events and `db_cancel_export`.
+/// The file is written next to `path` and moved into place when complete,
+/// so a failed or cancelled export leaves nothing behind. A query source is
+/// guarded like `db_query`.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn db_export(
+    app: AppHandle,
+    cm: State<'_, ConnectionManager>,
+    exports: State<'_, ExportService>,
+    session_id: String,
+    export_id: String,
+    source: ExportSource,
+    path: String,
+    options: Option<ExportOptions>,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
+) -> AppResult<ExportSummary> {
+    if path.trim().is_empty() {
+        return Err(AppError::validation("The export path is empty"));
+    }
+    let profile_id = session_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    let driver = drivers
+        .get(profile_id)
+        .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+    let db = driver.db_type();
+    let (sql, source_table) = match &source {
+        ExportSource::Query { sql } => {
+            cm.guard_sql(&session_id, db, sql, confirm_token.as_deref()).await?;
+            (sql.clone(), None)
+        }
+        ExportSource::Table { schema, table } => {
+            let name = db.dialect().qualify(schema.as_deref(), table);
+            (format!("SELECT * FROM {}", name), Some(table.clone()))
+        }
+    };
+    let options = options.unwrap_or_default();
+    let dialect = match options.dialect.as_deref() {
+        Some(name) => DbType::parse(name)
+            .ok_or_else(|| AppError::validation(format!("Unknown export dialect: {}", name)))?,
+        None => db,
+    };
+    let table = options.table_name.clone().or(source_table).unwrap_or_else(|| "export".to_string());
+    let writer = ExportWriter::new(options, dialect, &table)?;
+
+    let cancelled = exports.start(&export_id, &session_id).await?;
+    let call = || driver.open_cursor(&session_id, &sql, BinaryValues::Whole, timeout_ms);
+    let outcome = match cm.with_recovery(driver.as_ref(), &session_id, Some(&sql), call).await {
+        Ok((cursor, _)) => {
+            let export =
+                Export { app: &app, driver: driver.as_ref(), export_id: &export_id, cancelled: &cancelled };
+            let written = export.write(writer, &cursor.cursor_id, cursor.columns, &path).await;
+            if written.is_err() {
+                // Exhausted cursors are already released.
+                let _ = driver.close_cursor(&cursor.cursor_id).await;
+            }
+            written
+        }
+        Err(e) => Err(e),
+    };
+    exports.finish(&export_id).await;
+    outcome
+}
+
+/// Stop an export. Its file is discarded and `db_export` fails with kind
+/// `cancelled`.
+#[tauri::command]
+pub async fn db_cancel_export(
+    cm: State<'_, ConnectionManager>,
+    exports: State<'_, ExportService>,
+    export_id: String,
+) -> AppResult<()> {
+    let session_id = exports.cancel(&export_id).await?;
+    let profile_id = session_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    if let Some(driver) = drivers.get(profile_id) {
+        // Stops a statement still producing its first rows; otherwise the
+        // flag stops the export at the next page.
+        let _ = driver.cancel(&session_id).await;
+    }
+    Ok(())
+}
+
+struct Export<'a> {
+    app: &'a AppHandle,
+    driver: &'a dyn DbDriver,
+    export_id: &'a str,
+    cancelled: &'a AtomicBool,
+}
+
+impl Export<'_> {
+    async fn write(
+        &self,
+        writer: ExportWriter,
+        cursor_id: &str,
+        columns: Vec<String>,
+        path: &str,
+    ) -> AppResult<ExportSummary> {
+        let partial = PathBuf::from(format!("{}.part", path));
+        let written = self.write_file(writer, cursor_id, columns, &partial).await;
+        match written {
+            Ok((rows, bytes)) => {
+                tokio::fs::rename(&partial, path).await?;
+                Ok(ExportSummary { export_id: self.export_id.to_string(), path: path.to_string(), rows, bytes })
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the rows and bytes written.
+    async fn write_file(
+        &self,
+        mut writer: ExportWriter,
+        cursor_id: &str,
+        columns: Vec<String>,
+        partial: &Path,
+    ) -> AppResult<(u64, u64)> {
+        let mut encoder = Encoder::new(writer.encoding());
+        let mut file = BufWriter::new(tokio::fs::File::create(partial).await?);
+        let head = encoder.encode(&writer.begin(columns));
+        file.write_all(&head).await?;
+        let mut bytes = head.len() as u64;
+        loop {
+            if self.cancelled.load(Ordering::SeqCst) {
+                return Err(AppError::cancelled("Export cancelled"));
+            }
+            let page = self.driver.fetch_cursor(cursor_id, EXPORT_PAGE_ROWS).await?;
+            let chunk = encoder.encode(&writer.write_rows(&page.rows)?);
+            file.write_all(&chunk).await?;
+            bytes += chunk.len() as u64;
+            let progress = ExportProgress { export_id: self.export_id.to_string(), rows: writer.rows(), bytes };
+            let _ = self.app.emit(PROGRESS_EVENT, progress);
+            if page.done {
+                break;
+            }
+        }
+        let tail = encoder.encode(&writer.finish());
+        file.write_all(&tail).await?;
+        file.flush().await?;
+        bytes += tail.len() as u64;
+        Ok((writer.rows(), bytes))
+    }
+}
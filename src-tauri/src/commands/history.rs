@@ -0,0 +1,52 @@
+// Query history commands: search, grouping by fingerprint, deletion and the
+// retention limits. Recording happens in `db_query`/`db_execute`.
+
+use tauri::State;
+
+use crate::models::{HistoryGroup, HistoryPage, HistoryQuery, HistoryRetention};
+use crate::services::history::HistoryService;
+use crate::AppResult;
+
+/// Entries matching `query`, newest first, one page at a time.
+#[tauri::command]
+pub async fn history_search(
+    history: State<'_, HistoryService>,
+    query: Option<HistoryQuery>,
+) -> AppResult<HistoryPage> {
+    history.search(&query.unwrap_or_default()).await
+}
+
+/// Entries matching `query` grouped by statement fingerprint.
+#[tauri::command]
+pub async fn history_groups(
+    history: State<'_, HistoryService>,
+    query: Option<HistoryQuery>,
+) -> AppResult<Vec<HistoryGroup>> {
+    history.groups(&query.unwrap_or_default()).await
+}
+
+/// Delete entries by id; returns how many were deleted.
+#[tauri::command]
+pub async fn history_delete(history: State<'_, HistoryService>, ids: Vec<String>) -> AppResult<usize> {
+    history.delete(&ids).await
+}
+
+/// Delete the whole history, or the entries run before `before` (RFC 3339).
+#[tauri::command]
+pub async fn history_clear(history: State<'_, HistoryService>, before: Option<String>) -> AppResult<usize> {
+    history.clear(before.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn history_get_retention(history: State<'_, HistoryService>) -> AppResult<HistoryRetention> {
+    Ok(history.retention().await)
+}
+
+/// Change the retention limits; entries beyond them are removed at once.
+#[tauri::command]
+pub async fn history_set_retention(
+    history: State<'_, HistoryService>,
+    retention: HistoryRetention,
+) -> AppResult<HistoryRetention> {
+    history.set_retention(retention).await
+}
@@ -0,0 +1,287 @@
+// Import commands: preview a CSV/TSV/NDJSON file with inferred column types,
+// generate a CREATE TABLE for it, and load it into a table on a session in
+// batched transactions, reporting the rows that were rejected.
+
+use tauri::State;
+
+use crate::drivers::DbType;
+use crate::models::{
+    ImportColumn, ImportErrorPolicy, ImportFileOptions, ImportFormat, ImportMapping, ImportOptions,
+    ImportPreview, ImportRowError, ImportSummary, ImportTarget, ImportType, QueryParam, QueryParams,
+    TransactionOptions,
+};
+use crate::services::connection::ConnectionManager;
+use crate::services::import::{self, RecordReader, INFER_ROWS};
+use crate::{AppError, AppResult};
+
+/// Rows returned by `db_import_preview` unless asked otherwise.
+const PREVIEW_ROWS: usize = 50;
+/// Rejected rows listed in an `ImportSummary`; the rest are only counted.
+const MAX_REPORTED_ERRORS: usize = 1_000;
+/// Set before each INSERT so a rejected one can be undone alone.
+const SAVEPOINT: &str = "wg_import";
+
+/// The file's columns with types inferred from its first rows, and the
+/// first `rows` rows (default 50).
+#[tauri::command]
+pub async fn db_import_preview(
+    path: String,
+    options: Option<ImportFileOptions>,
+    rows: Option<usize>,
+) -> AppResult<ImportPreview> {
+    let mut reader = RecordReader::open(&path, options.unwrap_or_default())?;
+    let (columns, kept, sampled_rows) = import::infer(&mut reader, INFER_ROWS, rows.unwrap_or(PREVIEW_ROWS))?;
+    let rows = kept
+        .into_iter()
+        .map(|mut record| {
+            record.fields.resize(columns.len(), None);
+            record.fields
+        })
+        .collect();
+    Ok(ImportPreview { columns, rows, sampled_rows })
+}
+
+/// A CREATE TABLE for `columns` in the session's dialect, for review before
+/// `db_import` runs it (`options.createTable`).
+#[tauri::command]
+pub async fn db_import_create_table_sql(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    target: ImportTarget,
+    columns: Vec<ImportColumn>,
+) -> AppResult<String> {
+    let db = cm.session_db_type(&session_id).await?;
+    import::create_table_sql(db, &target, &columns)
+}
+
+/// Load a file into `target`, one transaction per `options.batchSize`
+/// rows. Values are bound as parameters of the mapping's type. A row the
+/// type check or the server rejects is left out (`skip`) or rolls back the
+/// batch in progress and stops the import (`abort`); committed batches
+/// stay either way. The session must not have a transaction open.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn db_import(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    path: String,
+    file_options: Option<ImportFileOptions>,
+    target: ImportTarget,
+    mapping: Vec<ImportMapping>,
+    options: Option<ImportOptions>,
+    timeout_ms: Option<u64>,
+) -> AppResult<ImportSummary> {
+    let options = options.unwrap_or_default();
+    if mapping.is_empty() {
+        return Err(AppError::validation("Map at least one file column to a table column"));
+    }
+    if options.batch_size == 0 {
+        return Err(AppError::validation("batchSize must be at least 1"));
+    }
+    if cm.transaction_state(&session_id).await.active {
+        return Err(AppError::state("Commit or roll back the session's transaction before importing"));
+    }
+    let db = cm.session_db_type(&session_id).await?;
+    let file_options = file_options.unwrap_or_default();
+    let ndjson = file_options.format == ImportFormat::Ndjson;
+    let mut reader = RecordReader::open(&path, file_options)?;
+    if !ndjson {
+        if let Some(m) = mapping.iter().find(|m| !reader.columns().contains(&m.source)) {
+            return Err(AppError::validation(format!("The file has no column {}", m.source)));
+        }
+    }
+
+    let created = options.create_table.as_deref().unwrap_or(&[]);
+    let types: Vec<ImportType> = mapping
+        .iter()
+        .map(|m| {
+            let created = created.iter().find(|c| c.name == m.target).map(|c| c.column_type);
+            m.value_type.or(created).unwrap_or(ImportType::Text)
+        })
+        .collect();
+    let importer = Importer {
+        cm: &cm,
+        session_id: &session_id,
+        db,
+        target: &target,
+        columns: mapping.iter().map(|m| m.target.clone()).collect(),
+        timeout_ms,
+    };
+    if let Some(columns) = &options.create_table {
+        let ddl = import::create_table_sql(db, &target, columns)?;
+        cm.guard_sql(&session_id, db, &ddl, None).await?;
+        importer.execute(&ddl).await?;
+    }
+    // A read-only profile fails here, before anything is read.
+    let (probe, _) = import::insert_statement(db, &target, &importer.columns, &[]);
+    cm.guard_sql(&session_id, db, &probe, None).await?;
+
+    let mut outcome =
+        Outcome { policy: options.on_error, max_errors: options.max_errors, ..Outcome::default() };
+    let width = reader.columns().len();
+    let mut sources: Vec<Option<usize>> = vec![None; mapping.len()];
+    let mut batch: Vec<(u64, Vec<QueryParam>)> = Vec::with_capacity(options.batch_size);
+    while let Some(record) = reader.next_record()? {
+        outcome.summary.rows_read += 1;
+        for (source, m) in sources.iter_mut().zip(&mapping) {
+            if source.is_none() {
+                *source = reader.columns().iter().position(|c| *c == m.source);
+            }
+        }
+        if !ndjson && record.fields.len() != width {
+            let message = format!("Expected {} fields, found {}", width, record.fields.len());
+            if outcome.reject(record.line, message) {
+                break;
+            }
+            continue;
+        }
+        let values = mapping
+            .iter()
+            .zip(&sources)
+            .zip(&types)
+            .map(|((m, source), value_type)| {
+                let field = source.and_then(|i| record.fields.get(i)).and_then(|f| f.as_deref());
+                import::to_param(*value_type, field).map_err(|e| format!("{}: {}", m.source, e))
+            })
+            .collect::<Result<Vec<_>, _>>();
+        match values {
+            Ok(values) => batch.push((record.line, values)),
+            Err(message) => {
+                if outcome.reject(record.line, message) {
+                    break;
+                }
+            }
+        }
+        if batch.len() >= options.batch_size {
+            importer.load(std::mem::take(&mut batch), &mut outcome).await?;
+            if outcome.summary.aborted {
+                break;
+            }
+        }
+    }
+    if !outcome.summary.aborted {
+        importer.load(batch, &mut outcome).await?;
+    }
+    Ok(outcome.summary)
+}
+
+#[derive(Default)]
+struct Outcome {
+    policy: ImportErrorPolicy,
+    max_errors: Option<usize>,
+    rejected: usize,
+    summary: ImportSummary,
+}
+
+impl Outcome {
+    /// Note a rejected row; returns whether the import stops.
+    fn reject(&mut self, line: u64, message: String) -> bool {
+        self.rejected += 1;
+        if self.summary.errors.len() < MAX_REPORTED_ERRORS {
+            self.summary.errors.push(ImportRowError { line, message });
+        }
+        let stop =
+            self.policy == ImportErrorPolicy::Abort || self.max_errors.is_some_and(|n| self.rejected >= n);
+        if self.policy == ImportErrorPolicy::Skip {
+            self.summary.skipped += 1;
+        }
+        self.summary.aborted |= stop;
+        stop
+    }
+}
+
+struct Importer<'a> {
+    cm: &'a ConnectionManager,
+    session_id: &'a str,
+    db: DbType,
+    target: &'a ImportTarget,
+    columns: Vec<String>,
+    timeout_ms: Option<u64>,
+}
+
+impl Importer<'_> {
+    /// Insert a batch in one transaction. Rejected rows are noted in
+    /// `outcome`; when one stops the import the batch is rolled back.
+    async fn load(&self, batch: Vec<(u64, Vec<QueryParam>)>, outcome: &mut Outcome) -> AppResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.cm.begin_transaction(self.session_id, &TransactionOptions::default()).await?;
+        match self.insert_batch(&batch, outcome).await {
+            Ok(inserted) if !outcome.summary.aborted => {
+                self.cm.end_transaction(self.session_id, true).await?;
+                outcome.summary.inserted += inserted;
+                Ok(())
+            }
+            Ok(_) => {
+                self.cm.end_transaction(self.session_id, false).await?;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = self.cm.end_transaction(self.session_id, false).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Multi-row INSERTs; a statement the server rejects is undone and its
+    /// rows retried one at a time to find the culprits.
+    async fn insert_batch(&self, batch: &[(u64, Vec<QueryParam>)], outcome: &mut Outcome) -> AppResult<u64> {
+        let mut inserted = 0;
+        for chunk in batch.chunks(import::rows_per_statement(self.columns.len())) {
+            let rows: Vec<Vec<QueryParam>> = chunk.iter().map(|(_, values)| values.clone()).collect();
+            if self.try_insert(&rows).await?.is_none() {
+                inserted += chunk.len() as u64;
+                continue;
+            }
+            for (line, values) in chunk {
+                match self.try_insert(std::slice::from_ref(values)).await? {
+                    None => inserted += 1,
+                    Some(e) => {
+                        if outcome.reject(*line, e.message) {
+                            return Ok(inserted);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(inserted)
+    }
+
+    /// Insert under a savepoint. A database error is undone and returned
+    /// as the value; anything else (a lost connection, a timeout) fails.
+    async fn try_insert(&self, rows: &[Vec<QueryParam>]) -> AppResult<Option<AppError>> {
+        self.cm.savepoint(self.session_id, SAVEPOINT).await?;
+        let (sql, values) = import::insert_statement(self.db, self.target, &self.columns, rows);
+        let rejected = match self.run(&sql, &QueryParams::Named(values)).await {
+            Ok(()) => None,
+            Err(e) if e.kind == "database" => {
+                self.cm.rollback_to_savepoint(self.session_id, SAVEPOINT).await?;
+                Some(e)
+            }
+            Err(e) => return Err(e),
+        };
+        self.cm.release_savepoint(self.session_id, SAVEPOINT).await?;
+        Ok(rejected)
+    }
+
+    async fn run(&self, sql: &str, params: &QueryParams) -> AppResult<()> {
+        let profile_id = self.session_id.split(':').next().unwrap_or("");
+        let drivers = self.cm.get_driver(profile_id).await?;
+        let driver = drivers
+            .get(profile_id)
+            .ok_or_else(|| AppError::state(format!("No connection for session {}", self.session_id)))?;
+        driver.query_params(self.session_id, sql, params, self.timeout_ms).await?;
+        Ok(())
+    }
+
+    async fn execute(&self, sql: &str) -> AppResult<()> {
+        let profile_id = self.session_id.split(':').next().unwrap_or("");
+        let drivers = self.cm.get_driver(profile_id).await?;
+        let driver = drivers
+            .get(profile_id)
+            .ok_or_else(|| AppError::state(format!("No connection for session {}", self.session_id)))?;
+        driver.execute(self.session_id, sql, self.timeout_ms).await?;
+        Ok(())
+    }
+}
@@ -1,24 +1,50 @@
 // Command barrel. All Tauri commands are registered here and re-exported for
 // the `generate_handler!` macro in lib.rs.
 
+pub mod cells;
 pub mod connection;
 pub mod crypto;
 pub mod credentials;
 pub mod db_servers;
+pub mod export;
+pub mod history;
+pub mod import;
 pub mod query;
+pub mod row_edits;
 pub mod ssh_servers;
 pub mod schema;
+pub mod snippets;
+pub mod transaction;
 pub mod tree;
 
 // Re-export all commands for the handler macro.
-pub use connection::{db_cancel_connect, db_connect, db_disconnect, db_list_profiles, db_ping};
+pub use cells::{db_fetch_cell, db_load_cell, db_save_cell};
+pub use connection::{db_cancel_connect, db_connect, db_disconnect, db_list_profiles, db_ping, db_pool_stats};
 pub use credentials::{
     credentials_copy_node, credentials_create_folder, credentials_delete_node, credentials_get_entry,
     credentials_get_tree, credentials_move_node, credentials_rename_node, credentials_upsert_entry,
 };
 pub use crypto::{decrypt_password, encrypt_password};
-pub use query::{db_begin_session, db_end_session, db_execute, db_query};
+pub use export::{db_cancel_export, db_export};
+pub use history::{
+    history_clear, history_delete, history_get_retention, history_groups, history_search, history_set_retention,
+};
+pub use import::{db_import, db_import_create_table_sql, db_import_preview};
+pub use query::{
+    db_begin_session, db_cancel_query, db_close_cursor, db_end_session, db_execute, db_explain,
+    db_fetch_cursor, db_get_session_scope, db_open_cursor, db_query, db_query_params, db_set_session_scope,
+};
+pub use row_edits::{db_apply_row_edits, db_preview_row_edits};
 pub use schema::{
     db_get_databases_info, db_get_tables_info, db_list_columns, db_list_databases, db_list_tables,
 };
+pub use transaction::{
+    db_begin_transaction, db_commit, db_release_savepoint, db_rollback, db_rollback_to_savepoint,
+    db_savepoint, db_transaction_state,
+};
+pub use snippets::{
+    snippets_copy_node, snippets_create_folder, snippets_delete_node, snippets_get, snippets_get_tree,
+    snippets_insert, snippets_move_node, snippets_rename_node, snippets_reorder_node, snippets_run,
+    snippets_search, snippets_set_expanded, snippets_upsert,
+};
 pub use tree::{tree_get_children, tree_get_roots};
@@ -4,9 +4,15 @@
 
 use tauri::State;
 
-use crate::models::{QueryResultSet, SessionId};
+use crate::drivers::cells::BinaryValues;
+use crate::drivers::recovery;
+use crate::models::{
+    CursorInfo, CursorPage, OpenTransactionPolicy, QueryParams, QueryPlan, QueryResultSet, SessionId,
+    SessionScope,
+};
 use crate::services::connection::ConnectionManager;
-use crate::AppResult;
+use crate::services::history::{row_count, HistoryService, QueryRun};
+use crate::{AppError, AppResult};
 
 /// Begin a session (pinned connection) on a profile. Returns a session id to
 /// pass to subsequent query/execute calls.
@@ -18,45 +24,244 @@ pub async fn db_begin_session(
     cm.begin_session(&profile_id).await
 }
 
-/// End a session (return the pinned connection to the pool).
+/// End a session (return the pinned connection to the pool). Fails when the
+/// session has an open transaction unless `on_open_transaction` is
+/// `rollback`.
 #[tauri::command]
 pub async fn db_end_session(
     cm: State<'_, ConnectionManager>,
     session_id: String,
+    on_open_transaction: Option<OpenTransactionPolicy>,
 ) -> AppResult<()> {
-    cm.end_session(&session_id).await
+    cm.end_session(&session_id, on_open_transaction.unwrap_or_default()).await
+}
+
+/// The session's current database and schema, for the status bar and
+/// autocomplete.
+#[tauri::command]
+pub async fn db_get_session_scope(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+) -> AppResult<SessionScope> {
+    cm.session_scope(&session_id).await
+}
+
+/// Switch the session's default database and/or schema; returns the new
+/// scope. A Postgres session switching database is given a connection to
+/// it, so its session settings are reset.
+#[tauri::command]
+pub async fn db_set_session_scope(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    database: Option<String>,
+    schema: Option<String>,
+) -> AppResult<SessionScope> {
+    cm.set_session_scope(&session_id, database.as_deref(), schema.as_deref()).await
 }
 
 /// Execute SQL that may return rows. Supports multiple statements (split by
-/// `;` respecting quotes); returns one QueryResultSet per statement.
+/// `;` respecting quotes); returns one QueryResultSet per statement. On a
+/// read-only connection, SQL that may write fails with kind `read_only`.
+/// Destructive statements (see `sql::destructive`) fail with kind
+/// `confirmation_required` until the same SQL is sent with the error's
+/// `details.token` as `confirm_token`. Runs are recorded in the query
+/// history.
 #[tauri::command]
 pub async fn db_query(
     cm: State<'_, ConnectionManager>,
+    history: State<'_, HistoryService>,
     session_id: String,
     sql: String,
     timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
 ) -> AppResult<Vec<QueryResultSet>> {
+    run_query(&cm, &history, &session_id, &sql, None, timeout_ms, confirm_token.as_deref()).await
+}
+
+/// Execute one statement with typed bind values (positional in the driver's
+/// native placeholder syntax, or named `:name`). Values are bound by the
+/// driver, never concatenated into the SQL.
+#[tauri::command]
+pub async fn db_query_params(
+    cm: State<'_, ConnectionManager>,
+    history: State<'_, HistoryService>,
+    session_id: String,
+    sql: String,
+    params: QueryParams,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
+) -> AppResult<Vec<QueryResultSet>> {
+    run_query(&cm, &history, &session_id, &sql, Some(&params), timeout_ms, confirm_token.as_deref()).await
+}
+
+/// Execute SQL that does not return rows (DDL, INSERT, UPDATE, DELETE).
+/// Returns the number of affected rows. Guarded and recorded like
+/// `db_query`.
+#[tauri::command]
+pub async fn db_execute(
+    cm: State<'_, ConnectionManager>,
+    history: State<'_, HistoryService>,
+    session_id: String,
+    sql: String,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
+) -> AppResult<u64> {
     let profile_id = session_id.split(':').next().unwrap_or("");
     let drivers = cm.get_driver(profile_id).await?;
     let driver = drivers
         .get(profile_id)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.query(&session_id, &sql, timeout_ms).await
+    cm.guard_sql(&session_id, driver.db_type(), &sql, confirm_token.as_deref()).await?;
+    let server_id = cm.session_server_id(&session_id).await;
+    let database = cm.session_database(&session_id).await;
+    let run = QueryRun::start(&session_id, server_id, driver.db_type(), database, &sql);
+    let call = || driver.execute(&session_id, &sql, timeout_ms);
+    let outcome = cm.with_recovery(driver.as_ref(), &session_id, Some(&sql), call).await;
+    record_history(&history, run, outcome.as_ref().map(|(affected, _)| *affected)).await;
+    let (affected, _) = outcome?;
+    cm.observe_statements(&session_id, driver.db_type(), &sql).await;
+    Ok(affected)
 }
 
-/// Execute SQL that does not return rows (DDL, INSERT, UPDATE, DELETE).
-/// Returns the number of affected rows.
+/// The execution plan of one statement (SQL Server: a batch) as a node
+/// tree shared by all drivers. With `analyze` the statement is run, side
+/// effects included, and actual rows and times are reported where the
+/// server has them.
 #[tauri::command]
-pub async fn db_execute(
+pub async fn db_explain(
     cm: State<'_, ConnectionManager>,
     session_id: String,
     sql: String,
+    analyze: bool,
     timeout_ms: Option<u64>,
-) -> AppResult<u64> {
+    confirm_token: Option<String>,
+) -> AppResult<QueryPlan> {
     let profile_id = session_id.split(':').next().unwrap_or("");
     let drivers = cm.get_driver(profile_id).await?;
     let driver = drivers
         .get(profile_id)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.execute(&session_id, &sql, timeout_ms).await
+    if analyze {
+        cm.guard_sql(&session_id, driver.db_type(), &sql, confirm_token.as_deref()).await?;
+    }
+    let call = || driver.explain(&session_id, &sql, analyze, timeout_ms);
+    let runs = analyze.then_some(sql.as_str());
+    let (plan, _) = cm.with_recovery(driver.as_ref(), &session_id, runs, call).await?;
+    if analyze {
+        cm.observe_statements(&session_id, driver.db_type(), &sql).await;
+    }
+    Ok(plan)
+}
+
+/// Cancel the statement running on a session (server-side kill). The
+/// interrupted `db_query`/`db_execute`/cursor call fails with kind
+/// `cancelled`. Does not wait for the session to become free.
+#[tauri::command]
+pub async fn db_cancel_query(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+) -> AppResult<()> {
+    let profile_id = session_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    let driver = drivers
+        .get(profile_id)
+        .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
+    driver.cancel(&session_id).await
+}
+
+/// Open a server-side cursor for one statement. Rows are streamed from the
+/// server as the caller pages with `db_fetch_cursor`; the session stays
+/// pinned to the cursor until it is exhausted or closed.
+#[tauri::command]
+pub async fn db_open_cursor(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    sql: String,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
+) -> AppResult<CursorInfo> {
+    let profile_id = session_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    let driver = drivers
+        .get(profile_id)
+        .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
+    cm.guard_sql(&session_id, driver.db_type(), &sql, confirm_token.as_deref()).await?;
+    let call = || driver.open_cursor(&session_id, &sql, BinaryValues::Preview, timeout_ms);
+    let (cursor, _) = cm.with_recovery(driver.as_ref(), &session_id, Some(&sql), call).await?;
+    Ok(cursor)
+}
+
+/// Fetch the next page (up to `max_rows`) from an open cursor.
+#[tauri::command]
+pub async fn db_fetch_cursor(
+    cm: State<'_, ConnectionManager>,
+    cursor_id: String,
+    max_rows: usize,
+) -> AppResult<CursorPage> {
+    let profile_id = cursor_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    let driver = drivers
+        .get(profile_id)
+        .ok_or_else(|| crate::AppError::state(format!("No connection for cursor {}", cursor_id)))?;
+    driver.fetch_cursor(&cursor_id, max_rows).await
+}
+
+/// Close a cursor early (e.g. the grid was closed before the last page).
+#[tauri::command]
+pub async fn db_close_cursor(
+    cm: State<'_, ConnectionManager>,
+    cursor_id: String,
+) -> AppResult<()> {
+    let profile_id = cursor_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    match drivers.get(profile_id) {
+        Some(driver) => driver.close_cursor(&cursor_id).await,
+        // Driver already gone; the cursor died with it.
+        None => Ok(()),
+    }
+}
+
+/// Note a run in the query history. A history that cannot be written must
+/// not fail the query, so the error is only logged.
+async fn record_history(history: &HistoryService, run: QueryRun, outcome: Result<u64, &AppError>) {
+    if let Err(e) = history.record(run, outcome).await {
+        eprintln!("workgrid: failed to record query history: {e}");
+    }
+}
+
+/// What `db_query`, `db_query_params` and `snippets_run` share: guard the
+/// SQL, run it (with `params`, if any) under recovery, record the run in
+/// the history and follow what it changed in the session.
+pub async fn run_query(
+    cm: &ConnectionManager,
+    history: &HistoryService,
+    session_id: &str,
+    sql: &str,
+    params: Option<&QueryParams>,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<&str>,
+) -> AppResult<Vec<QueryResultSet>> {
+    let profile_id = session_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    let driver = drivers
+        .get(profile_id)
+        .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+    cm.guard_sql(session_id, driver.db_type(), sql, confirm_token).await?;
+    let server_id = cm.session_server_id(session_id).await;
+    let database = cm.session_database(session_id).await;
+    let run = QueryRun::start(session_id, server_id, driver.db_type(), database, sql);
+    let call = || async {
+        match params {
+            Some(params) => driver.query_params(session_id, sql, params, timeout_ms).await,
+            None => driver.query(session_id, sql, timeout_ms).await,
+        }
+    };
+    let outcome = cm.with_recovery(driver.as_ref(), session_id, Some(sql), call).await;
+    record_history(history, run, outcome.as_ref().map(|(results, _)| row_count(results))).await;
+    let (mut results, reconnected) = outcome?;
+    if let Some(replayed) = reconnected {
+        recovery::note_reconnected(&mut results, replayed);
+    }
+    cm.observe_statements(session_id, driver.db_type(), sql).await;
+    Ok(results)
 }
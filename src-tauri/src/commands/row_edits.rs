@@ -0,0 +1,170 @@
+// Grid edit commands: turn a batch of row inserts, updates and deletes into
+// keyed statements (see `sql::row_edits`), show them for review, and apply
+// them all or none. Every UPDATE and DELETE must touch exactly one row; a
+// row changed or removed since the grid read it fails the batch with kind
+// `conflict` instead of editing the wrong row or none.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::drivers::{keys, DbType};
+use crate::models::{
+    QueryParam, QueryResultSet, RowChange, RowEditBatch, RowEditPlan, RowEditStatement, RowEditSummary,
+    TransactionOptions,
+};
+use crate::services::connection::ConnectionManager;
+use crate::sql::row_edits;
+use crate::{AppError, AppResult};
+
+/// Set around a batch applied inside the session's own transaction, so a
+/// failed batch is undone without ending it.
+const SAVEPOINT: &str = "wg_grid_edit";
+
+/// The `details` of a `conflict` error.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConflictDetails {
+    /// The change that failed, 0-based.
+    index: usize,
+    affected: u64,
+}
+
+/// The statements `db_apply_row_edits` would run for `batch`.
+#[tauri::command]
+pub async fn db_preview_row_edits(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    batch: RowEditBatch,
+) -> AppResult<RowEditPlan> {
+    let (_, plan) = prepare(&cm, &session_id, &batch).await?;
+    Ok(plan)
+}
+
+/// Apply `batch` atomically. In the session's open transaction the batch
+/// runs under a savepoint and is left for the caller to commit; otherwise
+/// it runs in a transaction of its own. Guarded like `db_query`.
+#[tauri::command]
+pub async fn db_apply_row_edits(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    batch: RowEditBatch,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
+) -> AppResult<RowEditSummary> {
+    let (db, plan) = prepare(&cm, &session_id, &batch).await?;
+    let script: Vec<&str> = plan.statements.iter().map(|s| s.sql.as_str()).collect();
+    cm.guard_sql(&session_id, db, &script.join(";\n"), confirm_token.as_deref()).await?;
+
+    let edit = Edit { cm: &cm, session_id: &session_id, db, batch: &batch, plan: &plan, timeout_ms };
+    if cm.transaction_state(&session_id).await.active {
+        cm.savepoint(&session_id, SAVEPOINT).await?;
+        let applied = edit.apply().await;
+        if applied.is_err() {
+            let _ = cm.rollback_to_savepoint(&session_id, SAVEPOINT).await;
+        }
+        let _ = cm.release_savepoint(&session_id, SAVEPOINT).await;
+        applied
+    } else {
+        cm.begin_transaction(&session_id, &TransactionOptions::default()).await?;
+        match edit.apply().await {
+            Ok(summary) => {
+                cm.end_transaction(&session_id, true).await?;
+                Ok(summary)
+            }
+            Err(e) => {
+                let _ = cm.end_transaction(&session_id, false).await;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Resolve the table's key and build the batch's statements.
+async fn prepare(
+    cm: &ConnectionManager,
+    session_id: &str,
+    batch: &RowEditBatch,
+) -> AppResult<(DbType, RowEditPlan)> {
+    let profile_id = session_id.split(':').next().unwrap_or("");
+    let drivers = cm.get_driver(profile_id).await?;
+    let driver = drivers
+        .get(profile_id)
+        .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+    let db = driver.db_type();
+    let key_columns = keys::row_key(driver.as_ref(), session_id, batch.schema.as_deref(), &batch.table).await?;
+    Ok((db, row_edits::plan(db, batch, &key_columns)?))
+}
+
+struct Edit<'a> {
+    cm: &'a ConnectionManager,
+    session_id: &'a str,
+    db: DbType,
+    batch: &'a RowEditBatch,
+    plan: &'a RowEditPlan,
+    timeout_ms: Option<u64>,
+}
+
+impl Edit<'_> {
+    async fn apply(&self) -> AppResult<RowEditSummary> {
+        let mut summary = RowEditSummary::default();
+        for (index, (change, statement)) in self.batch.changes.iter().zip(&self.plan.statements).enumerate() {
+            let affected = self
+                .run(statement)
+                .await
+                .map_err(|e| AppError { message: format!("Change {}: {}", index + 1, e.message), ..e })?;
+            match change {
+                RowChange::Insert { .. } => summary.inserted += affected,
+                RowChange::Update { key, .. } => {
+                    // MySQL counts an update that changes nothing as 0 rows.
+                    let affected = if affected == 0 && self.db == DbType::Mysql {
+                        self.count(key).await?
+                    } else {
+                        affected
+                    };
+                    self.check(index, "updated", affected)?;
+                    summary.updated += 1;
+                }
+                RowChange::Delete { .. } => {
+                    self.check(index, "deleted", affected)?;
+                    summary.deleted += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    fn check(&self, index: usize, verb: &str, affected: u64) -> AppResult<()> {
+        if affected == 1 {
+            return Ok(());
+        }
+        let message = if affected == 0 {
+            format!("Change {}: the row was changed or deleted by someone else and was not {}", index + 1, verb)
+        } else {
+            format!("Change {}: the key matched {} rows; none were {}", index + 1, affected, verb)
+        };
+        Err(AppError::conflict(message, &ConflictDetails { index, affected }))
+    }
+
+    async fn count(&self, key: &BTreeMap<String, QueryParam>) -> AppResult<u64> {
+        let statement = row_edits::count_statement(self.db, self.batch, &self.plan.key_columns, key)?;
+        let results = self.query(&statement).await?;
+        let count = results.first().and_then(|r| r.rows.first()).and_then(|row| row.first());
+        Ok(count.and_then(|c| c.as_u64()).unwrap_or(0))
+    }
+
+    async fn run(&self, statement: &RowEditStatement) -> AppResult<u64> {
+        let results = self.query(statement).await?;
+        Ok(results.first().map(|r| r.affected_rows).unwrap_or(0))
+    }
+
+    async fn query(&self, statement: &RowEditStatement) -> AppResult<Vec<QueryResultSet>> {
+        let profile_id = self.session_id.split(':').next().unwrap_or("");
+        let drivers = self.cm.get_driver(profile_id).await?;
+        let driver = drivers
+            .get(profile_id)
+            .ok_or_else(|| AppError::state(format!("No connection for session {}", self.session_id)))?;
+        driver.query_params(self.session_id, &statement.sql, &statement.params, self.timeout_ms).await
+    }
+}
@@ -20,7 +20,9 @@ pub async fn db_list_databases(
     let driver = drivers
         .get(pid)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.list_databases(&session_id).await
+    let call = || driver.list_databases(&session_id);
+    let (databases, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    Ok(databases)
 }
 
 #[tauri::command]
@@ -34,7 +36,9 @@ pub async fn db_list_tables(
     let driver = drivers
         .get(pid)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.list_tables(&session_id, &database).await
+    let call = || driver.list_tables(&session_id, &database);
+    let (tables, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    Ok(tables)
 }
 
 #[tauri::command]
@@ -49,7 +53,9 @@ pub async fn db_list_columns(
     let driver = drivers
         .get(pid)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.list_columns(&session_id, &database, &table).await
+    let call = || driver.list_columns(&session_id, &database, &table);
+    let (columns, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    Ok(columns)
 }
 
 #[tauri::command]
@@ -63,7 +69,9 @@ pub async fn db_get_tables_info(
     let driver = drivers
         .get(pid)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.get_tables_info(&session_id, &database).await
+    let call = || driver.get_tables_info(&session_id, &database);
+    let (tables, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    Ok(tables)
 }
 
 #[tauri::command]
@@ -76,5 +84,7 @@ pub async fn db_get_databases_info(
     let driver = drivers
         .get(pid)
         .ok_or_else(|| crate::AppError::state(format!("No connection for session {}", session_id)))?;
-    driver.get_databases_info(&session_id).await
+    let call = || driver.get_databases_info(&session_id);
+    let (databases, _) = cm.with_recovery(driver.as_ref(), &session_id, None, call).await?;
+    Ok(databases)
 }
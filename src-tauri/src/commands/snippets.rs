@@ -0,0 +1,138 @@
+// Snippet commands: the saved-query library (folders as in the credentials
+// vault), search, insertion into an editor, and running a snippet on a
+// session with its `${name}` placeholders bound as parameters.
+
+use std::collections::BTreeMap;
+
+use tauri::State;
+
+use crate::commands::query::run_query;
+use crate::models::{
+    QueryParam, QueryParams, QueryResultSet, SnippetInput, SnippetNode, SnippetQuery, SnippetText,
+};
+use crate::services::connection::ConnectionManager;
+use crate::services::history::HistoryService;
+use crate::services::snippets::SnippetService;
+use crate::AppResult;
+
+/// The snippet tree, folders with their contents.
+#[tauri::command]
+pub async fn snippets_get_tree(store: State<'_, SnippetService>) -> AppResult<Vec<SnippetNode>> {
+    Ok(store.tree().await)
+}
+
+#[tauri::command]
+pub async fn snippets_get(store: State<'_, SnippetService>, id: String) -> AppResult<SnippetNode> {
+    store.get(&id).await
+}
+
+/// Snippets matching `query`, in tree order.
+#[tauri::command]
+pub async fn snippets_search(
+    store: State<'_, SnippetService>,
+    query: Option<SnippetQuery>,
+) -> AppResult<Vec<SnippetNode>> {
+    Ok(store.search(&query.unwrap_or_default()).await)
+}
+
+/// Create or update a snippet. When an existing id is supplied the snippet
+/// is updated in place and stays in its folder.
+#[tauri::command]
+pub async fn snippets_upsert(store: State<'_, SnippetService>, input: SnippetInput) -> AppResult<SnippetNode> {
+    store.upsert_snippet(input).await
+}
+
+/// Create a folder under an optional parent. Defaults to root when omitted.
+#[tauri::command]
+pub async fn snippets_create_folder(
+    store: State<'_, SnippetService>,
+    parent_id: Option<String>,
+    name: String,
+) -> AppResult<SnippetNode> {
+    store.create_folder(parent_id.as_deref(), &name).await
+}
+
+/// Move a node to the end of another folder (`root` for the top level).
+#[tauri::command]
+pub async fn snippets_move_node(
+    store: State<'_, SnippetService>,
+    id: String,
+    target_parent: String,
+) -> AppResult<()> {
+    store.move_node(&id, &target_parent).await
+}
+
+/// Reorder a node under `target_parent`, inserting it before `before_id`
+/// (when supplied) or appending at the end.
+#[tauri::command]
+pub async fn snippets_reorder_node(
+    store: State<'_, SnippetService>,
+    id: String,
+    target_parent: String,
+    before_id: Option<String>,
+) -> AppResult<()> {
+    store.reorder_node(&id, &target_parent, before_id.as_deref()).await
+}
+
+/// Copy a node, with its contents, into a folder.
+#[tauri::command]
+pub async fn snippets_copy_node(
+    store: State<'_, SnippetService>,
+    id: String,
+    target_parent: String,
+) -> AppResult<SnippetNode> {
+    store.copy_node(&id, &target_parent).await
+}
+
+#[tauri::command]
+pub async fn snippets_rename_node(
+    store: State<'_, SnippetService>,
+    id: String,
+    new_name: String,
+) -> AppResult<SnippetNode> {
+    store.rename_node(&id, &new_name).await
+}
+
+/// Persist a folder's UI expansion state.
+#[tauri::command]
+pub async fn snippets_set_expanded(
+    store: State<'_, SnippetService>,
+    id: String,
+    expanded: bool,
+) -> AppResult<()> {
+    store.set_expanded(&id, expanded).await
+}
+
+/// Delete a node; folders go with their contents.
+#[tauri::command]
+pub async fn snippets_delete_node(store: State<'_, SnippetService>, id: String) -> AppResult<()> {
+    store.delete_node(&id).await
+}
+
+/// A snippet's SQL for inserting into an editor, with the placeholder
+/// positions so the editor can step through them.
+#[tauri::command]
+pub async fn snippets_insert(store: State<'_, SnippetService>, id: String) -> AppResult<SnippetText> {
+    store.text(&id).await
+}
+
+/// Run a snippet on a session. Each `${name}` is bound to `values[name]` as
+/// a real parameter, so a snippet with placeholders must be one statement;
+/// one without runs as a script. Guarded and recorded like `db_query`.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn snippets_run(
+    cm: State<'_, ConnectionManager>,
+    history: State<'_, HistoryService>,
+    store: State<'_, SnippetService>,
+    session_id: String,
+    id: String,
+    values: Option<BTreeMap<String, QueryParam>>,
+    timeout_ms: Option<u64>,
+    confirm_token: Option<String>,
+) -> AppResult<Vec<QueryResultSet>> {
+    let db = cm.session_db_type(&session_id).await?;
+    let (sql, names) = store.runnable(&id, db).await?;
+    let params = (!names.is_empty()).then(|| QueryParams::Named(values.unwrap_or_default()));
+    run_query(&cm, &history, &session_id, &sql, params.as_ref(), timeout_ms, confirm_token.as_deref()).await
+}
@@ -0,0 +1,74 @@
+// Transaction commands. Each returns the session's transaction state after
+// the call; the state is also tracked for statements run through `db_query`
+// and `db_execute` (see `drivers::transaction`).
+
+use tauri::State;
+
+use crate::models::{TransactionOptions, TransactionState};
+use crate::services::connection::ConnectionManager;
+use crate::AppResult;
+
+/// Start a transaction on a session with optional isolation level and
+/// read-only mode.
+#[tauri::command]
+pub async fn db_begin_transaction(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    options: Option<TransactionOptions>,
+) -> AppResult<TransactionState> {
+    cm.begin_transaction(&session_id, &options.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn db_commit(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+) -> AppResult<TransactionState> {
+    cm.end_transaction(&session_id, true).await
+}
+
+#[tauri::command]
+pub async fn db_rollback(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+) -> AppResult<TransactionState> {
+    cm.end_transaction(&session_id, false).await
+}
+
+#[tauri::command]
+pub async fn db_savepoint(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    name: String,
+) -> AppResult<TransactionState> {
+    cm.savepoint(&session_id, &name).await
+}
+
+/// Undo the work done since a savepoint. The savepoint stays open.
+#[tauri::command]
+pub async fn db_rollback_to_savepoint(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    name: String,
+) -> AppResult<TransactionState> {
+    cm.rollback_to_savepoint(&session_id, &name).await
+}
+
+/// Forget a savepoint (and any newer ones), keeping their work.
+#[tauri::command]
+pub async fn db_release_savepoint(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+    name: String,
+) -> AppResult<TransactionState> {
+    cm.release_savepoint(&session_id, &name).await
+}
+
+/// Whether a session has uncommitted work.
+#[tauri::command]
+pub async fn db_transaction_state(
+    cm: State<'_, ConnectionManager>,
+    session_id: String,
+) -> AppResult<TransactionState> {
+    Ok(cm.transaction_state(&session_id).await)
+}
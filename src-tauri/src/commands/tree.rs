@@ -12,7 +12,7 @@
 use serde_json::json;
 use tauri::State;
 
-use crate::models::TreeNode;
+use crate::models::{OpenTransactionPolicy, TreeNode};
 use crate::services::connection::ConnectionManager;
 use crate::AppResult;
 
@@ -31,7 +31,7 @@ pub async fn tree_get_roots(
         })?;
         driver.list_databases(&session_id).await?
     };
-    let _ = cm.end_session(&session_id).await;
+    let _ = cm.end_session(&session_id, OpenTransactionPolicy::Rollback).await;
 
     let nodes: Vec<TreeNode> = databases
         .into_iter()
@@ -138,6 +138,6 @@ pub async fn tree_get_children(
         _ => vec![],
     };
 
-    let _ = cm.end_session(&session_id).await;
+    let _ = cm.end_session(&session_id, OpenTransactionPolicy::Rollback).await;
     Ok(children)
 }
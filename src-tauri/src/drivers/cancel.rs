@@ -0,0 +1,173 @@
+// Stopping running statements: user cancellation and query timeouts.
+//
+// A running statement holds its session's mutex, so stopping it must never
+// touch the pinned connection. Each session gets a `QueryCancel` at
+// `begin_session` that lives outside the session lock. Both paths raise the
+// flag and then run the driver's server-side kill (side connection, cancel
+// token, interrupt handle); the statement's own error path uses the flag to
+// report `cancelled` or a timeout instead of a generic database error.
+//
+// Timeouts are enforced by a `Watchdog` rather than by dropping the
+// statement's future: the server stops the statement and the driver reads
+// the resulting error, so the pinned connection stays usable (except on SQL
+// Server, whose stop ends the session; see `recovery::session_reset`).
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::sql::{effective_query_timeout_ms, format_timeout_label};
+use crate::{AppError, AppResult};
+
+const RUNNING: u8 = 0;
+const CANCELLED: u8 = 1;
+const TIMED_OUT: u8 = 2;
+
+#[derive(Debug, Default)]
+pub struct QueryCancel {
+    state: AtomicU8,
+    /// The timeout that expired, for the error message.
+    timeout_ms: AtomicU64,
+    notify: Notify,
+}
+
+impl QueryCancel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear a stale stop before starting a new statement.
+    pub fn reset(&self) {
+        self.state.store(RUNNING, Ordering::SeqCst);
+    }
+
+    /// The user cancelled: raise the flag and wake anything in `stopped`.
+    pub fn request(&self) {
+        self.stop(CANCELLED);
+    }
+
+    /// The watchdog fired after `timeout_ms`.
+    pub fn expire(&self, timeout_ms: u64) {
+        self.timeout_ms.store(timeout_ms, Ordering::SeqCst);
+        self.stop(TIMED_OUT);
+    }
+
+    fn stop(&self, state: u8) {
+        // The first reason wins (a cancel racing the watchdog stays a cancel).
+        let _ = self
+            .state
+            .compare_exchange(RUNNING, state, Ordering::SeqCst, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.state.load(Ordering::SeqCst) != RUNNING
+    }
+
+    /// Resolve once the statement has been cancelled or timed out, with the
+    /// error to report.
+    pub async fn stopped(&self) -> AppError {
+        loop {
+            // Create the waiter before checking the flag so a stop landing
+            // in between is not lost.
+            let notified = self.notify.notified();
+            if let Err(stop) = self.check() {
+                return stop;
+            }
+            notified.await;
+        }
+    }
+
+    /// `Err` with the stop reason once stopped; checked between statements.
+    pub fn check(&self) -> AppResult<()> {
+        match self.state.load(Ordering::SeqCst) {
+            CANCELLED => Err(AppError::cancelled("Query cancelled by user")),
+            TIMED_OUT => Err(AppError::database(format!(
+                "Query timed out after {}",
+                format_timeout_label(self.timeout_ms.load(Ordering::SeqCst))
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Map a statement error to the stop reason when it was stopped (the
+    /// server reports a kill as an ordinary error).
+    pub fn map_err(&self, error: AppError) -> AppError {
+        match self.check() {
+            Err(stop) => AppError::new(&stop.kind, format!("{} ({})", stop.message, error.message)),
+            Ok(()) => error,
+        }
+    }
+}
+
+/// Stops a statement server-side once its timeout expires. Dropping the
+/// watchdog (the statement finished) disarms it.
+pub struct Watchdog(JoinHandle<()>);
+
+impl Watchdog {
+    /// Arm for `timeout_ms` (see `sql::effective_query_timeout_ms`); `kill`
+    /// is the driver's server-side stop and only runs if the timer fires.
+    /// Returns None when the caller asked for no timeout.
+    pub fn arm<K>(timeout_ms: Option<u64>, cancel: Arc<QueryCancel>, kill: K) -> Option<Self>
+    where
+        K: Future<Output = AppResult<()>> + Send + 'static,
+    {
+        let effective = effective_query_timeout_ms(timeout_ms)?;
+        Some(Self(tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(effective)).await;
+            cancel.expire(effective);
+            // Detached so disarming (the statement failing fast once the
+            // flag is up) cannot interrupt the kill half-way.
+            tokio::spawn(kill);
+        })))
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn request_wakes_waiters_and_maps_errors() {
+        let cancel = Arc::new(QueryCancel::new());
+        let waiter = {
+            let cancel = cancel.clone();
+            tokio::spawn(async move { cancel.stopped().await.kind })
+        };
+        assert_eq!(cancel.map_err(AppError::database("boom")).kind, "database");
+
+        cancel.request();
+        assert_eq!(waiter.await.unwrap(), "cancelled");
+        // A stop made before anyone waits is still observed.
+        cancel.stopped().await;
+        assert_eq!(cancel.map_err(AppError::database("boom")).kind, "cancelled");
+
+        cancel.reset();
+        assert!(!cancel.is_stopped());
+        assert!(cancel.check().is_ok());
+    }
+
+    #[tokio::test]
+    async fn timeouts_report_the_limit() {
+        let cancel = Arc::new(QueryCancel::new());
+        cancel.expire(5_000);
+        cancel.stopped().await;
+        // A late user cancel does not relabel the timeout.
+        cancel.request();
+        let err = cancel.map_err(AppError::database("interrupted"));
+        assert_eq!(err.kind, "database");
+        assert_eq!(err.message, "Query timed out after 5s (interrupted)");
+
+        assert!(Watchdog::arm(Some(0), cancel, async { Ok(()) }).is_none());
+    }
+}
@@ -0,0 +1,532 @@
+// Cell values shared by every driver.
+//
+// Values JSON represents exactly are plain JSON: null, booleans, strings,
+// finite floats, and integers within +/-(2^53 - 1), the range a JavaScript
+// number holds. Everything else is tagged so the grid never rounds or
+// guesses:
+//
+//   { "type": "bigint",   "value": "9007199254740993" }
+//   { "type": "decimal",  "value": "-12.3400" }           scale kept
+//   { "type": "float",    "value": "NaN" }                also Infinity, -Infinity
+//   { "type": "date",     "value": "2024-02-29" }
+//   { "type": "time",     "value": "13:45:07.123456" }    "+02:00" when zoned
+//   { "type": "dateTime", "value": "2024-02-29T13:45:07.5+02:00" }
+//   { "type": "interval", "value": "P1Y2M3DT4H5M6.5S" }   ISO 8601 duration
+//   { "type": "uuid",     "value": "1b4e28ba-2fa1-11d2-883f-0016d3cca427" }
+//   { "type": "json",     "value": "{\"a\": 1}" }          source text, unparsed
+//   { "type": "array",    "items": [ ... ] }              nested for more dimensions
+//   { "type": "range",    "lower": ..., "upper": ..., "lowerInclusive": true,
+//                         "upperInclusive": false, "empty": false }
+//   { "type": "record",   "fields": [ { "name": "x", "value": ... } ] }
+//
+// Fractional seconds carry exactly the digits the server sent, without
+// trailing zeros; a timestamp without a zone has no offset suffix. Years
+// outside 0000-9999 use the ISO expanded form (`-0044`, `+12000`).
+//
+// Binary values are a bounded preview so a grid of BLOBs stays small:
+//
+//   { "type": "bytes", "length": 51234, "preview": "<base64>", "truncated": true }
+//
+// When `truncated` is false the preview is the whole value. The full value of
+// a single cell is fetched on demand through a `CellRef` (table, column and
+// row key), which is turned into a keyed SELECT/UPDATE run with bind values.
+// Cursors opened with `BinaryValues::Whole` (exports) send whole values in
+// the same shape instead.
+
+use base64::Engine;
+
+use crate::drivers::DbType;
+use crate::models::{CellRef, QueryParam, QueryParams, TypeCategory};
+use crate::{AppError, AppResult};
+
+/// Bytes of a binary value included in result sets.
+pub const BINARY_PREVIEW_BYTES: usize = 256;
+
+/// The result-set representation of a binary value.
+pub fn binary_value(bytes: &[u8]) -> serde_json::Value {
+    let preview = &bytes[..bytes.len().min(BINARY_PREVIEW_BYTES)];
+    serde_json::json!({
+        "type": "bytes",
+        "length": bytes.len(),
+        "preview": base64::engine::general_purpose::STANDARD.encode(preview),
+        "truncated": preview.len() < bytes.len(),
+    })
+}
+
+/// How much of each binary value a cursor sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryValues {
+    /// The result-set preview, as the grid shows it.
+    Preview,
+    /// The whole value, for exports.
+    Whole,
+}
+
+/// Whether a cell is a binary value cut short to its preview.
+pub fn is_truncated(cell: &serde_json::Value) -> bool {
+    cell.get("type").and_then(|t| t.as_str()) == Some("bytes")
+        && cell.get("truncated").and_then(|t| t.as_bool()) == Some(true)
+}
+
+/// Put whole values in place of a row's truncated binary cells when
+/// `binary` asks for them; `raw` reads a column's bytes. Binary values
+/// nested in arrays and records keep their preview.
+pub fn widen_binary<'a>(
+    binary: BinaryValues,
+    row: &mut [serde_json::Value],
+    raw: impl Fn(usize) -> Option<&'a [u8]>,
+) {
+    if binary == BinaryValues::Preview {
+        return;
+    }
+    for (i, cell) in row.iter_mut().enumerate() {
+        if let Some(bytes) = raw(i).filter(|_| is_truncated(cell)) {
+            *cell = serde_json::json!({
+                "type": "bytes",
+                "length": bytes.len(),
+                "preview": base64::engine::general_purpose::STANDARD.encode(bytes),
+                "truncated": false,
+            });
+        }
+    }
+}
+
+/// The largest integer a JavaScript number represents exactly.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+fn tagged(kind: &str, value: impl Into<String>) -> serde_json::Value {
+    serde_json::json!({ "type": kind, "value": value.into() })
+}
+
+/// A signed integer: a number when it is safe in JavaScript, else `bigint`.
+pub fn int(n: i64) -> serde_json::Value {
+    if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&n) {
+        serde_json::Value::from(n)
+    } else {
+        tagged("bigint", n.to_string())
+    }
+}
+
+/// An unsigned integer: a number when it is safe in JavaScript, else `bigint`.
+pub fn uint(n: u64) -> serde_json::Value {
+    match i64::try_from(n) {
+        Ok(n) => int(n),
+        Err(_) => tagged("bigint", n.to_string()),
+    }
+}
+
+/// An integer sent as text. Digits that do not parse are kept as `bigint`
+/// text rather than dropped.
+pub fn int_text(s: &str) -> serde_json::Value {
+    let s = s.trim();
+    if let Ok(n) = s.parse::<i64>() {
+        int(n)
+    } else if let Ok(n) = s.parse::<u64>() {
+        uint(n)
+    } else {
+        tagged("bigint", s)
+    }
+}
+
+/// A double. NaN and the infinities have no JSON number form.
+pub fn float(f: f64) -> serde_json::Value {
+    match serde_json::Number::from_f64(f) {
+        Some(n) => serde_json::Value::Number(n),
+        None if f.is_nan() => tagged("float", "NaN"),
+        None if f > 0.0 => tagged("float", "Infinity"),
+        None => tagged("float", "-Infinity"),
+    }
+}
+
+/// A single-precision float, widened through its shortest decimal form so
+/// `0.1f32` shows as 0.1 and not 0.10000000149011612.
+pub fn float32(f: f32) -> serde_json::Value {
+    if f.is_finite() {
+        float(f.to_string().parse().unwrap_or(f64::from(f)))
+    } else {
+        float(f64::from(f))
+    }
+}
+
+/// A float sent as text (`"1.5"`, `"nan"`, `"-inf"`).
+pub fn float_text(s: &str) -> serde_json::Value {
+    let s = s.trim();
+    match s.to_ascii_lowercase().as_str() {
+        "nan" => tagged("float", "NaN"),
+        "inf" | "infinity" | "+inf" | "+infinity" => tagged("float", "Infinity"),
+        "-inf" | "-infinity" => tagged("float", "-Infinity"),
+        _ => match s.parse::<f64>() {
+            Ok(f) => float(f),
+            Err(_) => serde_json::Value::String(s.to_string()),
+        },
+    }
+}
+
+/// An exact decimal in its canonical text form.
+pub fn decimal(s: impl Into<String>) -> serde_json::Value {
+    tagged("decimal", s)
+}
+
+/// A scaled integer (`value * 10^-scale`) as decimal text.
+pub fn scaled_decimal(value: i128, scale: u32) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let sign = if value < 0 { "-" } else { "" };
+    let scale = scale as usize;
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, int_part, frac_part)
+}
+
+/// A calendar date.
+pub fn date(year: i64, month: u32, day: u32) -> serde_json::Value {
+    tagged("date", date_text(year, month, day))
+}
+
+/// A time of day; `offset` is seconds east of UTC for zoned times.
+pub fn time(t: TimeParts, offset: Option<i32>) -> serde_json::Value {
+    let mut text = time_text(t);
+    if let Some(offset) = offset {
+        text.push_str(&offset_text(offset));
+    }
+    tagged("time", text)
+}
+
+/// A timestamp; `offset` is seconds east of UTC for zoned timestamps.
+pub fn datetime(year: i64, month: u32, day: u32, t: TimeParts, offset: Option<i32>) -> serde_json::Value {
+    let mut text = format!("{}T{}", date_text(year, month, day), time_text(t));
+    if let Some(offset) = offset {
+        text.push_str(&offset_text(offset));
+    }
+    tagged("dateTime", text)
+}
+
+/// A date or timestamp already in ISO form, such as PG's `infinity`.
+pub fn temporal_text(kind: TypeCategory, text: impl Into<String>) -> serde_json::Value {
+    let kind = match kind {
+        TypeCategory::Date => "date",
+        TypeCategory::Time => "time",
+        _ => "dateTime",
+    };
+    tagged(kind, text)
+}
+
+/// A time of day with fractional seconds as `units` of `10^-digits` s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeParts {
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub units: u64,
+    pub digits: u32,
+}
+
+impl TimeParts {
+    pub fn new(hour: u32, minute: u32, second: u32) -> Self {
+        Self { hour, minute, second, units: 0, digits: 0 }
+    }
+
+    pub fn micros(hour: u32, minute: u32, second: u32, micros: u32) -> Self {
+        Self { hour, minute, second, units: u64::from(micros), digits: 6 }
+    }
+
+    /// Split a count of `10^-digits` second units since midnight.
+    pub fn from_units(units: u64, digits: u32) -> Self {
+        let per_second = 10u64.pow(digits);
+        let seconds = units / per_second;
+        Self {
+            hour: (seconds / 3600) as u32,
+            minute: (seconds / 60 % 60) as u32,
+            second: (seconds % 60) as u32,
+            units: units % per_second,
+            digits,
+        }
+    }
+}
+
+/// The proleptic Gregorian (year, month, day) of a day count from
+/// 1970-01-01. Protocols count from their own epochs; callers shift.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days_from_civil, inverted.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn date_text(year: i64, month: u32, day: u32) -> String {
+    let year = match year {
+        0..=9999 => format!("{:04}", year),
+        y if y < 0 => format!("-{:04}", y.unsigned_abs()),
+        y => format!("+{}", y),
+    };
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+fn time_text(t: TimeParts) -> String {
+    format!("{:02}:{:02}:{:02}{}", t.hour, t.minute, t.second, fraction(t.units, t.digits))
+}
+
+/// `.123` for `units` of `10^-digits` seconds, trailing zeros trimmed;
+/// empty for a whole second.
+pub fn fraction(units: u64, digits: u32) -> String {
+    if units == 0 || digits == 0 {
+        return String::new();
+    }
+    let text = format!("{:0width$}", units, width = digits as usize);
+    format!(".{}", text.trim_end_matches('0'))
+}
+
+/// `+05:30`, `-08:00`, `+00:00`; seconds are added when the offset has them.
+pub fn offset_text(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let s = seconds.unsigned_abs();
+    match s % 60 {
+        0 => format!("{}{:02}:{:02}", sign, s / 3600, s / 60 % 60),
+        secs => format!("{}{:02}:{:02}:{:02}", sign, s / 3600, s / 60 % 60, secs),
+    }
+}
+
+/// An interval as an ISO 8601 duration. Components keep their own sign the
+/// way PG's `iso_8601` interval style writes them (`P-1Y2M`).
+pub fn interval(months: i32, days: i32, micros: i64) -> serde_json::Value {
+    tagged("interval", iso_duration(months, days, micros))
+}
+
+pub fn iso_duration(months: i32, days: i32, micros: i64) -> String {
+    if months == 0 && days == 0 && micros == 0 {
+        return "PT0S".to_string();
+    }
+    let mut text = String::from("P");
+    let (years, months) = (months / 12, months % 12);
+    for (n, unit) in [(years, 'Y'), (months, 'M'), (days, 'D')] {
+        if n != 0 {
+            text.push_str(&format!("{}{}", n, unit));
+        }
+    }
+    if micros != 0 {
+        text.push('T');
+        let sign = if micros < 0 { "-" } else { "" };
+        let us = micros.unsigned_abs();
+        let (hours, minutes, seconds) = (us / 3_600_000_000, us / 60_000_000 % 60, us / 1_000_000 % 60);
+        if hours != 0 {
+            text.push_str(&format!("{}{}H", sign, hours));
+        }
+        if minutes != 0 {
+            text.push_str(&format!("{}{}M", sign, minutes));
+        }
+        let frac = us % 1_000_000;
+        if seconds != 0 || frac != 0 {
+            text.push_str(&format!("{}{}{}S", sign, seconds, fraction(frac, 6)));
+        }
+    }
+    text
+}
+
+/// A UUID from its 16 bytes, in the hyphenated lower-case form.
+pub fn uuid(bytes: [u8; 16]) -> serde_json::Value {
+    tagged("uuid", uuid::Uuid::from_bytes(bytes).hyphenated().to_string())
+}
+
+/// A UUID already in text form.
+pub fn uuid_text(s: &str) -> serde_json::Value {
+    match uuid::Uuid::parse_str(s.trim()) {
+        Ok(u) => tagged("uuid", u.hyphenated().to_string()),
+        Err(_) => serde_json::Value::String(s.to_string()),
+    }
+}
+
+/// A JSON document, kept as the server's text so large numbers and key
+/// order survive.
+pub fn json(text: impl Into<String>) -> serde_json::Value {
+    tagged("json", text)
+}
+
+pub fn array(items: Vec<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({ "type": "array", "items": items })
+}
+
+/// A range; an unbounded side is null.
+pub fn range(
+    lower: Option<(serde_json::Value, bool)>,
+    upper: Option<(serde_json::Value, bool)>,
+) -> serde_json::Value {
+    let (lower, lower_inclusive) = lower.unwrap_or((serde_json::Value::Null, false));
+    let (upper, upper_inclusive) = upper.unwrap_or((serde_json::Value::Null, false));
+    serde_json::json!({
+        "type": "range",
+        "lower": lower,
+        "upper": upper,
+        "lowerInclusive": lower_inclusive,
+        "upperInclusive": upper_inclusive,
+        "empty": false,
+    })
+}
+
+pub fn empty_range() -> serde_json::Value {
+    serde_json::json!({
+        "type": "range",
+        "lower": null,
+        "upper": null,
+        "lowerInclusive": false,
+        "upperInclusive": false,
+        "empty": true,
+    })
+}
+
+/// The text a cell shows as, for fetching a non-binary cell as bytes.
+pub fn text_form(cell: &serde_json::Value) -> String {
+    match cell {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Object(o) => match o.get("value") {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            _ => cell.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+/// `SELECT <column> FROM <table> WHERE <key>` for one cell.
+pub fn select_cell(db: DbType, cell: &CellRef) -> AppResult<(String, QueryParams)> {
+    let (filter, params) = key_filter(db, cell)?;
+    let dialect = db.dialect();
+    let sql = format!(
+        "SELECT {} FROM {} WHERE {}",
+        dialect.quote_ident(&cell.column),
+        dialect.qualify(cell.schema.as_deref(), &cell.table),
+        filter
+    );
+    Ok((sql, QueryParams::Named(params)))
+}
+
+/// `UPDATE <table> SET <column> = <bytes> WHERE <key>` for one cell.
+pub fn update_cell(db: DbType, cell: &CellRef, bytes: &[u8]) -> AppResult<(String, QueryParams)> {
+    let (filter, mut params) = key_filter(db, cell)?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    params.insert("v".to_string(), QueryParam::Bytes(encoded));
+    let dialect = db.dialect();
+    let sql = format!(
+        "UPDATE {} SET {} = :v WHERE {}",
+        dialect.qualify(cell.schema.as_deref(), &cell.table),
+        dialect.quote_ident(&cell.column),
+        filter
+    );
+    Ok((sql, QueryParams::Named(params)))
+}
+
+/// The WHERE clause matching the row key. Placeholders are `:k0`, `:k1`...
+/// so key column names never need to be valid parameter names.
+fn key_filter(
+    db: DbType,
+    cell: &CellRef,
+) -> AppResult<(String, std::collections::BTreeMap<String, QueryParam>)> {
+    if cell.key.is_empty() {
+        return Err(AppError::validation("A cell reference needs at least one key column"));
+    }
+    let mut terms = Vec::with_capacity(cell.key.len());
+    let mut params = std::collections::BTreeMap::new();
+    for (i, (column, value)) in cell.key.iter().enumerate() {
+        if matches!(value, QueryParam::Null) {
+            return Err(AppError::validation(format!(
+                "Key column {} is NULL and cannot identify a row",
+                column
+            )));
+        }
+        let name = format!("k{}", i);
+        terms.push(format!("{} = :{}", db.dialect().quote_ident(column), name));
+        params.insert(name, value.clone());
+    }
+    Ok((terms.join(" AND "), params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell() -> CellRef {
+        CellRef {
+            schema: Some("app".into()),
+            table: "files".into(),
+            column: "data".into(),
+            key: [("id".to_string(), QueryParam::Int(7))].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn previews_are_bounded() {
+        let small = binary_value(&[0xff, 0x00]);
+        assert_eq!(small["length"], 2);
+        assert_eq!(small["preview"], "/wA=");
+        assert_eq!(small["truncated"], false);
+
+        let long = vec![1u8; BINARY_PREVIEW_BYTES + 1];
+        let big = binary_value(&long);
+        assert_eq!(big["length"], BINARY_PREVIEW_BYTES + 1);
+        assert_eq!(big["truncated"], true);
+
+        let mut row = vec![big.clone(), small.clone()];
+        widen_binary(BinaryValues::Preview, &mut row, |_| Some(&long[..]));
+        assert!(is_truncated(&row[0]));
+        widen_binary(BinaryValues::Whole, &mut row, |_| Some(&long[..]));
+        assert_eq!(row[0]["truncated"], false);
+        assert_eq!(row[0]["preview"].as_str().map(str::len), Some((BINARY_PREVIEW_BYTES + 1).div_ceil(3) * 4));
+        assert_eq!(row[1], small);
+    }
+
+    #[test]
+    fn tags_values_json_cannot_hold() {
+        assert_eq!(int(MAX_SAFE_INTEGER), serde_json::json!(9_007_199_254_740_991i64));
+        assert_eq!(int(-MAX_SAFE_INTEGER - 1)["value"], "-9007199254740992");
+        assert_eq!(uint(u64::MAX)["value"], "18446744073709551615");
+        assert_eq!(int_text("18446744073709551615")["type"], "bigint");
+        assert_eq!(float(f64::NEG_INFINITY)["value"], "-Infinity");
+        assert_eq!(float32(0.1), serde_json::json!(0.1));
+        assert_eq!(float_text("nan")["value"], "NaN");
+        assert_eq!(scaled_decimal(-5, 3), "-0.005");
+        assert_eq!(scaled_decimal(12_340, 2), "123.40");
+        assert_eq!(text_form(&decimal("1.50")), "1.50");
+    }
+
+    #[test]
+    fn formats_temporal_values() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(-719_528), (0, 1, 1));
+        assert_eq!(date(-44, 3, 15)["value"], "-0044-03-15");
+        assert_eq!(date(12_000, 1, 1)["value"], "+12000-01-01");
+
+        let t = TimeParts::from_units(45_907_120_000, 7);
+        assert_eq!(time(t, None)["value"], "01:16:30.712");
+        let t = TimeParts::micros(13, 45, 7, 500_000);
+        assert_eq!(datetime(2024, 2, 29, t, Some(-34_200))["value"], "2024-02-29T13:45:07.5-09:30");
+        assert_eq!(datetime(2024, 2, 29, TimeParts::new(0, 0, 0), None)["type"], "dateTime");
+
+        assert_eq!(iso_duration(0, 0, 0), "PT0S");
+        assert_eq!(iso_duration(-13, 0, 3_600_000_001), "P-1Y-1MT1H0.000001S");
+    }
+
+    #[test]
+    fn builds_keyed_statements() {
+        let (sql, _) = select_cell(DbType::Mysql, &cell()).unwrap();
+        assert_eq!(sql, "SELECT `data` FROM `app`.`files` WHERE `id` = :k0");
+
+        let (sql, params) = update_cell(DbType::Mssql, &cell(), b"x").unwrap();
+        assert_eq!(sql, "UPDATE [app].[files] SET [data] = :v WHERE [id] = :k0");
+        let QueryParams::Named(params) = params else { panic!("expected named params") };
+        assert!(matches!(&params["v"], QueryParam::Bytes(b) if b == "eA=="));
+
+        let mut keyless = cell();
+        keyless.key.clear();
+        assert_eq!(select_cell(DbType::Sqlite, &keyless).unwrap_err().kind, "validation");
+        keyless.key.insert("id".into(), QueryParam::Null);
+        assert_eq!(select_cell(DbType::Sqlite, &keyless).unwrap_err().kind, "validation");
+    }
+}
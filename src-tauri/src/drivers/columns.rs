@@ -0,0 +1,64 @@
+// Result column metadata shared by every driver.
+//
+// Each driver maps its protocol's column descriptor (MySQL column definition
+// packets, PG RowDescription, SQLite declared types, TDS COLMETADATA) to a
+// `ResultColumn`. The helpers here cover the parts that do not depend on the
+// protocol.
+
+use crate::models::{ResultColumn, TypeCategory};
+
+/// A column with only its name and type known; drivers fill in the rest.
+pub fn describe(name: &str, native_type: &str, category: TypeCategory) -> ResultColumn {
+    ResultColumn {
+        name: name.to_string(),
+        native_type: native_type.to_string(),
+        category,
+        nullable: None,
+        precision: None,
+        scale: None,
+        length: None,
+        schema: None,
+        table: None,
+        column: None,
+    }
+}
+
+/// The column names, for the `columns` field that sits beside the metadata.
+pub fn names(columns: &[ResultColumn]) -> Vec<String> {
+    columns.iter().map(|c| c.name.clone()).collect()
+}
+
+/// Split a declared type such as `DECIMAL(10, 2)` or `VARCHAR(255)` into
+/// its base name and up to two numeric arguments.
+pub fn parse_declared_type(declared: &str) -> (String, Option<u32>, Option<u32>) {
+    let Some((base, rest)) = declared.split_once('(') else {
+        return (declared.trim().to_string(), None, None);
+    };
+    let args = rest.split(')').next().unwrap_or("");
+    let mut nums = args.split(',').map(|a| a.trim().parse::<u32>().ok());
+    let first = nums.next().flatten();
+    let second = nums.next().flatten();
+    (base.trim().to_string(), first, second)
+}
+
+/// Empty strings mean "not reported" in most protocols.
+pub fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_declared_types() {
+        assert_eq!(parse_declared_type("DECIMAL(10, 2)"), ("DECIMAL".into(), Some(10), Some(2)));
+        assert_eq!(parse_declared_type("varchar (255)"), ("varchar".into(), Some(255), None));
+        assert_eq!(parse_declared_type("TEXT"), ("TEXT".into(), None, None));
+        assert_eq!(parse_declared_type("NUMERIC(abc)"), ("NUMERIC".into(), None, None));
+    }
+}
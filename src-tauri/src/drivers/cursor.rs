@@ -0,0 +1,255 @@
+// Server-side cursors shared by every driver.
+//
+// A cursor is a producer task that owns a session's pinned connection for as
+// long as the result is open, reads rows from the server one at a time, and
+// pushes them through a bounded channel. The bounded channel is the
+// backpressure: once `CURSOR_BUFFER_ROWS` rows are waiting, the producer stops
+// reading, the socket buffer fills, and the server stops sending. The grid
+// pulls pages with `fetch`, so memory stays proportional to the page size no
+// matter how large the table is.
+//
+// Drivers build the producer (protocol specific) and hand it the
+// `CursorChannels`; the `CursorRegistry` below holds the consumer side.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::drivers::columns;
+use crate::models::{CursorInfo, CursorPage, ResultColumn};
+use crate::{AppError, AppResult};
+
+/// Rows buffered between the producer and the consumer.
+pub const CURSOR_BUFFER_ROWS: usize = 256;
+/// Upper bound for a single `fetch` call.
+pub const MAX_CURSOR_FETCH_ROWS: usize = 10_000;
+
+pub type CursorRow = Vec<serde_json::Value>;
+
+/// Producer half, moved into the driver's streaming task.
+pub struct CursorChannels {
+    columns: oneshot::Sender<AppResult<Vec<ResultColumn>>>,
+    rows: mpsc::Sender<AppResult<CursorRow>>,
+}
+
+impl CursorChannels {
+    /// Report the result columns and get the row sender. Returns `None` when
+    /// the opener already gave up (timed out), so the producer can stop.
+    pub fn start(self, columns: Vec<ResultColumn>) -> Option<mpsc::Sender<AppResult<CursorRow>>> {
+        self.columns.send(Ok(columns)).ok()?;
+        Some(self.rows)
+    }
+
+    /// Report a failure before any columns were produced.
+    pub fn fail(self, error: AppError) {
+        let _ = self.columns.send(Err(error));
+    }
+}
+
+/// Consumer half, waiting for the producer to report its columns.
+pub struct PendingCursor {
+    columns: oneshot::Receiver<AppResult<Vec<ResultColumn>>>,
+    rows: mpsc::Receiver<AppResult<CursorRow>>,
+}
+
+/// Create the channel pair for a new cursor.
+pub fn channel() -> (CursorChannels, PendingCursor) {
+    let (columns_tx, columns_rx) = oneshot::channel();
+    let (rows_tx, rows_rx) = mpsc::channel(CURSOR_BUFFER_ROWS);
+    (
+        CursorChannels { columns: columns_tx, rows: rows_tx },
+        PendingCursor { columns: columns_rx, rows: rows_rx },
+    )
+}
+
+impl PendingCursor {
+    /// Wait until the statement has started and its columns are known. The
+    /// driver's watchdog bounds the wait by stopping the producer.
+    pub async fn ready(self, session_id: &str) -> AppResult<Cursor> {
+        let PendingCursor { columns, rows } = self;
+        let columns = columns
+            .await
+            .map_err(|_| AppError::state("Cursor producer stopped before reporting columns"))??;
+        Ok(Cursor {
+            session_id: session_id.to_string(),
+            columns,
+            rows,
+            fetched: 0,
+            done: false,
+        })
+    }
+}
+
+/// An open cursor: the receiving end of a producer task.
+pub struct Cursor {
+    session_id: String,
+    columns: Vec<ResultColumn>,
+    rows: mpsc::Receiver<AppResult<CursorRow>>,
+    fetched: u64,
+    done: bool,
+}
+
+impl Cursor {
+    /// Receive up to `max_rows` rows. A short page means the result ended.
+    async fn fetch(&mut self, max_rows: usize) -> AppResult<CursorPage> {
+        let max_rows = max_rows.clamp(1, MAX_CURSOR_FETCH_ROWS);
+        let mut rows = Vec::with_capacity(max_rows.min(CURSOR_BUFFER_ROWS));
+        while rows.len() < max_rows && !self.done {
+            match self.rows.recv().await {
+                Some(Ok(row)) => rows.push(row),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Err(e);
+                }
+                None => self.done = true,
+            }
+        }
+        self.fetched += rows.len() as u64;
+        Ok(CursorPage {
+            rows,
+            done: self.done,
+            fetched_rows: self.fetched,
+        })
+    }
+}
+
+/// Open cursors of one driver, keyed by cursor id
+/// (`<session_id>:<uuid>`, so the profile prefix still routes commands).
+#[derive(Default)]
+pub struct CursorRegistry {
+    cursors: Mutex<HashMap<String, Arc<Mutex<Cursor>>>>,
+}
+
+impl CursorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a ready cursor and describe it to the caller.
+    pub async fn insert(&self, cursor: Cursor) -> CursorInfo {
+        let cursor_id = format!("{}:{}", cursor.session_id, uuid::Uuid::new_v4());
+        let info = CursorInfo {
+            cursor_id: cursor_id.clone(),
+            session_id: cursor.session_id.clone(),
+            columns: columns::names(&cursor.columns),
+            column_meta: cursor.columns.clone(),
+        };
+        let mut cursors = self.cursors.lock().await;
+        cursors.insert(cursor_id, Arc::new(Mutex::new(cursor)));
+        info
+    }
+
+    /// Fetch the next page. Exhausted (or failed) cursors are dropped, which
+    /// releases their session.
+    pub async fn fetch(&self, cursor_id: &str, max_rows: usize) -> AppResult<CursorPage> {
+        let cursor = {
+            let cursors = self.cursors.lock().await;
+            cursors
+                .get(cursor_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Cursor not found: {}", cursor_id)))?
+        };
+        let result = cursor.lock().await.fetch(max_rows).await;
+        if !matches!(result, Ok(CursorPage { done: false, .. })) {
+            self.close(cursor_id).await;
+        }
+        result
+    }
+
+    /// Close a cursor. Dropping the receiver makes the producer's next send
+    /// fail, so it stops the statement on the server and releases the pinned
+    /// connection.
+    pub async fn close(&self, cursor_id: &str) {
+        let mut cursors = self.cursors.lock().await;
+        cursors.remove(cursor_id);
+    }
+
+    /// Close every cursor opened on `session_id`.
+    pub async fn close_session(&self, session_id: &str) {
+        let prefix = format!("{}:", session_id);
+        let mut cursors = self.cursors.lock().await;
+        cursors.retain(|id, _| !id.starts_with(&prefix));
+    }
+}
+
+/// Cursors stream exactly one statement.
+pub fn single_statement(sql: &str) -> AppResult<String> {
+    let mut statements = crate::sql::split_sql_statements(sql);
+    if statements.len() != 1 {
+        return Err(AppError::validation(format!(
+            "A cursor takes exactly one statement (got {})",
+            statements.len()
+        )));
+    }
+    Ok(statements.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TypeCategory;
+
+    #[tokio::test]
+    async fn pages_through_a_producer() {
+        let (chans, pending) = channel();
+        tokio::spawn(async move {
+            let columns = vec![columns::describe("n", "INTEGER", TypeCategory::Int)];
+            let Some(rows) = chans.start(columns) else { return };
+            for n in 0..25 {
+                if rows.send(Ok(vec![serde_json::json!(n)])).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        let registry = CursorRegistry::new();
+        let cursor = pending.ready("p:s").await.unwrap();
+        let info = registry.insert(cursor).await;
+        assert_eq!(info.columns, vec!["n"]);
+        assert_eq!(info.column_meta[0].category, TypeCategory::Int);
+        assert!(info.cursor_id.starts_with("p:s:"));
+
+        let page = registry.fetch(&info.cursor_id, 10).await.unwrap();
+        assert_eq!(page.rows.len(), 10);
+        assert!(!page.done);
+        let page = registry.fetch(&info.cursor_id, 10).await.unwrap();
+        assert_eq!(page.rows[0][0], serde_json::json!(10));
+        let page = registry.fetch(&info.cursor_id, 10).await.unwrap();
+        assert_eq!(page.rows.len(), 5);
+        assert!(page.done);
+        assert_eq!(page.fetched_rows, 25);
+
+        // Exhausted cursors are released.
+        assert!(registry.fetch(&info.cursor_id, 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn closing_stops_the_producer() {
+        let (chans, pending) = channel();
+        let producer = tokio::spawn(async move {
+            let rows = chans.start(vec![]).unwrap();
+            let mut sent = 0u64;
+            while rows.send(Ok(vec![])).await.is_ok() {
+                sent += 1;
+            }
+            sent
+        });
+
+        let registry = CursorRegistry::new();
+        let info = registry.insert(pending.ready("p:s").await.unwrap()).await;
+        registry.fetch(&info.cursor_id, 1).await.unwrap();
+        registry.close_session("p:s").await;
+
+        // The producer never ran far ahead of the consumer.
+        let sent = producer.await.unwrap();
+        assert!(sent <= (CURSOR_BUFFER_ROWS + 1) as u64);
+    }
+
+    #[test]
+    fn cursors_take_one_statement() {
+        assert_eq!(single_statement("SELECT 1;").unwrap(), "SELECT 1");
+        assert!(single_statement("SELECT 1; SELECT 2").is_err());
+        assert!(single_statement(" ; ").is_err());
+    }
+}
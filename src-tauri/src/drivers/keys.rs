@@ -0,0 +1,147 @@
+// Row keys: the columns that identify one row of a table, read from the
+// server's catalog on a session.
+//
+// The primary key wins. Without one, the first unique index whose columns
+// are all NOT NULL serves (a nullable unique column can hold many NULLs, so
+// it does not identify a row). Partial/filtered indexes and expression keys
+// never qualify. SQLite rowids are not used: result sets do not carry them.
+
+use crate::drivers::{DbDriver, DbType};
+use crate::models::{QueryParam, QueryParams};
+use crate::{AppError, AppResult};
+
+/// The key columns of `table`, in key order. `schema` defaults to the
+/// session's current schema (MySQL: database; SQLite: `main`).
+pub async fn row_key(
+    driver: &dyn DbDriver,
+    session_id: &str,
+    schema: Option<&str>,
+    table: &str,
+) -> AppResult<Vec<String>> {
+    let (sql, params) = key_query(driver.db_type(), schema, table);
+    let results = driver.query_params(session_id, &sql, &params, None).await?;
+    let rows = results.into_iter().next().map(|r| r.rows).unwrap_or_default();
+    let key = pick_key(&rows);
+    if key.is_empty() {
+        return Err(AppError::validation(format!(
+            "{} has no primary key or NOT NULL unique key, so its rows cannot be edited safely",
+            table
+        )));
+    }
+    Ok(key)
+}
+
+/// One row per key column: index name, column name (NULL for an
+/// expression), whether the index is the primary key, whether the column is
+/// nullable. Primary key first, then by index name and key position.
+fn key_query(db: DbType, schema: Option<&str>, table: &str) -> (String, QueryParams) {
+    let schema_or_current = format!("COALESCE(:schema, {})", db.dialect().current_schema());
+    let sql = match db {
+        DbType::Mysql => format!(
+            "SELECT s.INDEX_NAME, s.COLUMN_NAME, s.INDEX_NAME = 'PRIMARY', c.IS_NULLABLE = 'YES' \
+             FROM information_schema.STATISTICS s \
+             LEFT JOIN information_schema.COLUMNS c ON c.TABLE_SCHEMA = s.TABLE_SCHEMA \
+               AND c.TABLE_NAME = s.TABLE_NAME AND c.COLUMN_NAME = s.COLUMN_NAME \
+             WHERE s.TABLE_SCHEMA = {s} AND s.TABLE_NAME = :table \
+               AND s.NON_UNIQUE = 0 \
+             ORDER BY s.INDEX_NAME <> 'PRIMARY', s.INDEX_NAME, s.SEQ_IN_INDEX",
+            s = schema_or_current
+        ),
+        DbType::Postgres => format!(
+            "SELECT i.relname::text, a.attname::text, x.indisprimary, NOT a.attnotnull \
+             FROM pg_index x \
+             JOIN pg_class t ON t.oid = x.indrelid \
+             JOIN pg_namespace n ON n.oid = t.relnamespace \
+             JOIN pg_class i ON i.oid = x.indexrelid \
+             CROSS JOIN LATERAL unnest(x.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord) \
+             LEFT JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum AND k.attnum > 0 \
+             WHERE x.indisunique AND x.indpred IS NULL \
+               AND t.relname = :table AND n.nspname = {s} \
+             ORDER BY x.indisprimary DESC, i.relname, k.ord",
+            s = schema_or_current
+        ),
+        DbType::Sqlite => format!(
+            "SELECT 'PRIMARY', p.name, 1, 0, p.pk \
+             FROM pragma_table_info(:table, {s}) p WHERE p.pk > 0 \
+             UNION ALL \
+             SELECT l.name, ii.name, 0, COALESCE(c.\"notnull\" = 0, 1), ii.seqno + 1 \
+             FROM pragma_index_list(:table, {s}) l \
+             JOIN pragma_index_info(l.name, {s}) ii \
+             LEFT JOIN pragma_table_info(:table, {s}) c ON c.name = ii.name \
+             WHERE l.\"unique\" = 1 AND l.partial = 0 AND l.origin <> 'pk' \
+             ORDER BY 3 DESC, 1, 5",
+            s = schema_or_current
+        ),
+        DbType::Mssql => format!(
+            "SELECT i.name, c.name, i.is_primary_key, c.is_nullable \
+             FROM sys.indexes i \
+             JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id \
+             JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id \
+             WHERE i.object_id = OBJECT_ID(QUOTENAME({s}) + '.' + QUOTENAME(:table)) \
+               AND i.is_unique = 1 AND i.has_filter = 0 AND ic.is_included_column = 0 \
+             ORDER BY i.is_primary_key DESC, i.name, ic.key_ordinal",
+            s = schema_or_current
+        ),
+    };
+    let mut params = std::collections::BTreeMap::new();
+    params.insert("table".to_string(), QueryParam::Text(table.to_string()));
+    let schema = schema.filter(|s| !s.is_empty());
+    params.insert("schema".to_string(), schema.map_or(QueryParam::Null, |s| QueryParam::Text(s.to_string())));
+    (sql, QueryParams::Named(params))
+}
+
+/// The columns of the first usable index in `rows` (see `key_query`).
+fn pick_key(rows: &[Vec<serde_json::Value>]) -> Vec<String> {
+    let mut start = 0;
+    while start < rows.len() {
+        let index = &rows[start][0];
+        let end = start + rows[start..].iter().take_while(|r| r[0] == *index).count();
+        let group = &rows[start..end];
+        let primary = truthy(&group[0][2]);
+        let columns: Option<Vec<String>> = group.iter().map(|r| r[1].as_str().map(String::from)).collect();
+        if let Some(columns) = columns {
+            if primary || group.iter().all(|r| !truthy(&r[3])) {
+                return columns;
+            }
+        }
+        start = end;
+    }
+    Vec::new()
+}
+
+/// Catalog flags come back as booleans, bits or integers.
+fn truthy(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        serde_json::Value::String(s) => matches!(s.as_str(), "1" | "t" | "true" | "YES"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn picks_primary_then_not_null_unique_key() {
+        let rows = |rows: &[(&str, Option<&str>, bool, bool)]| -> Vec<Vec<serde_json::Value>> {
+            rows.iter().map(|(i, c, p, n)| vec![json!(i), json!(c), json!(p), json!(n)]).collect()
+        };
+        let with_primary = rows(&[("PRIMARY", Some("a"), true, false), ("PRIMARY", Some("b"), true, false)]);
+        assert_eq!(pick_key(&with_primary), vec!["a", "b"]);
+
+        let unique_only = rows(&[
+            ("email_uq", Some("email"), false, true),
+            ("expr_uq", None, false, false),
+            ("code_uq", Some("org"), false, false),
+            ("code_uq", Some("code"), false, false),
+        ]);
+        assert_eq!(pick_key(&unique_only), vec!["org", "code"]);
+        assert!(pick_key(&rows(&[("email_uq", Some("email"), false, true)])).is_empty());
+
+        // SQLite reports flags as integers.
+        assert_eq!(pick_key(&[vec![json!("PRIMARY"), json!("id"), json!(1), json!(0)]]), vec!["id"]);
+    }
+}
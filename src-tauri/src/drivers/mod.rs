@@ -7,18 +7,69 @@
 // that pinned connection, enabling cross-command transactions, session
 // variables, temp tables, and `USE <db>` persistence. `end_session` returns
 // the connection to the pool.
+//
+// Recovery: `is_session_broken` and `recover_session` let a session whose
+// connection dropped carry on with a fresh one (see `recovery`).
+//
+// Read-only: profiles connected with `read_only` open their connections
+// read-only where the protocol allows it (see `read_only`).
+//
+// Pools: each profile's pool is sized and aged by its `PoolSettings` (see
+// `pool`); `pool_stats` reports what it holds right now.
+//
+// Cursors: `open_cursor` streams a single statement's rows from the server
+// on a session; the grid pages through them with `fetch_cursor`. The session
+// is busy until the cursor is exhausted or closed.
+//
+// Binary values: result sets carry a bounded preview (see `cells`);
+// `fetch_bytes` reads one full value, and export cursors send whole values.
+//
+// Row keys: `keys::row_key` reads the columns that identify a table's rows
+// from the catalog, for grid edits.
+//
+// Messages: each result set carries the warnings and notices the server
+// raised while its statement ran (SQLite has none).
+//
+// Plans: `explain` asks the server for a plan and normalizes it into one
+// node tree (see `plan`).
+//
+// Cancellation: `cancel` stops the statement currently running on a session
+// from another command, without waiting for the session's lock.
+//
+// Timeouts: `timeout_ms` (see `sql::effective_query_timeout_ms`; 0 means no
+// timeout) is enforced by every driver with the same server-side stop as
+// `cancel`, so a timed-out session stays usable. SQL Server is the exception:
+// its stop ends the server session, so the call fails with `connection_lost`
+// and the session is recovered with a fresh connection (see `recovery`).
 
+pub mod cancel;
+pub mod cells;
+pub mod columns;
+pub mod cursor;
+pub mod keys;
 pub mod mysql;
 pub mod mssql;
+pub mod pg_tls;
+pub mod pg_values;
+pub mod plan;
+pub mod pool;
+pub mod read_only;
 pub mod postgres;
+pub mod recovery;
+pub mod scope;
 pub mod sqlite;
+pub mod tds_events;
+pub mod transaction;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+use crate::drivers::cells::BinaryValues;
 use crate::models::{
-    ColumnInfo, ConnectParams, ConnectionHandle, DatabaseInfo, QueryResultSet, SessionId, TableInfo,
+    ColumnInfo, ConnectParams, ConnectionHandle, CursorInfo, CursorPage, DatabaseInfo, PoolStats, QueryParams,
+    QueryPlan, QueryResultSet, SessionId, TableInfo,
 };
+use crate::sql::dialect::SqlDialect;
 use crate::AppResult;
 
 /// The database type discriminator.
@@ -33,11 +84,17 @@ pub enum DbType {
 
 impl DbType {
     pub fn from_str(s: &str) -> Self {
+        Self::parse(s).unwrap_or(DbType::Mysql) // default
+    }
+
+    /// The type named `s` or one of its aliases; None for anything else.
+    pub fn parse(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
-            "postgres" | "postgresql" | "pg" => DbType::Postgres,
-            "sqlite" => DbType::Sqlite,
-            "mssql" | "sqlserver" => DbType::Mssql,
-            _ => DbType::Mysql, // default
+            "mysql" => Some(DbType::Mysql),
+            "postgres" | "postgresql" | "pg" => Some(DbType::Postgres),
+            "sqlite" => Some(DbType::Sqlite),
+            "mssql" | "sqlserver" => Some(DbType::Mssql),
+            _ => None,
         }
     }
 
@@ -49,6 +106,11 @@ impl DbType {
             DbType::Mssql => "mssql",
         }
     }
+
+    /// How generated SQL quotes names and values (see `sql::dialect`).
+    pub fn dialect(self) -> SqlDialect {
+        SqlDialect::new(self)
+    }
 }
 
 /// The driver service interface. Implementations are stateful (hold a pool
@@ -72,6 +134,9 @@ pub trait DbDriver: Send + Sync {
     /// Test that the connection is alive.
     async fn ping(&self, profile_id: &str) -> AppResult<()>;
 
+    /// What the profile's pool holds right now.
+    async fn pool_stats(&self, profile_id: &str) -> AppResult<PoolStats>;
+
     // ---- Sessions (pinned connections for cross-command affinity)
 
     /// Lease a connection from the pool and pin it under `session_id`. The
@@ -81,10 +146,26 @@ pub trait DbDriver: Send + Sync {
     /// Return a pinned session's connection to the pool.
     async fn end_session(&self, session_id: &str) -> AppResult<()>;
 
+    /// Whether a session's connection is gone: a probe got no reply from
+    /// the server. False for unknown sessions.
+    async fn is_session_broken(&self, session_id: &str) -> bool;
+
+    /// Pin a fresh connection from the pool in place of a session's lost
+    /// one, which is discarded with whatever state it held. Closes the
+    /// session's cursors.
+    async fn recover_session(&self, session_id: &str) -> AppResult<()>;
+
+    /// Pin a connection to another database of the server in place of the
+    /// session's (Postgres, where a connection is bound to one database).
+    /// Closes the session's cursors. The others switch with `USE`; see
+    /// `scope`.
+    async fn switch_database(&self, session_id: &str, database: &str) -> AppResult<()>;
+
     // ---- Query
 
     /// Execute SQL that may return rows. Supports multiple statements (split
     /// by `;` respecting quotes); returns one `QueryResultSet` per statement.
+    /// `timeout_ms` bounds the whole call.
     async fn query(
         &self,
         session_id: &str,
@@ -92,6 +173,27 @@ pub trait DbDriver: Send + Sync {
         timeout_ms: Option<u64>,
     ) -> AppResult<Vec<QueryResultSet>>;
 
+    /// Execute one statement with typed bind values. The driver binds them
+    /// natively (never spliced into the SQL); named params use `:name`.
+    async fn query_params(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> AppResult<Vec<QueryResultSet>>;
+
+    /// Run one statement with bind values and return the first column of
+    /// its first row as raw bytes (`None` for NULL), without the preview
+    /// limit applied to result sets. Fails when no row matches.
+    async fn fetch_bytes(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> AppResult<Option<Vec<u8>>>;
+
     /// Execute SQL that does not return rows (DDL, INSERT, UPDATE, DELETE).
     async fn execute(
         &self,
@@ -100,6 +202,43 @@ pub trait DbDriver: Send + Sync {
         timeout_ms: Option<u64>,
     ) -> AppResult<u64>;
 
+    // ---- Cursors (incremental fetching of large results)
+
+    /// Start streaming a single statement on a session. `binary` says how
+    /// much of each binary value to send. `timeout_ms` bounds the wait for
+    /// the statement to start returning (not the whole result).
+    async fn open_cursor(
+        &self,
+        session_id: &str,
+        sql: &str,
+        binary: BinaryValues,
+        timeout_ms: Option<u64>,
+    ) -> AppResult<CursorInfo>;
+
+    /// Pull up to `max_rows` rows from an open cursor.
+    async fn fetch_cursor(&self, cursor_id: &str, max_rows: usize) -> AppResult<CursorPage>;
+
+    /// Stop streaming and release the cursor's session.
+    async fn close_cursor(&self, cursor_id: &str) -> AppResult<()>;
+
+    /// Cancel whatever is running on `session_id`, stopping the work on the
+    /// server. The interrupted call fails with an error of kind `cancelled`;
+    /// cancelling an idle session is a no-op.
+    async fn cancel(&self, session_id: &str) -> AppResult<()>;
+
+    // ---- Plans
+
+    /// The execution plan of one statement (SQL Server: of a batch),
+    /// normalized by `plan`. With `analyze` the statement is run and the
+    /// actual rows and times are filled in where the server reports them.
+    async fn explain(
+        &self,
+        session_id: &str,
+        sql: &str,
+        analyze: bool,
+        timeout_ms: Option<u64>,
+    ) -> AppResult<QueryPlan>;
+
     // ---- Schema introspection
 
     async fn list_databases(&self, session_id: &str) -> AppResult<Vec<String>>;
@@ -1,60 +1,229 @@
 // SQL Server (MSSQL) driver. Implements `DbDriver` using `tiberius` (async,
-// Tokio runtime). A `tiberius::Client` is held per session, mirroring the
+// Tokio runtime). Clients come from a deadpool (`TdsManager`) sized by the
+// profile's `PoolSettings`, and one is leased per session, mirroring the
 // other drivers' session model.
 //
 // Introspection uses INFORMATION_SCHEMA / sys catalogs. The "database" target
 // maps to a SQL Server database name (selected via `USE` on connect / per
 // query).
+//
+// Cancellation and timeouts: tiberius cannot send an attention packet, so the
+// driver issues `KILL <spid>` from a side connection and the running call
+// abandons its request. The server rolls back the session's transaction and
+// its state (`USE`, `SET`, temp tables) is gone, so the client is dropped and
+// the call fails with `recovery::session_reset`; the `ConnectionManager` then
+// recovers the session like one whose connection dropped.
+//
+// Batches: every result set of a batch is returned separately, together with
+// the row count of each statement and any informational messages, which
+// tiberius only reports through `tracing` (see `tds_events`).
+//
+// Encryption follows `ssl_mode`; with only `ssl` set the server certificate
+// is verified (chain and host name) against the system roots or the CA file.
+// Accepting any certificate ("encrypt but trust") needs `ssl_mode` `require`
+// or `prefer`, since `ssl_reject_unauthorized` defaults to false and so cannot
+// be read as an opt-in. tiberius always checks the host name when it verifies,
+// so `verify-ca` is refused, and SQL Server has no client certificate auth.
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures_util::TryStreamExt;
-use tiberius::{Client, ColumnData, Config, AuthMethod, EncryptionLevel, QueryItem};
+use tiberius::{Client, ColumnData, ColumnType, Config, AuthMethod, EncryptionLevel, Query, QueryItem};
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 use tokio_util::compat::TokioAsyncWriteCompatExt;
+use tracing::instrument::WithSubscriber;
 
+use crate::drivers::cancel::{QueryCancel, Watchdog};
+use crate::drivers::{cells, columns, plan};
+use crate::drivers::cells::{BinaryValues, TimeParts};
+use crate::drivers::cursor::{self, CursorChannels, CursorRegistry};
+use crate::drivers::pool::{self, PoolLimits};
+use crate::drivers::recovery;
+use crate::drivers::tds_events::{TokenEvent, TokenLog};
 use crate::drivers::{DbType, DbDriver};
 use crate::models::{
-    ColumnInfo, ConnectParams, ConnectionHandle, DatabaseInfo, QueryResultSet, SessionId, TableInfo,
+    ColumnInfo, ConnectParams, ConnectionHandle, CursorInfo, CursorPage, DatabaseInfo, MessageSeverity,
+    PoolStats, QueryMessage, QueryParam, QueryParams, QueryPlan, QueryResultSet, ResultColumn, SessionId,
+    SslMode, TableInfo, TlsInfo, TypeCategory,
 };
 use crate::AppError;
 
-/// Convert a tiberius DATETIME/SMALLDATETIME to an ISO string.
-/// DATETIME floors at 1900-01-01; `seconds_fragments` is 1/300s since midnight.
-/// SMALLDATETIME floors at 1900-01-01; `seconds_fragments` is minutes since midnight.
-fn tiberius_dt_to_string(days: i32, seconds_fragments: u32, is_small: bool) -> String {
-    let base = match chrono::NaiveDate::from_ymd_opt(1900, 1, 1) {
-        Some(d) => d,
-        None => return String::new(),
-    };
-    let date = base + chrono::Duration::days(days as i64);
-    let total_seconds = if is_small {
-        (seconds_fragments as i64) * 60
-    } else {
-        (seconds_fragments as i64 + 150) / 300 // round to nearest second
-    };
-    let secs = total_seconds.rem_euclid(86400);
-    let time = chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs as u32, 0)
-        .unwrap_or_else(|| chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap());
-    chrono::NaiveDateTime::new(date, time).to_string()
+/// Days from 1970-01-01 to the DATETIME epoch (1900-01-01) and to the
+/// DATE/DATETIME2 epoch (0001-01-01).
+const DATETIME_EPOCH_DAYS: i64 = -25_567;
+const DATE_EPOCH_DAYS: i64 = -719_162;
+
+/// A DATETIME2 or DATETIMEOFFSET cell. DATETIMEOFFSET is stored in UTC
+/// with the offset beside it; the cell shows the local time the value was
+/// written with, as SQL Server does.
+fn datetime2_cell(dt: tiberius::time::DateTime2, offset_minutes: Option<i16>) -> serde_json::Value {
+    let (time, scale) = (dt.time(), u32::from(dt.time().scale()));
+    let per_day = 86_400 * 10i128.pow(scale);
+    let shift = i128::from(offset_minutes.unwrap_or(0)) * 60 * 10i128.pow(scale);
+    let units = i128::from(dt.date().days()) * per_day + i128::from(time.increments()) + shift;
+    let (y, m, d) = cells::civil_from_days(units.div_euclid(per_day) as i64 + DATE_EPOCH_DAYS);
+    let parts = TimeParts::from_units(units.rem_euclid(per_day) as u64, scale);
+    cells::datetime(y, m, d, parts, offset_minutes.map(|o| i32::from(o) * 60))
+}
+
+type MssqlClient = Client<tokio_util::compat::Compat<TcpStream>>;
+
+type Pool = deadpool::managed::Pool<TdsManager>;
+
+type PooledClient = deadpool::managed::Object<TdsManager>;
+
+type PinnedConn = Arc<Mutex<Option<PooledClient>>>;
+
+struct TdsManager {
+    params: ConnectParams,
+    limits: PoolLimits,
 }
 
-type PinnedConn = Arc<Mutex<Option<Client<tokio_util::compat::Compat<TcpStream>>>>>;
+#[async_trait]
+impl deadpool::managed::Manager for TdsManager {
+    type Type = MssqlClient;
+    type Error = AppError;
+
+    async fn create(&self) -> Result<MssqlClient, AppError> {
+        MssqlDriver::connect_raw(&self.params).await
+    }
+
+    /// tiberius cannot tell whether a connection is closed; the health
+    /// check, when set, is what finds out.
+    async fn recycle(
+        &self,
+        client: &mut MssqlClient,
+        metrics: &deadpool::managed::Metrics,
+    ) -> deadpool::managed::RecycleResult<AppError> {
+        if self.limits.too_old(metrics) {
+            return Err(deadpool::managed::RecycleError::StaticMessage("Connection expired"));
+        }
+        if let Some(ref check) = self.limits.health_check {
+            client
+                .execute(check.as_str(), &[])
+                .await
+                .map_err(|e| AppError::database(format!("Health check failed: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Shared between the session and `cancel`; `spid` changes whenever a
+/// cancelled session gets a fresh connection.
+struct SessionCancel {
+    spid: AtomicU32,
+    cancel: Arc<QueryCancel>,
+}
+
+/// Results of one batch, assembled from its rows and token events.
+#[derive(Default)]
+struct BatchResults {
+    results: Vec<QueryResultSet>,
+    /// The result set whose rows are being read, until its DONE token.
+    open: Option<(Vec<ResultColumn>, Vec<Vec<serde_json::Value>>)>,
+    /// Messages raised since the previous result; they go on the next one.
+    messages: Vec<QueryMessage>,
+}
+
+impl BatchResults {
+    fn open(&mut self, columns: Vec<ResultColumn>) {
+        self.close();
+        self.open = Some((columns, Vec::new()));
+    }
+
+    fn row(&mut self, row: Vec<serde_json::Value>) {
+        if let Some((_, rows)) = self.open.as_mut() {
+            rows.push(row);
+        }
+    }
+
+    fn events(&mut self, events: Vec<TokenEvent>) {
+        for event in events {
+            match event {
+                TokenEvent::Done { rows } => {
+                    // A DONE closes the open result set; otherwise it ends a
+                    // statement without one (only counted ones are reported).
+                    if self.open.is_some() {
+                        self.close();
+                    } else if let Some(n) = rows {
+                        self.push(Self::command(n));
+                    }
+                }
+                // The token carries no number or class tiberius exposes;
+                // anything above severity 10 arrives as an error instead.
+                TokenEvent::Info(text) => self.messages.push(QueryMessage {
+                    severity: MessageSeverity::Info,
+                    code: None,
+                    text,
+                }),
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some((columns, rows)) = self.open.take() {
+            let count = rows.len();
+            self.push(QueryResultSet {
+                columns: columns::names(&columns),
+                column_meta: columns,
+                rows,
+                affected_rows: count as u64,
+                info: format!("{} row(s) returned", count),
+                messages: vec![],
+            });
+        }
+    }
+
+    fn push(&mut self, mut result: QueryResultSet) {
+        result.messages = std::mem::take(&mut self.messages);
+        self.results.push(result);
+    }
+
+    fn command(affected: u64) -> QueryResultSet {
+        QueryResultSet {
+            columns: vec![],
+            column_meta: vec![],
+            rows: vec![],
+            affected_rows: affected,
+            info: format!("{} row(s) affected", affected),
+            messages: vec![],
+        }
+    }
+
+    /// Every result, or a single empty one for a batch that reported none
+    /// (`SET NOCOUNT ON`, `PRINT` only). Trailing messages go on the last.
+    fn finish(mut self) -> Vec<QueryResultSet> {
+        self.close();
+        match self.results.last_mut() {
+            Some(last) => last.messages.append(&mut self.messages),
+            None => self.push(Self::command(0)),
+        }
+        self.results
+    }
+}
 
 pub struct MssqlDriver {
-    /// One client per connected profile (tiberius holds the connection).
-    clients: Mutex<HashMap<String, Client<tokio_util::compat::Compat<TcpStream>>>>,
+    /// One pool per connected profile.
+    pools: Mutex<HashMap<String, Pool>>,
+    /// Connect params per profile, for side connections and reconnects.
+    params: Mutex<HashMap<String, ConnectParams>>,
     sessions: Mutex<HashMap<String, PinnedConn>>,
+    cancels: Mutex<HashMap<String, Arc<SessionCancel>>>,
+    cursors: CursorRegistry,
 }
 
 impl MssqlDriver {
     pub fn new() -> Self {
         Self {
-            clients: Mutex::new(HashMap::new()),
+            pools: Mutex::new(HashMap::new()),
+            params: Mutex::new(HashMap::new()),
             sessions: Mutex::new(HashMap::new()),
+            cancels: Mutex::new(HashMap::new()),
+            cursors: CursorRegistry::new(),
         }
     }
 
@@ -63,20 +232,73 @@ impl MssqlDriver {
         config.host(params.host.clone());
         config.port(params.port);
         config.database(params.database.clone().unwrap_or_default());
+        // ApplicationIntent=ReadOnly; writes are refused by `read_only::check`.
+        config.readonly(params.read_only);
         // SQL auth (user/password).
         config.authentication(AuthMethod::sql_server(params.user.clone(), params.password.clone()));
 
-        // Encryption: on when enabled, but allow untrusted certs in dev.
-        if params.ssl {
-            config.encryption(EncryptionLevel::Required);
-            config.trust_cert(); // accept self-signed for parity with MySQL's reject_unauthorized=false default
-        } else {
-            config.encryption(EncryptionLevel::NotSupported);
+        let has = |path: &Option<String>| path.as_ref().is_some_and(|p| !p.is_empty());
+        if has(&params.ssl_cert_file) || has(&params.ssl_key_file) {
+            return Err(AppError::validation(
+                "SQL Server does not support client certificate authentication",
+            ));
+        }
+        match Self::ssl_mode(params) {
+            SslMode::Disable => config.encryption(EncryptionLevel::NotSupported),
+            // `Off` encrypts the login only, or everything if the server requires it.
+            SslMode::Prefer => {
+                config.encryption(EncryptionLevel::Off);
+                config.trust_cert();
+            }
+            SslMode::Require => {
+                config.encryption(EncryptionLevel::Required);
+                config.trust_cert();
+            }
+            SslMode::VerifyCa => {
+                return Err(AppError::validation(
+                    "SSL mode verify-ca is not supported for SQL Server; use verify-full",
+                ))
+            }
+            SslMode::VerifyFull => {
+                config.encryption(EncryptionLevel::Required);
+                if let Some(ca) = params.ssl_ca_file.as_ref().filter(|s| !s.is_empty()) {
+                    Self::check_ca_file(ca)?;
+                    config.trust_cert_ca(ca);
+                }
+            }
         }
         Ok(config)
     }
 
-    async fn connect_raw(params: &ConnectParams) -> Result<Client<tokio_util::compat::Compat<TcpStream>>, AppError> {
+    /// The sslmode a connection uses; `ssl` alone means verify-full.
+    fn ssl_mode(params: &ConnectParams) -> SslMode {
+        match params.ssl_mode {
+            Some(mode) => mode,
+            None if params.ssl => SslMode::VerifyFull,
+            None => SslMode::Disable,
+        }
+    }
+
+    /// tiberius reads a single certificate and picks the format by extension.
+    fn check_ca_file(ca: &str) -> Result<(), AppError> {
+        let path = std::path::Path::new(ca);
+        if !path.exists() {
+            return Err(AppError::validation(format!("SSL CA file not found: {}", ca)));
+        }
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if !matches!(ext.as_str(), "pem" | "crt" | "der") {
+            return Err(AppError::validation(format!(
+                "SSL CA file must be a .pem, .crt or .der certificate: {}",
+                ca
+            )));
+        }
+        Ok(())
+    }
+
+    async fn connect_raw(params: &ConnectParams) -> Result<MssqlClient, AppError> {
         let config = Self::build_config(params)?;
         let tcp = TcpStream::connect((params.host.clone(), params.port))
             .await
@@ -86,54 +308,337 @@ impl MssqlDriver {
             .map_err(|e| AppError::database(format!("MSSQL connect failed: {}", e)))
     }
 
-    /// Convert a ColumnData value to serde_json::Value.
+    /// The server process id of a connection, the target of `KILL`.
+    async fn session_spid(client: &mut MssqlClient) -> Result<u32, AppError> {
+        let row = client
+            .query("SELECT @@SPID", &[])
+            .await
+            .map_err(|e| AppError::database(format!("SPID query failed: {}", e)))?
+            .into_row()
+            .await
+            .map_err(|e| AppError::database(format!("SPID read failed: {}", e)))?;
+        row.as_ref()
+            .and_then(|r| Self::cell_parse::<u32>(r, 0))
+            .ok_or_else(|| AppError::database("Server did not report a session id"))
+    }
+
+    /// Drop a session's client abandoned by a stop (its server session was
+    /// killed). It leaves the pool, so it is never handed out again, and the
+    /// session has no connection until `recover_session` pins one.
+    fn discard_client(slot: &mut Option<PooledClient>) {
+        if let Some(pooled) = slot.take() {
+            drop(deadpool::managed::Object::take(pooled));
+        }
+    }
+
+    async fn get_pool(&self, profile_id: &str) -> Result<Pool, AppError> {
+        let pools = self.pools.lock().await;
+        pools
+            .get(profile_id)
+            .cloned()
+            .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))
+    }
+
+    /// A connection-open failure as is; pool failures (timeouts) prefixed.
+    fn pool_error(e: deadpool::managed::PoolError<AppError>, context: &str) -> AppError {
+        match e {
+            deadpool::managed::PoolError::Backend(e) => e,
+            e => AppError::database(format!("{}: {}", context, e)),
+        }
+    }
+
+    /// `KILL` a session's server process from a side connection.
+    async fn kill_spid(params: ConnectParams, spid: u32) -> Result<(), AppError> {
+        let mut side = Self::connect_raw(&params).await?;
+        side.execute(format!("KILL {}", spid), &[])
+            .await
+            .map_err(|e| AppError::database(format!("Failed to cancel query: {}", e)))?;
+        Ok(())
+    }
+
+    async fn get_cancel(&self, session_id: &str) -> Result<Arc<SessionCancel>, AppError> {
+        let cancels = self.cancels.lock().await;
+        cancels
+            .get(session_id)
+            .cloned()
+            .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))
+    }
+
+    async fn get_params(&self, session_id: &str) -> Result<ConnectParams, AppError> {
+        let profile_id = session_id.split(':').next().unwrap_or(session_id);
+        let params = self.params.lock().await;
+        params
+            .get(profile_id)
+            .cloned()
+            .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))
+    }
+
+    /// Run a query on a session under its timeout; a stopped statement
+    /// ends the session (see `recovery::session_reset`).
+    async fn run_query(
+        &self,
+        session_id: &str,
+        query: Query<'_>,
+        timeout_ms: Option<u64>,
+    ) -> Result<Vec<QueryResultSet>, AppError> {
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let ctl = self.get_cancel(session_id).await?;
+        let params = self.get_params(session_id).await?;
+
+        let mut guard = pinned.lock().await;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
+        ctl.cancel.reset();
+        let kill = Self::kill_spid(params, ctl.spid.load(Ordering::SeqCst));
+        let watchdog = Watchdog::arm(timeout_ms, ctl.cancel.clone(), kill);
+
+        let log = TokenLog::new();
+        let outcome = tokio::select! {
+            res = Self::batch_results(client, query, &log).with_subscriber(log.dispatch()) => res,
+            stop = ctl.cancel.stopped() => Err(stop),
+        };
+        drop(watchdog);
+        if let Err(stop) = ctl.cancel.check() {
+            Self::discard_client(&mut guard);
+            return Err(recovery::session_reset(stop));
+        }
+        outcome
+    }
+
+    /// Read every result of a batch, in order: one set per result set and
+    /// one per statement that reported a row count. Informational messages
+    /// (`PRINT`, low-severity `RAISERROR`) are attached to them.
+    async fn batch_results(
+        client: &mut MssqlClient,
+        query: Query<'_>,
+        log: &TokenLog,
+    ) -> Result<Vec<QueryResultSet>, AppError> {
+        let mut stream = query
+            .query(client)
+            .await
+            .map_err(|e| AppError::database(format!("Query error: {}", e)))?;
+
+        let mut batch = BatchResults::default();
+        while let Some(item) = stream.try_next().await.map_err(|e| AppError::database(format!("Row error: {}", e)))? {
+            // Events logged before this item belong to earlier statements.
+            batch.events(log.drain());
+            match item {
+                QueryItem::Metadata(meta) => batch.open(Self::result_columns(meta.columns())),
+                QueryItem::Row(row) => batch.row(Self::row_to_json(&row)),
+            }
+        }
+        batch.events(log.drain());
+        Ok(batch.finish())
+    }
+
+    /// Cursor producer: stream the first result set of `stmt` into `chans`,
+    /// giving up (and reporting why) as soon as the statement is stopped.
+    /// Returns true when the cursor was closed before its rows ran out.
+    async fn stream_first_result(
+        client: &mut MssqlClient,
+        stmt: &str,
+        chans: CursorChannels,
+        binary: BinaryValues,
+        cancel: &QueryCancel,
+        watchdog: Option<Watchdog>,
+    ) -> bool {
+        let opened = tokio::select! {
+            res = async {
+                let mut stream = client.query(stmt, &[]).await?;
+                let columns = stream.columns().await?.map(Self::result_columns).unwrap_or_default();
+                Ok::<_, tiberius::error::Error>((stream, columns))
+            } => res.map_err(|e| AppError::database(format!("Query error: {}", e))),
+            stop = cancel.stopped() => Err(stop),
+        };
+        let (mut stream, columns) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                chans.fail(e);
+                return false;
+            }
+        };
+        // The timeout covers the statement starting, not the paging.
+        drop(watchdog);
+        let Some(tx) = chans.start(columns) else { return false };
+
+        // Rows of the first result set only; a second Metadata token means
+        // the batch moved on to another statement. Leftover tokens are
+        // flushed by tiberius before the session's next request.
+        let mut seen_metadata = false;
+        loop {
+            let next = tokio::select! {
+                next = stream.try_next() => {
+                    next.map_err(|e| AppError::database(format!("Row error: {}", e)))
+                }
+                stop = cancel.stopped() => Err(stop),
+            };
+            let item = match next {
+                Ok(Some(QueryItem::Metadata(_))) if seen_metadata => break,
+                Ok(Some(QueryItem::Metadata(_))) => {
+                    seen_metadata = true;
+                    continue;
+                }
+                Ok(Some(QueryItem::Row(row))) => {
+                    let mut vals = Self::row_to_json(&row);
+                    cells::widen_binary(binary, &mut vals, |i| match row.cells().nth(i) {
+                        Some((_, ColumnData::Binary(Some(b)))) => Some(&b[..]),
+                        _ => None,
+                    });
+                    Ok(vals)
+                }
+                Ok(None) => break,
+                Err(e) => Err(e),
+            };
+            let failed = item.is_err();
+            if tx.send(item).await.is_err() {
+                return true;
+            }
+            if failed {
+                break;
+            }
+        }
+        false
+    }
+
+    /// Build a single-statement query with its params bound. Temporal and
+    /// decimal values go as nvarchar; SQL Server converts them implicitly to
+    /// the target type.
+    fn bind_query(sql: &str, params: &QueryParams) -> Result<Query<'static>, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let (stmt, values) = crate::sql::positional_params(&stmt, params, |i| format!("@P{}", i))?;
+        let mut query = Query::new(stmt);
+        for value in values {
+            match value {
+                QueryParam::Null => query.bind(Option::<String>::None),
+                QueryParam::Bool(b) => query.bind(*b),
+                QueryParam::Int(n) => query.bind(*n),
+                QueryParam::Float(f) => query.bind(*f),
+                QueryParam::Decimal(s)
+                | QueryParam::Text(s)
+                | QueryParam::Date(s)
+                | QueryParam::Time(s)
+                | QueryParam::DateTime(s) => query.bind(s.clone()),
+                QueryParam::Bytes(b64) => query.bind(crate::sql::decode_bytes_param(b64)?),
+                QueryParam::Json(v) => query.bind(v.to_string()),
+            }
+        }
+        Ok(query)
+    }
+
+    /// Map COLMETADATA to result metadata. tiberius only surfaces the TDS
+    /// type of each column (not its length, precision or origin), so the
+    /// variable-width `...n` types get their family name.
+    fn result_columns(cols: &[tiberius::Column]) -> Vec<ResultColumn> {
+        cols.iter()
+            .map(|col| {
+                let (native_type, category, fixed) = match col.column_type() {
+                    ColumnType::Null => ("null", TypeCategory::Other, false),
+                    ColumnType::Bit => ("bit", TypeCategory::Bool, true),
+                    ColumnType::Bitn => ("bit", TypeCategory::Bool, false),
+                    ColumnType::Int1 => ("tinyint", TypeCategory::Int, true),
+                    ColumnType::Int2 => ("smallint", TypeCategory::Int, true),
+                    ColumnType::Int4 => ("int", TypeCategory::Int, true),
+                    ColumnType::Int8 => ("bigint", TypeCategory::Int, true),
+                    ColumnType::Intn => ("int", TypeCategory::Int, false),
+                    ColumnType::Float4 => ("real", TypeCategory::Float, true),
+                    ColumnType::Float8 => ("float", TypeCategory::Float, true),
+                    ColumnType::Floatn => ("float", TypeCategory::Float, false),
+                    ColumnType::Money => ("money", TypeCategory::Decimal, true),
+                    ColumnType::Money4 => ("smallmoney", TypeCategory::Decimal, true),
+                    ColumnType::Decimaln => ("decimal", TypeCategory::Decimal, false),
+                    ColumnType::Numericn => ("numeric", TypeCategory::Decimal, false),
+                    ColumnType::Datetime4 => ("smalldatetime", TypeCategory::DateTime, true),
+                    ColumnType::Datetime => ("datetime", TypeCategory::DateTime, true),
+                    ColumnType::Datetimen => ("datetime", TypeCategory::DateTime, false),
+                    ColumnType::Datetime2 => ("datetime2", TypeCategory::DateTime, false),
+                    ColumnType::DatetimeOffsetn => ("datetimeoffset", TypeCategory::DateTime, false),
+                    ColumnType::Daten => ("date", TypeCategory::Date, false),
+                    ColumnType::Timen => ("time", TypeCategory::Time, false),
+                    ColumnType::Guid => ("uniqueidentifier", TypeCategory::Uuid, false),
+                    ColumnType::BigVarChar => ("varchar", TypeCategory::Text, false),
+                    ColumnType::BigChar => ("char", TypeCategory::Text, false),
+                    ColumnType::NVarchar => ("nvarchar", TypeCategory::Text, false),
+                    ColumnType::NChar => ("nchar", TypeCategory::Text, false),
+                    ColumnType::Text => ("text", TypeCategory::Text, false),
+                    ColumnType::NText => ("ntext", TypeCategory::Text, false),
+                    ColumnType::Xml => ("xml", TypeCategory::Text, false),
+                    ColumnType::BigVarBin => ("varbinary", TypeCategory::Bytes, false),
+                    ColumnType::BigBinary => ("binary", TypeCategory::Bytes, false),
+                    ColumnType::Image => ("image", TypeCategory::Bytes, false),
+                    ColumnType::Udt => ("udt", TypeCategory::Other, false),
+                    ColumnType::SSVariant => ("sql_variant", TypeCategory::Other, false),
+                };
+                let mut meta = columns::describe(col.name(), native_type, category);
+                // Fixed-length types are only sent for NOT NULL columns.
+                if fixed {
+                    meta.nullable = Some(false);
+                }
+                meta
+            })
+            .collect()
+    }
+
+    /// Convert a ColumnData value to a cell (see `drivers::cells`).
     fn col_to_json(cd: &ColumnData) -> serde_json::Value {
         match cd {
             ColumnData::U8(Some(n)) => serde_json::json!(n),
             ColumnData::I16(Some(n)) => serde_json::json!(n),
             ColumnData::I32(Some(n)) => serde_json::json!(n),
-            ColumnData::I64(Some(n)) => serde_json::json!(n),
-            ColumnData::F32(Some(f)) => serde_json::json!(f),
-            ColumnData::F64(Some(f)) => serde_json::json!(f),
+            ColumnData::I64(Some(n)) => cells::int(*n),
+            ColumnData::F32(Some(f)) => cells::float32(*f),
+            ColumnData::F64(Some(f)) => cells::float(*f),
             ColumnData::Bit(Some(b)) => serde_json::Value::Bool(*b),
             ColumnData::String(Some(s)) => serde_json::Value::String(s.to_string()),
-            ColumnData::Guid(Some(g)) => serde_json::Value::String(g.to_string()),
-            ColumnData::Binary(Some(b)) => {
-                serde_json::Value::String(format!("[blob {} bytes]", b.len()))
+            ColumnData::Guid(Some(g)) => cells::uuid(*g.as_bytes()),
+            ColumnData::Binary(Some(b)) => cells::binary_value(b),
+            ColumnData::Numeric(Some(n)) => {
+                cells::decimal(cells::scaled_decimal(n.value(), u32::from(n.scale())))
             }
-            ColumnData::Numeric(Some(n)) => serde_json::Value::String(n.to_string()),
             ColumnData::Xml(Some(x)) => serde_json::Value::String(x.to_string()),
             ColumnData::DateTime(Some(d)) => {
-                serde_json::Value::String(tiberius_dt_to_string(d.days(), d.seconds_fragments(), false))
+                // 1/300 s ticks, shown rounded to milliseconds (.000/.003/.007).
+                let (y, m, day) = cells::civil_from_days(i64::from(d.days()) + DATETIME_EPOCH_DAYS);
+                let millis = (u64::from(d.seconds_fragments()) * 10 + 1) / 3;
+                cells::datetime(y, m, day, TimeParts::from_units(millis, 3), None)
             }
             ColumnData::SmallDateTime(Some(d)) => {
-                serde_json::Value::String(tiberius_dt_to_string(d.days() as i32, d.seconds_fragments() as u32, true))
+                let (y, m, day) = cells::civil_from_days(i64::from(d.days()) + DATETIME_EPOCH_DAYS);
+                let minutes = u64::from(d.seconds_fragments());
+                cells::datetime(y, m, day, TimeParts::from_units(minutes * 60, 0), None)
+            }
+            ColumnData::Date(Some(d)) => {
+                let (y, m, day) = cells::civil_from_days(i64::from(d.days()) + DATE_EPOCH_DAYS);
+                cells::date(y, m, day)
+            }
+            ColumnData::Time(Some(t)) => {
+                cells::time(TimeParts::from_units(t.increments(), u32::from(t.scale())), None)
             }
+            ColumnData::DateTime2(Some(dt)) => datetime2_cell(*dt, None),
+            ColumnData::DateTimeOffset(Some(dto)) => datetime2_cell(dto.datetime2(), Some(dto.offset())),
             _ => serde_json::Value::Null,
         }
     }
 
+    /// MONEY and SMALLMONEY are exact to four places; tiberius hands them
+    /// over as f64, which is exact in that scale below about 9 * 10^11.
+    fn money_to_json(value: f64) -> serde_json::Value {
+        cells::decimal(cells::scaled_decimal((value * 1e4).round() as i128, 4))
+    }
+
     /// Convert a ColumnData value to an owned String (NULL -> None).
     fn col_to_str(cd: &ColumnData) -> Option<String> {
         match cd {
-            ColumnData::U8(Some(n)) => Some(n.to_string()),
-            ColumnData::I16(Some(n)) => Some(n.to_string()),
-            ColumnData::I32(Some(n)) => Some(n.to_string()),
-            ColumnData::I64(Some(n)) => Some(n.to_string()),
-            ColumnData::F32(Some(f)) => Some(f.to_string()),
-            ColumnData::F64(Some(f)) => Some(f.to_string()),
-            ColumnData::Bit(Some(b)) => Some(b.to_string()),
             ColumnData::String(Some(s)) => Some(s.to_string()),
-            ColumnData::Guid(Some(g)) => Some(g.to_string()),
-            ColumnData::Numeric(Some(n)) => Some(n.to_string()),
-            ColumnData::Xml(Some(x)) => Some(x.to_string()),
-            ColumnData::DateTime(Some(d)) => {
-                Some(tiberius_dt_to_string(d.days(), d.seconds_fragments(), false))
-            }
-            ColumnData::SmallDateTime(Some(d)) => {
-                Some(tiberius_dt_to_string(d.days() as i32, d.seconds_fragments() as u32, true))
-            }
-            _ => None,
+            ColumnData::Binary(_) => None,
+            cd => match Self::col_to_json(cd) {
+                serde_json::Value::Null => None,
+                cell => Some(cells::text_form(&cell)),
+            },
         }
     }
 
@@ -149,7 +654,12 @@ impl MssqlDriver {
 
     /// Convert a tiberius row to a Vec of serde_json::Value.
     fn row_to_json(row: &tiberius::Row) -> Vec<serde_json::Value> {
-        row.cells().map(|(_, cd)| Self::col_to_json(cd)).collect()
+        row.cells()
+            .map(|(col, cd)| match (col.column_type(), cd) {
+                (ColumnType::Money | ColumnType::Money4, ColumnData::F64(Some(f))) => Self::money_to_json(*f),
+                _ => Self::col_to_json(cd),
+            })
+            .collect()
     }
 }
 
@@ -160,11 +670,22 @@ impl DbDriver for MssqlDriver {
     }
 
     async fn connect(&self, params: &ConnectParams) -> Result<ConnectionHandle, AppError> {
-        let mut client = Self::connect_raw(params).await?;
+        let limits = PoolLimits::new(params.pool.as_ref())?;
+        // Checked here so a bad setting is reported rather than retried by the pool.
+        Self::build_config(params)?;
+        let pool = Pool::builder(TdsManager { params: params.clone(), limits: limits.clone() })
+            .config(limits.pool_config())
+            .runtime(deadpool::Runtime::Tokio1)
+            .build()
+            .map_err(|e| AppError::database(format!("Failed to create pool: {}", e)))?;
+        let mut client = pool.get().await.map_err(|e| Self::pool_error(e, "Connection failed"))?;
 
-        // Server version.
+        // Server version, and whether the server sees the connection as encrypted.
         let version_row = client
-            .query("SELECT @@VERSION", &[])
+            .query(
+                "SELECT @@VERSION, CONVERT(nvarchar(10), CONNECTIONPROPERTY('encrypt_option'))",
+                &[],
+            )
             .await
             .map_err(|e| AppError::database(format!("Version query failed: {}", e)))?
             .into_row()
@@ -174,16 +695,40 @@ impl DbDriver for MssqlDriver {
             .as_ref()
             .and_then(|r| Self::cell_str(r, 0))
             .unwrap_or_else(|| "unknown".to_string());
+        let mode = Self::ssl_mode(params);
+        let encrypted = version_row
+            .as_ref()
+            .and_then(|r| Self::cell_str(r, 1))
+            .is_some_and(|e| e.eq_ignore_ascii_case("TRUE"));
+        // tiberius keeps the TLS stream to itself, so protocol and cipher are unknown.
+        let tls = TlsInfo {
+            mode,
+            encrypted,
+            certificate_verified: encrypted && mode == SslMode::VerifyFull,
+            protocol: None,
+            cipher: None,
+        };
+
+        drop(client);
+        pool::spawn_reaper(pool.clone(), limits);
 
         {
-            let mut clients = self.clients.lock().await;
-            clients.insert(params.profile_id.clone(), client);
+            let mut pools = self.pools.lock().await;
+            if let Some(old) = pools.insert(params.profile_id.clone(), pool) {
+                drop(pools);
+                old.close();
+            }
         }
+        self.params
+            .lock()
+            .await
+            .insert(params.profile_id.clone(), params.clone());
 
         Ok(ConnectionHandle {
             profile_id: params.profile_id.clone(),
             db_type: "mssql".to_string(),
             server_version: version,
+            tls: Some(tls),
         })
     }
 
@@ -200,16 +745,20 @@ impl DbDriver for MssqlDriver {
                 self.end_session(&sid).await?;
             }
         }
-        let mut clients = self.clients.lock().await;
-        clients.remove(profile_id);
+        self.params.lock().await.remove(profile_id);
+        let pool = {
+            let mut pools = self.pools.lock().await;
+            pools.remove(profile_id)
+        };
+        if let Some(pool) = pool {
+            pool.close();
+        }
         Ok(())
     }
 
     async fn ping(&self, profile_id: &str) -> Result<(), AppError> {
-        let mut clients = self.clients.lock().await;
-        let client = clients
-            .get_mut(profile_id)
-            .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))?;
+        let pool = self.get_pool(profile_id).await?;
+        let mut client = pool.get().await.map_err(|e| Self::pool_error(e, "Ping failed"))?;
         client
             .execute("SELECT 1", &[])
             .await
@@ -217,46 +766,136 @@ impl DbDriver for MssqlDriver {
         Ok(())
     }
 
+    async fn pool_stats(&self, profile_id: &str) -> Result<PoolStats, AppError> {
+        let pool = self.get_pool(profile_id).await?;
+        let mut stats = pool::deadpool_stats(&pool, &pool.manager().limits, profile_id, DbType::Mssql);
+        stats.sessions = pool::session_count(&*self.sessions.lock().await, profile_id);
+        Ok(stats)
+    }
+
     async fn begin_session(&self, profile_id: &str) -> Result<SessionId, AppError> {
-        // Move the profile's client into the session slot.
-        let client = {
-            let mut clients = self.clients.lock().await;
-            clients
-                .remove(profile_id)
-                .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))?
-        };
+        let pool = self.get_pool(profile_id).await?;
+        let mut client = pool
+            .get()
+            .await
+            .map_err(|e| Self::pool_error(e, "Failed to acquire connection for session"))?;
+        let spid = Self::session_spid(&mut client).await?;
         let pinned: PinnedConn = Arc::new(Mutex::new(Some(client)));
         let session_id = format!("{}:{}", profile_id, uuid::Uuid::new_v4());
         {
             let mut sessions = self.sessions.lock().await;
             sessions.insert(session_id.clone(), pinned);
         }
+        self.cancels.lock().await.insert(
+            session_id.clone(),
+            Arc::new(SessionCancel {
+                spid: AtomicU32::new(spid),
+                cancel: Arc::new(QueryCancel::new()),
+            }),
+        );
         Ok(session_id)
     }
 
     async fn end_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.cursors.close_session(session_id).await;
+        self.cancels.lock().await.remove(session_id);
         let pinned = {
             let mut sessions = self.sessions.lock().await;
             sessions.remove(session_id)
         };
         if let Some(pinned) = pinned {
-            let client_opt = pinned.lock().await.take();
-            // Return the client to the profile's pool for reuse.
-            if let Some(client) = client_opt {
-                let profile_id = session_id.split(':').next().unwrap_or("");
-                let mut clients = self.clients.lock().await;
-                clients.insert(profile_id.to_string(), client);
+            // Dropping the client returns it to the pool.
+            drop(pinned.lock().await.take());
+        }
+        Ok(())
+    }
+
+    async fn is_session_broken(&self, session_id: &str) -> bool {
+        let Some(pinned) = self.sessions.lock().await.get(session_id).cloned() else {
+            return false;
+        };
+        let mut guard = pinned.lock().await;
+        match guard.as_mut() {
+            Some(client) => {
+                let replied = |e: &tiberius::error::Error| matches!(e, tiberius::error::Error::Server(_));
+                recovery::probe_lost(client.execute("SELECT 1", &[]), replied).await
+            }
+            None => true,
+        }
+    }
+
+    async fn recover_session(&self, session_id: &str) -> Result<(), AppError> {
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        self.cursors.close_session(session_id).await;
+        let ctl = self.get_cancel(session_id).await?;
+        let profile_id = session_id.split(':').next().unwrap_or(session_id);
+        let pool = self.get_pool(profile_id).await?;
+        // Idle connections may have died with the session's, and nothing
+        // checks them unless a health check is set: try each in turn.
+        let mut attempts = pool.status().max_size + 1;
+        let (client, spid) = loop {
+            let mut client = pool
+                .get()
+                .await
+                .map_err(|e| Self::pool_error(e, "Failed to reconnect session"))?;
+            match Self::session_spid(&mut client).await {
+                Ok(spid) => break (client, spid),
+                Err(e) => {
+                    drop(deadpool::managed::Object::take(client));
+                    attempts -= 1;
+                    if attempts == 0 {
+                        return Err(e);
+                    }
+                }
             }
+        };
+        ctl.spid.store(spid, Ordering::SeqCst);
+        let lost = pinned.lock().await.replace(client);
+        if let Some(lost) = lost {
+            // Taken out of the pool, so it is never handed out again.
+            drop(deadpool::managed::Object::take(lost));
         }
         Ok(())
     }
 
+    async fn switch_database(&self, _session_id: &str, _database: &str) -> Result<(), AppError> {
+        Err(AppError::validation("SQL Server switches database with USE"))
+    }
+
     async fn query(
         &self,
         session_id: &str,
         sql: &str,
-        _timeout_ms: Option<u64>,
+        timeout_ms: Option<u64>,
+    ) -> Result<Vec<QueryResultSet>, AppError> {
+        self.run_query(session_id, Query::new(sql), timeout_ms).await
+    }
+
+    async fn query_params(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
     ) -> Result<Vec<QueryResultSet>, AppError> {
+        let query = Self::bind_query(sql, params)?;
+        self.run_query(session_id, query, timeout_ms).await
+    }
+
+    async fn fetch_bytes(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        let query = Self::bind_query(sql, params)?;
         let pinned = {
             let sessions = self.sessions.lock().await;
             sessions
@@ -264,51 +903,42 @@ impl DbDriver for MssqlDriver {
                 .cloned()
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
-        let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let ctl = self.get_cancel(session_id).await?;
+        let params = self.get_params(session_id).await?;
 
-        // tiberius executes batches; multiple result sets come as a stream.
-        let mut stream = client
-            .query(sql, &[])
-            .await
-            .map_err(|e| AppError::database(format!("Query error: {}", e)))?;
+        let mut guard = pinned.lock().await;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
+        ctl.cancel.reset();
+        let kill = Self::kill_spid(params, ctl.spid.load(Ordering::SeqCst));
+        let watchdog = Watchdog::arm(timeout_ms, ctl.cancel.clone(), kill);
 
-        let mut results = Vec::new();
-        // Process the first result set. tiberius yields QueryItem::Metadata
-        // (column info) then QueryItem::Row (data rows).
-        let mut columns = Vec::new();
-        let mut result_rows = Vec::new();
-        while let Some(item) = stream.try_next().await.map_err(|e| AppError::database(format!("Row error: {}", e)))? {
-            match item {
-                QueryItem::Metadata(meta) => {
-                    if columns.is_empty() {
-                        columns = meta.columns().iter().map(|c| c.name().to_string()).collect();
-                    }
-                }
-                QueryItem::Row(row) => {
-                    if columns.is_empty() {
-                        columns = row.columns().iter().map(|c| c.name().to_string()).collect();
-                    }
-                    result_rows.push(Self::row_to_json(&row));
-                }
-            }
+        let outcome = tokio::select! {
+            res = async {
+                query.query(client).await?.into_row().await
+            } => res.map_err(|e| AppError::database(format!("Query error: {}", e))),
+            stop = ctl.cancel.stopped() => Err(stop),
+        };
+        drop(watchdog);
+        if let Err(stop) = ctl.cancel.check() {
+            Self::discard_client(&mut guard);
+            return Err(recovery::session_reset(stop));
         }
-        let count = result_rows.len();
-        results.push(QueryResultSet {
-            columns,
-            rows: result_rows,
-            affected_rows: count as u64,
-            info: format!("{} row(s) returned", count),
-        });
 
-        Ok(results)
+        let row = outcome?.ok_or_else(|| AppError::database("No row matched"))?;
+        let bytes = match row.cells().next() {
+            Some((_, ColumnData::Binary(b))) => b.as_ref().map(|b| b.to_vec()),
+            // Anything else is returned as its text form.
+            Some((_, cell)) => Self::col_to_str(cell).map(String::into_bytes),
+            None => None,
+        };
+        Ok(bytes)
     }
 
     async fn execute(
         &self,
         session_id: &str,
         sql: &str,
-        _timeout_ms: Option<u64>,
+        timeout_ms: Option<u64>,
     ) -> Result<u64, AppError> {
         let pinned = {
             let sessions = self.sessions.lock().await;
@@ -317,14 +947,124 @@ impl DbDriver for MssqlDriver {
                 .cloned()
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
+        let ctl = self.get_cancel(session_id).await?;
+        let params = self.get_params(session_id).await?;
+
         let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
+        ctl.cancel.reset();
+        let kill = Self::kill_spid(params, ctl.spid.load(Ordering::SeqCst));
+        let watchdog = Watchdog::arm(timeout_ms, ctl.cancel.clone(), kill);
 
-        client
-            .execute(sql, &[])
-            .await
-            .map_err(|e| AppError::database(format!("Execute error: {}", e)))?;
-        Ok(0)
+        let outcome = tokio::select! {
+            res = client.execute(sql, &[]) => {
+                res.map_err(|e| AppError::database(format!("Execute error: {}", e)))
+            }
+            stop = ctl.cancel.stopped() => Err(stop),
+        };
+        drop(watchdog);
+        if let Err(stop) = ctl.cancel.check() {
+            Self::discard_client(&mut guard);
+            return Err(recovery::session_reset(stop));
+        }
+        Ok(outcome?.total())
+    }
+
+    async fn open_cursor(
+        &self,
+        session_id: &str,
+        sql: &str,
+        binary: BinaryValues,
+        timeout_ms: Option<u64>,
+    ) -> Result<CursorInfo, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+
+        let ctl = self.get_cancel(session_id).await?;
+        let params = self.get_params(session_id).await?;
+
+        let (chans, pending) = cursor::channel();
+        tokio::spawn(async move {
+            let mut guard = pinned.lock_owned().await;
+            let Some(client) = guard.as_mut() else {
+                chans.fail(recovery::session_lost());
+                return;
+            };
+            ctl.cancel.reset();
+            let spid = ctl.spid.load(Ordering::SeqCst);
+            let kill = Self::kill_spid(params.clone(), spid);
+            let watchdog = Watchdog::arm(timeout_ms, ctl.cancel.clone(), kill);
+            let closed = Self::stream_first_result(client, &stmt, chans, binary, &ctl.cancel, watchdog).await;
+            if closed {
+                // Closed early: kill the statement rather than have tiberius
+                // flush its unread rows before the session's next request.
+                // That ends the session, which the next call recovers.
+                let _ = Self::kill_spid(params, spid).await;
+                Self::discard_client(&mut guard);
+            } else if ctl.cancel.is_stopped() {
+                // The receiving side reports the stop; the session's next
+                // call finds it without a connection and recovers it.
+                Self::discard_client(&mut guard);
+            }
+        });
+
+        let cursor = pending.ready(session_id).await?;
+        Ok(self.cursors.insert(cursor).await)
+    }
+
+    async fn fetch_cursor(&self, cursor_id: &str, max_rows: usize) -> Result<CursorPage, AppError> {
+        self.cursors.fetch(cursor_id, max_rows).await
+    }
+
+    async fn close_cursor(&self, cursor_id: &str) -> Result<(), AppError> {
+        self.cursors.close(cursor_id).await;
+        Ok(())
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<(), AppError> {
+        let ctl = self.get_cancel(session_id).await?;
+        let params = self.get_params(session_id).await?;
+
+        // Read the spid before notifying: the waiting call abandons the
+        // client and reconnects, which replaces it.
+        let spid = ctl.spid.load(Ordering::SeqCst);
+        ctl.cancel.request();
+        Self::kill_spid(params, spid).await
+    }
+
+    async fn explain(
+        &self,
+        session_id: &str,
+        sql: &str,
+        analyze: bool,
+        timeout_ms: Option<u64>,
+    ) -> Result<QueryPlan, AppError> {
+        // Both settings must be alone in their batch. STATISTICS XML runs
+        // the batch and adds a plan result after each statement's results.
+        let setting = if analyze { "STATISTICS XML" } else { "SHOWPLAN_XML" };
+        self.execute(session_id, &format!("SET {} ON", setting), timeout_ms).await?;
+        let results = self.query(session_id, sql, timeout_ms).await;
+        let reset = self.execute(session_id, &format!("SET {} OFF", setting), None).await;
+        let results = results?;
+        reset?;
+
+        let mut raw = Vec::new();
+        let mut nodes = Vec::new();
+        let plans = results
+            .iter()
+            .filter(|r| r.columns.len() == 1 && r.columns[0].contains("XML Showplan"));
+        for result in plans {
+            let xml = plan::plan_text(std::slice::from_ref(result))?;
+            nodes.extend(plan::from_showplan_xml(&xml)?);
+            raw.push(xml);
+        }
+        Ok(QueryPlan { analyzed: analyze, nodes, raw: raw.join("\n") })
     }
 
     async fn list_databases(&self, session_id: &str) -> Result<Vec<String>, AppError> {
@@ -336,7 +1076,7 @@ impl DbDriver for MssqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let mut rows = client
             .query("SELECT name FROM sys.databases WHERE database_id > 4 ORDER BY name", &[])
@@ -365,12 +1105,12 @@ impl DbDriver for MssqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let sql = format!(
-            "USE [{}]; SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES \
+            "USE {}; SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES \
              WHERE TABLE_TYPE IN ('BASE TABLE','VIEW') ORDER BY TABLE_NAME",
-            database.replace(']', "]]")
+            DbType::Mssql.dialect().quote_ident(database)
         );
         let mut rows = client
             .query(sql, &[])
@@ -404,14 +1144,14 @@ impl DbDriver for MssqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let sql = format!(
-            "USE [{}]; \
+            "USE {}; \
              SELECT COLUMN_NAME, DATA_TYPE, IS_NULLABLE, COLUMN_DEFAULT \
              FROM INFORMATION_SCHEMA.COLUMNS \
              WHERE TABLE_NAME = @P1 ORDER BY ORDINAL_POSITION",
-            database.replace(']', "]]")
+            DbType::Mssql.dialect().quote_ident(database)
         );
         let mut rows = client
             .query(sql, &[&table])
@@ -449,10 +1189,10 @@ impl DbDriver for MssqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let sql = format!(
-            "USE [{}]; \
+            "USE {}; \
              SELECT t.name, CASE WHEN t.type = 'V' THEN 'view' ELSE 'table' END AS ttype, \
                     SUM(p.rows) AS row_count \
              FROM sys.tables t \
@@ -461,7 +1201,7 @@ impl DbDriver for MssqlDriver {
              UNION ALL \
              SELECT v.name, 'view', 0 FROM sys.views v \
              ORDER BY t.name",
-            database.replace(']', "]]")
+            DbType::Mssql.dialect().quote_ident(database)
         );
         let mut rows = client
             .query(sql, &[])
@@ -497,7 +1237,7 @@ impl DbDriver for MssqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let mut guard = pinned.lock().await;
-        let client = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let mut rows = client
             .query(
@@ -1,26 +1,40 @@
 // MySQL/MariaDB driver. Implements `DbDriver` using `mysql_async` (rustls).
 //
 // Patterns ported from the legacy db.rs:
-//   - Pool construction (OptsBuilder, SslOpts, PoolConstraints(0,5), mTLS)
+//   - Pool construction (OptsBuilder, SslOpts, PoolConstraints, mTLS)
 //   - split_sql_statements + per-statement query
 //   - mysql_async::Value → serde_json::Value exhaustive conversion
 //   - information_schema introspection SQL (SHOW DATABASES, SHOW TABLES, etc.)
 //
 // New vs legacy: sessions (pinned connections) for cross-command affinity.
+//
+// Cancellation and timeouts: `KILL QUERY <thread id>` on a fresh side
+// connection (not the pool, which may be exhausted by pinned sessions).
 
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mysql_async::consts::{ColumnFlags, ColumnType};
 use mysql_async::prelude::*;
 use mysql_async::{
-    Opts, OptsBuilder, Pool, PoolConstraints, PoolOpts, SslOpts, ClientIdentity, Value,
+    Column, Opts, OptsBuilder, Params, Pool, PoolConstraints, PoolOpts, SslOpts, ClientIdentity, Value,
 };
 use tokio::sync::Mutex;
 
+use crate::drivers::cancel::{QueryCancel, Watchdog};
+use crate::drivers::{cells, columns, plan};
+use crate::drivers::cells::{BinaryValues, TimeParts};
+use crate::drivers::cursor::{self, CursorRegistry};
+use crate::drivers::pool::{self, PoolLimits};
+use crate::drivers::recovery;
 use crate::drivers::{DbType, DbDriver};
 use crate::models::{
-    ColumnInfo, ConnectParams, ConnectionHandle, DatabaseInfo, QueryResultSet, SessionId, TableInfo,
+    ColumnInfo, ConnectParams, ConnectionHandle, CursorInfo, CursorPage, DatabaseInfo, MessageSeverity,
+    PoolStats, QueryMessage, QueryParam, QueryParams, QueryPlan, QueryResultSet, ResultColumn, SessionId,
+    TableInfo, TypeCategory,
 };
 use crate::sql::split_sql_statements;
 use crate::AppError;
@@ -30,18 +44,55 @@ use crate::AppError;
 /// (Tauri commands are concurrent).
 type PinnedConn = Arc<Mutex<Option<mysql_async::Conn>>>;
 
+/// What `cancel` needs to kill a session's running statement without taking
+/// the session lock.
+struct SessionCancel {
+    thread_id: u32,
+    cancel: Arc<QueryCancel>,
+}
+
+/// A pending `KILL QUERY` for one session's thread.
+#[derive(Clone)]
+struct KillQuery {
+    opts: Opts,
+    thread_id: u32,
+}
+
+impl KillQuery {
+    async fn run(self) -> Result<(), AppError> {
+        let mut side = mysql_async::Conn::new(self.opts).await.map_err(|e| {
+            AppError::database(format!("Failed to open connection to cancel query: {}", e))
+        })?;
+        let killed = side.query_drop(format!("KILL QUERY {}", self.thread_id)).await;
+        let _ = side.disconnect().await;
+        killed.map_err(|e| AppError::database(format!("Failed to cancel query: {}", e)))
+    }
+}
+
 pub struct MysqlDriver {
     /// One pool per connected profile.
     pools: Mutex<HashMap<String, Pool>>,
+    /// Connection options per profile, for side connections (cancellation).
+    opts: Mutex<HashMap<String, Opts>>,
+    /// Pool settings per profile (acquire timeout, health check, stats).
+    limits: Mutex<HashMap<String, PoolLimits>>,
     /// Pinned connections per session id.
     sessions: Mutex<HashMap<String, PinnedConn>>,
+    /// Cancellation state per session id.
+    cancels: Mutex<HashMap<String, SessionCancel>>,
+    /// Open cursors (each holds its session's connection while streaming).
+    cursors: CursorRegistry,
 }
 
 impl MysqlDriver {
     pub fn new() -> Self {
         Self {
             pools: Mutex::new(HashMap::new()),
+            opts: Mutex::new(HashMap::new()),
+            limits: Mutex::new(HashMap::new()),
             sessions: Mutex::new(HashMap::new()),
+            cancels: Mutex::new(HashMap::new()),
+            cursors: CursorRegistry::new(),
         }
     }
 
@@ -53,8 +104,66 @@ impl MysqlDriver {
             .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))
     }
 
-    /// Build the mysql_async pool from connect params (SSL, mTLS, pool constraints).
-    fn build_pool(params: &ConnectParams) -> Result<Pool, AppError> {
+    async fn get_limits(&self, profile_id: &str) -> Result<PoolLimits, AppError> {
+        let limits = self.limits.lock().await;
+        limits
+            .get(profile_id)
+            .cloned()
+            .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))
+    }
+
+    /// Take a connection within the acquire timeout. With a health check
+    /// (or `verify`, which falls back to `SELECT 1`), an idle connection that
+    /// fails it is closed and the next one tried. `context` prefixes the error.
+    async fn get_conn(
+        &self,
+        profile_id: &str,
+        context: &str,
+        verify: bool,
+    ) -> Result<mysql_async::Conn, AppError> {
+        let pool = self.get_pool(profile_id).await?;
+        let limits = self.get_limits(profile_id).await?;
+        let failed = |e: mysql_async::Error| AppError::database(format!("{}: {}", context, e));
+        let Some(check) = limits.health_check.as_deref().or(verify.then_some("SELECT 1")) else {
+            return limits.acquire(pool.get_conn()).await?.map_err(failed);
+        };
+        // Each idle connection may be stale; past them the pool opens a new one.
+        let mut last = None;
+        for _ in 0..=limits.max {
+            let mut conn = limits.acquire(pool.get_conn()).await?.map_err(failed)?;
+            match conn.query_drop(check).await {
+                Ok(()) => return Ok(conn),
+                Err(e) => {
+                    last = Some(e);
+                    let _ = conn.disconnect().await;
+                }
+            }
+        }
+        let e = last.map(|e| e.to_string()).unwrap_or_default();
+        Err(AppError::database(format!("{}: health check failed: {}", context, e)))
+    }
+
+    /// A session's stop flag and the kill that stops its running statement.
+    async fn stop_target(&self, session_id: &str) -> Result<(Arc<QueryCancel>, KillQuery), AppError> {
+        let (thread_id, cancel) = {
+            let cancels = self.cancels.lock().await;
+            let entry = cancels
+                .get(session_id)
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?;
+            (entry.thread_id, entry.cancel.clone())
+        };
+        let profile_id = session_id.split(':').next().unwrap_or(session_id);
+        let opts = {
+            let opts = self.opts.lock().await;
+            opts.get(profile_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))?
+        };
+        Ok((cancel, KillQuery { opts, thread_id }))
+    }
+
+    /// Build the mysql_async options from connect params (SSL, mTLS, pool constraints).
+    fn build_opts(params: &ConnectParams, limits: &PoolLimits) -> Result<Opts, AppError> {
         let mut builder = OptsBuilder::default()
             .ip_or_hostname(params.host.clone())
             .tcp_port(params.port)
@@ -66,6 +175,10 @@ impl MysqlDriver {
                 builder = builder.db_name(Some(db.clone()));
             }
         }
+        if params.read_only {
+            // `setup` runs again whenever the pool resets a connection.
+            builder = builder.setup(vec!["SET SESSION TRANSACTION READ ONLY"]);
+        }
 
         if params.ssl {
             let mut ssl_opts = SslOpts::default();
@@ -100,41 +213,257 @@ impl MysqlDriver {
             builder = builder.ssl_opts(Some(ssl_opts));
         }
 
-        let pool_opts = PoolOpts::new().with_constraints(PoolConstraints::new(0, 5).unwrap());
+        let constraints = PoolConstraints::new(limits.min, limits.max)
+            .ok_or_else(|| AppError::validation("Invalid pool size"))?;
+        let mut pool_opts = PoolOpts::new()
+            .with_constraints(constraints)
+            .with_abs_conn_ttl(limits.max_lifetime);
+        if let Some(idle) = limits.idle_timeout {
+            pool_opts = pool_opts.with_inactive_connection_ttl(idle);
+        }
         builder = builder.pool_opts(Some(pool_opts));
 
-        let opts: Opts = builder.into();
-        Ok(Pool::new(opts))
+        Ok(builder.into())
+    }
+
+    /// The last statement's warnings and notes. Its OK/EOF packet carries
+    /// only a count; `SHOW WARNINGS` reads them when there are any.
+    async fn statement_warnings(
+        conn: &mut mysql_async::Conn,
+    ) -> Result<Vec<QueryMessage>, mysql_async::Error> {
+        if conn.get_warnings() == 0 {
+            return Ok(vec![]);
+        }
+        let rows: Vec<(String, u32, String)> = conn.query("SHOW WARNINGS").await?;
+        Ok(rows
+            .into_iter()
+            .map(|(level, code, text)| QueryMessage {
+                severity: match level.as_str() {
+                    "Error" => MessageSeverity::Error,
+                    "Warning" => MessageSeverity::Warning,
+                    _ => MessageSeverity::Notice,
+                },
+                code: Some(code.to_string()),
+                text,
+            })
+            .collect())
+    }
+
+    /// Build a result set from fetched rows; no columns means a command,
+    /// which reports the server's affected count instead.
+    fn result_set(columns: Vec<ResultColumn>, rows: &[mysql_async::Row], affected: u64) -> QueryResultSet {
+        if columns.is_empty() {
+            return QueryResultSet {
+                columns: vec![],
+                column_meta: vec![],
+                rows: vec![],
+                affected_rows: affected,
+                info: format!("{} row(s) affected", affected),
+                messages: vec![],
+            };
+        }
+
+        let mut result_rows = Vec::with_capacity(rows.len());
+        for row in rows {
+            let vals = (0..row.len()).map(|i| Self::value_to_json(&row[i], &columns[i])).collect();
+            result_rows.push(vals);
+        }
+
+        let count = result_rows.len();
+        QueryResultSet {
+            columns: columns::names(&columns),
+            column_meta: columns,
+            rows: result_rows,
+            affected_rows: count as u64,
+            info: format!("{} row(s) returned", count),
+            messages: vec![],
+        }
+    }
+
+    /// Map column definition packets to result metadata.
+    fn result_columns(columns: &[Column]) -> Vec<ResultColumn> {
+        columns.iter().map(Self::result_column).collect()
+    }
+
+    fn result_column(col: &Column) -> ResultColumn {
+        use ColumnType::*;
+
+        let flags = col.flags();
+        // Collation 63 is `binary`: BLOB/VARBINARY rather than TEXT/VARCHAR.
+        let binary = col.character_set() == 63;
+        let unsigned = flags.contains(ColumnFlags::UNSIGNED_FLAG);
+        let (name, category) = match col.column_type() {
+            MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => ("DECIMAL", TypeCategory::Decimal),
+            MYSQL_TYPE_TINY => ("TINYINT", TypeCategory::Int),
+            MYSQL_TYPE_SHORT => ("SMALLINT", TypeCategory::Int),
+            MYSQL_TYPE_INT24 => ("MEDIUMINT", TypeCategory::Int),
+            MYSQL_TYPE_LONG => ("INT", TypeCategory::Int),
+            MYSQL_TYPE_LONGLONG => ("BIGINT", TypeCategory::Int),
+            MYSQL_TYPE_YEAR => ("YEAR", TypeCategory::Int),
+            MYSQL_TYPE_FLOAT => ("FLOAT", TypeCategory::Float),
+            MYSQL_TYPE_DOUBLE => ("DOUBLE", TypeCategory::Float),
+            MYSQL_TYPE_DATE | MYSQL_TYPE_NEWDATE => ("DATE", TypeCategory::Date),
+            MYSQL_TYPE_TIME | MYSQL_TYPE_TIME2 => ("TIME", TypeCategory::Time),
+            MYSQL_TYPE_DATETIME | MYSQL_TYPE_DATETIME2 => ("DATETIME", TypeCategory::DateTime),
+            MYSQL_TYPE_TIMESTAMP | MYSQL_TYPE_TIMESTAMP2 => ("TIMESTAMP", TypeCategory::DateTime),
+            MYSQL_TYPE_JSON => ("JSON", TypeCategory::Json),
+            MYSQL_TYPE_BIT => ("BIT", TypeCategory::Bytes),
+            // ENUM and SET arrive as strings with a flag set.
+            _ if flags.contains(ColumnFlags::ENUM_FLAG) => ("ENUM", TypeCategory::Text),
+            _ if flags.contains(ColumnFlags::SET_FLAG) => ("SET", TypeCategory::Text),
+            MYSQL_TYPE_ENUM => ("ENUM", TypeCategory::Text),
+            MYSQL_TYPE_SET => ("SET", TypeCategory::Text),
+            MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING if binary => ("VARBINARY", TypeCategory::Bytes),
+            MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING => ("VARCHAR", TypeCategory::Text),
+            MYSQL_TYPE_STRING if binary => ("BINARY", TypeCategory::Bytes),
+            MYSQL_TYPE_STRING => ("CHAR", TypeCategory::Text),
+            MYSQL_TYPE_TINY_BLOB | MYSQL_TYPE_MEDIUM_BLOB | MYSQL_TYPE_LONG_BLOB | MYSQL_TYPE_BLOB => {
+                if binary {
+                    ("BLOB", TypeCategory::Bytes)
+                } else {
+                    ("TEXT", TypeCategory::Text)
+                }
+            }
+            MYSQL_TYPE_GEOMETRY => ("GEOMETRY", TypeCategory::Other),
+            MYSQL_TYPE_VECTOR => ("VECTOR", TypeCategory::Other),
+            _ => ("UNKNOWN", TypeCategory::Other),
+        };
+        let numeric = matches!(category, TypeCategory::Int | TypeCategory::Decimal | TypeCategory::Float);
+        let native_type = if unsigned && numeric {
+            format!("{} UNSIGNED", name)
+        } else {
+            name.to_string()
+        };
+
+        let mut meta = columns::describe(&col.name_str(), &native_type, category);
+        meta.nullable = Some(!flags.contains(ColumnFlags::NOT_NULL_FLAG));
+        let decimals = u32::from(col.decimals());
+        match category {
+            TypeCategory::Decimal => {
+                // The display length counts the sign and the decimal point.
+                let extra = u32::from(decimals > 0) + u32::from(!unsigned);
+                meta.precision = Some(col.column_length().saturating_sub(extra));
+                meta.scale = Some(decimals);
+            }
+            TypeCategory::Time | TypeCategory::DateTime => meta.scale = Some(decimals),
+            TypeCategory::Text | TypeCategory::Bytes => {
+                // Lengths are in bytes; text columns are reported per
+                // character of the connection charset.
+                let per_char = if binary { 1 } else { Self::charset_max_len(col.character_set()) };
+                meta.length = Some(u64::from(col.column_length()) / per_char);
+            }
+            _ => {}
+        }
+        meta.schema = columns::non_empty(&col.schema_str());
+        meta.table = columns::non_empty(&col.org_table_str());
+        meta.column = columns::non_empty(&col.org_name_str());
+        meta
+    }
+
+    /// Bytes per character of the common multi-byte charsets, by collation
+    /// id. Anything else is treated as single-byte.
+    fn charset_max_len(collation: u16) -> u64 {
+        match collation {
+            33 | 76 | 83 | 192..=215 | 223 => 3,
+            45 | 46 | 224..=247 | 255..=323 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Convert typed params to mysql_async params. Temporal, decimal and
+    /// JSON values go as strings; the server converts them to the column
+    /// type.
+    fn bind_params(params: &QueryParams) -> Result<Params, AppError> {
+        fn value(param: &QueryParam) -> Result<Value, AppError> {
+            Ok(match param {
+                QueryParam::Null => Value::NULL,
+                QueryParam::Bool(b) => Value::Int(i64::from(*b)),
+                QueryParam::Int(n) => Value::Int(*n),
+                QueryParam::Float(f) => Value::Double(*f),
+                QueryParam::Decimal(s)
+                | QueryParam::Text(s)
+                | QueryParam::Date(s)
+                | QueryParam::Time(s)
+                | QueryParam::DateTime(s) => Value::Bytes(s.clone().into_bytes()),
+                QueryParam::Bytes(b64) => Value::Bytes(crate::sql::decode_bytes_param(b64)?),
+                QueryParam::Json(v) => Value::Bytes(v.to_string().into_bytes()),
+            })
+        }
+        Ok(match params {
+            QueryParams::Positional(values) if values.is_empty() => Params::Empty,
+            QueryParams::Positional(values) => {
+                Params::Positional(values.iter().map(value).collect::<Result<_, _>>()?)
+            }
+            QueryParams::Named(values) => Params::Named(
+                values
+                    .iter()
+                    .map(|(name, v)| Ok((name.clone().into_bytes(), value(v)?)))
+                    .collect::<Result<_, AppError>>()?,
+            ),
+        })
     }
 
-    /// Convert a mysql_async::Value to a serde_json::Value. Ported from legacy
-    /// db.rs; added Bool and Decimal arms for newer mysql_async versions.
-    fn value_to_json(val: &Value) -> serde_json::Value {
+    /// Convert a mysql_async::Value to a cell (see `drivers::cells`). The
+    /// text protocol sends every value as `Value::Bytes`, so the column's
+    /// category decides how the text is read.
+    fn value_to_json(val: &Value, col: &ResultColumn) -> serde_json::Value {
         match val {
             Value::NULL => serde_json::Value::Null,
-            Value::Bytes(b) => match String::from_utf8(b.clone()) {
-                Ok(s) => serde_json::Value::String(s),
-                Err(_) => serde_json::Value::String(format!("[binary {} bytes]", b.len())),
+            Value::Bytes(b) if col.category == TypeCategory::Bytes => cells::binary_value(b),
+            Value::Bytes(b) => match std::str::from_utf8(b) {
+                Ok(s) => Self::text_to_json(s, col.category),
+                Err(_) => cells::binary_value(b),
             },
-            Value::Int(n) => serde_json::Value::Number(serde_json::Number::from(*n)),
-            Value::UInt(n) => serde_json::Value::Number(serde_json::Number::from(*n)),
-            Value::Float(f) => serde_json::Number::from_f64(*f as f64)
-                .map(serde_json::Value::Number)
-                .unwrap_or_else(|| serde_json::Value::String(f.to_string())),
-            Value::Double(f) => serde_json::Number::from_f64(*f)
-                .map(serde_json::Value::Number)
-                .unwrap_or_else(|| serde_json::Value::String(f.to_string())),
-            Value::Date(y, m, d, h, mi, s, _us) => serde_json::Value::String(format!(
-                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
-                y, m, d, h, mi, s
-            )),
-            Value::Time(neg, d, h, mi, s, _us) => {
+            Value::Int(n) => cells::int(*n),
+            Value::UInt(n) => cells::uint(*n),
+            Value::Float(f) => cells::float32(*f),
+            Value::Double(f) => cells::float(*f),
+            Value::Date(y, m, d, ..) if col.category == TypeCategory::Date => {
+                cells::date(i64::from(*y), u32::from(*m), u32::from(*d))
+            }
+            Value::Date(y, m, d, h, mi, s, us) => cells::datetime(
+                i64::from(*y),
+                u32::from(*m),
+                u32::from(*d),
+                TimeParts::micros(u32::from(*h), u32::from(*mi), u32::from(*s), *us),
+                None,
+            ),
+            Value::Time(neg, d, h, mi, s, us) => {
+                // TIME is also a duration: hours run past 24 and it can be negative.
                 let sign = if *neg { "-" } else { "" };
-                let total_hours = *d * 24 + u32::from(*h);
-                serde_json::Value::String(format!("{}{:02}:{:02}:{:02}", sign, total_hours, mi, s))
+                let hours = *d * 24 + u32::from(*h);
+                let text = format!(
+                    "{}{:02}:{:02}:{:02}{}",
+                    sign,
+                    hours,
+                    mi,
+                    s,
+                    cells::fraction(u64::from(*us), 6)
+                );
+                cells::temporal_text(TypeCategory::Time, text)
             }
         }
     }
+
+    /// A text-protocol value. Temporal text is already ISO-like; it gets a
+    /// `T` separator and loses the trailing zeros of its fraction.
+    fn text_to_json(s: &str, category: TypeCategory) -> serde_json::Value {
+        match category {
+            TypeCategory::Int => cells::int_text(s),
+            TypeCategory::Decimal => cells::decimal(s),
+            TypeCategory::Float => cells::float_text(s),
+            TypeCategory::Json => cells::json(s),
+            TypeCategory::Date | TypeCategory::Time | TypeCategory::DateTime => {
+                let text = match s.split_once('.') {
+                    Some((whole, frac)) if frac.bytes().all(|b| b == b'0') => whole.to_string(),
+                    Some(_) => s.trim_end_matches('0').to_string(),
+                    None => s.to_string(),
+                };
+                cells::temporal_text(category, text.replacen(' ', "T", 1))
+            }
+            _ => serde_json::Value::String(s.to_string()),
+        }
+    }
 }
 
 #[async_trait]
@@ -144,10 +473,12 @@ impl DbDriver for MysqlDriver {
     }
 
     async fn connect(&self, params: &ConnectParams) -> Result<ConnectionHandle, AppError> {
-        let pool = Self::build_pool(params)?;
+        let limits = PoolLimits::new(params.pool.as_ref())?;
+        let opts = Self::build_opts(params, &limits)?;
+        let pool = Pool::new(opts.clone());
 
         // Test the connection with SELECT 1.
-        let mut conn = pool.get_conn().await.map_err(|e| {
+        let mut conn = limits.acquire(pool.get_conn()).await?.map_err(|e| {
             AppError::database(format!("Connection failed: {}", e))
         })?;
         let version: String = conn
@@ -158,6 +489,8 @@ impl DbDriver for MysqlDriver {
         drop(conn);
 
         // Store the pool.
+        self.opts.lock().await.insert(params.profile_id.clone(), opts);
+        self.limits.lock().await.insert(params.profile_id.clone(), limits);
         {
             let mut pools = self.pools.lock().await;
             // If reconnecting, disconnect the old pool first.
@@ -171,6 +504,7 @@ impl DbDriver for MysqlDriver {
             profile_id: params.profile_id.clone(),
             db_type: "mysql".to_string(),
             server_version: version,
+            tls: None,
         })
     }
 
@@ -189,6 +523,8 @@ impl DbDriver for MysqlDriver {
             }
         }
         // Drop the pool.
+        self.opts.lock().await.remove(profile_id);
+        self.limits.lock().await.remove(profile_id);
         let pool = {
             let mut pools = self.pools.lock().await;
             pools.remove(profile_id)
@@ -200,22 +536,38 @@ impl DbDriver for MysqlDriver {
     }
 
     async fn ping(&self, profile_id: &str) -> Result<(), AppError> {
-        let pool = self.get_pool(profile_id).await?;
-        let mut conn = pool.get_conn().await.map_err(|e| {
-            AppError::database(format!("Ping failed: {}", e))
-        })?;
+        let mut conn = self.get_conn(profile_id, "Ping failed", false).await?;
         conn.query_drop("SELECT 1").await.map_err(|e| {
             AppError::database(format!("Ping query failed: {}", e))
         })?;
         Ok(())
     }
 
-    async fn begin_session(&self, profile_id: &str) -> Result<SessionId, AppError> {
+    async fn pool_stats(&self, profile_id: &str) -> Result<PoolStats, AppError> {
         let pool = self.get_pool(profile_id).await?;
-        let conn = pool.get_conn().await.map_err(|e| {
-            AppError::database(format!("Failed to acquire connection for session: {}", e))
-        })?;
+        let limits = self.get_limits(profile_id).await?;
+        let metrics = pool.metrics();
+        let mut stats = limits.stats(
+            profile_id,
+            DbType::Mysql,
+            metrics.connection_count.load(Ordering::Relaxed),
+            metrics.connections_in_pool.load(Ordering::Relaxed),
+            metrics.active_wait_requests.load(Ordering::Relaxed),
+        );
+        stats.sessions = pool::session_count(&*self.sessions.lock().await, profile_id);
+        Ok(stats)
+    }
+
+    async fn begin_session(&self, profile_id: &str) -> Result<SessionId, AppError> {
+        let conn = self.get_conn(profile_id, "Failed to acquire connection for session", false).await?;
         let session_id = format!("{}:{}", profile_id, uuid::Uuid::new_v4());
+        self.cancels.lock().await.insert(
+            session_id.clone(),
+            SessionCancel {
+                thread_id: conn.id(),
+                cancel: Arc::new(QueryCancel::new()),
+            },
+        );
         let pinned = Arc::new(Mutex::new(Some(conn)));
         let mut sessions = self.sessions.lock().await;
         sessions.insert(session_id.clone(), pinned);
@@ -223,6 +575,8 @@ impl DbDriver for MysqlDriver {
     }
 
     async fn end_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.cursors.close_session(session_id).await;
+        self.cancels.lock().await.remove(session_id);
         let pinned = {
             let mut sessions = self.sessions.lock().await;
             sessions.remove(session_id)
@@ -235,6 +589,49 @@ impl DbDriver for MysqlDriver {
         Ok(())
     }
 
+    async fn is_session_broken(&self, session_id: &str) -> bool {
+        let Some(pinned) = self.sessions.lock().await.get(session_id).cloned() else {
+            return false;
+        };
+        let mut guard = pinned.lock().await;
+        match guard.as_mut() {
+            Some(conn) => {
+                let replied = |e: &mysql_async::Error| matches!(e, mysql_async::Error::Server(_));
+                recovery::probe_lost(conn.ping(), replied).await
+            }
+            None => true,
+        }
+    }
+
+    async fn recover_session(&self, session_id: &str) -> Result<(), AppError> {
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        self.cursors.close_session(session_id).await;
+        let profile_id = session_id.split(':').next().unwrap_or(session_id);
+        // Idle pooled connections may have died with the session's.
+        let conn = self.get_conn(profile_id, "Failed to reconnect session", true).await?;
+        if let Some(entry) = self.cancels.lock().await.get_mut(session_id) {
+            entry.thread_id = conn.id();
+        }
+        let lost = pinned.lock().await.replace(conn);
+        if let Some(lost) = lost {
+            // Closed rather than dropped, which would return it to the pool.
+            tokio::spawn(async move {
+                let _ = lost.disconnect().await;
+            });
+        }
+        Ok(())
+    }
+
+    async fn switch_database(&self, _session_id: &str, _database: &str) -> Result<(), AppError> {
+        Err(AppError::validation("MySQL switches database with USE"))
+    }
+
     async fn query(
         &self,
         session_id: &str,
@@ -249,83 +646,121 @@ impl DbDriver for MysqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
 
+        let (cancel, kill) = self.stop_target(session_id).await?;
         let statements = split_sql_statements(sql);
         let mut results = Vec::new();
 
-        for stmt in &statements {
-            // Hold the session lock for the duration of this statement.
-            let mut session_guard = pinned.lock().await;
-            let conn = session_guard
-                .as_mut()
-                .ok_or_else(|| AppError::state("Session connection was lost"))?;
-
-            // Run the query with a timeout. The future borrows conn, which is
-            // fine because the timeout polls it within this scope.
-            let rows_result = {
-                let fut = conn.query::<mysql_async::Row, _>(stmt.as_str());
-                tokio::time::timeout(
-                    std::time::Duration::from_millis(
-                        crate::sql::normalized_query_timeout_ms(timeout_ms),
-                    ),
-                    fut,
-                )
-                .await
-            };
+        // Hold the session lock for the whole batch; the timeout starts once
+        // the connection is ours.
+        let mut session_guard = pinned.lock().await;
+        let conn = session_guard
+            .as_mut()
+            .ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), kill.run());
 
-            let rows = match rows_result {
-                Ok(Ok(rows)) => rows,
-                Ok(Err(e)) => return Err(AppError::database(format!("Query error [{}]: {}", stmt, e))),
-                Err(_) => {
-                    return Err(AppError::database(format!(
-                        "Query timed out [{}]",
-                        stmt,
-                    )));
-                }
+        for stmt in &statements {
+            cancel.check()?;
+            let query_err = |e: mysql_async::Error| {
+                cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e)))
             };
+            // Read the columns off the result rather than the first row, so
+            // an empty SELECT still describes its columns.
+            let mut result = conn.query_iter(stmt.as_str()).await.map_err(query_err)?;
+            let columns = Self::result_columns(result.columns_ref());
+            let rows = result.collect::<mysql_async::Row>().await.map_err(query_err)?;
+            let affected = result.affected_rows();
+            result.drop_result().await.map_err(query_err)?;
 
-            // conn is still borrowed from session_guard for affected_rows.
-            let conn = session_guard
-                .as_mut()
-                .ok_or_else(|| AppError::state("Session connection was lost"))?;
-
-            if rows.is_empty() {
-                let affected = conn.affected_rows();
-                results.push(QueryResultSet {
-                    columns: vec![],
-                    rows: vec![],
-                    affected_rows: affected,
-                    info: format!("{} row(s) affected", affected),
-                });
-            } else {
-                let columns: Vec<String> = rows[0]
-                    .columns_ref()
-                    .iter()
-                    .map(|c| c.name_str().to_string())
-                    .collect();
-
-                let mut result_rows = Vec::with_capacity(rows.len());
-                for row in &rows {
-                    let mut vals = Vec::with_capacity(columns.len());
-                    for i in 0..columns.len() {
-                        let raw: &Value = &row[i];
-                        vals.push(Self::value_to_json(raw));
-                    }
-                    result_rows.push(vals);
-                }
-
-                let count = result_rows.len();
-                results.push(QueryResultSet {
-                    columns,
-                    rows: result_rows,
-                    affected_rows: count as u64,
-                    info: format!("{} row(s) returned", count),
-                });
-            }
+            let mut set = Self::result_set(columns, &rows, affected);
+            set.messages = Self::statement_warnings(conn).await.map_err(query_err)?;
+            results.push(set);
         }
 
         Ok(results)
     }
 
+    async fn query_params(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Vec<QueryResultSet>, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let params = Self::bind_params(params)?;
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let (cancel, kill) = self.stop_target(session_id).await?;
+
+        let mut session_guard = pinned.lock().await;
+        let conn = session_guard
+            .as_mut()
+            .ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), kill.run());
+
+        // Binary protocol: the server prepares the statement and receives
+        // the values separately.
+        let query_err = |e: mysql_async::Error| {
+            cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e)))
+        };
+        let mut result = conn.exec_iter(stmt.as_str(), params).await.map_err(query_err)?;
+        let columns = Self::result_columns(result.columns_ref());
+        let rows = result.collect::<mysql_async::Row>().await.map_err(query_err)?;
+        let affected = result.affected_rows();
+        result.drop_result().await.map_err(query_err)?;
+        let mut set = Self::result_set(columns, &rows, affected);
+        set.messages = Self::statement_warnings(conn).await.map_err(query_err)?;
+        Ok(vec![set])
+    }
+
+    async fn fetch_bytes(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let params = Self::bind_params(params)?;
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let (cancel, kill) = self.stop_target(session_id).await?;
+
+        let mut session_guard = pinned.lock().await;
+        let conn = session_guard
+            .as_mut()
+            .ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), kill.run());
+
+        let row = conn
+            .exec_first::<mysql_async::Row, _, _>(stmt.as_str(), params)
+            .await
+            .map_err(|e| cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e))))?
+            .ok_or_else(|| AppError::database("No row matched"))?;
+        Ok(match row.as_ref(0) {
+            None | Some(Value::NULL) => None,
+            Some(Value::Bytes(b)) => Some(b.clone()),
+            // Anything else is returned as its text form.
+            Some(other) => {
+                let col = Self::result_column(&row.columns_ref()[0]);
+                Some(cells::text_form(&Self::value_to_json(other, &col)).into_bytes())
+            }
+        })
+    }
+
     async fn execute(
         &self,
         session_id: &str,
@@ -340,38 +775,153 @@ impl DbDriver for MysqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
 
+        let (cancel, kill) = self.stop_target(session_id).await?;
         let statements = split_sql_statements(sql);
         let mut total_affected: u64 = 0;
 
+        let mut session_guard = pinned.lock().await;
+        let conn = session_guard
+            .as_mut()
+            .ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), kill.run());
+
         for stmt in &statements {
-            let mut session_guard = pinned.lock().await;
-            let conn = session_guard
-                .as_mut()
-                .ok_or_else(|| AppError::state("Session connection was lost"))?;
-
-            let fut = conn.query_drop(stmt.as_str());
-            let result = tokio::time::timeout(
-                std::time::Duration::from_millis(
-                    crate::sql::normalized_query_timeout_ms(timeout_ms),
-                ),
-                fut,
-            )
-            .await;
+            cancel.check()?;
+            conn.query_drop(stmt.as_str()).await.map_err(|e| {
+                cancel.map_err(AppError::database(format!("Execute error [{}]: {}", stmt, e)))
+            })?;
+            total_affected += conn.affected_rows();
+        }
+
+        Ok(total_affected)
+    }
+
+    async fn open_cursor(
+        &self,
+        session_id: &str,
+        sql: &str,
+        binary: BinaryValues,
+        timeout_ms: Option<u64>,
+    ) -> Result<CursorInfo, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+
+        let (cancel, kill) = self.stop_target(session_id).await?;
 
-            match result {
-                Ok(Ok(())) => {
-                    total_affected += conn.affected_rows();
+        let (chans, pending) = cursor::channel();
+        tokio::spawn(async move {
+            // The owned guard keeps the session pinned to this cursor until
+            // the producer finishes.
+            let mut session_guard = pinned.lock_owned().await;
+            let Some(conn) = session_guard.as_mut() else {
+                chans.fail(recovery::session_lost());
+                return;
+            };
+            // The timeout covers the statement starting, not the paging.
+            cancel.reset();
+            let watchdog = Watchdog::arm(timeout_ms, cancel.clone(), kill.clone().run());
+            let mut result = match conn.query_iter(stmt.as_str()).await {
+                Ok(result) => result,
+                Err(e) => {
+                    chans.fail(
+                        cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e))),
+                    );
+                    return;
                 }
-                Ok(Err(e)) => {
-                    return Err(AppError::database(format!("Execute error [{}]: {}", stmt, e)));
+            };
+            let columns = Self::result_columns(result.columns_ref());
+            drop(watchdog);
+            let Some(tx) = chans.start(columns.clone()) else { return };
+
+            // Text-protocol rows are read off the socket one at a time.
+            let mut stream = match result.stream::<mysql_async::Row>().await {
+                Ok(Some(stream)) => stream,
+                Ok(None) => return,
+                Err(e) => {
+                    let err = cancel.map_err(AppError::database(format!("Row error: {}", e)));
+                    let _ = tx.send(Err(err)).await;
+                    return;
+                }
+            };
+            loop {
+                let item = match stream.try_next().await {
+                    Ok(Some(row)) => {
+                        let mut vals: Vec<_> =
+                            (0..row.len()).map(|i| Self::value_to_json(&row[i], &columns[i])).collect();
+                        cells::widen_binary(binary, &mut vals, |i| match &row[i] {
+                            Value::Bytes(b) => Some(b.as_slice()),
+                            _ => None,
+                        });
+                        Ok(vals)
+                    }
+                    Ok(None) => break,
+                    Err(e) => Err(cancel.map_err(AppError::database(format!("Row error: {}", e)))),
+                };
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() {
+                    // Closed early: kill the statement, or dropping the
+                    // stream leaves its unread rows to be drained by the
+                    // session's next query.
+                    let _ = kill.run().await;
+                    break;
                 }
-                Err(_) => {
-                    return Err(AppError::database(format!("Execute timed out [{}]", stmt)));
+                if failed {
+                    break;
                 }
             }
-        }
+        });
 
-        Ok(total_affected)
+        let cursor = pending.ready(session_id).await?;
+        Ok(self.cursors.insert(cursor).await)
+    }
+
+    async fn fetch_cursor(&self, cursor_id: &str, max_rows: usize) -> Result<CursorPage, AppError> {
+        self.cursors.fetch(cursor_id, max_rows).await
+    }
+
+    async fn close_cursor(&self, cursor_id: &str) -> Result<(), AppError> {
+        self.cursors.close(cursor_id).await;
+        Ok(())
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<(), AppError> {
+        let (cancel, kill) = self.stop_target(session_id).await?;
+        cancel.request();
+        kill.run().await
+    }
+
+    async fn explain(
+        &self,
+        session_id: &str,
+        sql: &str,
+        analyze: bool,
+        timeout_ms: Option<u64>,
+    ) -> Result<QueryPlan, AppError> {
+        let statement = plan::single_statement(DbType::Mysql, sql)?;
+        // MySQL's EXPLAIN ANALYZE only prints a text tree; MariaDB has JSON.
+        let tree = analyze && {
+            let version = self.query(session_id, "SELECT VERSION()", timeout_ms).await?;
+            !plan::plan_text(&version)?.contains("MariaDB")
+        };
+        let explain = match (analyze, tree) {
+            (false, _) => format!("EXPLAIN FORMAT=JSON {}", statement),
+            (true, false) => format!("ANALYZE FORMAT=JSON {}", statement),
+            (true, true) => format!("EXPLAIN ANALYZE {}", statement),
+        };
+        let raw = plan::plan_text(&self.query(session_id, &explain, timeout_ms).await?)?;
+        let nodes = if tree {
+            plan::from_mysql_tree(&raw)
+        } else {
+            plan::from_mysql_json(&raw)?
+        };
+        Ok(QueryPlan { analyzed: analyze, nodes, raw })
     }
 
     async fn list_databases(&self, session_id: &str) -> Result<Vec<String>, AppError> {
@@ -384,7 +934,7 @@ impl DbDriver for MysqlDriver {
         };
 
         let mut guard = pinned.lock().await;
-        let conn = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let conn = guard.as_mut().ok_or_else(recovery::session_lost)?;
         let rows: Vec<String> = conn.query("SHOW DATABASES").await?;
         Ok(rows)
     }
@@ -402,9 +952,9 @@ impl DbDriver for MysqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
 
-        let sql = format!("SHOW TABLES FROM `{}`", database);
+        let sql = format!("SHOW TABLES FROM {}", DbType::Mysql.dialect().quote_ident(database));
         let mut guard = pinned.lock().await;
-        let conn = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let conn = guard.as_mut().ok_or_else(recovery::session_lost)?;
         let rows: Vec<String> = conn.query(sql).await?;
         Ok(rows)
     }
@@ -423,9 +973,9 @@ impl DbDriver for MysqlDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
 
-        let sql = format!("SHOW COLUMNS FROM `{}`.`{}`", database, table);
+        let sql = format!("SHOW COLUMNS FROM {}", DbType::Mysql.dialect().qualify(Some(database), table));
         let mut guard = pinned.lock().await;
-        let conn = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let conn = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         // SHOW COLUMNS returns: Field, Type, Null, Key, Default, Extra
         let rows: Vec<(String, String, String, String, Option<String>, String)> =
@@ -474,7 +1024,7 @@ impl DbDriver for MysqlDriver {
         "#;
 
         let mut guard = pinned.lock().await;
-        let conn = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let conn = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let rows: Vec<(
             String,
@@ -531,7 +1081,7 @@ impl DbDriver for MysqlDriver {
         "#;
 
         let mut guard = pinned.lock().await;
-        let conn = guard.as_mut().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let conn = guard.as_mut().ok_or_else(recovery::session_lost)?;
 
         let rows: Vec<(String, i64, i64, i64, String, Option<String>)> = conn.query(query).await?;
 
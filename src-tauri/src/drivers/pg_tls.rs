@@ -0,0 +1,268 @@
+// TLS for the PostgreSQL driver (rustls), with libpq's sslmode semantics:
+//
+//   disable      plain TCP only
+//   prefer       TLS when the server offers it, plain otherwise
+//   require      TLS; the certificate is not checked
+//   verify-ca    TLS; the chain is checked against the roots, the host name is not
+//   verify-full  TLS; chain and host name are checked
+//
+// As in libpq, `prefer` and `require` check the chain like `verify-ca` when a
+// CA file is given. Roots are that CA file, or the bundled Mozilla roots.
+//
+// Without an explicit `ssl_mode` the legacy flags decide, read the way the
+// MySQL driver reads them: no `ssl` is disable, `ssl` alone is require, and
+// `ssl` with `ssl_reject_unauthorized` is verify-full. A PEM client
+// certificate and key enable certificate authentication.
+
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use tokio_postgres::config::SslMode as PgSslMode;
+use tokio_postgres_rustls::MakeRustlsConnect;
+
+use crate::models::{ConnectParams, SslMode};
+use crate::AppError;
+
+/// The sslmode a connection uses.
+pub fn ssl_mode(params: &ConnectParams) -> SslMode {
+    match params.ssl_mode {
+        Some(mode) => mode,
+        None if !params.ssl => SslMode::Disable,
+        None if params.ssl_reject_unauthorized => SslMode::VerifyFull,
+        None => SslMode::Require,
+    }
+}
+
+/// The sslmode to give tokio-postgres and the connector it negotiates TLS
+/// with. Certificate checks live in the connector, so the verify modes are
+/// `Require` on the tokio-postgres side.
+pub fn connector(params: &ConnectParams) -> Result<(PgSslMode, MakeRustlsConnect), AppError> {
+    let mode = ssl_mode(params);
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?;
+    if mode == SslMode::Disable {
+        let config = builder
+            .with_root_certificates(RootCertStore::empty())
+            .with_no_client_auth();
+        return Ok((PgSslMode::Disable, MakeRustlsConnect::new(config)));
+    }
+
+    let ca = non_empty(&params.ssl_ca_file);
+    let verifier: Arc<dyn ServerCertVerifier> = match mode {
+        SslMode::Prefer | SslMode::Require if ca.is_none() => Arc::new(AcceptAnyCert(provider.clone())),
+        SslMode::VerifyFull => chain_verifier(ca, provider)?,
+        _ => Arc::new(SkipHostName(chain_verifier(ca, provider)?)),
+    };
+    let builder = builder.dangerous().with_custom_certificate_verifier(verifier);
+
+    let cert = non_empty(&params.ssl_cert_file);
+    let key = non_empty(&params.ssl_key_file);
+    let config = match (cert, key) {
+        (Some(cert), Some(key)) => builder
+            .with_client_auth_cert(load_certs(cert, "client cert")?, load_key(key)?)
+            .map_err(tls_error)?,
+        (None, None) => builder.with_no_client_auth(),
+        _ => {
+            return Err(AppError::validation(
+                "SSL client cert and key must both be provided for mTLS",
+            ))
+        }
+    };
+
+    let pg_mode = if mode == SslMode::Prefer {
+        PgSslMode::Prefer
+    } else {
+        PgSslMode::Require
+    };
+    Ok((pg_mode, MakeRustlsConnect::new(config)))
+}
+
+fn non_empty(path: &Option<String>) -> Option<&str> {
+    path.as_deref().filter(|p| !p.is_empty())
+}
+
+fn tls_error(e: rustls::Error) -> AppError {
+    AppError::validation(format!("Invalid TLS configuration: {}", e))
+}
+
+fn load_certs(path: &str, what: &str) -> Result<Vec<CertificateDer<'static>>, AppError> {
+    if !std::path::Path::new(path).exists() {
+        return Err(AppError::validation(format!("SSL {} file not found: {}", what, path)));
+    }
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| AppError::validation(format!("Failed to read SSL {} file {}: {}", what, path, e)))?;
+    if certs.is_empty() {
+        return Err(AppError::validation(format!("No certificates in SSL {} file: {}", what, path)));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>, AppError> {
+    if !std::path::Path::new(path).exists() {
+        return Err(AppError::validation(format!("SSL key file not found: {}", path)));
+    }
+    PrivateKeyDer::from_pem_file(path)
+        .map_err(|e| AppError::validation(format!("Failed to read SSL key file {}: {}", path, e)))
+}
+
+/// Standard chain and host name verification against the CA file, or the
+/// bundled roots when there is none.
+fn chain_verifier(
+    ca: Option<&str>,
+    provider: Arc<CryptoProvider>,
+) -> Result<Arc<WebPkiServerVerifier>, AppError> {
+    let mut roots = RootCertStore::empty();
+    match ca {
+        Some(ca) => {
+            let (added, _) = roots.add_parsable_certificates(load_certs(ca, "CA")?);
+            if added == 0 {
+                return Err(AppError::validation(format!("No usable certificates in SSL CA file: {}", ca)));
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider)
+        .build()
+        .map_err(|e| AppError::validation(format!("Invalid TLS configuration: {}", e)))
+}
+
+/// verify-ca: the chain must verify; a host name mismatch is accepted.
+#[derive(Debug)]
+struct SkipHostName(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for SkipHostName {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        // The name is checked last, so a name error means the chain verified.
+        match self.0.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now) {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            other => other,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// prefer / require: any certificate is accepted, but the handshake
+/// signatures are still checked against it.
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(ssl: bool, reject_unauthorized: bool, ssl_mode: Option<SslMode>) -> ConnectParams {
+        let mut params: ConnectParams = serde_json::from_value(serde_json::json!({
+            "profileId": "p",
+            "host": "localhost",
+            "port": 5432,
+            "user": "postgres",
+        }))
+        .unwrap();
+        params.ssl = ssl;
+        params.ssl_reject_unauthorized = reject_unauthorized;
+        params.ssl_mode = ssl_mode;
+        params
+    }
+
+    #[test]
+    fn resolves_ssl_mode() {
+        assert_eq!(ssl_mode(&params(false, true, None)), SslMode::Disable);
+        assert_eq!(ssl_mode(&params(true, false, None)), SslMode::Require);
+        assert_eq!(ssl_mode(&params(true, true, None)), SslMode::VerifyFull);
+        assert_eq!(ssl_mode(&params(false, false, Some(SslMode::VerifyCa))), SslMode::VerifyCa);
+
+        let mode = |m| connector(&params(false, false, Some(m))).unwrap().0;
+        assert_eq!(mode(SslMode::Disable), PgSslMode::Disable);
+        assert_eq!(mode(SslMode::Prefer), PgSslMode::Prefer);
+        assert_eq!(mode(SslMode::VerifyFull), PgSslMode::Require);
+    }
+
+    #[test]
+    fn validates_certificate_files() {
+        let mut p = params(true, true, None);
+        p.ssl_ca_file = Some("/nonexistent/ca.pem".into());
+        assert!(connector(&p).is_err());
+
+        let mut p = params(true, false, None);
+        p.ssl_cert_file = Some("/nonexistent/client.pem".into());
+        assert!(connector(&p).is_err());
+
+        // Files are not read when TLS is off.
+        let mut p = params(false, false, None);
+        p.ssl_ca_file = Some("/nonexistent/ca.pem".into());
+        assert!(connector(&p).is_ok());
+    }
+}
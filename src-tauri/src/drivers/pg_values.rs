@@ -0,0 +1,478 @@
+// PostgreSQL binary-format values to cells (see `drivers::cells`).
+//
+// tokio-postgres asks for every result column in binary format, so values are
+// decoded from their wire representation by type rather than guessed through
+// Rust types: NUMERIC digits, microsecond timestamps, intervals, arrays of
+// any element type, ranges and multiranges, composites, domains and enums.
+// Epochs and layouts follow the server's `*_send` functions.
+//
+// TIMESTAMPTZ and TIMETZ carry an instant, not the session zone, so they are
+// written in UTC with a `+00:00` offset (TIMETZ keeps its own offset).
+// Extension types without a known layout (citext, hstore, PostGIS...) are
+// text when the bytes are valid UTF-8 and binary otherwise.
+
+use std::error::Error;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use tokio_postgres::types::{FromSql, Kind, Type};
+use tokio_postgres::Row;
+
+use crate::drivers::cells::{self, TimeParts};
+use crate::models::TypeCategory;
+
+/// Days from 1970-01-01 to PG's epoch, 2000-01-01.
+const PG_EPOCH_DAYS: i64 = 10_957;
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// The cell for column `idx` of a row.
+pub fn cell(row: &Row, idx: usize) -> serde_json::Value {
+    match row.try_get::<_, Option<Raw>>(idx) {
+        Ok(Some(Raw(value))) => value,
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// The wire bytes of column `idx`, whatever its type; `None` for NULL.
+pub fn raw_bytes(row: &Row, idx: usize) -> Option<&[u8]> {
+    row.try_get::<_, Option<RawBytes>>(idx).ok().flatten().map(|b| b.0)
+}
+
+struct RawBytes<'a>(&'a [u8]);
+
+impl<'a> FromSql<'a> for RawBytes<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawBytes(raw))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Any non-NULL value, decoded by its type.
+struct Raw(serde_json::Value);
+
+impl<'a> FromSql<'a> for Raw {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Raw(value(ty, raw)))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// A value of type `ty`. Bytes that do not match the type's layout are kept
+/// as binary rather than dropped.
+fn value(ty: &Type, raw: &[u8]) -> serde_json::Value {
+    decode(ty, raw).unwrap_or_else(|| cells::binary_value(raw))
+}
+
+fn decode(ty: &Type, raw: &[u8]) -> Option<serde_json::Value> {
+    match ty.kind() {
+        Kind::Array(member) => return array(member, raw),
+        Kind::Range(member) => return range(member, raw),
+        Kind::Multirange(member) => return multirange(member, raw),
+        Kind::Domain(base) => return decode(base, raw),
+        Kind::Enum(_) => return text(raw),
+        Kind::Composite(fields) => return composite(fields, raw),
+        Kind::Simple | Kind::Pseudo => {}
+        _ => return None,
+    }
+
+    let mut r = Reader(raw);
+    let value = match *ty {
+        Type::BOOL => serde_json::Value::Bool(r.u8()? != 0),
+        Type::INT2 => cells::int(i64::from(r.i16()?)),
+        Type::INT4 => cells::int(i64::from(r.i32()?)),
+        Type::INT8 => cells::int(r.i64()?),
+        Type::OID | Type::XID | Type::CID | Type::REGPROC | Type::REGPROCEDURE | Type::REGOPER
+        | Type::REGOPERATOR | Type::REGCLASS | Type::REGTYPE | Type::REGCONFIG | Type::REGDICTIONARY
+        | Type::REGNAMESPACE | Type::REGROLE => cells::uint(u64::from(r.u32()?)),
+        Type::XID8 => cells::uint(r.u64()?),
+        Type::FLOAT4 => cells::float32(f32::from_bits(r.u32()?)),
+        Type::FLOAT8 => cells::float(f64::from_bits(r.u64()?)),
+        Type::NUMERIC => cells::decimal(numeric(&mut r)?),
+        // Money is a count of cents in the server's locale; two places is
+        // every common lc_monetary.
+        Type::MONEY => cells::decimal(cells::scaled_decimal(i128::from(r.i64()?), 2)),
+        Type::CHAR => serde_json::Value::String(char::from(r.u8()?).to_string()),
+        Type::JSON => cells::json(std::str::from_utf8(raw).ok()?),
+        Type::JSONB => {
+            // A version byte (1) precedes the text.
+            if r.u8()? != 1 {
+                return None;
+            }
+            cells::json(std::str::from_utf8(r.0).ok()?)
+        }
+        Type::UUID => cells::uuid(raw.try_into().ok()?),
+        Type::BYTEA => cells::binary_value(raw),
+        Type::DATE => date(r.i32()?),
+        Type::TIME => cells::time(TimeParts::from_units(u64::try_from(r.i64()?).ok()?, 6), None),
+        Type::TIMETZ => {
+            let micros = u64::try_from(r.i64()?).ok()?;
+            // The zone is sent in seconds west of UTC.
+            let offset = -r.i32()?;
+            cells::time(TimeParts::from_units(micros, 6), Some(offset))
+        }
+        Type::TIMESTAMP => timestamp(r.i64()?, None),
+        Type::TIMESTAMPTZ => timestamp(r.i64()?, Some(0)),
+        Type::INTERVAL => {
+            let micros = r.i64()?;
+            let days = r.i32()?;
+            let months = r.i32()?;
+            cells::interval(months, days, micros)
+        }
+        Type::INET | Type::CIDR => serde_json::Value::String(inet(&mut r, *ty == Type::CIDR)?),
+        Type::MACADDR | Type::MACADDR8 => serde_json::Value::String(
+            raw.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":"),
+        ),
+        Type::BIT | Type::VARBIT => {
+            let bits = usize::try_from(r.i32()?).ok()?;
+            let bytes = r.0;
+            if bytes.len() * 8 < bits {
+                return None;
+            }
+            let text = (0..bits)
+                .map(|i| if bytes[i / 8] & (0x80 >> (i % 8)) != 0 { '1' } else { '0' })
+                .collect();
+            serde_json::Value::String(text)
+        }
+        _ => return text(raw),
+    };
+    Some(value)
+}
+
+/// Text-like types and unknown extension types.
+fn text(raw: &[u8]) -> Option<serde_json::Value> {
+    std::str::from_utf8(raw)
+        .ok()
+        .map(|s| serde_json::Value::String(s.to_string()))
+}
+
+/// `numeric_send`: digit count, weight of the first digit, sign, display
+/// scale, then base-10000 digits.
+fn numeric(r: &mut Reader) -> Option<String> {
+    let ndigits = usize::try_from(r.i16()?).ok()?;
+    let weight = i64::from(r.i16()?);
+    let sign = r.u16()?;
+    let dscale = usize::from(r.u16()?);
+    let digits = (0..ndigits).map(|_| r.i16()).collect::<Option<Vec<_>>>()?;
+    match sign {
+        0x0000 | 0x4000 => {}
+        0xC000 => return Some("NaN".into()),
+        0xD000 => return Some("Infinity".into()),
+        0xF000 => return Some("-Infinity".into()),
+        _ => return None,
+    }
+    let digit = |i: i64| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut text = String::new();
+    if sign == 0x4000 {
+        text.push('-');
+    }
+    if weight < 0 {
+        text.push('0');
+    } else {
+        text.push_str(&digit(0).to_string());
+        for i in 1..=weight {
+            text.push_str(&format!("{:04}", digit(i)));
+        }
+    }
+    if dscale > 0 {
+        let mut frac = String::with_capacity(dscale + 4);
+        let mut i = weight + 1;
+        while frac.len() < dscale {
+            frac.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+        frac.truncate(dscale);
+        text.push('.');
+        text.push_str(&frac);
+    }
+    Some(text)
+}
+
+fn date(days: i32) -> serde_json::Value {
+    match days {
+        i32::MAX => cells::temporal_text(TypeCategory::Date, "infinity"),
+        i32::MIN => cells::temporal_text(TypeCategory::Date, "-infinity"),
+        days => {
+            let (y, m, d) = cells::civil_from_days(i64::from(days) + PG_EPOCH_DAYS);
+            cells::date(y, m, d)
+        }
+    }
+}
+
+fn timestamp(micros: i64, offset: Option<i32>) -> serde_json::Value {
+    match micros {
+        i64::MAX => cells::temporal_text(TypeCategory::DateTime, "infinity"),
+        i64::MIN => cells::temporal_text(TypeCategory::DateTime, "-infinity"),
+        micros => {
+            let days = micros.div_euclid(MICROS_PER_DAY);
+            let (y, m, d) = cells::civil_from_days(days + PG_EPOCH_DAYS);
+            let time = TimeParts::from_units(micros.rem_euclid(MICROS_PER_DAY) as u64, 6);
+            cells::datetime(y, m, d, time, offset)
+        }
+    }
+}
+
+/// `inet_send`: family, prefix bits, cidr flag, address length, address.
+/// As in the server's output, an inet host address has no `/bits`.
+fn inet(r: &mut Reader, cidr: bool) -> Option<String> {
+    let family = r.u8()?;
+    let bits = r.u8()?;
+    let _is_cidr = r.u8()?;
+    let len = usize::from(r.u8()?);
+    let addr = r.take(len)?;
+    let (text, full) = match family {
+        2 => (Ipv4Addr::from(<[u8; 4]>::try_from(addr).ok()?).to_string(), 32),
+        3 => (Ipv6Addr::from(<[u8; 16]>::try_from(addr).ok()?).to_string(), 128),
+        _ => return None,
+    };
+    Some(if cidr || bits != full {
+        format!("{}/{}", text, bits)
+    } else {
+        text
+    })
+}
+
+/// `array_send`: dimensions, a has-nulls flag, the element type, each
+/// dimension's length and lower bound, then the elements in row-major order.
+fn array(member: &Type, raw: &[u8]) -> Option<serde_json::Value> {
+    let mut r = Reader(raw);
+    let ndim = usize::try_from(r.i32()?).ok()?;
+    let _has_nulls = r.i32()?;
+    let _element_oid = r.u32()?;
+    let mut dims = Vec::with_capacity(ndim);
+    for _ in 0..ndim {
+        dims.push(usize::try_from(r.i32()?).ok()?);
+        let _lower_bound = r.i32()?;
+    }
+    if dims.is_empty() {
+        return Some(cells::array(vec![]));
+    }
+    let mut elements = Vec::new();
+    for _ in 0..dims.iter().product::<usize>() {
+        elements.push(r.element(member)?);
+    }
+    Some(nest(&dims, &mut elements.into_iter()))
+}
+
+fn nest(dims: &[usize], elements: &mut impl Iterator<Item = serde_json::Value>) -> serde_json::Value {
+    let items = match dims {
+        [len] => elements.take(*len).collect(),
+        [len, rest @ ..] => (0..*len).map(|_| nest(rest, elements)).collect(),
+        [] => vec![],
+    };
+    cells::array(items)
+}
+
+/// `range_send`: a flags byte, then each finite bound length-prefixed.
+fn range(member: &Type, raw: &[u8]) -> Option<serde_json::Value> {
+    const EMPTY: u8 = 0x01;
+    const LB_INC: u8 = 0x02;
+    const UB_INC: u8 = 0x04;
+    const LB_INF: u8 = 0x08;
+    const UB_INF: u8 = 0x10;
+
+    let mut r = Reader(raw);
+    let flags = r.u8()?;
+    if flags & EMPTY != 0 {
+        return Some(cells::empty_range());
+    }
+    let lower = match flags & LB_INF {
+        0 => Some((r.element(member)?, flags & LB_INC != 0)),
+        _ => None,
+    };
+    let upper = match flags & UB_INF {
+        0 => Some((r.element(member)?, flags & UB_INC != 0)),
+        _ => None,
+    };
+    Some(cells::range(lower, upper))
+}
+
+/// `multirange_send`: a range count, then each range length-prefixed. A
+/// multirange is an array of its ranges.
+fn multirange(member: &Type, raw: &[u8]) -> Option<serde_json::Value> {
+    let mut r = Reader(raw);
+    let count = r.u32()?;
+    let mut ranges = Vec::new();
+    for _ in 0..count {
+        let len = usize::try_from(r.i32()?).ok()?;
+        ranges.push(range(member, r.take(len)?)?);
+    }
+    Some(cells::array(ranges))
+}
+
+/// `record_send`: a field count, then each field's type and value.
+fn composite(fields: &[tokio_postgres::types::Field], raw: &[u8]) -> Option<serde_json::Value> {
+    let mut r = Reader(raw);
+    let count = usize::try_from(r.i32()?).ok()?;
+    if count != fields.len() {
+        return None;
+    }
+    let mut items = Vec::with_capacity(count);
+    for field in fields {
+        let _oid = r.u32()?;
+        let value = r.element(field.type_())?;
+        items.push(serde_json::json!({ "name": field.name(), "value": value }));
+    }
+    Some(serde_json::json!({ "type": "record", "fields": items }))
+}
+
+/// A cursor over big-endian wire data.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.0.len() < n {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.array().map(i16::from_be_bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.array().map(i32::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.array().map(i64::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    /// A length-prefixed element; a length of -1 is NULL.
+    fn element(&mut self, ty: &Type) -> Option<serde_json::Value> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Some(serde_json::Value::Null);
+        }
+        Some(value(ty, self.take(len as usize)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeric_text(ndigits: i16, weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> String {
+        let mut raw = Vec::new();
+        for n in [ndigits, weight] {
+            raw.extend(n.to_be_bytes());
+        }
+        for n in [sign, dscale] {
+            raw.extend(n.to_be_bytes());
+        }
+        for d in digits {
+            raw.extend(d.to_be_bytes());
+        }
+        numeric(&mut Reader(&raw)).unwrap()
+    }
+
+    #[test]
+    fn decodes_numeric() {
+        // 12345678.9012, kept to display scale 6.
+        assert_eq!(numeric_text(4, 1, 0, 6, &[1234, 5678, 9012, 0]), "12345678.901200");
+        assert_eq!(numeric_text(1, -1, 0x4000, 4, &[5]), "-0.0005");
+        assert_eq!(numeric_text(1, 2, 0, 0, &[7]), "700000000");
+        assert_eq!(numeric_text(0, 0, 0, 2, &[]), "0.00");
+        assert_eq!(numeric_text(0, 0, 0xC000, 0, &[]), "NaN");
+    }
+
+    #[test]
+    fn decodes_temporal_values() {
+        let micros: i64 = 86_400_000_000 + 3_723_000_450;
+        let cell = value(&Type::TIMESTAMPTZ, &micros.to_be_bytes());
+        assert_eq!(cell["value"], "2000-01-02T01:02:03.00045+00:00");
+        let cell = value(&Type::TIMESTAMP, &(-1i64).to_be_bytes());
+        assert_eq!(cell["value"], "1999-12-31T23:59:59.999999");
+        assert_eq!(value(&Type::DATE, &(-730_486i32).to_be_bytes())["value"], "-0001-12-31");
+        assert_eq!(value(&Type::DATE, &i32::MAX.to_be_bytes())["value"], "infinity");
+
+        let mut timetz = 3_600_000_000i64.to_be_bytes().to_vec();
+        timetz.extend((-19_800i32).to_be_bytes());
+        assert_eq!(value(&Type::TIMETZ, &timetz)["value"], "01:00:00+05:30");
+
+        let mut interval = (-90_500_000i64).to_be_bytes().to_vec();
+        interval.extend(3i32.to_be_bytes());
+        interval.extend(14i32.to_be_bytes());
+        assert_eq!(value(&Type::INTERVAL, &interval)["value"], "P1Y2M3DT-1M-30.5S");
+    }
+
+    #[test]
+    fn decodes_arrays_and_ranges() {
+        // int4[][] {{1,NULL},{3,4}}
+        let mut raw = Vec::new();
+        for n in [2i32, 1, 23, 2, 1, 2, 1] {
+            raw.extend(n.to_be_bytes());
+        }
+        for v in [Some(1i32), None, Some(3), Some(4)] {
+            match v {
+                Some(v) => {
+                    raw.extend(4i32.to_be_bytes());
+                    raw.extend(v.to_be_bytes());
+                }
+                None => raw.extend((-1i32).to_be_bytes()),
+            }
+        }
+        let cell = value(&Type::INT4_ARRAY, &raw);
+        assert_eq!(cell["type"], "array");
+        assert_eq!(cell["items"][0]["items"], serde_json::json!([1, null]));
+        assert_eq!(cell["items"][1]["items"], serde_json::json!([3, 4]));
+
+        // int8range [9007199254740993,)
+        let mut raw = vec![0x02 | 0x10];
+        raw.extend(8i32.to_be_bytes());
+        raw.extend(9_007_199_254_740_993i64.to_be_bytes());
+        let cell = value(&Type::INT8_RANGE, &raw);
+        assert_eq!(cell["lower"], serde_json::json!({ "type": "bigint", "value": "9007199254740993" }));
+        assert_eq!(cell["upper"], serde_json::Value::Null);
+        assert_eq!(cell["lowerInclusive"], true);
+        assert_eq!(value(&Type::INT4_RANGE, &[0x01])["empty"], true);
+    }
+
+    #[test]
+    fn decodes_other_simple_types() {
+        let uuid = [
+            0x1b, 0x4e, 0x28, 0xba, 0x2f, 0xa1, 0x11, 0xd2, 0x88, 0x3f, 0x00, 0x16, 0xd3, 0xcc, 0xa4, 0x27,
+        ];
+        assert_eq!(value(&Type::UUID, &uuid)["value"], "1b4e28ba-2fa1-11d2-883f-0016d3cca427");
+        let jsonb = value(&Type::JSONB, b"\x01{\"n\": 12345678901234567890}");
+        assert_eq!(jsonb, cells::json("{\"n\": 12345678901234567890}"));
+        assert_eq!(value(&Type::INET, &[2, 32, 0, 4, 10, 0, 0, 1]), "10.0.0.1");
+        assert_eq!(value(&Type::CIDR, &[2, 8, 1, 4, 10, 0, 0, 0]), "10.0.0.0/8");
+        assert_eq!(value(&Type::VARBIT, &[0, 0, 0, 5, 0b1010_1000]), "10101");
+        assert_eq!(value(&Type::INT8, &i64::MAX.to_be_bytes())["type"], "bigint");
+        assert_eq!(value(&Type::INT4, &[0, 1])["type"], "bytes");
+    }
+}
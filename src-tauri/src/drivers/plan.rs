@@ -0,0 +1,663 @@
+// Execution plans, normalized into one `PlanNode` tree.
+//
+// Each driver asks its server for a plan in the richest machine-readable
+// form it has and hands the output to the parser here:
+//
+//   MySQL      EXPLAIN FORMAT=JSON; analyze: EXPLAIN ANALYZE (tree text)
+//   MariaDB    EXPLAIN FORMAT=JSON; analyze: ANALYZE FORMAT=JSON
+//   Postgres   EXPLAIN (FORMAT JSON[, ANALYZE, BUFFERS])
+//   SQLite     EXPLAIN QUERY PLAN rows (no costs or row estimates)
+//   MSSQL      SET SHOWPLAN_XML / SET STATISTICS XML showplan documents
+//
+// Properties without a normalized field go into `details` as text.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use crate::drivers::{cells, DbType};
+use crate::models::{PlanNode, QueryResultSet};
+use crate::{AppError, AppResult};
+
+/// The one statement of `sql`; plans are requested per statement.
+pub fn single_statement(db: DbType, sql: &str) -> AppResult<String> {
+    let mut statements = match db {
+        DbType::Postgres => crate::sql::split_postgres_statements(sql),
+        _ => crate::sql::split_sql_statements(sql),
+    };
+    if statements.len() != 1 {
+        return Err(AppError::validation("EXPLAIN takes exactly one statement"));
+    }
+    Ok(statements.remove(0))
+}
+
+/// The text of a plan cell (the first column of the first row).
+pub fn plan_text(results: &[QueryResultSet]) -> AppResult<String> {
+    let cell = results.first().and_then(|r| r.rows.first()).and_then(|row| row.first());
+    match cell {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(Value::Null) | None => Err(AppError::database("The server returned no plan")),
+        Some(other) => Ok(cells::text_form(other)),
+    }
+}
+
+/// A property as display text; nested objects are left out.
+fn detail_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        Value::Array(items) => {
+            let parts: Option<Vec<String>> = items
+                .iter()
+                .map(|v| match v {
+                    Value::Object(_) | Value::Array(_) => None,
+                    _ => detail_text(v),
+                })
+                .collect();
+            parts.map(|p| p.join(", "))
+        }
+        Value::Null | Value::Object(_) => None,
+    }
+}
+
+/// Scalar properties of `obj`, except the `mapped` ones.
+fn details(obj: &Map<String, Value>, mapped: &[&str]) -> BTreeMap<String, String> {
+    obj.iter()
+        .filter(|(k, _)| !mapped.contains(&k.as_str()))
+        .filter_map(|(k, v)| Some((k.clone(), detail_text(v)?)))
+        .collect()
+}
+
+/// A number sent as a JSON number or a numeric string.
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+//  ------ MySQL / MariaDB JSON
+
+/// Nodes of `EXPLAIN FORMAT=JSON` (MySQL) or `ANALYZE FORMAT=JSON` (MariaDB).
+pub fn from_mysql_json(json: &str) -> AppResult<Vec<PlanNode>> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| AppError::database(format!("Unreadable plan: {}", e)))?;
+    Ok(mysql_children(&value))
+}
+
+/// Operation nodes below `value`. Wrappers that are not operations
+/// themselves (subquery lists, union members) are looked through.
+fn mysql_children(value: &Value) -> Vec<PlanNode> {
+    match value {
+        Value::Object(obj) => mysql_object_children(obj),
+        Value::Array(items) => items.iter().flat_map(mysql_children).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn mysql_object_children(obj: &Map<String, Value>) -> Vec<PlanNode> {
+    let mut nodes = Vec::new();
+    for (key, v) in obj {
+        match (key.as_str(), v) {
+            ("nested_loop", Value::Array(items)) => nodes.push(PlanNode {
+                operation: "Nested loop".to_string(),
+                children: items.iter().flat_map(mysql_children).collect(),
+                ..Default::default()
+            }),
+            (_, Value::Object(child)) => match mysql_operation(key, child) {
+                Some(operation) => nodes.push(mysql_node(key, operation, child)),
+                None => nodes.extend(mysql_object_children(child)),
+            },
+            (_, Value::Array(_)) => nodes.extend(mysql_children(v)),
+            _ => {}
+        }
+    }
+    nodes
+}
+
+fn mysql_operation(key: &str, obj: &Map<String, Value>) -> Option<String> {
+    let label = match key {
+        "query_block" => "Query block",
+        "table" => {
+            let access = obj.get("access_type").and_then(Value::as_str).unwrap_or("");
+            return Some(
+                match access {
+                    "ALL" => "Table scan",
+                    "index" => "Index scan",
+                    "range" => "Index range scan",
+                    "ref" | "eq_ref" | "ref_or_null" => "Index lookup",
+                    "const" | "system" => "Constant lookup",
+                    "fulltext" => "Full-text lookup",
+                    "index_merge" => "Index merge",
+                    "unique_subquery" | "index_subquery" => "Subquery lookup",
+                    "" => "Table access",
+                    other => other,
+                }
+                .to_string(),
+            );
+        }
+        "ordering_operation" | "filesort" => "Sort",
+        "grouping_operation" => "Group",
+        "duplicates_removal" => "Distinct",
+        "windowing" => "Window",
+        "union_result" => "Union",
+        "materialized_from_subquery" => "Materialize",
+        "buffer_result" => "Buffer result",
+        "temporary_table" => "Temporary table",
+        "read_sorted_file" => "Read sorted file",
+        "block-nl-join" => "Block nested loop",
+        _ => return None,
+    };
+    Some(label.to_string())
+}
+
+fn mysql_node(key: &str, operation: String, obj: &Map<String, Value>) -> PlanNode {
+    let cost_info = obj.get("cost_info");
+    let cost = match key {
+        "query_block" => number(cost_info.and_then(|c| c.get("query_cost"))),
+        _ => number(cost_info.and_then(|c| c.get("prefix_cost"))),
+    }
+    .or_else(|| number(obj.get("cost")));
+    // MariaDB reports r_rows per loop.
+    let actual_rows = number(obj.get("r_rows")).map(|rows| rows * number(obj.get("r_loops")).unwrap_or(1.0));
+    PlanNode {
+        operation,
+        object: obj.get("table_name").and_then(Value::as_str).map(str::to_string),
+        estimated_rows: number(obj.get("rows_examined_per_scan")).or_else(|| number(obj.get("rows"))),
+        actual_rows,
+        cost,
+        time_ms: number(obj.get("r_total_time_ms")),
+        details: details(
+            obj,
+            &[
+                "table_name",
+                "rows_examined_per_scan",
+                "rows",
+                "r_rows",
+                "r_total_time_ms",
+                "cost",
+                "used_columns",
+            ],
+        ),
+        children: mysql_object_children(obj),
+    }
+}
+
+//  ------ MySQL EXPLAIN ANALYZE (tree text)
+
+/// Nodes of MySQL's `EXPLAIN ANALYZE`, one `-> ` line per operation,
+/// nested by indentation:
+/// `-> Table scan on t  (cost=0.35 rows=1) (actual time=0.02..0.03 rows=1 loops=1)`
+pub fn from_mysql_tree(text: &str) -> Vec<PlanNode> {
+    // (depth, node) in document order, folded into a tree from the end.
+    let mut stack: Vec<(usize, PlanNode)> = Vec::new();
+    let mut roots = Vec::new();
+    for line in text.lines() {
+        let Some(pos) = line.find("-> ") else { continue };
+        let depth = line[..pos].len();
+        let node = mysql_tree_node(&line[pos + 3..]);
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            let (_, done) = stack.pop().unwrap();
+            attach(&mut stack, &mut roots, done);
+        }
+        stack.push((depth, node));
+    }
+    while let Some((_, done)) = stack.pop() {
+        attach(&mut stack, &mut roots, done);
+    }
+    roots
+}
+
+fn attach(stack: &mut [(usize, PlanNode)], roots: &mut Vec<PlanNode>, node: PlanNode) {
+    match stack.last_mut() {
+        Some((_, parent)) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+fn mysql_tree_node(line: &str) -> PlanNode {
+    let (text, figures) = match line.find("  (") {
+        Some(i) => (&line[..i], &line[i..]),
+        None => (line, ""),
+    };
+    let mut node = PlanNode::default();
+    node.details.insert("description".to_string(), text.to_string());
+
+    // "Filter: (t.a > 1)" / "Index lookup on u using PRIMARY (id=t.a)"
+    let head = match text.split_once(": ") {
+        Some((op, _)) => op,
+        None => text,
+    };
+    match head.split_once(" on ") {
+        Some((op, rest)) => {
+            node.operation = op.to_string();
+            let object = rest.split_whitespace().next().unwrap_or(rest);
+            node.object = Some(object.to_string());
+            if let Some((_, index)) = rest.split_once(" using ") {
+                let index = index.split_whitespace().next().unwrap_or(index);
+                node.details.insert("index".to_string(), index.to_string());
+            }
+        }
+        None => node.operation = head.to_string(),
+    }
+
+    for group in figures.split('(').skip(1) {
+        let group = group.trim_end().trim_end_matches(')');
+        let actual = group.starts_with("actual ");
+        let mut rows = None;
+        let mut loops = None;
+        for field in group.split_whitespace() {
+            let Some((key, value)) = field.split_once('=') else { continue };
+            match (key, actual) {
+                ("cost", false) => node.cost = value.parse().ok(),
+                ("rows", false) => node.estimated_rows = value.parse().ok(),
+                ("rows", true) => rows = value.parse::<f64>().ok(),
+                ("loops", true) => loops = value.parse::<f64>().ok(),
+                ("time", true) => {
+                    let last = value.rsplit("..").next().unwrap_or(value);
+                    node.time_ms = last.parse().ok();
+                }
+                _ => {}
+            }
+        }
+        if actual {
+            let loops = loops.unwrap_or(1.0);
+            node.actual_rows = rows.map(|r| r * loops);
+            node.time_ms = node.time_ms.map(|t| t * loops);
+            node.details.insert("loops".to_string(), loops.to_string());
+        }
+    }
+    node
+}
+
+//  ------ PostgreSQL JSON
+
+/// Nodes of `EXPLAIN (FORMAT JSON)`, one root per statement.
+pub fn from_postgres_json(json: &str) -> AppResult<Vec<PlanNode>> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| AppError::database(format!("Unreadable plan: {}", e)))?;
+    let entries = value
+        .as_array()
+        .ok_or_else(|| AppError::database("Unexpected plan format"))?;
+    let mut roots = Vec::new();
+    for entry in entries {
+        let Some(plan) = entry.get("Plan").and_then(Value::as_object) else { continue };
+        let mut root = postgres_node(plan);
+        if let Some(obj) = entry.as_object() {
+            root.details.extend(details(obj, &["Plan"]));
+        }
+        roots.push(root);
+    }
+    Ok(roots)
+}
+
+fn postgres_node(plan: &Map<String, Value>) -> PlanNode {
+    let text = |key: &str| plan.get(key).and_then(Value::as_str);
+    let mut operation = text("Node Type").unwrap_or("Unknown").to_string();
+    // Named like the text format: "Hash Left Join", "Nested Loop Anti Join".
+    if let Some(join) = text("Join Type").filter(|j| *j != "Inner") {
+        operation = match operation.strip_suffix(" Join") {
+            Some(kind) => format!("{} {} Join", kind, join),
+            None => format!("{} {} Join", operation, join),
+        };
+    }
+    if plan.get("Parallel Aware").and_then(Value::as_bool) == Some(true) {
+        operation = format!("Parallel {}", operation);
+    }
+    let object = match (text("Schema"), text("Relation Name")) {
+        (Some(schema), Some(rel)) => Some(format!("{}.{}", schema, rel)),
+        (None, Some(rel)) => Some(rel.to_string()),
+        _ => text("Index Name")
+            .or_else(|| text("CTE Name"))
+            .or_else(|| text("Function Name"))
+            .map(str::to_string),
+    };
+    // Actual figures are per loop.
+    let loops = number(plan.get("Actual Loops")).unwrap_or(1.0);
+    PlanNode {
+        operation,
+        object,
+        estimated_rows: number(plan.get("Plan Rows")),
+        actual_rows: number(plan.get("Actual Rows")).map(|r| r * loops),
+        cost: number(plan.get("Total Cost")),
+        time_ms: number(plan.get("Actual Total Time")).map(|t| t * loops),
+        details: details(
+            plan,
+            &[
+                "Node Type",
+                "Schema",
+                "Relation Name",
+                "Plan Rows",
+                "Actual Rows",
+                "Total Cost",
+                "Actual Total Time",
+            ],
+        ),
+        children: plan
+            .get("Plans")
+            .and_then(Value::as_array)
+            .map(|plans| plans.iter().filter_map(Value::as_object).map(postgres_node).collect())
+            .unwrap_or_default(),
+    }
+}
+
+//  ------ SQLite EXPLAIN QUERY PLAN
+
+/// Nodes of `EXPLAIN QUERY PLAN` from its (id, parent, notused, detail) rows.
+pub fn from_sqlite_rows(rows: &[Vec<Value>]) -> Vec<PlanNode> {
+    let int = |v: Option<&Value>| number(v).map(|n| n as i64);
+    let entries: Vec<(i64, i64, String)> = rows
+        .iter()
+        .filter_map(|row| {
+            let detail = detail_text(row.get(3)?)?;
+            Some((int(row.first())?, int(row.get(1))?, detail))
+        })
+        .collect();
+    sqlite_children(&entries, 0)
+}
+
+fn sqlite_children(entries: &[(i64, i64, String)], parent: i64) -> Vec<PlanNode> {
+    entries
+        .iter()
+        .filter(|(id, p, _)| *p == parent && *id != parent)
+        .map(|(id, _, detail)| {
+            let mut node = sqlite_node(detail);
+            node.children = sqlite_children(entries, *id);
+            node
+        })
+        .collect()
+}
+
+/// "SEARCH t USING INDEX i (a=?)", "SCAN TABLE t" (before 3.36), or a
+/// free-form step such as "USE TEMP B-TREE FOR ORDER BY".
+fn sqlite_node(detail: &str) -> PlanNode {
+    let mut node = PlanNode::default();
+    node.details.insert("detail".to_string(), detail.to_string());
+    let mut words = detail.split_whitespace();
+    match words.next() {
+        Some(op @ ("SCAN" | "SEARCH")) => {
+            node.operation = if op == "SCAN" { "Scan" } else { "Search" }.to_string();
+            let object = match words.next() {
+                Some("TABLE") | Some("SUBQUERY") => words.next(),
+                other => other,
+            };
+            node.object = object.map(str::to_string);
+        }
+        _ => node.operation = detail.to_string(),
+    }
+    node
+}
+
+//  ------ SQL Server showplan XML
+
+/// Nodes of a showplan document: one per statement, with its operators
+/// (`RelOp`) below.
+pub fn from_showplan_xml(xml: &str) -> AppResult<Vec<PlanNode>> {
+    let doc = roxmltree::Document::parse(xml)
+        .map_err(|e| AppError::database(format!("Unreadable plan: {}", e)))?;
+    Ok(showplan_children(doc.root()))
+}
+
+/// Statements and operators directly below `node`, looking through the
+/// wrapper elements in between.
+fn showplan_children(node: roxmltree::Node) -> Vec<PlanNode> {
+    let mut nodes = Vec::new();
+    for child in node.children().filter(|c| c.is_element()) {
+        if child.tag_name().name() == "RelOp" {
+            nodes.push(showplan_operator(child));
+        } else if child.has_attribute("StatementText") {
+            nodes.push(showplan_statement(child));
+        } else {
+            nodes.extend(showplan_children(child));
+        }
+    }
+    nodes
+}
+
+fn showplan_statement(stmt: roxmltree::Node) -> PlanNode {
+    let attr = |name: &str| stmt.attribute(name);
+    let time_ms = stmt
+        .descendants()
+        .find(|n| n.tag_name().name() == "QueryTimeStats")
+        .and_then(|n| n.attribute("ElapsedTime"))
+        .and_then(|t| t.parse().ok());
+    let mut details: BTreeMap<String, String> = stmt
+        .attributes()
+        .filter(|a| !matches!(a.name(), "StatementType" | "StatementEstRows" | "StatementSubTreeCost"))
+        .map(|a| (a.name().to_string(), a.value().to_string()))
+        .collect();
+    if let Some(text) = details.remove("StatementText") {
+        details.insert("statement".to_string(), text.trim().to_string());
+    }
+    PlanNode {
+        operation: attr("StatementType").unwrap_or("Statement").to_string(),
+        object: None,
+        estimated_rows: attr("StatementEstRows").and_then(|v| v.parse().ok()),
+        actual_rows: None,
+        cost: attr("StatementSubTreeCost").and_then(|v| v.parse().ok()),
+        time_ms,
+        details,
+        children: showplan_children(stmt),
+    }
+}
+
+fn showplan_operator(op: roxmltree::Node) -> PlanNode {
+    let attr = |name: &str| op.attribute(name);
+    let mut details: BTreeMap<String, String> = op
+        .attributes()
+        .filter(|a| !matches!(a.name(), "PhysicalOp" | "EstimateRows" | "EstimatedTotalSubtreeCost"))
+        .map(|a| (a.name().to_string(), a.value().to_string()))
+        .collect();
+
+    // Runtime counters come per thread: rows add up, the slowest thread
+    // is the elapsed time.
+    let counters: Vec<roxmltree::Node> = op
+        .children()
+        .filter(|c| c.tag_name().name() == "RunTimeInformation")
+        .flat_map(|c| c.children())
+        .filter(|c| c.tag_name().name() == "RunTimeCountersPerThread")
+        .collect();
+    let sum = |name: &str| -> Option<f64> {
+        let values: Vec<f64> = counters
+            .iter()
+            .filter_map(|c| c.attribute(name)?.parse().ok())
+            .collect();
+        (!values.is_empty()).then(|| values.iter().sum())
+    };
+    let actual_rows = sum("ActualRows");
+    if let Some(executions) = sum("ActualExecutions") {
+        details.insert("ActualExecutions".to_string(), executions.to_string());
+    }
+    let time_ms = counters
+        .iter()
+        .filter_map(|c| c.attribute("ActualElapsedms")?.parse::<f64>().ok())
+        .reduce(f64::max);
+
+    let object = own_object(op).map(|obj| {
+        let part = |name: &str| obj.attribute(name);
+        if let Some(index) = part("Index") {
+            details.insert("Index".to_string(), index.to_string());
+        }
+        [part("Schema"), part("Table")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(".")
+    });
+
+    PlanNode {
+        operation: attr("PhysicalOp").unwrap_or("Unknown").to_string(),
+        object: object.filter(|o| !o.is_empty()),
+        estimated_rows: attr("EstimateRows").and_then(|v| v.parse().ok()),
+        actual_rows,
+        cost: attr("EstimatedTotalSubtreeCost").and_then(|v| v.parse().ok()),
+        time_ms,
+        details,
+        children: showplan_children(op),
+    }
+}
+
+/// The `Object` an operator reads, not one of its child operators'.
+fn own_object<'a, 'input>(op: roxmltree::Node<'a, 'input>) -> Option<roxmltree::Node<'a, 'input>> {
+    let mut pending: Vec<roxmltree::Node> = op.children().collect();
+    while let Some(node) = pending.pop() {
+        match node.tag_name().name() {
+            "Object" => return Some(node),
+            "RelOp" => {}
+            _ => pending.extend(node.children()),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mysql_json_plan() {
+        let json = r#"{"query_block": {"select_id": 1, "cost_info": {"query_cost": "1.20"},
+            "ordering_operation": {"using_filesort": true, "nested_loop": [
+                {"table": {"table_name": "o", "access_type": "ALL", "rows_examined_per_scan": 10,
+                    "cost_info": {"prefix_cost": "0.75"}, "used_columns": ["id"]}},
+                {"table": {"table_name": "c", "access_type": "eq_ref", "key": "PRIMARY",
+                    "rows_examined_per_scan": 1, "r_rows": 1.0, "r_loops": 10, "r_total_time_ms": 0.5}}
+            ]}}}"#;
+        let nodes = from_mysql_json(json).unwrap();
+        assert_eq!(nodes.len(), 1);
+        let block = &nodes[0];
+        assert_eq!(block.operation, "Query block");
+        assert_eq!(block.cost, Some(1.2));
+        assert_eq!(block.details["select_id"], "1");
+        let sort = &block.children[0];
+        assert_eq!(sort.operation, "Sort");
+        let nl = &sort.children[0];
+        assert_eq!(nl.operation, "Nested loop");
+        assert_eq!(nl.children.len(), 2);
+        assert_eq!(nl.children[0].operation, "Table scan");
+        assert_eq!(nl.children[0].object.as_deref(), Some("o"));
+        assert_eq!(nl.children[0].estimated_rows, Some(10.0));
+        assert_eq!(nl.children[0].cost, Some(0.75));
+        assert_eq!(nl.children[1].operation, "Index lookup");
+        assert_eq!(nl.children[1].actual_rows, Some(10.0));
+        assert_eq!(nl.children[1].time_ms, Some(0.5));
+        assert_eq!(nl.children[1].details["key"], "PRIMARY");
+    }
+
+    #[test]
+    fn parses_mysql_tree_plan() {
+        let text = "-> Nested loop inner join  (cost=0.70 rows=2) \
+                    (actual time=0.040..0.050 rows=2 loops=1)\n    \
+                    -> Table scan on t  (cost=0.35 rows=2) (actual time=0.020..0.025 rows=2 loops=1)\n    \
+                    -> Single-row index lookup on u using PRIMARY (id=t.a)  (cost=0.30 rows=1) \
+                    (actual time=0.005..0.010 rows=1 loops=2)\n";
+        let nodes = from_mysql_tree(text);
+        assert_eq!(nodes.len(), 1);
+        let join = &nodes[0];
+        assert_eq!(join.operation, "Nested loop inner join");
+        assert_eq!(join.cost, Some(0.7));
+        assert_eq!(join.estimated_rows, Some(2.0));
+        assert_eq!(join.actual_rows, Some(2.0));
+        assert_eq!(join.time_ms, Some(0.05));
+        assert_eq!(join.children.len(), 2);
+        let lookup = &join.children[1];
+        assert_eq!(lookup.operation, "Single-row index lookup");
+        assert_eq!(lookup.object.as_deref(), Some("u"));
+        assert_eq!(lookup.details["index"], "PRIMARY");
+        assert_eq!(lookup.actual_rows, Some(2.0));
+        assert_eq!(lookup.time_ms, Some(0.02));
+    }
+
+    #[test]
+    fn parses_postgres_json_plan() {
+        let plan = serde_json::json!([{
+            "Plan": {
+                "Node Type": "Hash Join", "Join Type": "Left", "Total Cost": 35.5, "Plan Rows": 100,
+                "Actual Rows": 90, "Actual Loops": 1, "Actual Total Time": 1.5,
+                "Hash Cond": "(o.customer_id = c.id)",
+                "Plans": [{
+                    "Node Type": "Seq Scan", "Relation Name": "orders", "Schema": "public",
+                    "Alias": "o", "Total Cost": 20.0, "Plan Rows": 100, "Actual Rows": 45,
+                    "Actual Loops": 2, "Actual Total Time": 0.25, "Shared Hit Blocks": 3
+                }]
+            },
+            "Planning Time": 0.1,
+            "Execution Time": 1.7
+        }]);
+        let nodes = from_postgres_json(&plan.to_string()).unwrap();
+        let join = &nodes[0];
+        assert_eq!(join.operation, "Hash Left Join");
+        assert_eq!(join.cost, Some(35.5));
+        assert_eq!(join.details["Hash Cond"], "(o.customer_id = c.id)");
+        assert_eq!(join.details["Execution Time"], "1.7");
+        let scan = &join.children[0];
+        assert_eq!(scan.operation, "Seq Scan");
+        assert_eq!(scan.object.as_deref(), Some("public.orders"));
+        assert_eq!(scan.actual_rows, Some(90.0));
+        assert_eq!(scan.time_ms, Some(0.5));
+        assert_eq!(scan.details["Shared Hit Blocks"], "3");
+    }
+
+    #[test]
+    fn parses_sqlite_plan_rows() {
+        let rows = vec![
+            vec![2.into(), 0.into(), 0.into(), "SCAN o".into()],
+            vec![5.into(), 0.into(), 0.into(), "SEARCH TABLE c USING INTEGER PRIMARY KEY (rowid=?)".into()],
+            vec![9.into(), 0.into(), 0.into(), "USE TEMP B-TREE FOR ORDER BY".into()],
+            vec![12.into(), 9.into(), 0.into(), "SCAN x".into()],
+        ];
+        let nodes = from_sqlite_rows(&rows);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!((nodes[0].operation.as_str(), nodes[0].object.as_deref()), ("Scan", Some("o")));
+        assert_eq!((nodes[1].operation.as_str(), nodes[1].object.as_deref()), ("Search", Some("c")));
+        assert_eq!(nodes[2].operation, "USE TEMP B-TREE FOR ORDER BY");
+        assert_eq!(nodes[2].children[0].object.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn parses_showplan_xml() {
+        let xml = r#"<ShowPlanXML xmlns="http://schemas.microsoft.com/sqlserver/2004/07/showplan">
+          <BatchSequence><Batch><Statements>
+            <StmtSimple StatementText="SELECT * FROM dbo.t WHERE id = 1" StatementType="SELECT"
+                StatementSubTreeCost="0.0032" StatementEstRows="1">
+              <QueryPlan>
+                <QueryTimeStats CpuTime="1" ElapsedTime="2" />
+                <RelOp NodeId="0" PhysicalOp="Nested Loops" LogicalOp="Inner Join" EstimateRows="1"
+                    EstimatedTotalSubtreeCost="0.0032">
+                  <RunTimeInformation>
+                    <RunTimeCountersPerThread Thread="0" ActualRows="1" ActualExecutions="1"
+                        ActualElapsedms="1" />
+                  </RunTimeInformation>
+                  <NestedLoops>
+                    <RelOp NodeId="1" PhysicalOp="Clustered Index Seek" LogicalOp="Clustered Index Seek"
+                        EstimateRows="1" EstimatedTotalSubtreeCost="0.0016">
+                      <IndexScan>
+                        <Object Database="[app]" Schema="[dbo]" Table="[t]" Index="[PK_t]" />
+                      </IndexScan>
+                    </RelOp>
+                  </NestedLoops>
+                </RelOp>
+              </QueryPlan>
+            </StmtSimple>
+          </Statements></Batch></BatchSequence>
+        </ShowPlanXML>"#;
+        let nodes = from_showplan_xml(xml).unwrap();
+        assert_eq!(nodes.len(), 1);
+        let stmt = &nodes[0];
+        assert_eq!(stmt.operation, "SELECT");
+        assert_eq!(stmt.cost, Some(0.0032));
+        assert_eq!(stmt.time_ms, Some(2.0));
+        assert_eq!(stmt.details["statement"], "SELECT * FROM dbo.t WHERE id = 1");
+        let join = &stmt.children[0];
+        assert_eq!(join.operation, "Nested Loops");
+        assert_eq!(join.object, None);
+        assert_eq!(join.actual_rows, Some(1.0));
+        assert_eq!(join.time_ms, Some(1.0));
+        let seek = &join.children[0];
+        assert_eq!(seek.operation, "Clustered Index Seek");
+        assert_eq!(seek.object.as_deref(), Some("[dbo].[t]"));
+        assert_eq!(seek.details["Index"], "[PK_t]");
+        assert_eq!(seek.actual_rows, None);
+    }
+}
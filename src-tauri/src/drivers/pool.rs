@@ -0,0 +1,234 @@
+// Connection pool settings shared by the pooled drivers.
+//
+// MySQL sizes its own pool (mysql_async); Postgres and SQL Server use a
+// deadpool with a driver-specific manager. `PoolSettings` maps onto them as:
+//
+//   minConnections     kept open (deadpool pools are topped up by a reaper)
+//   maxConnections     hard cap, default 5; sessions count against it
+//   idleTimeoutSecs    idle connections above the minimum are closed
+//   maxLifetimeSecs    connections this old are closed instead of reused
+//   acquireTimeoutMs   how long taking a connection may wait, including
+//                      opening a new one
+//   healthCheckQuery   run on an idle connection before it is handed out;
+//                      a connection that fails it is discarded
+//
+// SQLite keeps a single connection per profile and ignores them.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use deadpool::managed::{Manager, Metrics, Object, Pool, PoolConfig, Timeouts};
+
+use crate::drivers::DbType;
+use crate::models::{PoolSettings, PoolStats};
+use crate::{AppError, AppResult};
+
+pub const DEFAULT_MAX_CONNECTIONS: usize = 5;
+
+/// How often expired idle connections are closed.
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Validated pool settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolLimits {
+    pub min: usize,
+    pub max: usize,
+    pub idle_timeout: Option<Duration>,
+    pub max_lifetime: Option<Duration>,
+    pub acquire_timeout: Option<Duration>,
+    pub health_check: Option<String>,
+}
+
+impl PoolLimits {
+    pub fn new(settings: Option<&PoolSettings>) -> AppResult<Self> {
+        let default = PoolSettings::default();
+        let s = settings.unwrap_or(&default);
+        let max = s.max_connections.map_or(DEFAULT_MAX_CONNECTIONS, |n| n as usize);
+        let min = s.min_connections.unwrap_or(0) as usize;
+        if max == 0 {
+            return Err(AppError::validation("maxConnections must be at least 1"));
+        }
+        if min > max {
+            return Err(AppError::validation(format!(
+                "minConnections ({}) is greater than maxConnections ({})",
+                min, max
+            )));
+        }
+        let health_check = match s.health_check_query.as_deref().map(str::trim) {
+            Some(q) if !q.is_empty() => {
+                let mut statements = crate::sql::split_sql_statements(q);
+                if statements.len() != 1 {
+                    return Err(AppError::validation("healthCheckQuery must be a single statement"));
+                }
+                Some(statements.remove(0))
+            }
+            _ => None,
+        };
+        let secs = |v: Option<u64>| v.filter(|n| *n > 0).map(Duration::from_secs);
+        Ok(Self {
+            min,
+            max,
+            idle_timeout: secs(s.idle_timeout_secs),
+            max_lifetime: secs(s.max_lifetime_secs),
+            acquire_timeout: s.acquire_timeout_ms.filter(|n| *n > 0).map(Duration::from_millis),
+            health_check,
+        })
+    }
+
+    /// A connection past its max lifetime; it is closed rather than reused.
+    pub fn too_old(&self, metrics: &Metrics) -> bool {
+        self.max_lifetime.is_some_and(|t| metrics.age() >= t)
+    }
+
+    fn idle_too_long(&self, metrics: &Metrics) -> bool {
+        self.idle_timeout.is_some_and(|t| metrics.last_used() >= t)
+    }
+
+    /// The deadpool config: the cap, with the acquire timeout bounding both
+    /// the wait for a slot and opening a connection.
+    pub fn pool_config(&self) -> PoolConfig {
+        let mut config = PoolConfig::new(self.max);
+        config.timeouts = Timeouts {
+            wait: self.acquire_timeout,
+            create: self.acquire_timeout,
+            recycle: None,
+        };
+        config
+    }
+
+    /// Bound taking a connection by the acquire timeout. The pool's own
+    /// result is passed through for the caller to report.
+    pub async fn acquire<T>(&self, get: impl std::future::Future<Output = T>) -> AppResult<T> {
+        match self.acquire_timeout {
+            Some(limit) => tokio::time::timeout(limit, get).await.map_err(|_| {
+                AppError::database(format!("Timed out after {} ms waiting for a connection", limit.as_millis()))
+            }),
+            None => Ok(get.await),
+        }
+    }
+
+    /// Statistics of a pool holding `open` connections, `idle` of them free.
+    pub fn stats(&self, profile_id: &str, db: DbType, open: usize, idle: usize, waiting: usize) -> PoolStats {
+        PoolStats {
+            profile_id: profile_id.to_string(),
+            db_type: db.as_str().to_string(),
+            min_connections: self.min as u32,
+            max_connections: self.max as u32,
+            open: open as u32,
+            in_use: open.saturating_sub(idle) as u32,
+            idle: idle as u32,
+            waiting: waiting as u32,
+            sessions: 0,
+        }
+    }
+}
+
+/// Statistics of a deadpool.
+pub fn deadpool_stats<M: Manager>(
+    pool: &Pool<M>,
+    limits: &PoolLimits,
+    profile_id: &str,
+    db: DbType,
+) -> PoolStats {
+    let status = pool.status();
+    limits.stats(profile_id, db, status.size, status.available, status.waiting)
+}
+
+/// Sessions of a profile, in a driver's map keyed by session id.
+pub fn session_count<V>(sessions: &HashMap<String, V>, profile_id: &str) -> u32 {
+    let prefix = format!("{}:", profile_id);
+    sessions.keys().filter(|k| k.starts_with(&prefix)).count() as u32
+}
+
+/// Keep a deadpool at its minimum and close expired idle connections, until
+/// the pool is closed. Does nothing when no setting needs it.
+pub fn spawn_reaper<M>(pool: Pool<M>, limits: PoolLimits)
+where
+    M: Manager + 'static,
+    M::Type: Send,
+    M::Error: Send,
+{
+    if limits.min == 0 && limits.idle_timeout.is_none() && limits.max_lifetime.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(REAP_INTERVAL);
+        loop {
+            tick.tick().await;
+            if pool.is_closed() {
+                break;
+            }
+            reap(&pool, &limits);
+            fill(&pool, limits.min).await;
+        }
+    });
+}
+
+/// Close idle connections past their lifetime, and those idle too long
+/// while the pool holds more than its minimum.
+fn reap<M: Manager>(pool: &Pool<M>, limits: &PoolLimits) {
+    let status = pool.status();
+    let kept = AtomicUsize::new(status.size - status.available);
+    pool.retain(|_, metrics| {
+        let keep = !limits.too_old(&metrics)
+            && (!limits.idle_too_long(&metrics) || kept.load(Ordering::Relaxed) < limits.min);
+        if keep {
+            kept.fetch_add(1, Ordering::Relaxed);
+        }
+        keep
+    });
+}
+
+/// Open connections until the pool holds `min`. Idle ones are taken first,
+/// so new ones are only opened for the shortfall.
+pub async fn fill<M: Manager>(pool: &Pool<M>, min: usize) {
+    let mut held: Vec<Object<M>> = Vec::new();
+    while pool.status().size < min {
+        match pool.get().await {
+            Ok(conn) => held.push(conn),
+            Err(_) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_settings() {
+        let limits = PoolLimits::new(None).unwrap();
+        assert_eq!((limits.min, limits.max), (0, DEFAULT_MAX_CONNECTIONS));
+        assert_eq!(limits.acquire_timeout, None);
+
+        let settings = PoolSettings {
+            min_connections: Some(2),
+            max_connections: Some(8),
+            idle_timeout_secs: Some(0),
+            acquire_timeout_ms: Some(1500),
+            health_check_query: Some("  SELECT 1;  ".into()),
+            ..Default::default()
+        };
+        let limits = PoolLimits::new(Some(&settings)).unwrap();
+        assert_eq!((limits.min, limits.max), (2, 8));
+        assert_eq!(limits.idle_timeout, None);
+        assert_eq!(limits.acquire_timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(limits.health_check.as_deref(), Some("SELECT 1"));
+
+        let bad = |s: PoolSettings| PoolLimits::new(Some(&s)).unwrap_err().kind;
+        assert_eq!(bad(PoolSettings { max_connections: Some(0), ..Default::default() }), "validation");
+        assert_eq!(bad(PoolSettings { min_connections: Some(6), ..Default::default() }), "validation");
+        let two = PoolSettings { health_check_query: Some("SELECT 1; SELECT 2".into()), ..Default::default() };
+        assert_eq!(bad(two), "validation");
+    }
+
+    #[tokio::test]
+    async fn acquire_times_out() {
+        let settings = PoolSettings { acquire_timeout_ms: Some(10), ..Default::default() };
+        let limits = PoolLimits::new(Some(&settings)).unwrap();
+        let never = std::future::pending::<()>();
+        assert_eq!(limits.acquire(never).await.unwrap_err().kind, "database");
+        assert_eq!(limits.acquire(async { 7 }).await.unwrap(), 7);
+    }
+}
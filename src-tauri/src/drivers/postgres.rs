@@ -7,34 +7,217 @@
 // with MySQL we expose the connected database as a single "database" and list
 // schemas/tables under it. Here `list_databases` returns the current DB name,
 // and `list_tables` lists relations in the `public` (or given) schema.
+//
+// Cancellation and timeouts use the protocol's CancelRequest
+// (`Client::cancel_token`), which the server honours from a separate socket.
+//
+// The pool uses its own connection manager (`PgManager`) so NOTICE/WARNING
+// messages reach the results of the statements that raised them.
+//
+// Pool sizing and ageing come from the profile's `PoolSettings` (see `pool`);
+// `recycle` runs the health check before an idle connection is reused.
+//
+// TLS (rustls, libpq sslmode semantics) is set up in `pg_tls`; cancel
+// requests go through the same connector as the pooled connections.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use deadpool_postgres::{Config as PgConfig, Pool, Runtime};
+use deadpool_postgres::{Config as PgConfig, Runtime};
+use futures_util::{StreamExt, TryStreamExt};
 use tokio::sync::Mutex;
-use tokio_postgres::types::Type;
-use tokio_postgres::{NoTls, Row};
+use tokio_postgres::types::private::BytesMut;
+use tokio_postgres::types::{to_sql_checked, Format, IsNull, Kind, ToSql, Type};
+use tokio_postgres::error::{DbError, Severity};
+use tokio_postgres::{AsyncMessage, CancelToken, Client, Column, Row};
+use tokio_postgres_rustls::MakeRustlsConnect;
 
+use crate::drivers::cancel::{QueryCancel, Watchdog};
+use crate::drivers::cells::BinaryValues;
+use crate::drivers::{cells, columns, pg_tls, pg_values, plan};
+use crate::drivers::cursor::{self, CursorRegistry};
+use crate::drivers::pool::{self, PoolLimits};
+use crate::drivers::recovery;
 use crate::drivers::{DbType, DbDriver};
 use crate::models::{
-    ColumnInfo, ConnectParams, ConnectionHandle, DatabaseInfo, QueryResultSet, SessionId, TableInfo,
+    ColumnInfo, ConnectParams, ConnectionHandle, CursorInfo, CursorPage, DatabaseInfo, MessageSeverity,
+    PoolStats, QueryMessage, QueryParam, QueryParams, QueryPlan, QueryResultSet, ResultColumn, SessionId,
+    TableInfo, TypeCategory,
 };
 use crate::AppError;
 
-type PinnedConn = Arc<Mutex<Option<deadpool_postgres::Object>>>;
+type PinnedConn = Arc<Mutex<Option<deadpool::managed::Object<PgManager>>>>;
+
+/// A bind value sent in the text format, so the server parses it as the
+/// type it inferred for the placeholder (no client-side type matching).
+#[derive(Debug)]
+struct TextParam(Option<String>);
+
+impl TextParam {
+    fn from_param(param: &QueryParam) -> Result<Self, AppError> {
+        Ok(TextParam(match param {
+            QueryParam::Null => None,
+            QueryParam::Bool(b) => Some(if *b { "t" } else { "f" }.to_string()),
+            QueryParam::Int(n) => Some(n.to_string()),
+            QueryParam::Float(f) => Some(f.to_string()),
+            QueryParam::Decimal(s)
+            | QueryParam::Text(s)
+            | QueryParam::Date(s)
+            | QueryParam::Time(s)
+            | QueryParam::DateTime(s) => Some(s.clone()),
+            QueryParam::Bytes(b64) => {
+                let bytes = crate::sql::decode_bytes_param(b64)?;
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                Some(format!("\\x{}", hex))
+            }
+            QueryParam::Json(v) => Some(v.to_string()),
+        }))
+    }
+}
+
+impl ToSql for TextParam {
+    fn to_sql(
+        &self,
+        _ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        match &self.0 {
+            Some(text) => {
+                out.extend_from_slice(text.as_bytes());
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, _ty: &Type) -> Format {
+        Format::Text
+    }
+
+    to_sql_checked!();
+}
+
+/// Pool of `PgConn`s. deadpool-postgres spawns the connection task itself
+/// and tokio-postgres only logs NOTICE/WARNING messages there, so the pool
+/// is built on its config with our own manager.
+type Pool = deadpool::managed::Pool<PgManager>;
+
+struct PgManager {
+    pg_config: tokio_postgres::Config,
+    tls: MakeRustlsConnect,
+    limits: PoolLimits,
+}
+
+/// A pooled connection whose task keeps the notices the server sends.
+struct PgConn {
+    client: Client,
+    notices: Arc<std::sync::Mutex<Vec<DbError>>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl PgConn {
+    /// Notices received since the last call. They arrive before the reply
+    /// of the statement that raised them, so after a statement completes
+    /// they are all here.
+    fn take_notices(&self) -> Vec<DbError> {
+        let mut notices = self.notices.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *notices)
+    }
+}
+
+impl std::ops::Deref for PgConn {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl Drop for PgConn {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[async_trait]
+impl deadpool::managed::Manager for PgManager {
+    type Type = PgConn;
+    type Error = tokio_postgres::Error;
+
+    async fn create(&self) -> Result<PgConn, tokio_postgres::Error> {
+        let (client, mut connection) = self.pg_config.connect(self.tls.clone()).await?;
+        let notices = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = notices.clone();
+        // Polling for messages is what drives the connection.
+        let task = tokio::spawn(async move {
+            let mut messages = futures_util::stream::poll_fn(move |cx| connection.poll_message(cx));
+            while let Some(Ok(message)) = messages.next().await {
+                if let AsyncMessage::Notice(notice) = message {
+                    sink.lock().unwrap_or_else(|e| e.into_inner()).push(notice);
+                }
+            }
+        });
+        Ok(PgConn { client, notices, task })
+    }
+
+    async fn recycle(
+        &self,
+        conn: &mut PgConn,
+        metrics: &deadpool::managed::Metrics,
+    ) -> deadpool::managed::RecycleResult<tokio_postgres::Error> {
+        if conn.is_closed() {
+            return Err(deadpool::managed::RecycleError::StaticMessage("Connection closed"));
+        }
+        if self.limits.too_old(metrics) {
+            return Err(deadpool::managed::RecycleError::StaticMessage("Connection expired"));
+        }
+        if let Some(ref check) = self.limits.health_check {
+            conn.simple_query(check).await?;
+        }
+        conn.take_notices();
+        Ok(())
+    }
+}
+
+/// Captured at `begin_session` so `cancel` never needs the session lock.
+struct SessionCancel {
+    token: PgCancelToken,
+    cancel: Arc<QueryCancel>,
+}
+
+/// A cancel token and the TLS connector its connection was opened with.
+#[derive(Clone)]
+struct PgCancelToken {
+    token: CancelToken,
+    tls: MakeRustlsConnect,
+}
 
 pub struct PostgresDriver {
     pools: Mutex<HashMap<String, Pool>>,
+    /// Pools on the profile's other databases, by (profile, database), opened
+    /// by `switch_database` with the profile's settings but no minimum.
+    database_pools: Mutex<HashMap<(String, String), Pool>>,
     sessions: Mutex<HashMap<String, PinnedConn>>,
+    /// Sessions switched off the profile's database, and where to.
+    session_databases: Mutex<HashMap<String, String>>,
+    cancels: Mutex<HashMap<String, SessionCancel>>,
+    cursors: CursorRegistry,
 }
 
 impl PostgresDriver {
     pub fn new() -> Self {
         Self {
             pools: Mutex::new(HashMap::new()),
+            database_pools: Mutex::new(HashMap::new()),
             sessions: Mutex::new(HashMap::new()),
+            session_databases: Mutex::new(HashMap::new()),
+            cancels: Mutex::new(HashMap::new()),
+            cursors: CursorRegistry::new(),
         }
     }
 
@@ -46,6 +229,56 @@ impl PostgresDriver {
             .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))
     }
 
+    /// The pool a session's connections come from: the profile's, or the
+    /// one on the database it switched to.
+    async fn session_pool(&self, session_id: &str) -> Result<Pool, AppError> {
+        let profile_id = session_id.split(':').next().unwrap_or(session_id);
+        match self.session_databases.lock().await.get(session_id) {
+            Some(database) => self.database_pool(profile_id, database).await,
+            None => self.get_pool(profile_id).await,
+        }
+    }
+
+    /// The pool on another database of a profile, opened on first use.
+    async fn database_pool(&self, profile_id: &str, database: &str) -> Result<Pool, AppError> {
+        let home = self.get_pool(profile_id).await?;
+        let key = (profile_id.to_string(), database.to_string());
+        let mut pools = self.database_pools.lock().await;
+        if let Some(pool) = pools.get(&key) {
+            return Ok(pool.clone());
+        }
+        let manager = home.manager();
+        let mut pg_config = manager.pg_config.clone();
+        pg_config.dbname(database);
+        let limits = PoolLimits { min: 0, ..manager.limits.clone() };
+        let pool_config = limits.pool_config();
+        let pool = Pool::builder(PgManager { pg_config, tls: manager.tls.clone(), limits })
+            .config(pool_config)
+            .runtime(Runtime::Tokio1)
+            .build()
+            .map_err(|e| AppError::database(format!("Failed to create pool: {}", e)))?;
+        pool::spawn_reaper(pool.clone(), pool.manager().limits.clone());
+        pools.insert(key, pool.clone());
+        Ok(pool)
+    }
+
+    /// A session's stop flag and the token that cancels its running statement.
+    async fn stop_target(&self, session_id: &str) -> Result<(Arc<QueryCancel>, PgCancelToken), AppError> {
+        let cancels = self.cancels.lock().await;
+        cancels
+            .get(session_id)
+            .map(|c| (c.cancel.clone(), c.token.clone()))
+            .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))
+    }
+
+    async fn cancel_query(token: PgCancelToken) -> Result<(), AppError> {
+        token
+            .token
+            .cancel_query(token.tls)
+            .await
+            .map_err(|e| AppError::database(format!("Failed to cancel query: {}", e)))
+    }
+
     fn build_config(params: &ConnectParams) -> Result<PgConfig, AppError> {
         let mut cfg = PgConfig::new();
         cfg.host = Some(params.host.clone());
@@ -58,62 +291,143 @@ impl PostgresDriver {
             }
         }
         cfg.application_name = Some("WorkGrid Studio".to_string());
-        // Pool sizing mirrors MySQL (0..5).
-        cfg.pool = Some(deadpool_postgres::PoolConfig {
-            max_size: 5,
-            ..Default::default()
-        });
         Ok(cfg)
     }
 
-    /// Convert a postgres Row column to serde_json::Value by its type.
-    fn value_to_json(row: &Row, idx: usize) -> serde_json::Value {
-        let col = &row.columns()[idx];
-        let t = col.type_();
-
-        // NULL check first.
-        if row.try_get::<_, Option<bool>>(idx).ok().is_none() && row.try_get::<_, Option<String>>(idx).ok().is_none() {
-            // Heuristic NULL detection: try to read as Option<serde_json::Value>.
+    fn result_set(columns: Vec<ResultColumn>, rows: &[Row]) -> QueryResultSet {
+        let col_count = columns.len();
+        let mut result_rows = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut vals = Vec::with_capacity(col_count);
+            for i in 0..col_count {
+                vals.push(pg_values::cell(row, i));
+            }
+            result_rows.push(vals);
         }
-
-        // Use a typed approach: try each common type as Option, fall back to string.
-        macro_rules! try_opt {
-            ($t:ty) => {
-                if let Ok(v) = row.try_get::<_, Option<$t>>(idx) {
-                    return match v {
-                        Some(x) => serde_json::to_value(x).unwrap_or(serde_json::Value::Null),
-                        None => serde_json::Value::Null,
-                    };
-                }
-            };
+        let count = result_rows.len();
+        QueryResultSet {
+            columns: columns::names(&columns),
+            column_meta: columns,
+            rows: result_rows,
+            affected_rows: count as u64,
+            info: format!("{} row(s) returned", count),
+            messages: vec![],
         }
+    }
 
-        // Boolean
-        try_opt!(bool);
-        // Integer families
-        try_opt!(i16);
-        try_opt!(i32);
-        try_opt!(i64);
-        try_opt!(f32);
-        try_opt!(f64);
-        // Text
-        try_opt!(String);
-        // JSON / JSONB
-        if t == &Type::JSON || t == &Type::JSONB {
-            if let Ok(Some(s)) = row.try_get::<_, Option<String>>(idx) {
-                return serde_json::from_str(&s).unwrap_or(serde_json::Value::String(s));
+    /// Run one prepared statement: rows when it has columns, otherwise the
+    /// statement's kind and affected count. The notices it raised become the
+    /// result's messages.
+    async fn run_statement(
+        conn: &PgConn,
+        stmt: &str,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<QueryResultSet, tokio_postgres::Error> {
+        // Left over from a statement that failed; not this one's.
+        conn.take_notices();
+        let prepared = conn.prepare(stmt).await?;
+        let mut result = if prepared.columns().is_empty() {
+            let affected = conn.execute(&prepared, params).await?;
+            let kind = statement_kind(stmt);
+            let info = if reports_row_count(&kind) {
+                format!("{}: {} row(s) affected", kind, affected)
+            } else {
+                kind
+            };
+            QueryResultSet {
+                columns: vec![],
+                column_meta: vec![],
+                rows: vec![],
+                affected_rows: affected,
+                info,
+                messages: vec![],
             }
-            return serde_json::Value::Null;
+        } else {
+            let rows = conn.query(&prepared, params).await?;
+            let columns = Self::describe_columns(conn, prepared.columns()).await;
+            Self::result_set(columns, &rows)
+        };
+        result.messages = conn.take_notices().iter().map(notice_message).collect();
+        Ok(result)
+    }
+
+    /// Metadata from RowDescription, plus origin names and NOT NULL looked
+    /// up in pg_attribute (the protocol only carries the table OID and
+    /// attribute number).
+    async fn describe_columns(client: &Client, cols: &[Column]) -> Vec<ResultColumn> {
+        let mut described: Vec<ResultColumn> = cols.iter().map(Self::result_column).collect();
+        let mut oids: Vec<u32> = cols.iter().filter_map(|c| c.table_oid()).filter(|&oid| oid != 0).collect();
+        if oids.is_empty() {
+            return described;
         }
-        // Bytes / blobs
-        if let Ok(Some(b)) = row.try_get::<_, Option<Vec<u8>>>(idx) {
-            return serde_json::Value::String(format!("[blob {} bytes]", b.len()));
+        oids.sort_unstable();
+        oids.dedup();
+        // Best effort: the result is still useful without origins.
+        let Ok(rows) = client
+            .query(
+                "SELECT a.attrelid, a.attnum, n.nspname, c.relname, a.attname, a.attnotnull \
+                 FROM pg_attribute a \
+                 JOIN pg_class c ON c.oid = a.attrelid \
+                 JOIN pg_namespace n ON n.oid = c.relnamespace \
+                 WHERE a.attrelid = ANY($1) AND a.attnum > 0",
+                &[&oids],
+            )
+            .await
+        else {
+            return described;
+        };
+        for (meta, col) in described.iter_mut().zip(cols) {
+            let (Some(oid), Some(attnum)) = (col.table_oid(), col.column_id()) else { continue };
+            let origin = rows
+                .iter()
+                .find(|r| r.get::<_, u32>(0) == oid && r.get::<_, i16>(1) == attnum);
+            if let Some(origin) = origin {
+                meta.schema = Some(origin.get(2));
+                meta.table = Some(origin.get(3));
+                meta.column = Some(origin.get(4));
+                meta.nullable = Some(!origin.get::<_, bool>(5));
+            }
         }
-        // Everything else -> string representation.
-        if let Ok(Some(s)) = row.try_get::<_, Option<String>>(idx) {
-            return serde_json::Value::String(s);
+        described
+    }
+
+    fn result_column(col: &Column) -> ResultColumn {
+        let t = col.type_();
+        let category = match *t {
+            Type::BOOL => TypeCategory::Bool,
+            Type::INT2 | Type::INT4 | Type::INT8 | Type::OID => TypeCategory::Int,
+            Type::NUMERIC | Type::MONEY => TypeCategory::Decimal,
+            Type::FLOAT4 | Type::FLOAT8 => TypeCategory::Float,
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::CHAR | Type::NAME | Type::XML => {
+                TypeCategory::Text
+            }
+            Type::BYTEA => TypeCategory::Bytes,
+            Type::DATE => TypeCategory::Date,
+            Type::TIME | Type::TIMETZ => TypeCategory::Time,
+            Type::TIMESTAMP | Type::TIMESTAMPTZ => TypeCategory::DateTime,
+            Type::JSON | Type::JSONB => TypeCategory::Json,
+            Type::UUID => TypeCategory::Uuid,
+            _ if matches!(t.kind(), Kind::Enum(_)) || t.name() == "citext" => TypeCategory::Text,
+            _ => TypeCategory::Other,
+        };
+        let mut meta = columns::describe(col.name(), t.name(), category);
+
+        // The type modifier carries the declared arguments; -1 means none.
+        let typmod = col.type_modifier();
+        match *t {
+            Type::NUMERIC if typmod >= 4 => {
+                let packed = (typmod - 4) as u32;
+                meta.precision = Some((packed >> 16) & 0xffff);
+                meta.scale = Some(packed & 0xffff);
+            }
+            Type::VARCHAR | Type::BPCHAR if typmod >= 4 => meta.length = Some((typmod - 4) as u64),
+            Type::BIT | Type::VARBIT if typmod >= 0 => meta.length = Some(typmod as u64),
+            Type::TIME | Type::TIMETZ | Type::TIMESTAMP | Type::TIMESTAMPTZ if typmod >= 0 => {
+                meta.scale = Some(typmod as u32)
+            }
+            _ => {}
         }
-        serde_json::Value::Null
+        meta
     }
 }
 
@@ -125,8 +439,20 @@ impl DbDriver for PostgresDriver {
 
     async fn connect(&self, params: &ConnectParams) -> Result<ConnectionHandle, AppError> {
         let cfg = Self::build_config(params)?;
-        let pool = cfg
-            .create_pool(Some(Runtime::Tokio1), NoTls)
+        let limits = PoolLimits::new(params.pool.as_ref())?;
+        let (ssl_mode, tls) = pg_tls::connector(params)?;
+        let mut pg_config = cfg
+            .get_pg_config()
+            .map_err(|e| AppError::database(format!("Failed to create pool: {}", e)))?;
+        pg_config.ssl_mode(ssl_mode);
+        if params.read_only {
+            pg_config.options("-c default_transaction_read_only=on");
+        }
+        let pool_config = limits.pool_config();
+        let pool = Pool::builder(PgManager { pg_config, tls, limits })
+            .config(pool_config)
+            .runtime(Runtime::Tokio1)
+            .build()
             .map_err(|e| AppError::database(format!("Failed to create pool: {}", e)))?;
 
         // Test with a connection.
@@ -140,6 +466,7 @@ impl DbDriver for PostgresDriver {
             .map_err(|e| AppError::database(format!("Failed to query version: {}", e)))?
             .get(0);
         drop(client);
+        pool::spawn_reaper(pool.clone(), pool.manager().limits.clone());
 
         {
             let mut pools = self.pools.lock().await;
@@ -153,6 +480,7 @@ impl DbDriver for PostgresDriver {
             profile_id: params.profile_id.clone(),
             db_type: "postgres".to_string(),
             server_version: version,
+            tls: None,
         })
     }
 
@@ -176,6 +504,13 @@ impl DbDriver for PostgresDriver {
         if let Some(pool) = pool {
             pool.close();
         }
+        self.database_pools.lock().await.retain(|(profile, _), pool| {
+            if profile != profile_id {
+                return true;
+            }
+            pool.close();
+            false
+        });
         Ok(())
     }
 
@@ -192,15 +527,33 @@ impl DbDriver for PostgresDriver {
         Ok(())
     }
 
+    async fn pool_stats(&self, profile_id: &str) -> Result<PoolStats, AppError> {
+        let pool = self.get_pool(profile_id).await?;
+        let mut stats = pool::deadpool_stats(&pool, &pool.manager().limits, profile_id, DbType::Postgres);
+        stats.sessions = pool::session_count(&*self.sessions.lock().await, profile_id);
+        Ok(stats)
+    }
+
     async fn begin_session(&self, profile_id: &str) -> Result<SessionId, AppError> {
         let pool = self.get_pool(profile_id).await?;
         let client = pool
             .get()
             .await
             .map_err(|e| AppError::database(format!("Failed to acquire connection: {}", e)))?;
+        let token = PgCancelToken {
+            token: client.cancel_token(),
+            tls: pool.manager().tls.clone(),
+        };
         // Lease the client for the session lifetime.
         let pinned: PinnedConn = Arc::new(Mutex::new(Some(client)));
         let session_id = format!("{}:{}", profile_id, uuid::Uuid::new_v4());
+        self.cancels.lock().await.insert(
+            session_id.clone(),
+            SessionCancel {
+                token,
+                cancel: Arc::new(QueryCancel::new()),
+            },
+        );
         {
             let mut sessions = self.sessions.lock().await;
             sessions.insert(session_id.clone(), pinned);
@@ -209,6 +562,9 @@ impl DbDriver for PostgresDriver {
     }
 
     async fn end_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.cursors.close_session(session_id).await;
+        self.cancels.lock().await.remove(session_id);
+        self.session_databases.lock().await.remove(session_id);
         let pinned = {
             let mut sessions = self.sessions.lock().await;
             sessions.remove(session_id)
@@ -220,11 +576,90 @@ impl DbDriver for PostgresDriver {
         Ok(())
     }
 
+    async fn is_session_broken(&self, session_id: &str) -> bool {
+        let Some(pinned) = self.sessions.lock().await.get(session_id).cloned() else {
+            return false;
+        };
+        let guard = pinned.lock().await;
+        match guard.as_ref() {
+            Some(conn) => {
+                conn.is_closed()
+                    || recovery::probe_lost(conn.simple_query("SELECT 1"), |e| e.as_db_error().is_some()).await
+            }
+            None => true,
+        }
+    }
+
+    async fn recover_session(&self, session_id: &str) -> Result<(), AppError> {
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        self.cursors.close_session(session_id).await;
+        let pool = self.session_pool(session_id).await?;
+        // `recycle` discards idle connections that died with the session's.
+        let client = pool
+            .get()
+            .await
+            .map_err(|e| AppError::database(format!("Failed to reconnect session: {}", e)))?;
+        if let Some(entry) = self.cancels.lock().await.get_mut(session_id) {
+            entry.token = PgCancelToken {
+                token: client.cancel_token(),
+                tls: pool.manager().tls.clone(),
+            };
+        }
+        let lost = pinned.lock().await.replace(client);
+        if let Some(lost) = lost {
+            // Taken out of the pool, so it is never handed out again.
+            drop(deadpool::managed::Object::take(lost));
+        }
+        Ok(())
+    }
+
+    async fn switch_database(&self, session_id: &str, database: &str) -> Result<(), AppError> {
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let profile_id = session_id.split(':').next().unwrap_or(session_id);
+        let home = self.get_pool(profile_id).await?;
+        let cfg = &home.manager().pg_config;
+        // Without a dbname the server connects to the user's database.
+        let is_home = cfg.get_dbname().or(cfg.get_user()) == Some(database);
+        let pool = if is_home { home.clone() } else { self.database_pool(profile_id, database).await? };
+        let client = pool
+            .get()
+            .await
+            .map_err(|e| AppError::database(format!("Failed to connect to database {}: {}", database, e)))?;
+        self.cursors.close_session(session_id).await;
+        if let Some(entry) = self.cancels.lock().await.get_mut(session_id) {
+            entry.token = PgCancelToken {
+                token: client.cancel_token(),
+                tls: pool.manager().tls.clone(),
+            };
+        }
+        // The old connection goes back to its own pool.
+        drop(pinned.lock().await.replace(client));
+        let mut databases = self.session_databases.lock().await;
+        if is_home {
+            databases.remove(session_id);
+        } else {
+            databases.insert(session_id.to_string(), database.to_string());
+        }
+        Ok(())
+    }
+
     async fn query(
         &self,
         session_id: &str,
         sql: &str,
-        _timeout_ms: Option<u64>,
+        timeout_ms: Option<u64>,
     ) -> Result<Vec<QueryResultSet>, AppError> {
         let pinned = {
             let sessions = self.sessions.lock().await;
@@ -234,50 +669,110 @@ impl DbDriver for PostgresDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
 
-        // PostgreSQL's simple query protocol executes multiple statements in
-        // one call and returns multiple result sets.
+        let (cancel, token) = self.stop_target(session_id).await?;
+
+        // The extended protocol takes one statement per call, so split with
+        // PostgreSQL's own lexical rules and run them in order. Each
+        // statement autocommits unless the script opens a transaction.
+        let statements = crate::sql::split_postgres_statements(sql);
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::cancel_query(token));
 
-        let rows = client
-            .query(sql, &[])
+        let mut results = Vec::with_capacity(statements.len());
+        for stmt in &statements {
+            cancel.check()?;
+            let result = Self::run_statement(client, stmt, &[]).await.map_err(|e| {
+                cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e)))
+            })?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    async fn query_params(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Vec<QueryResultSet>, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let (stmt, values) = crate::sql::positional_params(&stmt, params, |i| format!("${}", i))?;
+        let values = values
+            .into_iter()
+            .map(TextParam::from_param)
+            .collect::<Result<Vec<_>, _>>()?;
+        let refs: Vec<&(dyn ToSql + Sync)> = values.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let (cancel, token) = self.stop_target(session_id).await?;
+
+        let guard = pinned.lock().await;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::cancel_query(token));
+
+        let result = Self::run_statement(client, &stmt, &refs)
             .await
-            .map_err(|e| AppError::database(format!("Query error: {}", e)))?;
+            .map_err(|e| cancel.map_err(AppError::database(format!("Query error: {}", e))))?;
+        Ok(vec![result])
+    }
 
-        // If no columns (command like INSERT without RETURNING), report affected.
-        if rows.is_empty() {
-            return Ok(vec![QueryResultSet {
-                columns: vec![],
-                rows: vec![],
-                affected_rows: 0,
-                info: "OK".to_string(),
-            }]);
-        }
+    async fn fetch_bytes(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let (stmt, values) = crate::sql::positional_params(&stmt, params, |i| format!("${}", i))?;
+        let values = values
+            .into_iter()
+            .map(TextParam::from_param)
+            .collect::<Result<Vec<_>, _>>()?;
+        let refs: Vec<&(dyn ToSql + Sync)> = values.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let (cancel, token) = self.stop_target(session_id).await?;
 
-        let columns: Vec<String> = rows[0].columns().iter().map(|c| c.name().to_string()).collect();
-        let col_count = columns.len();
-        let mut result_rows = Vec::with_capacity(rows.len());
-        for row in &rows {
-            let mut vals = Vec::with_capacity(col_count);
-            for i in 0..col_count {
-                vals.push(Self::value_to_json(row, i));
-            }
-            result_rows.push(vals);
+        let guard = pinned.lock().await;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::cancel_query(token));
+
+        let row = client
+            .query_opt(stmt.as_str(), &refs)
+            .await
+            .map_err(|e| cancel.map_err(AppError::database(format!("Query error: {}", e))))?
+            .ok_or_else(|| AppError::database("No row matched"))?;
+        if let Ok(bytes) = row.try_get::<_, Option<Vec<u8>>>(0) {
+            return Ok(bytes);
         }
-        let count = result_rows.len();
-        Ok(vec![QueryResultSet {
-            columns,
-            rows: result_rows,
-            affected_rows: count as u64,
-            info: format!("{} row(s) returned", count),
-        }])
+        // Anything else is returned as its text form.
+        Ok(match pg_values::cell(&row, 0) {
+            serde_json::Value::Null => None,
+            cell => Some(cells::text_form(&cell).into_bytes()),
+        })
     }
 
     async fn execute(
         &self,
         session_id: &str,
         sql: &str,
-        _timeout_ms: Option<u64>,
+        timeout_ms: Option<u64>,
     ) -> Result<u64, AppError> {
         let pinned = {
             let sessions = self.sessions.lock().await;
@@ -286,15 +781,133 @@ impl DbDriver for PostgresDriver {
                 .cloned()
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
+        let (cancel, token) = self.stop_target(session_id).await?;
+
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::cancel_query(token));
 
-        // For commands that return rows (e.g. INSERT ... RETURNING), count them.
-        let rows = client
-            .query(sql, &[])
-            .await
-            .map_err(|e| AppError::database(format!("Execute error: {}", e)))?;
-        Ok(rows.len() as u64)
+        // `execute` reads the count from each statement's command tag and
+        // discards any rows (INSERT ... RETURNING, SELECT).
+        let mut total_affected: u64 = 0;
+        for stmt in &crate::sql::split_postgres_statements(sql) {
+            cancel.check()?;
+            total_affected += client.execute(stmt.as_str(), &[]).await.map_err(|e| {
+                cancel.map_err(AppError::database(format!("Execute error [{}]: {}", stmt, e)))
+            })?;
+        }
+        Ok(total_affected)
+    }
+
+    async fn open_cursor(
+        &self,
+        session_id: &str,
+        sql: &str,
+        binary: BinaryValues,
+        timeout_ms: Option<u64>,
+    ) -> Result<CursorInfo, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let pinned = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+
+        let (cancel, token) = self.stop_target(session_id).await?;
+
+        let (chans, pending) = cursor::channel();
+        tokio::spawn(async move {
+            let guard = pinned.lock_owned().await;
+            let Some(client) = guard.as_ref() else {
+                chans.fail(recovery::session_lost());
+                return;
+            };
+            // The timeout covers the statement starting, not the paging.
+            cancel.reset();
+            let watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::cancel_query(token.clone()));
+            // Prepare first so the columns are known even for empty results.
+            let prepared = match client.prepare(&stmt).await {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    chans.fail(cancel.map_err(AppError::database(format!("Query error: {}", e))));
+                    return;
+                }
+            };
+            let columns = Self::describe_columns(client, prepared.columns()).await;
+            let stream = match client.query_raw(&prepared, std::iter::empty::<i32>()).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    chans.fail(cancel.map_err(AppError::database(format!("Query error: {}", e))));
+                    return;
+                }
+            };
+            drop(watchdog);
+            let Some(tx) = chans.start(columns) else { return };
+
+            // RowStream yields DataRow messages as they arrive on the socket.
+            futures_util::pin_mut!(stream);
+            loop {
+                let item = match stream.try_next().await {
+                    Ok(Some(row)) => {
+                        let mut vals: Vec<_> = (0..row.len()).map(|i| pg_values::cell(&row, i)).collect();
+                        cells::widen_binary(binary, &mut vals, |i| pg_values::raw_bytes(&row, i));
+                        Ok(vals)
+                    }
+                    Ok(None) => break,
+                    Err(e) => Err(cancel.map_err(AppError::database(format!("Row error: {}", e)))),
+                };
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() {
+                    // Closed early: cancel the statement, or the connection
+                    // reads its unread rows before the session's next query.
+                    let _ = Self::cancel_query(token).await;
+                    break;
+                }
+                if failed {
+                    break;
+                }
+            }
+        });
+
+        let cursor = pending.ready(session_id).await?;
+        Ok(self.cursors.insert(cursor).await)
+    }
+
+    async fn fetch_cursor(&self, cursor_id: &str, max_rows: usize) -> Result<CursorPage, AppError> {
+        self.cursors.fetch(cursor_id, max_rows).await
+    }
+
+    async fn close_cursor(&self, cursor_id: &str) -> Result<(), AppError> {
+        self.cursors.close(cursor_id).await;
+        Ok(())
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<(), AppError> {
+        let (cancel, token) = self.stop_target(session_id).await?;
+        cancel.request();
+        Self::cancel_query(token).await
+    }
+
+    async fn explain(
+        &self,
+        session_id: &str,
+        sql: &str,
+        analyze: bool,
+        timeout_ms: Option<u64>,
+    ) -> Result<QueryPlan, AppError> {
+        let statement = plan::single_statement(DbType::Postgres, sql)?;
+        let options = if analyze {
+            "FORMAT JSON, ANALYZE, BUFFERS"
+        } else {
+            "FORMAT JSON"
+        };
+        let explain = format!("EXPLAIN ({}) {}", options, statement);
+        let raw = plan::plan_text(&self.query(session_id, &explain, timeout_ms).await?)?;
+        let nodes = plan::from_postgres_json(&raw)?;
+        Ok(QueryPlan { analyzed: analyze, nodes, raw })
     }
 
     async fn list_databases(&self, session_id: &str) -> Result<Vec<String>, AppError> {
@@ -306,7 +919,7 @@ impl DbDriver for PostgresDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
         let rows = client
             .query("SELECT datname FROM pg_database WHERE datistemplate = false ORDER BY datname", &[])
             .await
@@ -323,7 +936,7 @@ impl DbDriver for PostgresDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
         let rows = client
             .query(
                 "SELECT table_name FROM information_schema.tables \
@@ -350,7 +963,7 @@ impl DbDriver for PostgresDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
 
         let rows = client
             .query(
@@ -389,7 +1002,7 @@ impl DbDriver for PostgresDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
 
         let rows = client
             .query(
@@ -430,7 +1043,7 @@ impl DbDriver for PostgresDriver {
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
         let guard = pinned.lock().await;
-        let client = guard.as_ref().ok_or_else(|| AppError::state("Session connection was lost"))?;
+        let client = guard.as_ref().ok_or_else(recovery::session_lost)?;
         let rows = client
             .query(
                 "SELECT datname, pg_database_size(datname) \
@@ -453,3 +1066,96 @@ impl DbDriver for PostgresDriver {
             .collect())
     }
 }
+
+/// What a statement does, named like the server's command tags (`INSERT`,
+/// `CREATE TABLE`, ...) but read off its leading keywords: tokio-postgres
+/// keeps only the row count of CommandComplete, not the tag itself.
+fn statement_kind(stmt: &str) -> String {
+    let mut rest = stmt.trim_start();
+    // Skip leading comments.
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.split_once('\n').map_or("", |(_, r)| r).trim_start();
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/").map_or("", |(_, r)| r).trim_start();
+        } else {
+            break;
+        }
+    }
+    let mut words = rest
+        .split(|c: char| !c.is_ascii_alphabetic() && c != '_')
+        .filter(|w| !w.is_empty())
+        .map(str::to_uppercase);
+    let Some(verb) = words.next() else { return String::new() };
+    if verb == "WITH" {
+        // The statement proper follows its CTEs (`WITH d AS (...) UPDATE t`).
+        return crate::sql::statement_words(DbType::Postgres, stmt)
+            .into_iter()
+            .filter(|w| w.depth == 0)
+            .find(|w| ["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE"].iter().any(|k| w.is(k)))
+            .map_or(verb, |w| w.text);
+    }
+    if !matches!(verb.as_str(), "CREATE" | "ALTER" | "DROP") {
+        return verb;
+    }
+    // DDL kinds name the object type: CREATE [OR REPLACE] [TEMP] TABLE.
+    const MODIFIERS: &[&str] = &[
+        "OR", "REPLACE", "TEMP", "TEMPORARY", "UNLOGGED", "GLOBAL", "LOCAL", "UNIQUE", "MATERIALIZED",
+        "RECURSIVE", "TRUSTED", "PROCEDURAL",
+    ];
+    let mut kind = verb;
+    for word in words {
+        // MATERIALIZED is part of the kind (CREATE MATERIALIZED VIEW).
+        let modifier = MODIFIERS.contains(&word.as_str());
+        if !modifier || word == "MATERIALIZED" {
+            kind.push(' ');
+            kind.push_str(&word);
+        }
+        if !modifier {
+            break;
+        }
+    }
+    kind
+}
+
+/// Kinds whose row count is meaningful (`UPDATE 3`); DDL reports 0.
+fn reports_row_count(kind: &str) -> bool {
+    matches!(kind, "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "SELECT" | "COPY" | "MOVE" | "FETCH")
+}
+
+/// A NOTICE/WARNING (`RAISE`, implicit notices such as "table does not exist,
+/// skipping") as a result message. The parsed severity is the untranslated
+/// one; the display severity follows `lc_messages`.
+fn notice_message(notice: &DbError) -> QueryMessage {
+    let severity = match notice.parsed_severity() {
+        Some(Severity::Warning) => MessageSeverity::Warning,
+        Some(Severity::Notice) | None => MessageSeverity::Notice,
+        Some(Severity::Info | Severity::Debug | Severity::Log) => MessageSeverity::Info,
+        Some(Severity::Error | Severity::Fatal | Severity::Panic) => MessageSeverity::Error,
+    };
+    let text = match notice.detail() {
+        Some(detail) => format!("{}\n{}", notice.message(), detail),
+        None => notice.message().to_string(),
+    };
+    QueryMessage {
+        severity,
+        code: Some(notice.code().code().to_string()),
+        text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_statement_kinds() {
+        assert_eq!(statement_kind("-- note\n  update t set a = 1"), "UPDATE");
+        assert_eq!(statement_kind("CREATE OR REPLACE VIEW v AS SELECT 1"), "CREATE VIEW");
+        assert_eq!(statement_kind("create materialized view m as select 1"), "CREATE MATERIALIZED VIEW");
+        assert_eq!(statement_kind("/* x */ DROP TABLE IF EXISTS t"), "DROP TABLE");
+        assert!(reports_row_count(&statement_kind("insert into t values (1)")));
+        assert_eq!(statement_kind("WITH d AS (SELECT 1) UPDATE t SET a = 1"), "UPDATE");
+        assert_eq!(statement_kind("with recursive r(n) as (select 1) delete from t"), "DELETE");
+    }
+}
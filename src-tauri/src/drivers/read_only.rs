@@ -0,0 +1,135 @@
+// Read-only connections (`ConnectParams::read_only`).
+//
+// The drivers open read-only connections at the protocol level where the
+// server has such a thing: MySQL sessions run `SET SESSION TRANSACTION READ
+// ONLY`, Postgres connections start with `default_transaction_read_only`, and
+// SQLite files are opened with SQLITE_OPEN_READ_ONLY. SQL Server logins only
+// declare `ApplicationIntent=ReadOnly`, which routes to a readable secondary
+// of an availability group but does not stop writes on a primary.
+//
+// So `ConnectionManager::guard_sql` also runs `check` on the SQL a
+// read-only session sends through it, refusing writes with kind `read_only`
+// before they reach the server. A statement passes when it starts with a
+// reading keyword and names no writing keyword outside strings, quoted
+// identifiers and comments (MySQL's `/*! ... */` bodies count as code);
+// session statements may not switch the access mode back. Postgres
+// `set_config` is refused outright: the setting it changes can be any
+// expression. T-SQL needs no `;` between statements, so its statement
+// keywords (`SHUTDOWN`, `DBCC`...) count as writing wherever they appear.
+
+use crate::drivers::DbType;
+use crate::{AppError, AppResult};
+
+/// Statements that read, or only change the session.
+const READING: &[&str] = &[
+    "SELECT", "WITH", "VALUES", "TABLE", "SHOW", "EXPLAIN", "DESCRIBE", "DESC", "PRAGMA", "USE", "SET",
+    "RESET", "DECLARE", "PRINT", "FETCH", "CLOSE", "BEGIN", "START", "COMMIT", "ROLLBACK", "END",
+    "SAVEPOINT", "RELEASE",
+];
+
+/// Keywords that write wherever they appear (a CTE's `DELETE`, `SELECT ...
+/// INTO`, `FOR UPDATE`, a statement inside an MSSQL `BEGIN ... END`).
+const WRITING: &[&str] = &[
+    "INSERT", "UPDATE", "DELETE", "MERGE", "INTO", "TRUNCATE", "CREATE", "ALTER", "DROP", "RENAME", "GRANT",
+    "REVOKE", "EXEC", "EXECUTE", "CALL", "COPY",
+];
+
+/// SQL Server statements that write or act on the server, and rowset
+/// functions that run SQL elsewhere.
+const MSSQL_WRITING: &[&str] = &[
+    "BACKUP", "RESTORE", "DBCC", "KILL", "SHUTDOWN", "DENY", "RECONFIGURE", "ENABLE", "DISABLE", "WRITETEXT",
+    "UPDATETEXT", "BULK", "CHECKPOINT", "OPENQUERY", "OPENROWSET", "OPENDATASOURCE",
+];
+
+/// Refuse a script on a read-only connection unless every statement reads.
+pub fn check(db: DbType, sql: &str) -> AppResult<()> {
+    let statements = match db {
+        DbType::Postgres => crate::sql::split_postgres_statements(sql),
+        _ => crate::sql::split_sql_statements(sql),
+    };
+    for stmt in &statements {
+        check_statement(db, stmt)?;
+    }
+    Ok(())
+}
+
+fn check_statement(db: DbType, stmt: &str) -> AppResult<()> {
+    let all_words = crate::sql::statement_words(db, stmt);
+    if db == DbType::Postgres && all_words.iter().any(|w| w.text.eq_ignore_ascii_case("set_config")) {
+        return Err(AppError::read_only("The connection is read-only; set_config is not allowed"));
+    }
+    let words: Vec<String> = all_words.into_iter().filter(|w| !w.quoted).map(|w| w.text).collect();
+    let Some(first) = words.first() else {
+        return Ok(());
+    };
+    if !READING.contains(&first.as_str()) {
+        return Err(refused(first));
+    }
+    // `SHOW CREATE TABLE` and the like name writing keywords but read.
+    if matches!(first.as_str(), "SHOW" | "DESCRIBE" | "DESC" | "PRAGMA") {
+        return Ok(());
+    }
+    let writes = |w: &&String| {
+        WRITING.contains(&w.as_str()) || (db == DbType::Mssql && MSSQL_WRITING.contains(&w.as_str()))
+    };
+    if let Some(word) = words.iter().find(writes) {
+        return Err(refused(word));
+    }
+    let session = matches!(first.as_str(), "SET" | "RESET" | "BEGIN" | "START");
+    if session && words.iter().any(|w| w == "WRITE" || w.ends_with("READ_ONLY")) {
+        return Err(AppError::read_only("The connection is read-only; its access mode cannot be changed"));
+    }
+    Ok(())
+}
+
+fn refused(word: &str) -> AppError {
+    AppError::read_only(format!("The connection is read-only; {} statements are not allowed", word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lets_reads_through() {
+        assert!(check(DbType::Mysql, "SELECT 'DELETE' AS `update`; SHOW CREATE TABLE t; USE app").is_ok());
+        assert!(check(DbType::Postgres, "SELECT $$DROP$$, \"insert\" -- delete\nFROM t /* drop */").is_ok());
+        assert!(check(DbType::Postgres, "SET search_path TO app; BEGIN; SELECT $1::int; COMMIT").is_ok());
+        assert!(check(DbType::Mssql, "SELECT [delete] FROM t WHERE n = N'it''s an update'").is_ok());
+        assert!(check(DbType::Sqlite, "PRAGMA table_info(t); WITH c AS (SELECT 1) SELECT * FROM c").is_ok());
+    }
+
+    #[test]
+    fn refuses_writes() {
+        let kind = |db: DbType, sql: &str| check(db, sql).unwrap_err().kind;
+        assert_eq!(kind(DbType::Mysql, "SELECT 1; delete from t"), "read_only");
+        assert_eq!(kind(DbType::Postgres, "SELECT 'a\\'; DROP TABLE t; --' FROM t"), "read_only");
+        assert_eq!(kind(DbType::Postgres, "WITH d AS (DELETE FROM t RETURNING *) TABLE d"), "read_only");
+        assert_eq!(kind(DbType::Postgres, "SELECT * INTO copy FROM t"), "read_only");
+        assert_eq!(kind(DbType::Postgres, "SET default_transaction_read_only = off"), "read_only");
+        assert_eq!(kind(DbType::Mysql, "START TRANSACTION READ WRITE"), "read_only");
+        assert_eq!(kind(DbType::Mysql, "/*! SET SESSION TRANSACTION READ WRITE */"), "read_only");
+        assert_eq!(kind(DbType::Mysql, "SELECT 1 /*!50000 , (SELECT 1 INTO @x) */"), "read_only");
+        assert_eq!(kind(DbType::Mysql, "SELECT 1 /*! ; DROP TABLE t */"), "read_only");
+        let set_config = "SELECT set_config('default_transaction_read_only','off',false)";
+        assert_eq!(kind(DbType::Postgres, set_config), "read_only");
+        let qualified = "SELECT pg_catalog.SET_CONFIG('transaction_read_only', 'off', true)";
+        assert_eq!(kind(DbType::Postgres, qualified), "read_only");
+        let built = "SELECT set_config('default_transaction_'||'read_only','off',false)";
+        assert_eq!(kind(DbType::Postgres, built), "read_only");
+        assert_eq!(kind(DbType::Postgres, "SELECT \"set_config\"('search_path', 'app', false)"), "read_only");
+        for sql in [
+            "SELECT 1 RESTORE DATABASE x FROM DISK='a'",
+            "SELECT 1 KILL 55",
+            "SELECT 1 SHUTDOWN WITH NOWAIT",
+            "SELECT 1 DENY SELECT ON t TO u",
+            "SELECT 1 DBCC SHRINKDATABASE(x)",
+            "SELECT * FROM OPENQUERY(srv, 'DELETE FROM t')",
+        ] {
+            assert_eq!(kind(DbType::Mssql, sql), "read_only", "{}", sql);
+        }
+        assert_eq!(kind(DbType::Mssql, "BEGIN TRAN; UPDATE t SET a = 1; COMMIT"), "read_only");
+        assert_eq!(kind(DbType::Mssql, "EXEC sp_rename 'a', 'b'"), "read_only");
+        assert_eq!(kind(DbType::Sqlite, "VACUUM"), "read_only");
+    }
+}
@@ -0,0 +1,236 @@
+// Automatic recovery of sessions whose connection dropped.
+//
+// When a session call fails, the `ConnectionManager` asks the driver whether
+// the session's connection is still alive (`is_session_broken`: a probe that
+// counts any reply from the server, even an error, as alive). A dead session
+// with no open transaction is given a fresh connection (`recover_session`),
+// its context is replayed, and the call is retried once (`may_rerun`) if that
+// cannot repeat what it did: the call never reached the server, or all its
+// statements read. Query results then carry a "reconnected" notice. A call
+// that may have written is not run again, since whether it took effect is
+// unknown; nor is one that ran in a transaction, which the server has rolled
+// back. The session is reconnected either way but the call fails with kind
+// `connection_lost`.
+//
+// SQL Server can only stop a statement (cancel or timeout) by ending its
+// session: the driver drops the session's connection and fails the call
+// with `session_reset`, and the session is recovered the same way, without
+// running the call again.
+//
+// The context replayed is what `observe` follows in the statements a session
+// ran successfully: the current database (`USE`) and session settings
+// (`SET`), the latest value of each, in the order they were first set. It
+// also keeps the schema `scope` tracks for servers that do not hold one.
+// Transaction-scoped and server-wide settings are not replayed. SQLite
+// connections are file handles and never drop.
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::drivers::DbType;
+use crate::models::{MessageSeverity, QueryMessage, QueryResultSet};
+use crate::AppError;
+
+/// How long a liveness probe may take before the connection counts as lost.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The replayable context of a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionContext {
+    /// The database selected with `USE` (Postgres: switched to; see `scope`).
+    pub database: Option<String>,
+    /// The schema kept for servers that do not hold one (see `scope`).
+    pub schema: Option<String>,
+    /// Session settings as (key, statement), one per key.
+    settings: Vec<(String, String)>,
+}
+
+impl SessionContext {
+    /// Statements that restore the context on a fresh connection.
+    pub fn replay(&self, db: DbType) -> Vec<String> {
+        let mut statements = Vec::new();
+        // Postgres switches database by connecting to it; see `scope`.
+        if let Some(database) = self.database.as_ref().filter(|_| db != DbType::Postgres) {
+            statements.push(format!("USE {}", db.dialect().quote_ident(database)));
+        }
+        statements.extend(self.settings.iter().map(|(_, sql)| sql.clone()));
+        statements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.database.is_none() && self.schema.is_none() && self.settings.is_empty()
+    }
+
+    fn set(&mut self, key: String, sql: &str) {
+        match self.settings.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = sql.trim().to_string(),
+            None => self.settings.push((key, sql.trim().to_string())),
+        }
+    }
+
+    fn reset(&mut self, key: &str) {
+        self.settings.retain(|(k, _)| k != key);
+    }
+}
+
+/// Follow the context statements of a script that ran successfully.
+pub fn observe(db: DbType, ctx: &mut SessionContext, sql: &str) {
+    if db == DbType::Sqlite {
+        return;
+    }
+    let statements = match db {
+        DbType::Postgres => crate::sql::split_postgres_statements(sql),
+        _ => crate::sql::split_sql_statements(sql),
+    };
+    for stmt in &statements {
+        observe_statement(db, ctx, stmt);
+    }
+}
+
+fn observe_statement(db: DbType, ctx: &mut SessionContext, stmt: &str) {
+    let words = crate::drivers::transaction::leading_words(stmt, 4);
+    let word = |i: usize| words.get(i).map(|w| w.to_ascii_uppercase()).unwrap_or_default();
+    match word(0).as_str() {
+        "USE" if matches!(db, DbType::Mysql | DbType::Mssql) => {
+            if let Some(name) = words.get(1) {
+                ctx.database = Some(crate::drivers::transaction::unquote(name));
+                ctx.schema = None;
+            }
+        }
+        "SET" => {
+            let mut i = 1;
+            if word(i) == "SESSION" {
+                i += 1;
+            }
+            let mut key = setting_key(words.get(i).copied().unwrap_or_default());
+            if db == DbType::Mssql && key == "STATISTICS" {
+                key = format!("STATISTICS {}", word(i + 1));
+            }
+            // Scoped to the transaction or the server, or not a setting;
+            // the plan switches belong to `explain`.
+            let skip = matches!(
+                key.as_str(),
+                "" | "TRANSACTION" | "LOCAL" | "GLOBAL" | "PERSIST" | "PERSIST_ONLY" | "CONSTRAINTS"
+                    | "PASSWORD" | "SHOWPLAN_XML" | "STATISTICS XML"
+            ) || key.starts_with("@@GLOBAL.")
+                || key.starts_with("@@PERSIST");
+            if !skip {
+                ctx.set(key, stmt);
+            }
+        }
+        "RESET" if db == DbType::Postgres => match word(1).as_str() {
+            "ALL" => ctx.settings.clear(),
+            _ => ctx.reset(&setting_key(words.get(1).copied().unwrap_or_default())),
+        },
+        "DISCARD" if db == DbType::Postgres && word(1) == "ALL" => ctx.settings.clear(),
+        _ => {}
+    }
+}
+
+/// The setting a `SET` names, up to `=`: `search_path`, `@v`, `NOCOUNT`.
+fn setting_key(word: &str) -> String {
+    let name = word.split(['=', ':']).next().unwrap_or_default();
+    crate::drivers::transaction::unquote(name).to_ascii_uppercase()
+}
+
+/// Whether a probe shows the connection lost: it failed without a reply
+/// from the server (`replied`), or took longer than `PROBE_TIMEOUT`.
+pub async fn probe_lost<T, E>(
+    probe: impl Future<Output = Result<T, E>>,
+    replied: impl Fn(&E) -> bool,
+) -> bool {
+    match tokio::time::timeout(PROBE_TIMEOUT, probe).await {
+        Ok(Ok(_)) => false,
+        Ok(Err(e)) => !replied(&e),
+        Err(_) => true,
+    }
+}
+
+/// What drivers fail a call with when the session holds no connection, so
+/// nothing was sent.
+pub fn session_lost() -> AppError {
+    AppError::state(SESSION_LOST)
+}
+
+const SESSION_LOST: &str = "Session connection was lost";
+
+/// What drivers fail a call with when stopping it (`stop`, a cancel or
+/// timeout) ended the session on the server, taking its transaction and
+/// context with it. The connection was dropped, so the session recovers.
+pub fn session_reset(stop: AppError) -> AppError {
+    AppError::connection_lost(format!("{}; stopping it ended the session on the server", stop.message))
+}
+
+/// Whether a call that failed with `error` may run again on a recovered
+/// session. `sql` is what the call sent, `None` for calls that only read.
+pub fn may_rerun(db: DbType, sql: Option<&str>, error: &AppError) -> bool {
+    let never_sent = error.kind == "state" && error.message == SESSION_LOST;
+    never_sent || sql.is_none_or(|sql| crate::drivers::read_only::check(db, sql).is_ok())
+}
+
+/// Tell the caller of a retried query that its session was reconnected.
+pub fn note_reconnected(results: &mut [QueryResultSet], replayed: usize) {
+    let mut text =
+        "The connection was lost; the session reconnected and the statement was run again".to_string();
+    if replayed > 0 {
+        text.push_str(&format!(" ({} session setting(s) restored)", replayed));
+    }
+    if let Some(first) = results.first_mut() {
+        first.messages.insert(0, QueryMessage { severity: MessageSeverity::Notice, code: None, text });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_session_context() {
+        let mut ctx = SessionContext::default();
+        observe(DbType::Mysql, &mut ctx, "USE `shop`; SET @limit = 10; SET SESSION sql_mode = 'ANSI'");
+        observe(DbType::Mysql, &mut ctx, "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE; set @limit=20");
+        observe(DbType::Mysql, &mut ctx, "SET @@GLOBAL.max_connections = 10");
+        assert_eq!(ctx.database.as_deref(), Some("shop"));
+        assert_eq!(
+            ctx.replay(DbType::Mysql),
+            vec!["USE `shop`", "set @limit=20", "SET SESSION sql_mode = 'ANSI'"]
+        );
+
+        let mut ctx = SessionContext::default();
+        observe(DbType::Postgres, &mut ctx, "SET search_path TO app, public; SET LOCAL work_mem = '1MB'");
+        observe(DbType::Postgres, &mut ctx, "SET statement_timeout = 0");
+        assert_eq!(
+            ctx.replay(DbType::Postgres),
+            vec!["SET search_path TO app, public", "SET statement_timeout = 0"]
+        );
+        observe(DbType::Postgres, &mut ctx, "RESET search_path");
+        assert_eq!(ctx.replay(DbType::Postgres), vec!["SET statement_timeout = 0"]);
+        observe(DbType::Postgres, &mut ctx, "DISCARD ALL");
+        assert!(ctx.is_empty());
+
+        let mut ctx = SessionContext::default();
+        observe(DbType::Mssql, &mut ctx, "USE [sales]; SET NOCOUNT ON; SET SHOWPLAN_XML ON");
+        observe(DbType::Mssql, &mut ctx, "SET STATISTICS XML ON; SET STATISTICS IO ON");
+        assert_eq!(ctx.replay(DbType::Mssql), vec!["USE [sales]", "SET NOCOUNT ON", "SET STATISTICS IO ON"]);
+
+        let mut ctx = SessionContext::default();
+        observe(DbType::Sqlite, &mut ctx, "PRAGMA foreign_keys = ON");
+        assert!(ctx.is_empty());
+    }
+
+    #[test]
+    fn reruns_only_what_cannot_repeat_a_write() {
+        let dropped = AppError::database("Query error: broken pipe");
+        assert!(may_rerun(DbType::Mysql, None, &dropped));
+        assert!(may_rerun(DbType::Mysql, Some("SELECT 1; SET @a = 1"), &dropped));
+        assert!(!may_rerun(DbType::Mysql, Some("UPDATE t SET a = 1"), &dropped));
+        assert!(may_rerun(DbType::Mysql, Some("UPDATE t SET a = 1"), &session_lost()));
+    }
+
+    #[tokio::test]
+    async fn probes_count_server_replies_as_alive() {
+        assert!(!probe_lost(async { Ok::<_, &str>(()) }, |_| false).await);
+        assert!(!probe_lost(async { Err::<(), _>("server") }, |e| *e == "server").await);
+        assert!(probe_lost(async { Err::<(), _>("io") }, |e| *e == "server").await);
+    }
+}
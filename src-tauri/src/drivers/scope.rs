@@ -0,0 +1,142 @@
+// A session's current database and schema.
+//
+// `current_sql` asks the server where a session is; `plan` turns a requested
+// database and schema into what switching takes in each dialect:
+//
+//   MySQL     USE <db>; a schema is a database
+//   Postgres  another database needs another connection (`switch_database`);
+//             the schema goes first on search_path, ahead of public
+//   MSSQL     USE <db>; the default schema belongs to the user and cannot be
+//             changed, so the schema is checked and kept for the session
+//   SQLite    one database per connection; the schema (main, temp or an
+//             attached name) is checked and kept for the session
+//
+// The `ConnectionManager` runs the statements on the session and observes
+// them, so `recovery` replays them after a dropped connection, and keeps the
+// schemas the server does not hold in the session's context.
+
+use crate::drivers::DbType;
+use crate::models::SessionScope;
+use crate::{AppError, AppResult};
+
+/// What switching a session takes, in order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Switch {
+    /// Reconnect the session to this database (Postgres).
+    pub reconnect: Option<String>,
+    pub statements: Vec<String>,
+    /// A query whose first value is NULL (or that has no row) when the
+    /// schema does not exist.
+    pub check: Option<String>,
+    /// A schema kept for the session instead of set on the server.
+    pub kept_schema: Option<String>,
+}
+
+/// A query returning the session's database and schema, in that order.
+pub fn current_sql(db: DbType) -> String {
+    let dialect = db.dialect();
+    format!("SELECT {}, {}", dialect.current_database(), dialect.current_schema())
+}
+
+/// Whether the server holds the session's schema; otherwise it is the one
+/// kept by `plan`.
+pub fn server_keeps_schema(db: DbType) -> bool {
+    matches!(db, DbType::Mysql | DbType::Postgres)
+}
+
+/// What moving a session at `current` to `database` and/or `schema` takes.
+pub fn plan(
+    db: DbType,
+    current: &SessionScope,
+    database: Option<&str>,
+    schema: Option<&str>,
+) -> AppResult<Switch> {
+    let (database, schema) = (name(database), name(schema));
+    if database.is_none() && schema.is_none() {
+        return Err(AppError::validation("Give a database or a schema to switch to"));
+    }
+    let dialect = db.dialect();
+    let mut switch = Switch::default();
+    match db {
+        DbType::Mysql => {
+            let target = match (database, schema) {
+                (Some(d), Some(s)) if d != s => {
+                    return Err(AppError::validation("In MySQL a schema is a database; give one name"))
+                }
+                (d, s) => d.or(s).unwrap_or_default(),
+            };
+            switch.statements.push(format!("USE {}", dialect.quote_ident(target)));
+        }
+        DbType::Postgres => {
+            if let Some(d) = database.filter(|d| current.database.as_deref() != Some(*d)) {
+                switch.reconnect = Some(d.to_string());
+            }
+            if let Some(s) = schema {
+                let path = match s {
+                    "public" => dialect.quote_ident(s),
+                    _ => format!("{}, public", dialect.quote_ident(s)),
+                };
+                switch.statements.push(format!("SET search_path TO {}", path));
+            }
+        }
+        DbType::Mssql => {
+            if let Some(d) = database {
+                switch.statements.push(format!("USE {}", dialect.quote_ident(d)));
+            }
+            if let Some(s) = schema {
+                switch.check = Some(format!("SELECT SCHEMA_ID({})", dialect.string_literal(s)));
+                switch.kept_schema = Some(s.to_string());
+            }
+        }
+        DbType::Sqlite => {
+            if database.is_some() {
+                return Err(AppError::validation(
+                    "SQLite has one database per connection; attach another and switch to its schema",
+                ));
+            }
+            if let Some(s) = schema {
+                let literal = dialect.string_literal(s);
+                let sql = format!("SELECT name FROM pragma_database_list WHERE name = {}", literal);
+                switch.check = Some(sql);
+                switch.kept_schema = Some(s.to_string());
+            }
+        }
+    }
+    Ok(switch)
+}
+
+/// A requested name, trimmed; `None` when blank.
+fn name(v: Option<&str>) -> Option<&str> {
+    v.map(str::trim).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_dialect_switches() {
+        let current = SessionScope { database: Some("app".into()), schema: Some("public".into()) };
+
+        let mysql = plan(DbType::Mysql, &current, None, Some("shop")).unwrap();
+        assert_eq!(mysql.statements, vec!["USE `shop`"]);
+        assert_eq!(plan(DbType::Mysql, &current, Some("a"), Some("b")).unwrap_err().kind, "validation");
+
+        let pg = plan(DbType::Postgres, &current, Some("app"), Some("sales")).unwrap();
+        assert_eq!(pg.reconnect, None);
+        assert_eq!(pg.statements, vec![r#"SET search_path TO "sales", public"#]);
+        let pg = plan(DbType::Postgres, &current, Some("reports"), None).unwrap();
+        assert_eq!(pg.reconnect.as_deref(), Some("reports"));
+        assert!(pg.statements.is_empty());
+
+        let mssql = plan(DbType::Mssql, &current, Some("crm"), Some("o'neil")).unwrap();
+        assert_eq!(mssql.statements, vec!["USE [crm]"]);
+        assert_eq!(mssql.check.as_deref(), Some("SELECT SCHEMA_ID(N'o''neil')"));
+        assert_eq!(mssql.kept_schema.as_deref(), Some("o'neil"));
+
+        assert_eq!(plan(DbType::Sqlite, &current, Some("x"), None).unwrap_err().kind, "validation");
+        let sqlite = plan(DbType::Sqlite, &current, None, Some("aux")).unwrap();
+        assert_eq!(sqlite.kept_schema.as_deref(), Some("aux"));
+        assert_eq!(plan(DbType::Postgres, &current, Some(" "), None).unwrap_err().kind, "validation");
+    }
+}
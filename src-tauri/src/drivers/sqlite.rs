@@ -1,10 +1,18 @@
 // SQLite driver. Implements `DbDriver` using `rusqlite` (bundled C build).
 //
-// SQLite is synchronous. `rusqlite::Connection` is NOT `Send` (it holds a
-// RefCell), so we cannot move it into `spawn_blocking`. Instead we keep one
-// `Connection` per profile/session inside a `Mutex<Option<Connection>>` and
-// run rusqlite calls inline while holding the guard. The Mutex already
-// serializes access, which is correct for SQLite's single-writer model.
+// SQLite is synchronous. `rusqlite::Connection` is `Send` but not `Sync` (it
+// holds a RefCell), so we keep one `Connection` per profile/session inside a
+// `Mutex<Option<Connection>>` and run short rusqlite calls inline while
+// holding the guard. The Mutex already serializes access, which is correct
+// for SQLite's single-writer model. Cursors are the exception: they step a
+// statement for as long as the grid keeps paging, so they take an owned guard
+// onto the blocking pool.
+//
+// Cancellation and timeouts use `sqlite3_interrupt` via the connection's
+// `InterruptHandle`, which is safe to call from another thread while a
+// statement is stepping.
+// Sessions share the profile's connection, so an interrupt stops whatever is
+// running on it.
 //
 // Connection target:
 //   - file_path set  -> open that file (creates if missing; ":memory:" for
@@ -15,33 +23,69 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use rusqlite::{Connection, Row};
+use rusqlite::{Connection, InterruptHandle, OpenFlags, Row, Statement};
 use tokio::sync::Mutex;
 
+use crate::drivers::cancel::{QueryCancel, Watchdog};
+use crate::drivers::cells::BinaryValues;
+use crate::drivers::{cells, columns, plan, pool};
+use crate::drivers::cursor::{self, CursorRegistry};
 use crate::drivers::{DbType, DbDriver};
 use crate::models::{
-    ColumnInfo, ConnectParams, ConnectionHandle, DatabaseInfo, QueryResultSet, SessionId, TableInfo,
+    ColumnInfo, ConnectParams, ConnectionHandle, CursorInfo, CursorPage, DatabaseInfo, PoolStats, QueryParam,
+    QueryParams, QueryPlan, QueryResultSet, ResultColumn, SessionId, TableInfo, TypeCategory,
 };
 use crate::AppError;
 
 /// A live SQLite connection guarded so it can be shared across async commands.
 type ConnSlot = Arc<Mutex<Option<Connection>>>;
 
+/// Captured at `begin_session` so `cancel` never needs the connection lock.
+struct SessionCancel {
+    interrupt: Arc<InterruptHandle>,
+    cancel: Arc<QueryCancel>,
+}
+
 pub struct SqliteDriver {
     /// One open connection per connected profile.
     conns: Mutex<HashMap<String, ConnSlot>>,
+    /// Interrupt handle of each profile's connection.
+    interrupts: Mutex<HashMap<String, Arc<InterruptHandle>>>,
     /// Pinned connections per session id.
     sessions: Mutex<HashMap<String, ConnSlot>>,
+    /// Cancellation state per session id.
+    cancels: Mutex<HashMap<String, SessionCancel>>,
+    cursors: CursorRegistry,
 }
 
 impl SqliteDriver {
     pub fn new() -> Self {
         Self {
             conns: Mutex::new(HashMap::new()),
+            interrupts: Mutex::new(HashMap::new()),
             sessions: Mutex::new(HashMap::new()),
+            cancels: Mutex::new(HashMap::new()),
+            cursors: CursorRegistry::new(),
         }
     }
 
+    /// A session's stop flag and the interrupt handle of its connection.
+    async fn stop_target(
+        &self,
+        session_id: &str,
+    ) -> Result<(Arc<QueryCancel>, Arc<InterruptHandle>), AppError> {
+        let cancels = self.cancels.lock().await;
+        cancels
+            .get(session_id)
+            .map(|c| (c.cancel.clone(), c.interrupt.clone()))
+            .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))
+    }
+
+    async fn interrupt(handle: Arc<InterruptHandle>) -> Result<(), AppError> {
+        handle.interrupt();
+        Ok(())
+    }
+
     /// Resolve the SQLite target path from connect params.
     fn resolve_path(params: &ConnectParams) -> String {
         if let Some(ref fp) = params.file_path {
@@ -56,27 +100,109 @@ impl SqliteDriver {
     }
 
     /// Open a SQLite connection (blocking).
-    fn open(path: &str) -> Result<Connection, AppError> {
-        Connection::open(path)
+    fn open(path: &str, read_only: bool) -> Result<Connection, AppError> {
+        let mut flags = OpenFlags::default();
+        if read_only {
+            flags.remove(OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE);
+            flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        }
+        Connection::open_with_flags(path, flags)
             .map_err(|e| AppError::database(format!("Failed to open SQLite database '{}': {}", path, e)))
     }
 
-    /// Convert a rusqlite ValueRef into serde_json::Value.
+    /// Convert a typed param to a SQLite value (SQLite has no date, decimal
+    /// or JSON storage class; those stay text, bools become 0/1).
+    fn bind_value(param: &QueryParam) -> Result<rusqlite::types::Value, AppError> {
+        use rusqlite::types::Value;
+        Ok(match param {
+            QueryParam::Null => Value::Null,
+            QueryParam::Bool(b) => Value::Integer(i64::from(*b)),
+            QueryParam::Int(n) => Value::Integer(*n),
+            QueryParam::Float(f) => Value::Real(*f),
+            QueryParam::Decimal(s)
+            | QueryParam::Text(s)
+            | QueryParam::Date(s)
+            | QueryParam::Time(s)
+            | QueryParam::DateTime(s) => Value::Text(s.clone()),
+            QueryParam::Bytes(b64) => Value::Blob(crate::sql::decode_bytes_param(b64)?),
+            QueryParam::Json(v) => Value::Text(v.to_string()),
+        })
+    }
+
+    /// Bind typed params to a prepared statement.
+    fn bind_all(prepared: &mut Statement, params: &QueryParams) -> Result<(), AppError> {
+        let bind_err = |e: rusqlite::Error| AppError::database(format!("Bind error: {}", e));
+        match params {
+            QueryParams::Positional(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    prepared
+                        .raw_bind_parameter(i + 1, Self::bind_value(value)?)
+                        .map_err(bind_err)?;
+                }
+            }
+            QueryParams::Named(values) => {
+                for (name, value) in values {
+                    // SQLite accepts :name, @name and $name.
+                    let mut index = None;
+                    for prefix in [":", "@", "$"] {
+                        index = prepared
+                            .parameter_index(&format!("{}{}", prefix, name))
+                            .map_err(bind_err)?;
+                        if index.is_some() {
+                            break;
+                        }
+                    }
+                    let index = index.ok_or_else(|| {
+                        AppError::validation(format!("No placeholder for parameter :{}", name))
+                    })?;
+                    prepared
+                        .raw_bind_parameter(index, Self::bind_value(value)?)
+                        .map_err(bind_err)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Metadata from the declared column types. SQLite is dynamically typed:
+    /// expressions have no declared type, and rusqlite does not expose the
+    /// origin table or nullability.
+    fn result_columns(prepared: &Statement) -> Vec<ResultColumn> {
+        prepared
+            .columns()
+            .iter()
+            .map(|col| {
+                let declared = col.decl_type().unwrap_or("");
+                let (_, first, second) = columns::parse_declared_type(declared);
+                let category = declared_category(declared);
+                let mut meta = columns::describe(col.name(), declared, category);
+                match category {
+                    TypeCategory::Decimal => {
+                        meta.precision = first;
+                        meta.scale = second;
+                    }
+                    TypeCategory::Text | TypeCategory::Bytes => meta.length = first.map(u64::from),
+                    _ => {}
+                }
+                meta
+            })
+            .collect()
+    }
+
+    /// Convert a rusqlite ValueRef to a cell (see `drivers::cells`). SQLite
+    /// stores values by storage class, not by declared type, so the class
+    /// alone decides: dates and decimals stored as text stay text.
     fn value_to_json(row: &Row, idx: usize) -> serde_json::Value {
         use rusqlite::types::ValueRef;
         match row.get_ref(idx) {
             Ok(ValueRef::Null) => serde_json::Value::Null,
-            Ok(ValueRef::Integer(i)) => serde_json::Number::from_i128(i as i128)
-                .map(serde_json::Value::Number)
-                .unwrap_or(serde_json::Value::String(i.to_string())),
-            Ok(ValueRef::Real(f)) => serde_json::Number::from_f64(f)
-                .map(serde_json::Value::Number)
-                .unwrap_or(serde_json::Value::String(f.to_string())),
+            Ok(ValueRef::Integer(i)) => cells::int(i),
+            Ok(ValueRef::Real(f)) => cells::float(f),
             Ok(ValueRef::Text(b)) => match String::from_utf8(b.to_vec()) {
                 Ok(s) => serde_json::Value::String(s),
-                Err(_) => serde_json::Value::String(format!("[binary {} bytes]", b.len())),
+                Err(_) => cells::binary_value(b),
             },
-            Ok(ValueRef::Blob(b)) => serde_json::Value::String(format!("[blob {} bytes]", b.len())),
+            Ok(ValueRef::Blob(b)) => cells::binary_value(b),
             Err(e) => serde_json::Value::String(format!("[error: {}]", e)),
         }
     }
@@ -90,22 +216,28 @@ impl DbDriver for SqliteDriver {
 
     async fn connect(&self, params: &ConnectParams) -> Result<ConnectionHandle, AppError> {
         let path = Self::resolve_path(params);
-        let conn = Self::open(&path)?;
+        let conn = Self::open(&path, params.read_only)?;
 
         let version: String = conn
             .query_row("SELECT sqlite_version()", [], |r| r.get::<_, String>(0))
             .map_err(|e| AppError::database(format!("Failed to query version: {}", e)))?;
 
+        let interrupt = Arc::new(conn.get_interrupt_handle());
         let slot: ConnSlot = Arc::new(Mutex::new(Some(conn)));
         {
             let mut conns = self.conns.lock().await;
             conns.insert(params.profile_id.clone(), slot);
         }
+        self.interrupts
+            .lock()
+            .await
+            .insert(params.profile_id.clone(), interrupt);
 
         Ok(ConnectionHandle {
             profile_id: params.profile_id.clone(),
             db_type: "sqlite".to_string(),
             server_version: version,
+            tls: None,
         })
     }
 
@@ -122,6 +254,7 @@ impl DbDriver for SqliteDriver {
                 self.end_session(&sid).await?;
             }
         }
+        self.interrupts.lock().await.remove(profile_id);
         let slot = {
             let mut conns = self.conns.lock().await;
             conns.remove(profile_id)
@@ -148,6 +281,30 @@ impl DbDriver for SqliteDriver {
         Ok(())
     }
 
+    /// A single connection, shared by the profile's sessions; it is in use
+    /// while a call holds it.
+    async fn pool_stats(&self, profile_id: &str) -> Result<PoolStats, AppError> {
+        let slot = {
+            let conns = self.conns.lock().await;
+            conns
+                .get(profile_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))?
+        };
+        let in_use = u32::from(slot.try_lock().is_err());
+        Ok(PoolStats {
+            profile_id: profile_id.to_string(),
+            db_type: DbType::Sqlite.as_str().to_string(),
+            min_connections: 1,
+            max_connections: 1,
+            open: 1,
+            in_use,
+            idle: 1 - in_use,
+            waiting: 0,
+            sessions: pool::session_count(&*self.sessions.lock().await, profile_id),
+        })
+    }
+
     async fn begin_session(&self, profile_id: &str) -> Result<SessionId, AppError> {
         let slot = {
             let conns = self.conns.lock().await;
@@ -156,25 +313,54 @@ impl DbDriver for SqliteDriver {
                 .cloned()
                 .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))?
         };
+        let interrupt = {
+            let interrupts = self.interrupts.lock().await;
+            interrupts
+                .get(profile_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("No connection for profile {}", profile_id)))?
+        };
         let session_id = format!("{}:{}", profile_id, uuid::Uuid::new_v4());
         {
             let mut sessions = self.sessions.lock().await;
             sessions.insert(session_id.clone(), slot);
         }
+        self.cancels.lock().await.insert(
+            session_id.clone(),
+            SessionCancel {
+                interrupt,
+                cancel: Arc::new(QueryCancel::new()),
+            },
+        );
         Ok(session_id)
     }
 
     async fn end_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.cursors.close_session(session_id).await;
+        self.cancels.lock().await.remove(session_id);
         let mut sessions = self.sessions.lock().await;
         sessions.remove(session_id);
         Ok(())
     }
 
+    /// A file connection does not drop.
+    async fn is_session_broken(&self, _session_id: &str) -> bool {
+        false
+    }
+
+    async fn recover_session(&self, _session_id: &str) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    async fn switch_database(&self, _session_id: &str, _database: &str) -> Result<(), AppError> {
+        Err(AppError::validation("SQLite has one database per connection"))
+    }
+
     async fn query(
         &self,
         session_id: &str,
         sql: &str,
-        _timeout_ms: Option<u64>,
+        timeout_ms: Option<u64>,
     ) -> Result<Vec<QueryResultSet>, AppError> {
         let slot = {
             let sessions = self.sessions.lock().await;
@@ -187,16 +373,22 @@ impl DbDriver for SqliteDriver {
         // SQLite executes one statement at a time; split to match the
         // multi-statement contract used by MySQL.
         let statements = crate::sql::split_sql_statements(sql);
+        let (cancel, interrupt) = self.stop_target(session_id).await?;
 
         let guard = slot.lock().await;
         let conn = guard.as_ref().ok_or_else(|| AppError::state("Connection was lost"))?;
+        // The statements step inline on this thread; the watchdog fires from
+        // another worker.
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::interrupt(interrupt));
 
         let mut results = Vec::new();
         for stmt in &statements {
+            cancel.check()?;
             let mut prepared = conn
                 .prepare(stmt)
                 .map_err(|e| AppError::database(format!("Query error [{}]: {}", stmt, e)))?;
-            let columns: Vec<String> = prepared.column_names().iter().map(|c| c.to_string()).collect();
+            let columns = Self::result_columns(&prepared);
             let col_count = columns.len();
 
             let rows = prepared
@@ -207,30 +399,130 @@ impl DbDriver for SqliteDriver {
                     }
                     Ok(vals)
                 })
-                .map_err(|e| AppError::database(format!("Query error [{}]: {}", stmt, e)))?;
+                .map_err(|e| cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e))))?;
 
             let mut result_rows = Vec::new();
             for r in rows {
-                result_rows.push(r.map_err(|e| AppError::database(format!("Row error: {}", e)))?);
+                result_rows.push(
+                    r.map_err(|e| cancel.map_err(AppError::database(format!("Row error: {}", e))))?,
+                );
             }
 
             let count = result_rows.len();
             results.push(QueryResultSet {
-                columns,
+                columns: columns::names(&columns),
+                column_meta: columns,
                 rows: result_rows,
                 affected_rows: count as u64,
                 info: format!("{} row(s) returned", count),
+                messages: vec![],
             });
         }
 
         Ok(results)
     }
 
+    async fn query_params(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Vec<QueryResultSet>, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let slot = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let (cancel, interrupt) = self.stop_target(session_id).await?;
+
+        let guard = slot.lock().await;
+        let conn = guard.as_ref().ok_or_else(|| AppError::state("Connection was lost"))?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::interrupt(interrupt));
+
+        let query_err = |e: rusqlite::Error| {
+            cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e)))
+        };
+        let mut prepared = conn.prepare(&stmt).map_err(query_err)?;
+        Self::bind_all(&mut prepared, params)?;
+
+        let columns = Self::result_columns(&prepared);
+        if columns.is_empty() {
+            let affected = prepared.raw_execute().map_err(query_err)? as u64;
+            return Ok(vec![QueryResultSet {
+                columns: vec![],
+                column_meta: vec![],
+                rows: vec![],
+                affected_rows: affected,
+                info: format!("{} row(s) affected", affected),
+                messages: vec![],
+            }]);
+        }
+        let col_count = columns.len();
+        let mut rows = prepared.raw_query();
+        let mut result_rows = Vec::new();
+        while let Some(row) = rows.next().map_err(query_err)? {
+            result_rows.push((0..col_count).map(|i| SqliteDriver::value_to_json(row, i)).collect());
+        }
+        let count = result_rows.len();
+        Ok(vec![QueryResultSet {
+            columns: columns::names(&columns),
+            column_meta: columns,
+            rows: result_rows,
+            affected_rows: count as u64,
+            info: format!("{} row(s) returned", count),
+            messages: vec![],
+        }])
+    }
+
+    async fn fetch_bytes(
+        &self,
+        session_id: &str,
+        sql: &str,
+        params: &QueryParams,
+        timeout_ms: Option<u64>,
+    ) -> Result<Option<Vec<u8>>, AppError> {
+        use rusqlite::types::ValueRef;
+
+        let stmt = cursor::single_statement(sql)?;
+        let slot = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+        let (cancel, interrupt) = self.stop_target(session_id).await?;
+
+        let guard = slot.lock().await;
+        let conn = guard.as_ref().ok_or_else(|| AppError::state("Connection was lost"))?;
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::interrupt(interrupt));
+
+        let query_err = |e: rusqlite::Error| {
+            cancel.map_err(AppError::database(format!("Query error [{}]: {}", stmt, e)))
+        };
+        let mut prepared = conn.prepare(&stmt).map_err(query_err)?;
+        Self::bind_all(&mut prepared, params)?;
+        let mut rows = prepared.raw_query();
+        let row = rows.next().map_err(query_err)?.ok_or_else(|| AppError::database("No row matched"))?;
+        Ok(match row.get_ref(0).map_err(query_err)? {
+            ValueRef::Null => None,
+            ValueRef::Blob(b) | ValueRef::Text(b) => Some(b.to_vec()),
+            ValueRef::Integer(i) => Some(i.to_string().into_bytes()),
+            ValueRef::Real(f) => Some(f.to_string().into_bytes()),
+        })
+    }
+
     async fn execute(
         &self,
         session_id: &str,
         sql: &str,
-        _timeout_ms: Option<u64>,
+        timeout_ms: Option<u64>,
     ) -> Result<u64, AppError> {
         let slot = {
             let sessions = self.sessions.lock().await;
@@ -241,20 +533,139 @@ impl DbDriver for SqliteDriver {
         };
 
         let statements = crate::sql::split_sql_statements(sql);
+        let (cancel, interrupt) = self.stop_target(session_id).await?;
 
         let guard = slot.lock().await;
         let conn = guard.as_ref().ok_or_else(|| AppError::state("Connection was lost"))?;
+        // The statements step inline on this thread; the watchdog fires from
+        // another worker.
+        cancel.reset();
+        let _watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::interrupt(interrupt));
 
         let mut total_affected: u64 = 0;
         for stmt in &statements {
-            conn.execute_batch(stmt)
-                .map_err(|e| AppError::database(format!("Execute error [{}]: {}", stmt, e)))?;
+            cancel.check()?;
+            conn.execute_batch(stmt).map_err(|e| {
+                cancel.map_err(AppError::database(format!("Execute error [{}]: {}", stmt, e)))
+            })?;
             total_affected += conn.changes();
         }
 
         Ok(total_affected)
     }
 
+    async fn open_cursor(
+        &self,
+        session_id: &str,
+        sql: &str,
+        binary: BinaryValues,
+        timeout_ms: Option<u64>,
+    ) -> Result<CursorInfo, AppError> {
+        let stmt = cursor::single_statement(sql)?;
+        let slot = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(session_id)
+                .cloned()
+                .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
+        };
+
+        let (cancel, interrupt) = self.stop_target(session_id).await?;
+
+        // Stepping a statement blocks, so the producer runs on the blocking
+        // pool; it owns the connection guard for the statement's lifetime.
+        let (chans, pending) = cursor::channel();
+        tokio::task::spawn_blocking(move || {
+            let guard = slot.blocking_lock_owned();
+            let Some(conn) = guard.as_ref() else {
+                chans.fail(AppError::state("Connection was lost"));
+                return;
+            };
+            // The timeout covers the statement producing its first row (SQLite
+            // does the work on the first step), not the paging.
+            cancel.reset();
+            let mut watchdog = Watchdog::arm(timeout_ms, cancel.clone(), Self::interrupt(interrupt));
+            let mut prepared = match conn.prepare(&stmt) {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    chans.fail(AppError::database(format!("Query error [{}]: {}", stmt, e)));
+                    return;
+                }
+            };
+            let columns = Self::result_columns(&prepared);
+            let col_count = columns.len();
+            let Some(tx) = chans.start(columns) else { return };
+
+            let mut rows = match prepared.query([]) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    let err = AppError::database(format!("Query error [{}]: {}", stmt, e));
+                    let _ = tx.blocking_send(Err(cancel.map_err(err)));
+                    return;
+                }
+            };
+            loop {
+                let item = match rows.next() {
+                    Ok(Some(row)) => {
+                        use rusqlite::types::ValueRef;
+                        let mut vals: Vec<_> =
+                            (0..col_count).map(|i| SqliteDriver::value_to_json(row, i)).collect();
+                        cells::widen_binary(binary, &mut vals, |i| match row.get_ref(i) {
+                            Ok(ValueRef::Blob(b) | ValueRef::Text(b)) => Some(b),
+                            _ => None,
+                        });
+                        Ok(vals)
+                    }
+                    Ok(None) => break,
+                    Err(e) => Err(cancel.map_err(AppError::database(format!("Row error: {}", e)))),
+                };
+                drop(watchdog.take());
+                let failed = item.is_err();
+                if tx.blocking_send(item).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let cursor = pending.ready(session_id).await?;
+        Ok(self.cursors.insert(cursor).await)
+    }
+
+    async fn fetch_cursor(&self, cursor_id: &str, max_rows: usize) -> Result<CursorPage, AppError> {
+        self.cursors.fetch(cursor_id, max_rows).await
+    }
+
+    async fn close_cursor(&self, cursor_id: &str) -> Result<(), AppError> {
+        self.cursors.close(cursor_id).await;
+        Ok(())
+    }
+
+    async fn cancel(&self, session_id: &str) -> Result<(), AppError> {
+        let (cancel, interrupt) = self.stop_target(session_id).await?;
+        cancel.request();
+        Self::interrupt(interrupt).await
+    }
+
+    async fn explain(
+        &self,
+        session_id: &str,
+        sql: &str,
+        _analyze: bool,
+        timeout_ms: Option<u64>,
+    ) -> Result<QueryPlan, AppError> {
+        // SQLite has no actual figures to report, so `analyze` is ignored
+        // and the plan says so.
+        let statement = plan::single_statement(DbType::Sqlite, sql)?;
+        let explain = format!("EXPLAIN QUERY PLAN {}", statement);
+        let results = self.query(session_id, &explain, timeout_ms).await?;
+        let rows = results.first().map(|r| r.rows.as_slice()).unwrap_or_default();
+        Ok(QueryPlan {
+            analyzed: false,
+            nodes: plan::from_sqlite_rows(rows),
+            raw: serde_json::Value::from(rows.to_vec()).to_string(),
+        })
+    }
+
     async fn list_databases(&self, _session_id: &str) -> Result<Vec<String>, AppError> {
         // SQLite has a single attached database per connection.
         Ok(vec!["main".to_string()])
@@ -297,12 +708,11 @@ impl DbDriver for SqliteDriver {
                 .cloned()
                 .ok_or_else(|| AppError::state(format!("Session not found: {}", session_id)))?
         };
-        let table = table.replace('\'', "''");
         let guard = slot.lock().await;
         let conn = guard.as_ref().ok_or_else(|| AppError::state("Connection was lost"))?;
 
         let mut stmt = conn
-            .prepare(&format!("PRAGMA table_info('{}')", table))
+            .prepare(&format!("PRAGMA table_info({})", DbType::Sqlite.dialect().string_literal(table)))
             .map_err(|e| AppError::database(format!("list_columns error: {}", e)))?;
         let rows = stmt
             .query_map([], |r| {
@@ -382,3 +792,62 @@ impl DbDriver for SqliteDriver {
         }])
     }
 }
+
+/// Type family of a declared column type: well-known names first, then
+/// SQLite's own affinity rules (https://sqlite.org/datatype3.html).
+fn declared_category(declared: &str) -> TypeCategory {
+    let (base, _, _) = columns::parse_declared_type(declared);
+    let upper = base.to_uppercase();
+    match upper.as_str() {
+        "" => return TypeCategory::Other,
+        "BOOL" | "BOOLEAN" => return TypeCategory::Bool,
+        "DATE" => return TypeCategory::Date,
+        "TIME" => return TypeCategory::Time,
+        "DATETIME" | "TIMESTAMP" => return TypeCategory::DateTime,
+        "DECIMAL" | "NUMERIC" => return TypeCategory::Decimal,
+        "JSON" | "JSONB" => return TypeCategory::Json,
+        "UUID" | "GUID" => return TypeCategory::Uuid,
+        _ => {}
+    }
+    if upper.contains("INT") {
+        TypeCategory::Int
+    } else if upper.contains("CHAR") || upper.contains("CLOB") || upper.contains("TEXT") {
+        TypeCategory::Text
+    } else if upper.contains("BLOB") {
+        TypeCategory::Bytes
+    } else if upper.contains("REAL") || upper.contains("FLOA") || upper.contains("DOUB") {
+        TypeCategory::Float
+    } else {
+        TypeCategory::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_declared_types() {
+        assert_eq!(declared_category("INTEGER"), TypeCategory::Int);
+        assert_eq!(declared_category("unsigned big int"), TypeCategory::Int);
+        assert_eq!(declared_category("VARCHAR(255)"), TypeCategory::Text);
+        assert_eq!(declared_category("DECIMAL(10,2)"), TypeCategory::Decimal);
+        assert_eq!(declared_category("double precision"), TypeCategory::Float);
+        assert_eq!(declared_category("timestamp"), TypeCategory::DateTime);
+        assert_eq!(declared_category("BLOB"), TypeCategory::Bytes);
+        assert_eq!(declared_category(""), TypeCategory::Other);
+    }
+
+    #[test]
+    fn describes_result_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER, name VARCHAR(40), price DECIMAL(8,2))")
+            .unwrap();
+        let prepared = conn.prepare("SELECT id, name, price, 1 + 1 AS two FROM t").unwrap();
+        let cols = SqliteDriver::result_columns(&prepared);
+        assert_eq!(cols[1].native_type, "VARCHAR(40)");
+        assert_eq!(cols[1].length, Some(40));
+        assert_eq!((cols[2].precision, cols[2].scale), (Some(8), Some(2)));
+        assert_eq!(cols[3].category, TypeCategory::Other);
+    }
+}
@@ -0,0 +1,178 @@
+// TDS tokens that tiberius reads but does not hand out.
+//
+// tiberius' `QueryStream` yields result metadata and rows only. DONE tokens
+// (the per-statement row counts) and INFO tokens (`PRINT`, `RAISERROR` with
+// severity 10 or lower) are consumed by its token reader and only reported as
+// `tracing` events. `TokenLog` is a subscriber scoped to one request (attach
+// it with `WithSubscriber`) that records those events in arrival order, so the
+// MSSQL driver can interleave them with the rows it reads.
+//
+// Tokens are decoded lazily as the stream is polled, so every event for a
+// statement is recorded before the stream yields the next result set.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Dispatch, Event, Level, Metadata, Subscriber};
+
+/// Module path of tiberius' token reader.
+const TOKEN_TARGET: &str = "tiberius::tds::stream::token";
+
+/// ENVCHANGE tokens are logged at INFO as well; these are the prefixes of
+/// their messages.
+const ENV_CHANGE_PREFIXES: &[&str] = &[
+    "Database change from ",
+    "Packet size change from ",
+    "SQL collation change",
+    "Begin transaction",
+    "Commit transaction",
+    "Rollback transaction",
+    "Defect transaction",
+    "Server requested routing ",
+    "Fallback mirror server: ",
+    "Ignored env change: ",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenEvent {
+    /// A statement finished; `rows` is set when the server sent a row count.
+    Done { rows: Option<u64> },
+    /// An informational message.
+    Info(String),
+}
+
+/// Token events of one request, in arrival order.
+#[derive(Clone, Default)]
+pub struct TokenLog {
+    events: Arc<Mutex<Vec<TokenEvent>>>,
+}
+
+impl TokenLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A dispatcher recording into this log.
+    pub fn dispatch(&self) -> Dispatch {
+        Dispatch::new(self.clone())
+    }
+
+    /// Take the events recorded so far.
+    pub fn drain(&self) -> Vec<TokenEvent> {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *events)
+    }
+}
+
+impl Subscriber for TokenLog {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_event() && metadata.target() == TOKEN_TARGET
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = MessageField(None);
+        event.record(&mut message);
+        let Some(message) = message.0 else { return };
+        if let Some(token) = parse_event(*event.metadata().level(), &message) {
+            let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+            events.push(token);
+        }
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+struct MessageField(Option<String>);
+
+impl Visit for MessageField {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Classify one token reader event. DONE tokens are logged at TRACE as
+/// `Done with status BitFlags<DoneStatus>(0b10001, More | Count) (3 rows left)`.
+fn parse_event(level: Level, message: &str) -> Option<TokenEvent> {
+    if level == Level::TRACE {
+        let status = message.strip_prefix("Done with status ")?;
+        let (flags, count) = match status.split_once(") (") {
+            Some((flags, count)) => (flags, count),
+            None => (status, ""),
+        };
+        let counted = flags
+            .split(['(', ',', '|', ')'])
+            .any(|flag| flag.trim() == "Count");
+        let rows = counted.then(|| {
+            count
+                .split_whitespace()
+                .next()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0)
+        });
+        return Some(TokenEvent::Done { rows });
+    }
+    if level == Level::INFO && !ENV_CHANGE_PREFIXES.iter().any(|p| message.starts_with(p)) {
+        return Some(TokenEvent::Info(message.to_string()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_token_events() {
+        let done = |m: &str| parse_event(Level::TRACE, m);
+        assert_eq!(
+            done("Done with status BitFlags<DoneStatus>(0b10001, More | Count) (3 rows left)"),
+            Some(TokenEvent::Done { rows: Some(3) })
+        );
+        assert_eq!(
+            done("Done with status BitFlags<DoneStatus>(0b10000, Count)"),
+            Some(TokenEvent::Done { rows: Some(0) })
+        );
+        assert_eq!(
+            done("Done with status BitFlags<DoneStatus>(0b1, More) (1 row left)"),
+            Some(TokenEvent::Done { rows: None })
+        );
+        assert_eq!(done("ColMetaData { columns: [] }"), None);
+
+        assert_eq!(
+            parse_event(Level::INFO, "Changed database context to 'app'."),
+            Some(TokenEvent::Info("Changed database context to 'app'.".into()))
+        );
+        assert_eq!(parse_event(Level::INFO, "Database change from 'master' to 'app'"), None);
+    }
+
+    #[tokio::test]
+    async fn records_scoped_events() {
+        use tracing::instrument::WithSubscriber;
+
+        let log = TokenLog::new();
+        async {
+            tracing::event!(target: "tiberius::tds::stream::token", Level::INFO, "{}", "hello");
+            tracing::event!(target: "other", Level::INFO, "ignored");
+        }
+        .with_subscriber(log.dispatch())
+        .await;
+        tracing::event!(target: "tiberius::tds::stream::token", Level::INFO, "outside");
+
+        assert_eq!(log.drain(), vec![TokenEvent::Info("hello".into())]);
+        assert!(log.drain().is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+// Transaction control shared by every driver.
+//
+// Transactions are plain SQL on a session's pinned connection, so the
+// statements for each dialect are built here and run with `execute`. The
+// `ConnectionManager` keeps a `TransactionState` per session: the transaction
+// commands set it, and `observe` follows the transaction statements a session
+// runs as text (`BEGIN`, `COMMIT`, `SAVEPOINT`, MySQL's implicit commit on
+// DDL), so `end_session`/`disconnect` know when work is uncommitted.
+
+use crate::drivers::DbType;
+use crate::models::{IsolationLevel, TransactionOptions, TransactionState};
+use crate::{AppError, AppResult};
+
+/// A statement's effect on the session's transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    Begin,
+    Commit,
+    Rollback,
+    Savepoint(String),
+    RollbackTo(String),
+    Release(String),
+}
+
+fn isolation_sql(level: IsolationLevel) -> &'static str {
+    match level {
+        IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+        IsolationLevel::ReadCommitted => "READ COMMITTED",
+        IsolationLevel::RepeatableRead => "REPEATABLE READ",
+        IsolationLevel::Snapshot => "SNAPSHOT",
+        IsolationLevel::Serializable => "SERIALIZABLE",
+    }
+}
+
+/// Statements that start a transaction with `options`.
+pub fn begin_statements(db: DbType, options: &TransactionOptions) -> AppResult<Vec<String>> {
+    let unsupported = |what: &str| {
+        Err(AppError::validation(format!("{} is not supported by {}", what, db.as_str())))
+    };
+    if options.isolation == Some(IsolationLevel::Snapshot) && db != DbType::Mssql {
+        return unsupported("SNAPSHOT isolation");
+    }
+    let mut statements = Vec::new();
+    match db {
+        DbType::Mysql => {
+            if let Some(level) = options.isolation {
+                // Applies to the next transaction only.
+                statements.push(format!("SET TRANSACTION ISOLATION LEVEL {}", isolation_sql(level)));
+            }
+            let mode = if options.read_only { " READ ONLY" } else { "" };
+            statements.push(format!("START TRANSACTION{}", mode));
+        }
+        DbType::Postgres => {
+            let mut begin = "BEGIN".to_string();
+            if let Some(level) = options.isolation {
+                begin.push_str(" ISOLATION LEVEL ");
+                begin.push_str(isolation_sql(level));
+            }
+            if options.read_only {
+                begin.push_str(" READ ONLY");
+            }
+            statements.push(begin);
+        }
+        DbType::Mssql => {
+            if options.read_only {
+                return unsupported("A read-only transaction");
+            }
+            if let Some(level) = options.isolation {
+                // Stays set for the session; `end_statements` restores it.
+                statements.push(format!("SET TRANSACTION ISOLATION LEVEL {}", isolation_sql(level)));
+            }
+            statements.push("BEGIN TRANSACTION".to_string());
+        }
+        DbType::Sqlite => {
+            // SQLite transactions are always serializable.
+            if matches!(options.isolation, Some(level) if level != IsolationLevel::Serializable) {
+                return unsupported("An isolation level other than SERIALIZABLE");
+            }
+            if options.read_only {
+                statements.push("PRAGMA query_only = ON".to_string());
+            }
+            statements.push("BEGIN".to_string());
+        }
+    }
+    Ok(statements)
+}
+
+/// Statements that commit (or roll back) the transaction in `state` and
+/// undo session settings `begin_statements` changed for it.
+pub fn end_statements(db: DbType, state: &TransactionState, commit: bool) -> Vec<String> {
+    let mut statements = vec![match (db, commit) {
+        (DbType::Mssql, true) => "COMMIT TRANSACTION".to_string(),
+        // A failed statement may already have rolled the transaction back.
+        (DbType::Mssql, false) => "IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION".to_string(),
+        (_, true) => "COMMIT".to_string(),
+        (_, false) => "ROLLBACK".to_string(),
+    }];
+    match db {
+        DbType::Mssql if state.isolation.is_some() => {
+            statements.push("SET TRANSACTION ISOLATION LEVEL READ COMMITTED".to_string());
+        }
+        DbType::Sqlite if state.read_only => statements.push("PRAGMA query_only = OFF".to_string()),
+        _ => {}
+    }
+    statements
+}
+
+fn savepoint_name(name: &str) -> AppResult<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::validation("A savepoint needs a name"));
+    }
+    Ok(name)
+}
+
+pub fn savepoint_sql(db: DbType, name: &str) -> AppResult<String> {
+    let name = db.dialect().quote_ident(savepoint_name(name)?);
+    Ok(match db {
+        DbType::Mssql => format!("SAVE TRANSACTION {}", name),
+        _ => format!("SAVEPOINT {}", name),
+    })
+}
+
+pub fn rollback_to_sql(db: DbType, name: &str) -> AppResult<String> {
+    let name = db.dialect().quote_ident(savepoint_name(name)?);
+    Ok(match db {
+        DbType::Mssql => format!("ROLLBACK TRANSACTION {}", name),
+        _ => format!("ROLLBACK TO SAVEPOINT {}", name),
+    })
+}
+
+/// `None` for SQL Server, which has no RELEASE: its savepoints live until
+/// the transaction ends.
+pub fn release_sql(db: DbType, name: &str) -> AppResult<Option<String>> {
+    let name = db.dialect().quote_ident(savepoint_name(name)?);
+    Ok(match db {
+        DbType::Mssql => None,
+        _ => Some(format!("RELEASE SAVEPOINT {}", name)),
+    })
+}
+
+/// Follow the transaction statements of a script that ran successfully.
+pub fn observe(db: DbType, state: &mut TransactionState, sql: &str) {
+    let statements = match db {
+        DbType::Postgres => crate::sql::split_postgres_statements(sql),
+        _ => crate::sql::split_sql_statements(sql),
+    };
+    for stmt in &statements {
+        if let Some(effect) = statement_effect(db, stmt) {
+            apply(db, state, effect);
+        }
+    }
+}
+
+/// Update `state` for one effect.
+pub fn apply(db: DbType, state: &mut TransactionState, effect: Effect) {
+    match effect {
+        Effect::Begin => state.active = true,
+        Effect::Commit | Effect::Rollback => *state = TransactionState::default(),
+        Effect::Savepoint(name) => {
+            // A SQLite savepoint outside a transaction starts one.
+            if db == DbType::Sqlite {
+                state.active = true;
+            }
+            if state.active {
+                state.savepoints.push(name);
+            }
+        }
+        Effect::RollbackTo(name) => match state.savepoints.iter().rposition(|s| *s == name) {
+            Some(i) => state.savepoints.truncate(i + 1),
+            // `ROLLBACK TRANSACTION <name>` names the transaction itself.
+            None if db == DbType::Mssql => *state = TransactionState::default(),
+            None => {}
+        },
+        Effect::Release(name) => {
+            if let Some(i) = state.savepoints.iter().rposition(|s| *s == name) {
+                state.savepoints.truncate(i);
+            }
+        }
+    }
+}
+
+/// The transaction effect of one statement, from its leading keywords.
+pub fn statement_effect(db: DbType, stmt: &str) -> Option<Effect> {
+    let words = leading_words(stmt, 4);
+    let word = |i: usize| words.get(i).map(|w| w.to_ascii_uppercase()).unwrap_or_default();
+    let is_tran = |w: &str| matches!(w, "TRAN" | "TRANSACTION" | "WORK");
+    match word(0).as_str() {
+        "BEGIN" => {
+            // In T-SQL a bare BEGIN opens a block, not a transaction.
+            let tran = is_tran(&word(1)) || word(1) == "DISTRIBUTED";
+            (db != DbType::Mssql || tran).then_some(Effect::Begin)
+        }
+        "START" if word(1) == "TRANSACTION" => Some(Effect::Begin),
+        "COMMIT" | "END" if db != DbType::Mssql || word(0) == "COMMIT" => Some(Effect::Commit),
+        "ABORT" if db == DbType::Postgres => Some(Effect::Rollback),
+        "ROLLBACK" => {
+            let mut i = 1;
+            if is_tran(&word(i)) {
+                i += 1;
+            }
+            if word(i) == "TO" {
+                i += 1;
+                if word(i) == "SAVEPOINT" {
+                    i += 1;
+                }
+                return words.get(i).map(|w| Effect::RollbackTo(unquote(w)));
+            }
+            match words.get(i) {
+                Some(name) if db == DbType::Mssql => Some(Effect::RollbackTo(unquote(name))),
+                _ => Some(Effect::Rollback),
+            }
+        }
+        "SAVEPOINT" => words.get(1).map(|w| Effect::Savepoint(unquote(w))),
+        "SAVE" if db == DbType::Mssql && is_tran(&word(1)) => {
+            words.get(2).map(|w| Effect::Savepoint(unquote(w)))
+        }
+        "RELEASE" => {
+            let i = if word(1) == "SAVEPOINT" { 2 } else { 1 };
+            words.get(i).map(|w| Effect::Release(unquote(w)))
+        }
+        // MySQL commits the open transaction before most DDL.
+        "CREATE" | "ALTER" | "DROP" | "RENAME" | "TRUNCATE"
+            if db == DbType::Mysql && word(1) != "TEMPORARY" =>
+        {
+            Some(Effect::Commit)
+        }
+        _ => None,
+    }
+}
+
+/// Up to `n` whitespace-separated words after any leading comments.
+pub(crate) fn leading_words(stmt: &str, n: usize) -> Vec<&str> {
+    let mut rest = stmt.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.split_once('\n').map_or("", |(_, r)| r).trim_start();
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/").map_or("", |(_, r)| r).trim_start();
+        } else {
+            break;
+        }
+    }
+    rest.split(|c: char| c.is_whitespace() || c == ';')
+        .filter(|w| !w.is_empty())
+        .take(n)
+        .collect()
+}
+
+/// A savepoint name as the server knows it: delimiters removed.
+pub(crate) fn unquote(word: &str) -> String {
+    let inner = word
+        .strip_prefix('"')
+        .and_then(|w| w.strip_suffix('"'))
+        .or_else(|| word.strip_prefix('`').and_then(|w| w.strip_suffix('`')))
+        .or_else(|| word.strip_prefix('[').and_then(|w| w.strip_suffix(']')));
+    inner.unwrap_or(word).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_dialect_statements() {
+        let options = TransactionOptions {
+            isolation: Some(IsolationLevel::Serializable),
+            read_only: true,
+        };
+        assert_eq!(
+            begin_statements(DbType::Postgres, &options).unwrap(),
+            vec!["BEGIN ISOLATION LEVEL SERIALIZABLE READ ONLY"]
+        );
+        assert_eq!(
+            begin_statements(DbType::Mysql, &options).unwrap(),
+            vec!["SET TRANSACTION ISOLATION LEVEL SERIALIZABLE", "START TRANSACTION READ ONLY"]
+        );
+        assert_eq!(begin_statements(DbType::Mssql, &options).unwrap_err().kind, "validation");
+
+        let state = TransactionState {
+            active: true,
+            isolation: Some(IsolationLevel::Snapshot),
+            ..Default::default()
+        };
+        assert_eq!(
+            end_statements(DbType::Mssql, &state, true),
+            vec!["COMMIT TRANSACTION", "SET TRANSACTION ISOLATION LEVEL READ COMMITTED"]
+        );
+        assert_eq!(savepoint_sql(DbType::Mysql, "a").unwrap(), "SAVEPOINT `a`");
+        assert_eq!(release_sql(DbType::Mssql, "a").unwrap(), None);
+        assert!(savepoint_sql(DbType::Sqlite, " ").is_err());
+    }
+
+    #[test]
+    fn follows_transaction_statements() {
+        let mut state = TransactionState::default();
+        observe(DbType::Postgres, &mut state, "begin; insert into t values (1); savepoint a; savepoint b");
+        assert!(state.active);
+        assert_eq!(state.savepoints, vec!["a", "b"]);
+        observe(DbType::Postgres, &mut state, "ROLLBACK TO SAVEPOINT a");
+        assert_eq!(state.savepoints, vec!["a"]);
+        observe(DbType::Postgres, &mut state, "/* done */ COMMIT");
+        assert!(!state.active);
+
+        observe(DbType::Mssql, &mut state, "BEGIN SELECT 1 END");
+        assert!(!state.active);
+        observe(DbType::Mssql, &mut state, "BEGIN TRAN; SAVE TRAN [s1]; ROLLBACK TRAN s1");
+        assert_eq!(state.savepoints, vec!["s1"]);
+        observe(DbType::Mssql, &mut state, "ROLLBACK TRANSACTION");
+        assert!(!state.active);
+
+        observe(DbType::Mysql, &mut state, "START TRANSACTION; CREATE TEMPORARY TABLE t (a int)");
+        assert!(state.active);
+        observe(DbType::Mysql, &mut state, "ALTER TABLE t ADD b int");
+        assert!(!state.active);
+    }
+}
@@ -1,6 +1,7 @@
 // Structured application error. Serialized as `{ kind, message }` so the
 // frontend can branch on error kind (unlike the legacy backend which
-// serialized to a bare string, losing the variant).
+// serialized to a bare string, losing the variant). Kinds the caller has to
+// act on carry `details` as well.
 
 use serde::Serialize;
 use std::fmt;
@@ -9,13 +10,15 @@ use std::fmt;
 pub struct AppError {
     pub kind: String,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
 }
 
 pub type AppResult<T> = Result<T, AppError>;
 
 impl AppError {
     pub fn new(kind: &str, message: impl Into<String>) -> Self {
-        Self { kind: kind.to_string(), message: message.into() }
+        Self { kind: kind.to_string(), message: message.into(), details: None }
     }
 
     pub fn database(message: impl Into<String>) -> Self {
@@ -45,6 +48,34 @@ impl AppError {
     pub fn not_implemented(message: impl Into<String>) -> Self {
         Self::new("not_implemented", message)
     }
+    /// The user cancelled a running statement (`db_cancel_query`).
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        Self::new("cancelled", message)
+    }
+    /// A session's connection dropped and could not be recovered
+    /// transparently (see `drivers::recovery`).
+    pub fn connection_lost(message: impl Into<String>) -> Self {
+        Self::new("connection_lost", message)
+    }
+    /// A statement that may write was sent on a read-only connection (see
+    /// `drivers::read_only`).
+    pub fn read_only(message: impl Into<String>) -> Self {
+        Self::new("read_only", message)
+    }
+    /// Destructive SQL held back until the caller confirms it; `details` is
+    /// a `ConfirmationRequired` (see `sql::destructive`).
+    pub fn confirmation_required(message: impl Into<String>, details: &impl Serialize) -> Self {
+        Self { details: serde_json::to_value(details).ok(), ..Self::new("confirmation_required", message) }
+    }
+    /// A keyed edit matched no row or more than one, because the row was
+    /// changed or removed since it was read; `details` says which edit.
+    pub fn conflict(message: impl Into<String>, details: &impl Serialize) -> Self {
+        Self { details: serde_json::to_value(details).ok(), ..Self::new("conflict", message) }
+    }
+    /// Destructive SQL on a server whose policy refuses it.
+    pub fn destructive(message: impl Into<String>) -> Self {
+        Self::new("destructive", message)
+    }
 }
 
 impl fmt::Display for AppError {
@@ -7,11 +7,12 @@
 //   - Commands are thin handlers that resolve services via State<T> and delegate.
 //
 // Modules:
-//   - error:     structured AppError {kind, message}
+//   - error:     structured AppError {kind, message, details}
 //   - models:    serde data structs (camelCase, matching TS interfaces)
-//   - sql:       split_sql_statements + timeout helpers
+//   - sql:       split_sql_statements + timeout helpers, per-database dialects
 //   - drivers:   DbDriver trait + MySQL/PG/SQLite/MSSQL impls
-//   - services:  ConnectionManager (sessions), credentials vault, crypto, files
+//   - services:  ConnectionManager (sessions), credentials vault, crypto, files,
+//                query history, snippets, export, import
 //   - ssh:       russh tunnel + TOFU host keys
 //   - commands:  Tauri #[command] handlers
 
@@ -27,8 +28,13 @@ pub use error::{AppError, AppResult};
 pub use services::connection::ConnectionManager;
 pub use services::credentials::CredentialService;
 pub use services::db_servers::DbServerService;
+pub use services::export::ExportService;
+pub use services::history::HistoryService;
+pub use services::snippets::SnippetService;
 pub use services::ssh_servers::SshServerService;
 
+use tauri::Manager;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Load the persisted vault (or start empty when no file exists yet).
@@ -46,6 +52,14 @@ pub fn run() {
         eprintln!("workgrid: failed to load database server registry: {e}; starting empty");
         DbServerService::default()
     });
+    let history = tauri::async_runtime::block_on(HistoryService::new()).unwrap_or_else(|e| {
+        eprintln!("workgrid: failed to load query history: {e}; starting empty");
+        HistoryService::default()
+    });
+    let snippets = tauri::async_runtime::block_on(SnippetService::new()).unwrap_or_else(|e| {
+        eprintln!("workgrid: failed to load snippet library: {e}; starting empty");
+        SnippetService::default()
+    });
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -53,6 +67,9 @@ pub fn run() {
         .manage(credentials)
         .manage(ssh_servers)
         .manage(db_servers)
+        .manage(history)
+        .manage(snippets)
+        .manage(ExportService::default())
         .invoke_handler(tauri::generate_handler![
             // Connection lifecycle
             commands::connection::db_connect,
@@ -60,11 +77,63 @@ pub fn run() {
             commands::connection::db_cancel_connect,
             commands::connection::db_list_profiles,
             commands::connection::db_ping,
+            commands::connection::db_pool_stats,
             // Sessions + queries
             commands::query::db_begin_session,
             commands::query::db_end_session,
+            commands::query::db_get_session_scope,
+            commands::query::db_set_session_scope,
             commands::query::db_query,
+            commands::query::db_query_params,
             commands::query::db_execute,
+            commands::query::db_explain,
+            commands::query::db_cancel_query,
+            commands::query::db_open_cursor,
+            commands::query::db_fetch_cursor,
+            commands::query::db_close_cursor,
+            // Binary cells
+            commands::cells::db_fetch_cell,
+            commands::cells::db_save_cell,
+            commands::cells::db_load_cell,
+            // Transactions
+            commands::transaction::db_begin_transaction,
+            commands::transaction::db_commit,
+            commands::transaction::db_rollback,
+            commands::transaction::db_savepoint,
+            commands::transaction::db_rollback_to_savepoint,
+            commands::transaction::db_release_savepoint,
+            commands::transaction::db_transaction_state,
+            // Query history
+            commands::history::history_search,
+            commands::history::history_groups,
+            commands::history::history_delete,
+            commands::history::history_clear,
+            commands::history::history_get_retention,
+            commands::history::history_set_retention,
+            // Snippets
+            commands::snippets::snippets_get_tree,
+            commands::snippets::snippets_get,
+            commands::snippets::snippets_search,
+            commands::snippets::snippets_upsert,
+            commands::snippets::snippets_create_folder,
+            commands::snippets::snippets_move_node,
+            commands::snippets::snippets_reorder_node,
+            commands::snippets::snippets_copy_node,
+            commands::snippets::snippets_rename_node,
+            commands::snippets::snippets_set_expanded,
+            commands::snippets::snippets_delete_node,
+            commands::snippets::snippets_insert,
+            commands::snippets::snippets_run,
+            // Export
+            commands::export::db_export,
+            commands::export::db_cancel_export,
+            // Import
+            commands::import::db_import_preview,
+            commands::import::db_import_create_table_sql,
+            commands::import::db_import,
+            // Row edits
+            commands::row_edits::db_preview_row_edits,
+            commands::row_edits::db_apply_row_edits,
             // Schema introspection
             commands::schema::db_list_databases,
             commands::schema::db_list_tables,
@@ -102,6 +171,15 @@ pub fn run() {
             commands::db_servers::docker_list_containers,
             commands::db_servers::ssh_docker_list_containers,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Recorded runs are written with a delay; keep the last ones.
+            if let tauri::RunEvent::Exit = event {
+                let history = app.state::<HistoryService>();
+                if let Err(e) = tauri::async_runtime::block_on(history.flush()) {
+                    eprintln!("workgrid: failed to write query history: {e}");
+                }
+            }
+        });
 }
@@ -9,12 +9,293 @@ use serde::{Deserialize, Serialize};
 pub struct QueryResultSet {
     #[serde(rename = "columns")]
     pub columns: Vec<String>,
+    /// Per-column metadata, parallel to `columns`.
+    #[serde(rename = "columnMeta")]
+    pub column_meta: Vec<ResultColumn>,
     #[serde(rename = "rows")]
     pub rows: Vec<Vec<serde_json::Value>>,
     #[serde(rename = "affectedRows")]
     pub affected_rows: u64,
     #[serde(rename = "info")]
     pub info: String,
+    /// Warnings and notices the server raised while the statement ran.
+    #[serde(rename = "messages")]
+    pub messages: Vec<QueryMessage>,
+}
+
+/// A server message attached to a result: MySQL `SHOW WARNINGS`, PostgreSQL
+/// NOTICE/WARNING, SQL Server `PRINT` and informational `RAISERROR`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryMessage {
+    pub severity: MessageSeverity,
+    /// The server's code (MySQL error number, SQLSTATE), when it sends one.
+    pub code: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MessageSeverity {
+    Info,
+    Notice,
+    Warning,
+    Error,
+}
+
+/// Describes one result column from the driver's protocol column descriptor.
+/// Anything the protocol does not report is `None`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultColumn {
+    pub name: String,
+    /// The server's own type name (`VARCHAR`, `int4`, `NVARCHAR`, ...).
+    pub native_type: String,
+    pub category: TypeCategory,
+    pub nullable: Option<bool>,
+    /// Total digits for exact numerics.
+    pub precision: Option<u32>,
+    /// Digits after the decimal point; fractional-second digits for
+    /// temporal types.
+    pub scale: Option<u32>,
+    /// Declared maximum length of string and binary types.
+    pub length: Option<u64>,
+    /// Where the column came from, when it maps straight to a table column.
+    pub schema: Option<String>,
+    pub table: Option<String>,
+    pub column: Option<String>,
+}
+
+/// Driver-independent type family, so the grid can align and format cells
+/// without knowing every dialect's type names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TypeCategory {
+    Bool,
+    Int,
+    Decimal,
+    Float,
+    Text,
+    Bytes,
+    Date,
+    Time,
+    DateTime,
+    Json,
+    Uuid,
+    Other,
+}
+
+/// An open server-side cursor (see `drivers::cursor`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorInfo {
+    pub cursor_id: String,
+    pub session_id: String,
+    pub columns: Vec<String>,
+    pub column_meta: Vec<ResultColumn>,
+}
+
+/// One page of rows pulled from a cursor. `done` means the result is
+/// exhausted and the cursor has been released.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPage {
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub done: bool,
+    /// Rows fetched from this cursor so far, this page included.
+    pub fetched_rows: u64,
+}
+
+//  ------ Bind parameters (db_query_params)
+
+/// A typed bind value. Wire format is `{ "type": "int", "value": 42 }`;
+/// `null` has no value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum QueryParam {
+    Null,
+    Bool(bool),
+    Int(i64),
+    /// Exact numeric as text, so no precision is lost on the way.
+    Decimal(String),
+    Float(f64),
+    Text(String),
+    /// Base64-encoded bytes.
+    Bytes(String),
+    /// `YYYY-MM-DD`.
+    Date(String),
+    /// `HH:MM:SS[.fff]`.
+    Time(String),
+    /// `YYYY-MM-DD HH:MM:SS[.fff]`.
+    DateTime(String),
+    Json(serde_json::Value),
+}
+
+/// Bind values for one statement: `{ "positional": [...] }` for the
+/// driver's native placeholders (`?`, `$1`, `@P1`), or `{ "named": {...} }`
+/// for `:name` placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryParams {
+    Positional(Vec<QueryParam>),
+    Named(std::collections::BTreeMap<String, QueryParam>),
+}
+
+//  ------ Binary cells (db_fetch_cell / db_save_cell / db_load_cell)
+
+/// Locates one cell of a table: `column` of the row whose `key` columns
+/// (normally the primary key) have the given values. `schema` is the
+/// qualifier as reported in `ResultColumn.schema`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellRef {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub column: String,
+    pub key: std::collections::BTreeMap<String, QueryParam>,
+}
+
+//  ------ Transactions (db_begin_transaction / db_commit / db_rollback / savepoints)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    /// SQL Server only (needs ALLOW_SNAPSHOT_ISOLATION on the database).
+    Snapshot,
+    Serializable,
+}
+
+/// Options for `db_begin_transaction`. Unset fields keep the server's
+/// session defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionOptions {
+    #[serde(default)]
+    pub isolation: Option<IsolationLevel>,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// What a session's transaction looks like, as far as the statements it ran
+/// through WorkGrid tell. `isolation`/`read_only` are only known for
+/// transactions started with `db_begin_transaction`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionState {
+    pub active: bool,
+    pub isolation: Option<IsolationLevel>,
+    pub read_only: bool,
+    /// Open savepoints, oldest first.
+    pub savepoints: Vec<String>,
+}
+
+/// What `db_end_session`/`db_disconnect` do when a session still has an
+/// open transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenTransactionPolicy {
+    /// Fail and leave the session (and its transaction) as it is.
+    #[default]
+    Refuse,
+    /// Roll the transaction back first.
+    Rollback,
+}
+
+/// The database and schema a session resolves unqualified names against.
+/// In MySQL a schema is a database, so both are the same; SQLite has no
+/// database name, and its schema is `main` or an attached one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionScope {
+    pub database: Option<String>,
+    pub schema: Option<String>,
+}
+
+//  ------ Destructive statements (see `sql::destructive`)
+
+/// What a server's connections do with destructive statements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DestructivePolicy {
+    /// Hold them back until the caller confirms with a token.
+    #[default]
+    Confirm,
+    /// Run them like any other statement.
+    Allow,
+    /// Never run them.
+    Refuse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DestructiveKind {
+    UpdateWithoutWhere,
+    DeleteWithoutWhere,
+    Truncate,
+    DropDatabase,
+    DropSchema,
+    DropTable,
+}
+
+/// One destructive statement of a script.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DestructiveStatement {
+    /// Position in the script, from 0.
+    pub index: usize,
+    pub kind: DestructiveKind,
+    /// The table, schema or database it acts on, as written.
+    pub object: Option<String>,
+    /// What running it does, e.g. "Deletes every row of orders".
+    pub impact: String,
+    /// The statement, shortened for display.
+    pub statement: String,
+}
+
+/// The `details` of a `confirmation_required` error: send the same SQL
+/// again with `token` to run it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationRequired {
+    pub token: String,
+    pub statements: Vec<DestructiveStatement>,
+    pub expires_in_secs: u64,
+}
+
+//  ------ Query plans (db_explain)
+
+/// A statement's execution plan, normalized across drivers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryPlan {
+    /// The statement was run and the actual figures are filled in.
+    pub analyzed: bool,
+    /// Root operations; one per statement of the explained SQL.
+    pub nodes: Vec<PlanNode>,
+    /// The server's own plan output (JSON, XML or text).
+    pub raw: String,
+}
+
+/// One operation of a plan. Costs are in the server's own units; actual
+/// rows and time are totals over all loops of the operation, time in ms
+/// and including the children.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanNode {
+    pub operation: String,
+    /// The table, index or view the operation reads.
+    pub object: Option<String>,
+    pub estimated_rows: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub cost: Option<f64>,
+    pub time_ms: Option<f64>,
+    /// Remaining properties the server reported, as display text.
+    pub details: std::collections::BTreeMap<String, String>,
+    pub children: Vec<PlanNode>,
 }
 
 //  ------ Schema introspection
@@ -111,6 +392,10 @@ pub struct ConnectParams {
     pub ssl_key_file: Option<String>,
     #[serde(default)]
     pub ssl_reject_unauthorized: bool,
+    /// libpq-style sslmode (Postgres, SQL Server). Unset means the driver
+    /// derives it from `ssl` (and, for Postgres, `ssl_reject_unauthorized`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_mode: Option<SslMode>,
     #[serde(default)]
     pub db_type: String,
     #[serde(default)]
@@ -139,12 +424,74 @@ pub struct ConnectParams {
     pub docker_container: Option<String>,
     #[serde(default)]
     pub connection_verbose_logging: bool,
+    /// Connection pool settings; unset means the driver defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<PoolSettings>,
+    /// Open every connection read-only and refuse statements that may write
+    /// (see `drivers::read_only`).
+    #[serde(default)]
+    pub read_only: bool,
+    /// What to do with destructive statements (see `sql::destructive`).
+    #[serde(default)]
+    pub destructive: DestructivePolicy,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// How a connection negotiates TLS, named after libpq's `sslmode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+/// Connection pool settings of a profile (see `drivers::pool`). Every field
+/// is optional; timeouts of 0 are the same as unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_connections: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    /// Idle connections above the minimum are closed after this long.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+    /// Connections are closed once this old, when next idle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lifetime_secs: Option<u64>,
+    /// How long taking a connection from the pool may wait.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acquire_timeout_ms: Option<u64>,
+    /// Run on an idle connection before it is handed out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check_query: Option<String>,
+}
+
+/// Live statistics of one connected profile's pool.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    pub profile_id: String,
+    pub db_type: String,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    /// Open connections, in use or idle.
+    pub open: u32,
+    pub in_use: u32,
+    pub idle: u32,
+    /// Requests waiting for a connection.
+    pub waiting: u32,
+    /// Connections pinned by sessions (counted in `in_use`).
+    pub sessions: u32,
+}
+
 //  ------ Connection / session handles
 
 /// Opaque handle to a connected database (a pool + profile metadata).
@@ -154,6 +501,22 @@ pub struct ConnectionHandle {
     pub profile_id: String,
     pub db_type: String,
     pub server_version: String,
+    /// Transport security, for drivers that report it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsInfo>,
+}
+
+/// TLS state of a connection as negotiated on connect.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsInfo {
+    /// The mode that was requested.
+    pub mode: SslMode,
+    pub encrypted: bool,
+    /// The server certificate was checked against trusted roots.
+    pub certificate_verified: bool,
+    pub protocol: Option<String>,
+    pub cipher: Option<String>,
 }
 
 /// Opaque session id (a pinned connection for cross-command affinity).
@@ -499,6 +862,16 @@ pub struct DbServerRecord {
     pub ssh_server_id: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
+    /// Connection pool settings (see `PoolSettings`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool: Option<PoolSettings>,
+    /// Connect read-only: the backend refuses writes (e.g. production).
+    #[serde(default)]
+    pub read_only: bool,
+    /// Whether destructive statements need confirming (the default), run,
+    /// or are refused.
+    #[serde(default)]
+    pub destructive: DestructivePolicy,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub created_at: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -536,6 +909,12 @@ pub struct DbServerInput {
     pub ssh_server_id: Option<String>,
     #[serde(default)]
     pub notes: Option<String>,
+    #[serde(default)]
+    pub pool: Option<PoolSettings>,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub destructive: DestructivePolicy,
 }
 
 impl From<&DbServerRecord> for DbServerInput {
@@ -554,6 +933,9 @@ impl From<&DbServerRecord> for DbServerInput {
             docker_container: r.docker_container.clone(),
             ssh_server_id: r.ssh_server_id.clone(),
             notes: r.notes.clone(),
+            pool: r.pool.clone(),
+            read_only: r.read_only,
+            destructive: r.destructive,
         }
     }
 }
@@ -583,3 +965,496 @@ pub struct DockerContainerDto {
     pub name: String,
     pub image: String,
 }
+
+//  ------ Query history (see `services::history`)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryStatus {
+    Success,
+    Error,
+    Cancelled,
+}
+
+/// One script a session ran through `db_query`, `db_query_params` or
+/// `db_execute`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    /// The registered server, for connections made from the registry.
+    pub server_id: Option<String>,
+    pub profile_id: String,
+    pub db_type: String,
+    /// The session's database when it ran, where known.
+    pub database: Option<String>,
+    pub sql: String,
+    /// Shared by scripts that differ only in literals and layout (see
+    /// `sql::fingerprint`).
+    pub fingerprint: String,
+    /// RFC 3339, UTC.
+    pub executed_at: String,
+    pub duration_ms: u64,
+    /// Rows returned or affected; `None` when it failed.
+    pub row_count: Option<u64>,
+    pub status: HistoryStatus,
+    pub error: Option<String>,
+}
+
+/// Filters for `history_search` and `history_groups`, combined with AND.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    /// Words that must all appear in the SQL, in any case.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub server_id: Option<String>,
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub status: Option<HistoryStatus>,
+    /// RFC 3339 bounds on `executed_at`, both inclusive.
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Page size (default 200) and start, newest first.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    /// Matches before paging.
+    pub total: usize,
+}
+
+/// The runs of one fingerprint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryGroup {
+    pub fingerprint: String,
+    /// The latest run's SQL.
+    pub sql: String,
+    pub runs: usize,
+    pub errors: usize,
+    pub avg_duration_ms: u64,
+    pub last_executed_at: String,
+}
+
+/// How much history is kept; the oldest entries go first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryRetention {
+    /// 0 turns recording off.
+    pub max_entries: usize,
+    /// `None` keeps entries until `max_entries` pushes them out.
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self { max_entries: 5_000, max_age_days: Some(90) }
+    }
+}
+
+//  ------ Query snippets (see `services::snippets`)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetNodeType {
+    Folder,
+    Snippet,
+}
+
+/// A folder or a saved query in the snippet tree.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: SnippetNodeType,
+    /// `None` at the top level.
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// The database type the SQL is written for; `None` runs anywhere.
+    pub db_type: Option<String>,
+    pub sql: String,
+    /// The `${name}` placeholders of `sql`, in order of first use.
+    pub placeholders: Vec<String>,
+    /// Folders only: UI expansion state.
+    pub expanded: bool,
+    pub children: Vec<SnippetNode>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetInput {
+    /// Existing snippet to update in place. None/null creates a new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The folder to create it in; the top level when omitted.
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub db_type: Option<String>,
+    pub sql: String,
+}
+
+/// Filters for `snippets_search`, combined with AND.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetQuery {
+    /// Words that must all appear in the name, description, tags or SQL.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Snippets for this database type, and those for any.
+    #[serde(default)]
+    pub db_type: Option<String>,
+}
+
+/// A snippet's SQL for inserting into an editor, with its placeholders
+/// marked so the editor can step through them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetText {
+    pub sql: String,
+    pub placeholders: Vec<PlaceholderSpan>,
+}
+
+/// Where a `${name}` sits in `SnippetText::sql`, in UTF-16 code units as
+/// editors count them, braces included.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceholderSpan {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+//  ------ Export (see `services::export`)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    /// One array of row objects.
+    Json,
+    /// One row object per line.
+    Ndjson,
+    SqlInsert,
+    Markdown,
+}
+
+/// What to export: the rows of one statement, or a whole table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportSource {
+    Query { sql: String },
+    #[serde(rename_all = "camelCase")]
+    Table { schema: Option<String>, table: String },
+}
+
+/// When CSV/TSV fields are quoted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportQuoting {
+    /// Only fields holding the delimiter, the quote or a line break.
+    #[default]
+    Minimal,
+    /// Every field except NULLs, so they stay distinguishable.
+    All,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportEncoding {
+    #[default]
+    #[serde(rename = "utf8")]
+    Utf8,
+    /// UTF-8 with a byte order mark, for spreadsheet apps that need one.
+    #[serde(rename = "utf8Bom")]
+    Utf8Bom,
+    /// UTF-16 little endian with a byte order mark.
+    #[serde(rename = "utf16le")]
+    Utf16Le,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// CSV: `,`; TSV: a tab.
+    pub delimiter: Option<char>,
+    pub quote: char,
+    pub quoting: ExportQuoting,
+    /// Column names as the first line (CSV, TSV; Markdown always has them).
+    pub header: bool,
+    /// How NULL is written in CSV, TSV and Markdown.
+    pub null_text: String,
+    pub encoding: ExportEncoding,
+    /// Rows per INSERT statement.
+    pub batch_size: usize,
+    /// The database type the INSERT statements are written for (mysql |
+    /// postgres | sqlite | mssql); the session's when omitted.
+    pub dialect: Option<String>,
+    /// The INSERT target; the source table's name, else `export`.
+    pub table_name: Option<String>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Csv,
+            delimiter: None,
+            quote: '"',
+            quoting: ExportQuoting::Minimal,
+            header: true,
+            null_text: String::new(),
+            encoding: ExportEncoding::Utf8,
+            batch_size: 100,
+            dialect: None,
+            table_name: None,
+        }
+    }
+}
+
+/// Emitted as `export://progress` after each batch is written.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgress {
+    pub export_id: String,
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub export_id: String,
+    pub path: String,
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+//  ------ Import (see `services::import`)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportFormat {
+    Csv,
+    Tsv,
+    /// One JSON object per line; its keys are the columns.
+    Ndjson,
+}
+
+/// How a file is read. UTF-8 and UTF-16 with a byte order mark are
+/// recognised; anything else must be UTF-8.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportFileOptions {
+    pub format: ImportFormat,
+    /// CSV: `,`; TSV: a tab.
+    pub delimiter: Option<char>,
+    pub quote: char,
+    /// The first line holds the column names; otherwise they are
+    /// `column1`, `column2`, ...
+    pub header: bool,
+    /// An unquoted field with this text is NULL.
+    pub null_text: String,
+}
+
+impl Default for ImportFileOptions {
+    fn default() -> Self {
+        Self { format: ImportFormat::Csv, delimiter: None, quote: '"', header: true, null_text: String::new() }
+    }
+}
+
+/// The type a column is read as, and created with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportType {
+    Bool,
+    Int,
+    Decimal,
+    Float,
+    Date,
+    Time,
+    DateTime,
+    Text,
+    Json,
+}
+
+/// A file column with its inferred type; also the column list of a
+/// generated CREATE TABLE.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ImportType,
+    /// Whether NULLs (empty fields) were seen.
+    #[serde(default)]
+    pub nullable: bool,
+    /// Text: the longest value, in characters.
+    #[serde(default)]
+    pub length: Option<u32>,
+    /// Decimal: total digits and digits after the point.
+    #[serde(default)]
+    pub precision: Option<u32>,
+    #[serde(default)]
+    pub scale: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub columns: Vec<ImportColumn>,
+    /// The first rows, aligned with `columns`; `None` is NULL.
+    pub rows: Vec<Vec<Option<String>>>,
+    /// Rows the types were inferred from.
+    pub sampled_rows: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportTarget {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+}
+
+/// Loads file column `source` into table column `target`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportMapping {
+    pub source: String,
+    pub target: String,
+    /// How values are checked and bound. Text when omitted, leaving the
+    /// conversion to the server.
+    #[serde(default, rename = "type")]
+    pub value_type: Option<ImportType>,
+}
+
+/// What happens to a row the server (or the type check) rejects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportErrorPolicy {
+    /// Roll back the batch in progress and stop.
+    #[default]
+    Abort,
+    /// Leave the row out and go on.
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportOptions {
+    /// Rows per transaction.
+    pub batch_size: usize,
+    pub on_error: ImportErrorPolicy,
+    /// With `skip`: stop as `abort` does after this many rejected rows.
+    pub max_errors: Option<usize>,
+    /// Create the target table with these columns first.
+    pub create_table: Option<Vec<ImportColumn>>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self { batch_size: 1_000, on_error: ImportErrorPolicy::Abort, max_errors: None, create_table: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    /// The line the row starts on, 1-based.
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub rows_read: u64,
+    /// Rows committed.
+    pub inserted: u64,
+    /// Rows rejected and left out.
+    pub skipped: u64,
+    /// Stopped on a rejected row; the batch in progress was rolled back.
+    pub aborted: bool,
+    /// The first rejected rows (up to 1000).
+    pub errors: Vec<ImportRowError>,
+}
+
+//  ------ Row edits (db_preview_row_edits / db_apply_row_edits)
+
+/// Changes made to one table's rows in the grid, applied together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowEditBatch {
+    #[serde(default)]
+    pub schema: Option<String>,
+    pub table: String,
+    pub changes: Vec<RowChange>,
+}
+
+/// One row change. `key` holds the row's key columns as they were loaded
+/// (see `drivers::keys`); `values` the columns to write.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum RowChange {
+    Insert {
+        values: std::collections::BTreeMap<String, QueryParam>,
+    },
+    Update {
+        key: std::collections::BTreeMap<String, QueryParam>,
+        values: std::collections::BTreeMap<String, QueryParam>,
+    },
+    Delete {
+        key: std::collections::BTreeMap<String, QueryParam>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowEditStatement {
+    pub sql: String,
+    pub params: QueryParams,
+}
+
+/// The statements a batch runs, one per change in order.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowEditPlan {
+    /// The columns rows are matched on.
+    pub key_columns: Vec<String>,
+    pub statements: Vec<RowEditStatement>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowEditSummary {
+    pub inserted: u64,
+    pub updated: u64,
+    pub deleted: u64,
+}
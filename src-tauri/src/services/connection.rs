@@ -6,17 +6,35 @@
 //   - drivers: one Box<dyn DbDriver> per connected profile (each holds its pool)
 //   - tunnels: SSH tunnel handles per profile (for teardown)
 //   - cancel_tokens: per-profile cancellation flags for connect-in-progress
+//   - transactions: the open transaction of each session, so ending a session
+//     or disconnecting never silently drops uncommitted work
+//   - contexts: what each session selected and set, replayed when a dropped
+//     connection is recovered (see `drivers::recovery`), and the schema kept
+//     for servers that do not hold one (see `drivers::scope`)
+//   - databases, server_ids: the database each profile connected to and the
+//     saved server it came from, for the query history
+//   - read_only: profiles whose sessions may only read (see `drivers::read_only`)
+//   - destructive, confirmations: each profile's `DestructivePolicy` and the
+//     destructive scripts waiting for their token (see `sql::destructive`)
 //
 // Uses tokio::sync::RwLock (fixes the legacy std::sync::Mutex-in-async foot-gun).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::sync::RwLock;
 
-use crate::drivers::{create_driver, DbType, DbDriver};
-use crate::models::{ConnectParams, ConnectionHandle, SessionId};
+use crate::drivers::recovery::{self, SessionContext};
+use crate::drivers::transaction::{self, Effect};
+use crate::drivers::{create_driver, read_only, scope, DbType, DbDriver};
+use crate::models::{
+    ConfirmationRequired, ConnectParams, ConnectionHandle, DestructivePolicy, OpenTransactionPolicy, PoolStats,
+    SessionId, SessionScope, TransactionOptions, TransactionState,
+};
+use crate::sql::destructive;
 use crate::ssh::TunnelHandle;
 use crate::{AppError, AppResult};
 
@@ -27,6 +45,31 @@ pub struct ConnectionManager {
     tunnels: RwLock<HashMap<String, TunnelHandle>>,
     /// Per-profile cancellation flags, set during connect to allow abort.
     cancel_tokens: RwLock<HashMap<String, Arc<AtomicBool>>>,
+    /// Sessions with an open transaction (see `drivers::transaction`).
+    transactions: RwLock<HashMap<SessionId, TransactionState>>,
+    /// Replayable context of each session (see `drivers::recovery`).
+    contexts: RwLock<HashMap<SessionId, SessionContext>>,
+    /// The database each profile connected to, when it named one.
+    databases: RwLock<HashMap<String, String>>,
+    /// The saved server each profile was connected from (see
+    /// `services::db_servers`).
+    server_ids: RwLock<HashMap<String, String>>,
+    /// Profiles connected with `read_only`.
+    read_only: RwLock<HashSet<String>>,
+    /// What each profile does with destructive statements.
+    destructive: RwLock<HashMap<String, DestructivePolicy>>,
+    /// Destructive scripts held back, by confirmation token.
+    confirmations: RwLock<HashMap<String, PendingConfirmation>>,
+}
+
+/// How long a confirmation token stays valid.
+const CONFIRMATION_TTL: Duration = Duration::from_secs(120);
+
+/// A destructive script a session sent, waiting to be confirmed.
+struct PendingConfirmation {
+    session_id: SessionId,
+    sql: String,
+    expires: Instant,
 }
 
 impl ConnectionManager {
@@ -35,6 +78,13 @@ impl ConnectionManager {
             drivers: RwLock::new(HashMap::new()),
             tunnels: RwLock::new(HashMap::new()),
             cancel_tokens: RwLock::new(HashMap::new()),
+            transactions: RwLock::new(HashMap::new()),
+            contexts: RwLock::new(HashMap::new()),
+            databases: RwLock::new(HashMap::new()),
+            server_ids: RwLock::new(HashMap::new()),
+            read_only: RwLock::new(HashSet::new()),
+            destructive: RwLock::new(HashMap::new()),
+            confirmations: RwLock::new(HashMap::new()),
         }
     }
 
@@ -79,6 +129,13 @@ impl ConnectionManager {
             let mut drivers = self.drivers.write().await;
             drivers.insert(profile_id.clone(), driver);
         }
+        if let Some(database) = params.database.clone().filter(|d| !d.is_empty()) {
+            self.databases.write().await.insert(profile_id.clone(), database);
+        }
+        if params.read_only {
+            self.read_only.write().await.insert(profile_id.clone());
+        }
+        self.destructive.write().await.insert(profile_id.clone(), params.destructive);
 
         // Clear cancellation token.
         {
@@ -92,10 +149,12 @@ impl ConnectionManager {
     /// Connect with a pre-resolved address and (optionally) a pre-built
     /// tunnel — used by the db-servers feature, which resolves docker /
     /// ssh targets itself before handing the driver a plain host:port.
+    /// `server_id` names the saved server, when it is one.
     pub async fn connect_resolved(
         &self,
         params: &ConnectParams,
         tunnel: Option<TunnelHandle>,
+        server_id: Option<&str>,
     ) -> AppResult<ConnectionHandle> {
         let profile_id = params.profile_id.clone();
 
@@ -131,6 +190,16 @@ impl ConnectionManager {
             let mut drivers = self.drivers.write().await;
             drivers.insert(profile_id.clone(), driver);
         }
+        if let Some(database) = params.database.clone().filter(|d| !d.is_empty()) {
+            self.databases.write().await.insert(profile_id.clone(), database);
+        }
+        if let Some(server_id) = server_id.filter(|id| !id.is_empty()) {
+            self.server_ids.write().await.insert(profile_id.clone(), server_id.to_string());
+        }
+        if params.read_only {
+            self.read_only.write().await.insert(profile_id.clone());
+        }
+        self.destructive.write().await.insert(profile_id.clone(), params.destructive);
         {
             let mut tokens = self.cancel_tokens.write().await;
             tokens.remove(&profile_id);
@@ -140,13 +209,46 @@ impl ConnectionManager {
     }
 
     /// Disconnect a profile: end sessions, drop the driver (and its pool), tear
-    /// down the SSH tunnel.
-    pub async fn disconnect(&self, profile_id: &str) -> AppResult<()> {
+    /// down the SSH tunnel. Sessions with an open transaction are handled
+    /// per `policy`.
+    pub async fn disconnect(&self, profile_id: &str, policy: OpenTransactionPolicy) -> AppResult<()> {
+        let prefix = format!("{}:", profile_id);
+        let open: Vec<SessionId> = {
+            let transactions = self.transactions.read().await;
+            transactions.keys().filter(|sid| sid.starts_with(&prefix)).cloned().collect()
+        };
+        if !open.is_empty() {
+            if policy == OpenTransactionPolicy::Refuse {
+                return Err(AppError::state(format!(
+                    "{} session(s) have an open transaction; commit or roll back first",
+                    open.len()
+                )));
+            }
+            for session_id in &open {
+                // Best effort: closing the connections discards the
+                // transactions anyway.
+                let _ = self.end_transaction(session_id, false).await;
+            }
+        }
         self.disconnect_internal(profile_id).await;
         Ok(())
     }
 
     async fn disconnect_internal(&self, profile_id: &str) {
+        {
+            let prefix = format!("{}:", profile_id);
+            let mut transactions = self.transactions.write().await;
+            transactions.retain(|sid, _| !sid.starts_with(&prefix));
+            let mut contexts = self.contexts.write().await;
+            contexts.retain(|sid, _| !sid.starts_with(&prefix));
+        }
+        self.databases.write().await.remove(profile_id);
+        self.server_ids.write().await.remove(profile_id);
+        self.read_only.write().await.remove(profile_id);
+        self.destructive.write().await.remove(profile_id);
+        let prefix = format!("{}:", profile_id);
+        self.confirmations.write().await.retain(|_, p| !p.session_id.starts_with(&prefix));
+
         // Drop the driver (ends sessions + drops pool internally).
         let driver_opt = {
             let mut drivers = self.drivers.write().await;
@@ -189,6 +291,22 @@ impl ConnectionManager {
         drivers.keys().cloned().collect()
     }
 
+    /// Pool statistics of one connected profile, or of all of them.
+    pub async fn pool_stats(&self, profile_id: Option<&str>) -> AppResult<Vec<PoolStats>> {
+        let drivers = self.drivers.read().await;
+        let mut stats = Vec::new();
+        for (id, driver) in drivers.iter() {
+            if profile_id.is_none_or(|p| p == id) {
+                stats.push(driver.pool_stats(id).await?);
+            }
+        }
+        if let Some(id) = profile_id.filter(|_| stats.is_empty()) {
+            return Err(AppError::state(format!("No connection for profile {}", id)));
+        }
+        stats.sort_by(|a, b| a.profile_id.cmp(&b.profile_id));
+        Ok(stats)
+    }
+
     /// Get the driver for a profile. Returns a reference to the boxed driver.
     /// (We can't return a direct borrow across the RwLock guard in async, so
     /// callers use `with_driver` instead.)
@@ -215,8 +333,21 @@ impl ConnectionManager {
         driver.begin_session(profile_id).await
     }
 
-    /// End a session.
-    pub async fn end_session(&self, session_id: &str) -> AppResult<()> {
+    /// End a session. An open transaction is handled per `policy`; rolling
+    /// back must succeed, since the connection goes back to the pool.
+    pub async fn end_session(&self, session_id: &str, policy: OpenTransactionPolicy) -> AppResult<()> {
+        if self.transaction_state(session_id).await.active {
+            if policy == OpenTransactionPolicy::Refuse {
+                return Err(AppError::state(
+                    "The session has an open transaction; commit or roll it back first",
+                ));
+            }
+            self.end_transaction(session_id, false).await?;
+        }
+        self.transactions.write().await.remove(session_id);
+        self.contexts.write().await.remove(session_id);
+        self.confirmations.write().await.retain(|_, p| p.session_id != session_id);
+
         // Sessions are stored inside the driver; find which driver owns this
         // session by profile_id prefix.
         let profile_id = session_id.split(':').next().unwrap_or("");
@@ -229,6 +360,351 @@ impl ConnectionManager {
         }
     }
 
+    //  ------ Transactions
+
+    /// The tracked transaction of a session.
+    pub async fn transaction_state(&self, session_id: &str) -> TransactionState {
+        let transactions = self.transactions.read().await;
+        transactions.get(session_id).cloned().unwrap_or_default()
+    }
+
+    /// Start a transaction on a session.
+    pub async fn begin_transaction(
+        &self,
+        session_id: &str,
+        options: &TransactionOptions,
+    ) -> AppResult<TransactionState> {
+        if self.transaction_state(session_id).await.active {
+            return Err(AppError::state("A transaction is already open on this session"));
+        }
+        let db = self.session_db_type(session_id).await?;
+        let statements = transaction::begin_statements(db, options)?;
+        self.run_statements(session_id, &statements).await?;
+        let state = TransactionState {
+            active: true,
+            isolation: options.isolation,
+            read_only: options.read_only,
+            savepoints: vec![],
+        };
+        self.transactions.write().await.insert(session_id.to_string(), state.clone());
+        Ok(state)
+    }
+
+    /// Commit or roll back a session's transaction. Runs even when none is
+    /// tracked, in case one was opened in a way the tracking cannot see.
+    /// Losing the connection rolls the transaction back, which fails a
+    /// commit but is all a rollback asks for.
+    pub async fn end_transaction(&self, session_id: &str, commit: bool) -> AppResult<TransactionState> {
+        let db = self.session_db_type(session_id).await?;
+        let state = self.transaction_state(session_id).await;
+        match self.run_statements(session_id, &transaction::end_statements(db, &state, commit)).await {
+            Err(e) if !commit && e.kind == "connection_lost" => {}
+            res => res?,
+        }
+        self.transactions.write().await.remove(session_id);
+        Ok(TransactionState::default())
+    }
+
+    pub async fn savepoint(&self, session_id: &str, name: &str) -> AppResult<TransactionState> {
+        let db = self.session_db_type(session_id).await?;
+        self.run_statements(session_id, &[transaction::savepoint_sql(db, name)?]).await?;
+        Ok(self.apply_effect(session_id, db, Effect::Savepoint(name.trim().to_string())).await)
+    }
+
+    pub async fn rollback_to_savepoint(&self, session_id: &str, name: &str) -> AppResult<TransactionState> {
+        let db = self.session_db_type(session_id).await?;
+        self.run_statements(session_id, &[transaction::rollback_to_sql(db, name)?]).await?;
+        Ok(self.apply_effect(session_id, db, Effect::RollbackTo(name.trim().to_string())).await)
+    }
+
+    pub async fn release_savepoint(&self, session_id: &str, name: &str) -> AppResult<TransactionState> {
+        let db = self.session_db_type(session_id).await?;
+        if let Some(sql) = transaction::release_sql(db, name)? {
+            self.run_statements(session_id, &[sql]).await?;
+        }
+        Ok(self.apply_effect(session_id, db, Effect::Release(name.trim().to_string())).await)
+    }
+
+    /// Follow the transaction and context statements in SQL a session ran
+    /// successfully. Takes the driver's type so callers can hold the drivers
+    /// lock.
+    pub async fn observe_statements(&self, session_id: &str, db: DbType, sql: &str) {
+        {
+            let mut transactions = self.transactions.write().await;
+            let state = transactions.entry(session_id.to_string()).or_default();
+            transaction::observe(db, state, sql);
+            if !state.active {
+                transactions.remove(session_id);
+            }
+        }
+        let mut contexts = self.contexts.write().await;
+        let ctx = contexts.entry(session_id.to_string()).or_default();
+        recovery::observe(db, ctx, sql);
+        if ctx.is_empty() {
+            contexts.remove(session_id);
+        }
+    }
+
+    async fn apply_effect(&self, session_id: &str, db: DbType, effect: Effect) -> TransactionState {
+        let mut transactions = self.transactions.write().await;
+        let state = transactions.entry(session_id.to_string()).or_default();
+        transaction::apply(db, state, effect);
+        let state = state.clone();
+        if !state.active {
+            transactions.remove(session_id);
+        }
+        state
+    }
+
+    /// The database type of a session's connection.
+    pub async fn session_db_type(&self, session_id: &str) -> AppResult<DbType> {
+        let profile_id = session_id.split(':').next().unwrap_or("");
+        self.with_driver(profile_id, |driver| driver.db_type()).await
+    }
+
+    /// Run statements on a session, in order, under the default timeout.
+    async fn run_statements(&self, session_id: &str, statements: &[String]) -> AppResult<()> {
+        let profile_id = session_id.split(':').next().unwrap_or("");
+        let drivers = self.drivers.read().await;
+        let driver = drivers
+            .get(profile_id)
+            .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+        for stmt in statements {
+            let call = || driver.execute(session_id, stmt, None);
+            self.with_recovery(driver.as_ref(), session_id, Some(stmt), call).await?;
+        }
+        Ok(())
+    }
+
+    //  ------ Guardrails
+
+    /// Check SQL before a session runs it: refuse what may write on a
+    /// read-only profile, and hold destructive statements back as the
+    /// profile's `DestructivePolicy` says. `confirm_token` is the token of
+    /// an earlier `confirmation_required` error for the same SQL. Takes the
+    /// driver's type so callers can hold the drivers lock.
+    pub async fn guard_sql(
+        &self,
+        session_id: &str,
+        db: DbType,
+        sql: &str,
+        confirm_token: Option<&str>,
+    ) -> AppResult<()> {
+        let profile_id = session_id.split(':').next().unwrap_or("");
+        if self.read_only.read().await.contains(profile_id) {
+            read_only::check(db, sql)?;
+        }
+        let policy = self.destructive.read().await.get(profile_id).copied().unwrap_or_default();
+        if policy == DestructivePolicy::Allow {
+            return Ok(());
+        }
+        let statements = destructive::classify(db, sql);
+        let Some(first) = statements.first() else {
+            return Ok(());
+        };
+        if policy == DestructivePolicy::Refuse {
+            return Err(AppError::destructive(format!(
+                "The server's policy refuses destructive statements: {}",
+                first.impact
+            )));
+        }
+
+        let mut pending = self.confirmations.write().await;
+        let now = Instant::now();
+        pending.retain(|_, p| p.expires > now);
+        if let Some(token) = confirm_token {
+            // Single use, and only for the SQL it was issued for.
+            if pending.get(token).is_some_and(|p| p.session_id == session_id && p.sql == sql) {
+                pending.remove(token);
+                return Ok(());
+            }
+        }
+        let token = uuid::Uuid::new_v4().to_string();
+        let held = PendingConfirmation {
+            session_id: session_id.to_string(),
+            sql: sql.to_string(),
+            expires: now + CONFIRMATION_TTL,
+        };
+        pending.insert(token.clone(), held);
+        let impacts: Vec<&str> = statements.iter().map(|s| s.impact.as_str()).collect();
+        let message = match confirm_token {
+            Some(_) => "The confirmation expired or was for other SQL; confirm again",
+            None => "Confirm to run",
+        };
+        let message = format!("{}: {}", message, impacts.join("; "));
+        let details = ConfirmationRequired { token, statements, expires_in_secs: CONFIRMATION_TTL.as_secs() };
+        Err(AppError::confirmation_required(message, &details))
+    }
+
+    //  ------ Database and schema
+
+    /// The database and schema a session resolves unqualified names against.
+    pub async fn session_scope(&self, session_id: &str) -> AppResult<SessionScope> {
+        let db = self.session_db_type(session_id).await?;
+        let row = self.first_row(session_id, &scope::current_sql(db)).await?;
+        let text = |i: usize| row.get(i).and_then(|v| v.as_str()).map(str::to_string);
+        let mut current = SessionScope { database: text(0), schema: text(1) };
+        if !scope::server_keeps_schema(db) {
+            let contexts = self.contexts.read().await;
+            if let Some(kept) = contexts.get(session_id).and_then(|c| c.schema.clone()) {
+                current.schema = Some(kept);
+            }
+        }
+        Ok(current)
+    }
+
+    /// Move a session to another database and/or schema, and return where
+    /// it is now. Switching a Postgres session's database gives it a new
+    /// connection, so it fails while a transaction is open, and what the
+    /// session had set does not carry over.
+    pub async fn set_session_scope(
+        &self,
+        session_id: &str,
+        database: Option<&str>,
+        schema: Option<&str>,
+    ) -> AppResult<SessionScope> {
+        let db = self.session_db_type(session_id).await?;
+        let current = self.session_scope(session_id).await?;
+        let switch = scope::plan(db, &current, database, schema)?;
+        if let Some(ref target) = switch.reconnect {
+            if self.transaction_state(session_id).await.active {
+                return Err(AppError::state(
+                    "Commit or roll back the open transaction before switching database",
+                ));
+            }
+            let profile_id = session_id.split(':').next().unwrap_or("");
+            let drivers = self.drivers.read().await;
+            let driver = drivers
+                .get(profile_id)
+                .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+            driver.switch_database(session_id, target).await?;
+            let mut fresh = SessionContext::default();
+            fresh.database = Some(target.clone());
+            self.contexts.write().await.insert(session_id.to_string(), fresh);
+        }
+        self.run_statements(session_id, &switch.statements).await?;
+        for stmt in &switch.statements {
+            self.observe_statements(session_id, db, stmt).await;
+        }
+        if let Some(ref check) = switch.check {
+            let row = self.first_row(session_id, check).await?;
+            if row.first().is_none_or(|v| v.is_null()) {
+                let name = switch.kept_schema.as_deref().unwrap_or_default();
+                return Err(AppError::validation(format!("Schema {} not found", name)));
+            }
+        }
+        if let Some(kept) = switch.kept_schema {
+            self.contexts.write().await.entry(session_id.to_string()).or_default().schema = Some(kept);
+        }
+        self.session_scope(session_id).await
+    }
+
+    /// The database a session last selected, or else the one its profile
+    /// connected to. Known without asking the server, so it is cheap enough
+    /// to note on every query.
+    pub async fn session_database(&self, session_id: &str) -> Option<String> {
+        if let Some(selected) = self.contexts.read().await.get(session_id).and_then(|c| c.database.clone()) {
+            return Some(selected);
+        }
+        let profile_id = session_id.split(':').next().unwrap_or("");
+        self.databases.read().await.get(profile_id).cloned()
+    }
+
+    /// The saved server a session's profile was connected from, if any.
+    pub async fn session_server_id(&self, session_id: &str) -> Option<String> {
+        let profile_id = session_id.split(':').next().unwrap_or("");
+        self.server_ids.read().await.get(profile_id).cloned()
+    }
+
+    /// The first row of a query run on a session, empty when it has none.
+    async fn first_row(&self, session_id: &str, sql: &str) -> AppResult<Vec<serde_json::Value>> {
+        let profile_id = session_id.split(':').next().unwrap_or("");
+        let drivers = self.drivers.read().await;
+        let driver = drivers
+            .get(profile_id)
+            .ok_or_else(|| AppError::state(format!("No connection for session {}", session_id)))?;
+        let (results, _) =
+            self.with_recovery(driver.as_ref(), session_id, None, || driver.query(session_id, sql, None))
+                .await?;
+        Ok(results.into_iter().next().and_then(|r| r.rows.into_iter().next()).unwrap_or_default())
+    }
+
+    //  ------ Recovery
+
+    /// Run a session call; if it failed because the session's connection
+    /// dropped, recover the session and run the call once more when that
+    /// cannot repeat a write (see `recovery::may_rerun`). `sql` is what the
+    /// call sends, `None` for calls that only read. The second value is the
+    /// number of context statements replayed when the session was
+    /// reconnected, `None` when it was not.
+    pub async fn with_recovery<T, F, Fut>(
+        &self,
+        driver: &dyn DbDriver,
+        session_id: &str,
+        sql: Option<&str>,
+        call: F,
+    ) -> AppResult<(T, Option<usize>)>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        match call().await {
+            Ok(value) => Ok((value, None)),
+            Err(e) => {
+                let rerun = recovery::may_rerun(driver.db_type(), sql, &e);
+                let replayed = self.recover(driver, session_id, e).await?;
+                if !rerun {
+                    return Err(AppError::connection_lost(
+                        "The connection was lost while the statement ran, so whether it took effect \
+                         is unknown; the session has reconnected",
+                    ));
+                }
+                Ok((call().await?, Some(replayed)))
+            }
+        }
+    }
+
+    /// Reconnect a session whose call failed with `error`, if its connection
+    /// is gone, and replay its context. Returns `error` when the connection
+    /// is fine, and fails with `connection_lost` when a transaction was open
+    /// or the driver ended the session itself (`recovery::session_reset`).
+    async fn recover(&self, driver: &dyn DbDriver, session_id: &str, error: AppError) -> AppResult<usize> {
+        let recoverable = !matches!(error.kind.as_str(), "cancelled" | "validation");
+        if !recoverable || !driver.is_session_broken(session_id).await {
+            return Err(error);
+        }
+        let reset = error.kind == "connection_lost";
+        let lost_transaction = self.transactions.write().await.remove(session_id).is_some_and(|t| t.active);
+        driver.recover_session(session_id).await.map_err(|e| {
+            AppError::connection_lost(format!("The connection was lost and reconnecting failed: {}", e.message))
+        })?;
+        let statements = {
+            let contexts = self.contexts.read().await;
+            contexts.get(session_id).map(|c| c.replay(driver.db_type())).unwrap_or_default()
+        };
+        for stmt in &statements {
+            driver.execute(session_id, stmt, None).await.map_err(|e| {
+                AppError::connection_lost(format!(
+                    "The session reconnected but restoring `{}` failed: {}",
+                    stmt, e.message
+                ))
+            })?;
+        }
+        if reset {
+            let rolled_back = if lost_transaction { " and the open transaction rolled back" } else { "" };
+            return Err(AppError::connection_lost(format!(
+                "{}{}; the session has reconnected",
+                error.message, rolled_back
+            )));
+        }
+        if lost_transaction {
+            return Err(AppError::connection_lost(
+                "The connection was lost and the open transaction rolled back; the session has reconnected",
+            ));
+        }
+        Ok(statements.len())
+    }
+
     /// Get a reference to the driver for a profile, holding the read lock.
     /// Callers must not await while holding the returned guard in a way that
     /// deadlocks. For most operations, use the dedicated methods above.
@@ -236,3 +712,141 @@ impl ConnectionManager {
         Ok(self.drivers.read().await)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::cells::BinaryValues;
+    use crate::models::{
+        ColumnInfo, CursorInfo, CursorPage, DatabaseInfo, QueryParams, QueryPlan, QueryResultSet, TableInfo,
+    };
+    use std::sync::atomic::Ordering;
+    use std::sync::Mutex;
+
+    /// A SQL Server session as far as recovery sees it: `WAITFOR` stands
+    /// for a statement stopped by a KILL, which drops the connection.
+    #[derive(Default)]
+    struct KilledSession {
+        lost: AtomicBool,
+        ran: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl DbDriver for KilledSession {
+        fn db_type(&self) -> DbType {
+            DbType::Mssql
+        }
+        async fn connect(&self, _: &ConnectParams) -> AppResult<ConnectionHandle> {
+            unimplemented!()
+        }
+        async fn disconnect(&self, _: &str) -> AppResult<()> {
+            Ok(())
+        }
+        async fn ping(&self, _: &str) -> AppResult<()> {
+            Ok(())
+        }
+        async fn pool_stats(&self, _: &str) -> AppResult<PoolStats> {
+            unimplemented!()
+        }
+        async fn begin_session(&self, profile_id: &str) -> AppResult<SessionId> {
+            Ok(format!("{}:1", profile_id))
+        }
+        async fn end_session(&self, _: &str) -> AppResult<()> {
+            Ok(())
+        }
+        async fn is_session_broken(&self, _: &str) -> bool {
+            self.lost.load(Ordering::SeqCst)
+        }
+        async fn recover_session(&self, _: &str) -> AppResult<()> {
+            self.lost.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+        async fn switch_database(&self, _: &str, _: &str) -> AppResult<()> {
+            unimplemented!()
+        }
+        async fn query(&self, _: &str, _: &str, _: Option<u64>) -> AppResult<Vec<QueryResultSet>> {
+            unimplemented!()
+        }
+        async fn query_params(
+            &self,
+            _: &str,
+            _: &str,
+            _: &QueryParams,
+            _: Option<u64>,
+        ) -> AppResult<Vec<QueryResultSet>> {
+            unimplemented!()
+        }
+        async fn fetch_bytes(
+            &self,
+            _: &str,
+            _: &str,
+            _: &QueryParams,
+            _: Option<u64>,
+        ) -> AppResult<Option<Vec<u8>>> {
+            unimplemented!()
+        }
+        async fn execute(&self, _: &str, sql: &str, _: Option<u64>) -> AppResult<u64> {
+            self.ran.lock().unwrap().push(sql.to_string());
+            if sql.starts_with("WAITFOR") {
+                self.lost.store(true, Ordering::SeqCst);
+                return Err(recovery::session_reset(AppError::cancelled("Query cancelled by user")));
+            }
+            Ok(0)
+        }
+        async fn open_cursor(
+            &self,
+            _: &str,
+            _: &str,
+            _: BinaryValues,
+            _: Option<u64>,
+        ) -> AppResult<CursorInfo> {
+            unimplemented!()
+        }
+        async fn fetch_cursor(&self, _: &str, _: usize) -> AppResult<CursorPage> {
+            unimplemented!()
+        }
+        async fn close_cursor(&self, _: &str) -> AppResult<()> {
+            Ok(())
+        }
+        async fn cancel(&self, _: &str) -> AppResult<()> {
+            Ok(())
+        }
+        async fn explain(&self, _: &str, _: &str, _: bool, _: Option<u64>) -> AppResult<QueryPlan> {
+            unimplemented!()
+        }
+        async fn list_databases(&self, _: &str) -> AppResult<Vec<String>> {
+            unimplemented!()
+        }
+        async fn list_tables(&self, _: &str, _: &str) -> AppResult<Vec<String>> {
+            unimplemented!()
+        }
+        async fn list_columns(&self, _: &str, _: &str, _: &str) -> AppResult<Vec<ColumnInfo>> {
+            unimplemented!()
+        }
+        async fn get_tables_info(&self, _: &str, _: &str) -> AppResult<Vec<TableInfo>> {
+            unimplemented!()
+        }
+        async fn get_databases_info(&self, _: &str) -> AppResult<Vec<DatabaseInfo>> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn a_killed_session_loses_its_transaction_and_keeps_its_context() {
+        let cm = ConnectionManager::new();
+        let driver = KilledSession::default();
+        let ran = driver.ran.clone();
+        cm.drivers.write().await.insert("p".into(), Box::new(driver));
+        let session = cm.begin_session("p").await.unwrap();
+        cm.observe_statements(&session, DbType::Mssql, "USE [sales]; SET NOCOUNT ON").await;
+        cm.begin_transaction(&session, &TransactionOptions::default()).await.unwrap();
+
+        let err = cm.run_statements(&session, &["WAITFOR DELAY '01:00'".into()]).await.unwrap_err();
+        assert_eq!(err.kind, "connection_lost");
+        assert!(err.message.starts_with("Query cancelled by user; ") && err.message.contains("rolled back"));
+        assert!(!cm.transaction_state(&session).await.active);
+        assert_eq!(cm.session_database(&session).await.as_deref(), Some("sales"));
+        let ran = ran.lock().unwrap();
+        assert_eq!(ran[ran.len() - 2..], ["USE [sales]".to_string(), "SET NOCOUNT ON".to_string()]);
+    }
+}
@@ -881,6 +881,7 @@ fn unseal_secret_fields(fields: &mut CredentialFields) -> AppResult<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::files::TempHome;
 
     fn input(id: Option<String>, parent_id: Option<String>, kind: &str, name: &str, secret: &str) -> CredentialEntryInput {
         CredentialEntryInput {
@@ -900,30 +901,6 @@ mod tests {
         }
     }
 
-    // Redirect HOME for the whole test so persistence lands in a sandbox.
-    // Both tests mutate the environment, so they serialize on this lock
-    // (cargo test runs them in parallel otherwise).
-    static HOME_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
-
-    struct TempHome {
-        dir: std::path::PathBuf,
-        _guard: std::sync::MutexGuard<'static, ()>,
-    }
-    impl TempHome {
-        fn new() -> Self {
-            let guard = HOME_LOCK.lock().unwrap_or_else(|p| p.into_inner());
-            let dir = std::env::temp_dir().join(format!("workgrid-vault-test-{}", uuid::Uuid::new_v4()));
-            std::fs::create_dir_all(&dir).unwrap();
-            std::env::set_var("HOME", &dir);
-            Self { dir, _guard: guard }
-        }
-    }
-    impl Drop for TempHome {
-        fn drop(&mut self) {
-            let _ = std::fs::remove_dir_all(&self.dir);
-        }
-    }
-
     #[tokio::test]
     async fn vault_round_trip_update_in_place_and_decrypt() {
         let _home = TempHome::new();
@@ -25,6 +25,7 @@ use crate::services::crypto::{decrypt_password, encrypt_password};
 use crate::services::files::data_file_path;
 use crate::services::ssh_servers::{self, SshServerService};
 use crate::ssh::{establish_registry_tunnel, TunnelHandle, TunnelTarget};
+use crate::drivers::pool::PoolLimits;
 use crate::{drivers::create_driver, drivers::DbType, AppError, AppResult};
 
 const SERVERS_FILE: &str = "db_servers.json";
@@ -97,6 +98,9 @@ impl DbServerService {
             existing.docker_container = input.docker_container;
             existing.ssh_server_id = input.ssh_server_id;
             existing.notes = input.notes;
+            existing.pool = input.pool;
+            existing.read_only = input.read_only;
+            existing.destructive = input.destructive;
             existing.updated_at = Some(now);
             let out = existing.clone();
             drop(servers);
@@ -118,6 +122,9 @@ impl DbServerService {
             docker_container: input.docker_container,
             ssh_server_id: input.ssh_server_id,
             notes: input.notes,
+            pool: input.pool,
+            read_only: input.read_only,
+            destructive: input.destructive,
             created_at: Some(now.clone()),
             updated_at: Some(now),
         };
@@ -183,6 +190,7 @@ fn validate_input(input: &DbServerInput) -> AppResult<()> {
             require(input.docker_container.as_ref(), "container", "ssh + docker")?;
         }
     }
+    PoolLimits::new(input.pool.as_ref())?;
     Ok(())
 }
 
@@ -223,6 +231,9 @@ fn coalesce(input: &DbServerInput) -> DbServerRecord {
         docker_container: input.docker_container.clone(),
         ssh_server_id: input.ssh_server_id.clone(),
         notes: input.notes.clone(),
+        pool: input.pool.clone(),
+        read_only: input.read_only,
+        destructive: input.destructive,
         created_at: None,
         updated_at: None,
     }
@@ -259,6 +270,7 @@ pub async fn resolve_connection(
         ssl_cert_file: None,
         ssl_key_file: None,
         ssl_reject_unauthorized: true,
+        ssl_mode: None,
         db_type: server.db_type.clone(),
         ssh: false,
         ssh_host: None,
@@ -273,6 +285,9 @@ pub async fn resolve_connection(
         use_docker: false,
         docker_container: None,
         connection_verbose_logging: false,
+        pool: server.pool.clone(),
+        read_only: server.read_only,
+        destructive: server.destructive,
     };
 
     match server.connection_type {
@@ -491,7 +506,7 @@ pub async fn connect_db_server(
     vault: &CredentialService,
 ) -> AppResult<ConnectionHandle> {
     let resolved = resolve_connection(server, ssh, vault).await?;
-    manager.connect_resolved(&resolved.params, resolved.tunnel).await
+    manager.connect_resolved(&resolved.params, resolved.tunnel, Some(&server.id)).await
 }
 
 // Helper: Option<String> → Option<String> keeping non-empty only.
@@ -509,23 +524,8 @@ impl NonEmpty for String {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    static HOME_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
-    struct TempHome(std::path::PathBuf, #[allow(dead_code)] Option<std::sync::MutexGuard<'static, ()>>);
-    impl TempHome {
-        fn new() -> Self {
-            let guard = HOME_LOCK.lock().unwrap_or_else(|p| p.into_inner());
-            let dir = std::env::temp_dir().join(format!("workgrid-dbserver-test-{}", uuid::Uuid::new_v4()));
-            std::fs::create_dir_all(&dir).unwrap();
-            std::env::set_var("HOME", &dir);
-            Self(dir, Some(guard))
-        }
-    }
-    impl Drop for TempHome {
-        fn drop(&mut self) {
-            let _ = std::fs::remove_dir_all(&self.0);
-        }
-    }
+    use crate::models::{DestructivePolicy, PoolSettings};
+    use crate::services::files::TempHome;
 
     #[tokio::test]
     async fn db_server_registry_round_trip_encrypted() {
@@ -546,6 +546,9 @@ mod tests {
                 docker_container: None,
                 ssh_server_id: Some("ssh-1".into()),
                 notes: Some("behind bastion".into()),
+                pool: Some(PoolSettings { max_connections: Some(2), ..Default::default() }),
+                read_only: true,
+                destructive: DestructivePolicy::Refuse,
             })
             .await
             .unwrap();
@@ -557,6 +560,9 @@ mod tests {
         assert_eq!(list[0].name, "prod mysql");
         assert_eq!(list[0].connection_type, DbConnectionType::Ssh);
         assert_eq!(list[0].password.as_deref(), Some("s3cret"));
+        assert_eq!(list[0].pool.as_ref().and_then(|p| p.max_connections), Some(2));
+        assert!(list[0].read_only);
+        assert_eq!(list[0].destructive, DestructivePolicy::Refuse);
 
         // Encrypted at rest.
         let bytes = std::fs::read(data_file_path(SERVERS_FILE).unwrap()).unwrap();
@@ -592,9 +598,15 @@ mod tests {
             docker_container: None,
             ssh_server_id: None,
             notes: None,
+            pool: None,
+            read_only: false,
+            destructive: DestructivePolicy::Confirm,
         };
         assert!(validate_input(&base).is_err(), "tcp needs a host");
         assert!(validate_input(&DbServerInput { host: Some("h".into()), ..base.clone() }).is_ok());
+        let pool = PoolSettings { min_connections: Some(3), max_connections: Some(2), ..Default::default() };
+        let bad_pool = DbServerInput { host: Some("h".into()), pool: Some(pool), ..base.clone() };
+        assert!(validate_input(&bad_pool).is_err(), "min above max");
 
         let docker = DbServerInput { connection_type: DbConnectionType::Docker, ..base.clone() };
         assert!(validate_input(&docker).is_err(), "docker needs a container");
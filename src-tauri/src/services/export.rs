@@ -0,0 +1,561 @@
+// Result export: rows streamed from a cursor straight to a file as CSV, TSV,
+// JSON, NDJSON, SQL INSERT statements or a Markdown table.
+//
+// `ExportWriter` is the pure part: it turns column names and pages of cells
+// (see `drivers::cells`) into text, one page at a time, so memory stays
+// proportional to the page size. `Encoder` turns that text into the file's
+// encoding. `ExportService` tracks the exports in progress so they can be
+// cancelled; the streaming itself is `commands::export`.
+//
+// Tagged cells are written as their text. JSON keeps exact numbers exact
+// (`bigint` and `decimal` become bare numbers) and embeds `json` documents.
+// INSERT statements use the target dialect's identifier quoting, string
+// escaping, boolean and binary literals. Export cursors send binary values
+// whole (`BinaryValues::Whole`); a value still cut short to its preview, as
+// binary values nested in arrays and records are, fails the export rather
+// than be written incomplete.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use base64::Engine;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use crate::drivers::{cells, DbType};
+use crate::models::{ExportEncoding, ExportFormat, ExportOptions, ExportQuoting};
+use crate::{AppError, AppResult};
+
+/// The event reporting an export's progress (`ExportProgress`).
+pub const PROGRESS_EVENT: &str = "export://progress";
+/// Rows pulled from the cursor per page.
+pub const EXPORT_PAGE_ROWS: usize = 1_000;
+/// SQL Server refuses more rows in one VALUES list.
+const MSSQL_MAX_INSERT_ROWS: usize = 1_000;
+
+/// Formats an export's text, one page of rows at a time.
+pub struct ExportWriter {
+    options: ExportOptions,
+    /// The INSERT dialect.
+    db: DbType,
+    table: String,
+    delimiter: char,
+    columns: Vec<String>,
+    rows: u64,
+    /// Rows written to the INSERT statement still open.
+    in_statement: usize,
+}
+
+impl ExportWriter {
+    /// Check the options; `table` is the INSERT target.
+    pub fn new(options: ExportOptions, dialect: DbType, table: &str) -> AppResult<Self> {
+        let delimiter = options.delimiter.unwrap_or(match options.format {
+            ExportFormat::Tsv => '\t',
+            _ => ',',
+        });
+        if matches!(delimiter, '\r' | '\n') || delimiter == options.quote {
+            return Err(AppError::validation("The delimiter must differ from the quote and line breaks"));
+        }
+        if matches!(options.quote, '\r' | '\n') {
+            return Err(AppError::validation("The quote character cannot be a line break"));
+        }
+        if options.batch_size == 0 {
+            return Err(AppError::validation("batchSize must be at least 1"));
+        }
+        if table.trim().is_empty() {
+            return Err(AppError::validation("The INSERT table name is empty"));
+        }
+        Ok(Self {
+            options,
+            db: dialect,
+            table: table.to_string(),
+            delimiter,
+            columns: Vec::new(),
+            rows: 0,
+            in_statement: 0,
+        })
+    }
+
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    pub fn encoding(&self) -> ExportEncoding {
+        self.options.encoding
+    }
+
+    /// What comes before the first row.
+    pub fn begin(&mut self, columns: Vec<String>) -> String {
+        self.columns = columns;
+        let mut out = String::new();
+        match self.options.format {
+            ExportFormat::Csv | ExportFormat::Tsv if self.options.header => {
+                let names: Vec<Option<String>> = self.columns.iter().cloned().map(Some).collect();
+                self.delimited_line(&names, &mut out);
+            }
+            ExportFormat::Json => out.push('['),
+            ExportFormat::Markdown => {
+                let names: Vec<Option<String>> = self.columns.iter().cloned().map(Some).collect();
+                self.markdown_line(&names, &mut out);
+                out.push('|');
+                out.push_str(&" --- |".repeat(self.columns.len()));
+                out.push('\n');
+            }
+            _ => {}
+        }
+        out
+    }
+
+    /// One page of rows. Fails on a binary value that is not whole.
+    pub fn write_rows(&mut self, rows: &[Vec<Value>]) -> AppResult<String> {
+        let mut out = String::new();
+        for row in rows {
+            if let Some(i) = row.iter().position(holds_truncated) {
+                let column = self.columns.get(i).map_or("?", String::as_str);
+                return Err(AppError::validation(format!(
+                    "Row {}, column {}: a binary value could not be read whole, so nothing was exported",
+                    self.rows + 1,
+                    column
+                )));
+            }
+            match self.options.format {
+                ExportFormat::Csv | ExportFormat::Tsv => {
+                    let fields: Vec<Option<String>> = row.iter().map(cell_text).collect();
+                    self.delimited_line(&fields, &mut out);
+                }
+                ExportFormat::Json => {
+                    out.push_str(if self.rows == 0 { "\n  " } else { ",\n  " });
+                    self.json_object(row, &mut out);
+                }
+                ExportFormat::Ndjson => {
+                    self.json_object(row, &mut out);
+                    out.push('\n');
+                }
+                ExportFormat::SqlInsert => self.insert_row(row, &mut out),
+                ExportFormat::Markdown => {
+                    let fields: Vec<Option<String>> = row.iter().map(cell_text).collect();
+                    self.markdown_line(&fields, &mut out);
+                }
+            }
+            self.rows += 1;
+        }
+        Ok(out)
+    }
+
+    /// What comes after the last row.
+    pub fn finish(&mut self) -> String {
+        match self.options.format {
+            ExportFormat::Json if self.rows == 0 => "]\n".to_string(),
+            ExportFormat::Json => "\n]\n".to_string(),
+            ExportFormat::SqlInsert if self.in_statement > 0 => {
+                self.in_statement = 0;
+                ";\n".to_string()
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn delimited_line(&self, fields: &[Option<String>], out: &mut String) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                out.push(self.delimiter);
+            }
+            let Some(text) = field else {
+                out.push_str(&self.options.null_text);
+                continue;
+            };
+            let quote = self.options.quote;
+            let needs_quotes = match self.options.quoting {
+                ExportQuoting::All => true,
+                ExportQuoting::Never => false,
+                // An empty string is quoted when NULL is written as nothing.
+                ExportQuoting::Minimal => {
+                    text.contains([self.delimiter, quote, '\r', '\n'])
+                        || (text.is_empty() && self.options.null_text.is_empty())
+                }
+            };
+            if needs_quotes {
+                out.push(quote);
+                for c in text.chars() {
+                    if c == quote {
+                        out.push(quote);
+                    }
+                    out.push(c);
+                }
+                out.push(quote);
+            } else {
+                out.push_str(text);
+            }
+        }
+        out.push('\n');
+    }
+
+    fn markdown_line(&self, fields: &[Option<String>], out: &mut String) {
+        out.push('|');
+        for field in fields {
+            let text = field.as_deref().unwrap_or(&self.options.null_text);
+            out.push(' ');
+            out.push_str(&text.replace('|', "\\|").replace("\r\n", "<br>").replace(['\r', '\n'], "<br>"));
+            out.push_str(" |");
+        }
+        out.push('\n');
+    }
+
+    fn json_object(&self, row: &[Value], out: &mut String) {
+        out.push('{');
+        for (i, (name, cell)) in self.columns.iter().zip(row).enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&Value::from(name.as_str()).to_string());
+            out.push(':');
+            json_cell(cell, out);
+        }
+        out.push('}');
+    }
+
+    fn insert_row(&mut self, row: &[Value], out: &mut String) {
+        if self.in_statement == 0 {
+            let dialect = self.db.dialect();
+            let columns: Vec<String> = self.columns.iter().map(|c| dialect.quote_ident(c)).collect();
+            out.push_str(&format!(
+                "INSERT INTO {} ({}) VALUES\n  (",
+                dialect.quote_ident(&self.table),
+                columns.join(", ")
+            ));
+        } else {
+            out.push_str(",\n  (");
+        }
+        for (i, cell) in row.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            sql_literal(self.db, cell, out);
+        }
+        out.push(')');
+        self.in_statement += 1;
+        let batch = match self.db {
+            DbType::Mssql => self.options.batch_size.min(MSSQL_MAX_INSERT_ROWS),
+            _ => self.options.batch_size,
+        };
+        if self.in_statement >= batch {
+            out.push_str(";\n");
+            self.in_statement = 0;
+        }
+    }
+}
+
+/// Encodes export text, starting with a byte order mark where the encoding
+/// has one.
+pub struct Encoder {
+    encoding: ExportEncoding,
+    started: bool,
+}
+
+impl Encoder {
+    pub fn new(encoding: ExportEncoding) -> Self {
+        Self { encoding, started: false }
+    }
+
+    pub fn encode(&mut self, text: &str) -> Vec<u8> {
+        let first = !std::mem::replace(&mut self.started, true);
+        match self.encoding {
+            ExportEncoding::Utf8 => text.as_bytes().to_vec(),
+            ExportEncoding::Utf8Bom => {
+                let bom: &[u8] = if first { b"\xEF\xBB\xBF" } else { b"" };
+                [bom, text.as_bytes()].concat()
+            }
+            ExportEncoding::Utf16Le => {
+                let bom = first.then_some(0xFEFF);
+                bom.into_iter().chain(text.encode_utf16()).flat_map(u16::to_le_bytes).collect()
+            }
+        }
+    }
+}
+
+fn cell_type(cell: &Value) -> Option<&str> {
+    cell.get("type")?.as_str()
+}
+
+fn tagged_value(cell: &Value) -> Option<&str> {
+    cell.get("value")?.as_str()
+}
+
+/// Whether a cell is, or holds, a binary value cut short to its preview.
+fn holds_truncated(cell: &Value) -> bool {
+    match cell_type(cell) {
+        Some("array") => {
+            cell.get("items").and_then(Value::as_array).is_some_and(|items| items.iter().any(holds_truncated))
+        }
+        Some("record") => cell
+            .get("fields")
+            .and_then(Value::as_array)
+            .is_some_and(|f| f.iter().any(|f| f.get("value").is_some_and(holds_truncated))),
+        _ => cells::is_truncated(cell),
+    }
+}
+
+/// The bytes of a whole binary cell.
+fn cell_bytes(cell: &Value) -> Vec<u8> {
+    let preview = cell.get("preview").and_then(Value::as_str).unwrap_or("");
+    base64::engine::general_purpose::STANDARD.decode(preview).unwrap_or_default()
+}
+
+/// A cell as plain text; `None` for NULL. Binary values are base64, arrays
+/// and records JSON.
+fn cell_text(cell: &Value) -> Option<String> {
+    match cell {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Object(_) => Some(match cell_type(cell) {
+            Some("bytes") => cell.get("preview").and_then(Value::as_str).unwrap_or("").to_string(),
+            Some("array") | Some("record") => {
+                let mut out = String::new();
+                json_cell(cell, &mut out);
+                out
+            }
+            Some("range") => range_text(cell),
+            _ => match tagged_value(cell) {
+                Some(value) => value.to_string(),
+                None => cell.to_string(),
+            },
+        }),
+        _ => Some(cell.to_string()),
+    }
+}
+
+/// Postgres range syntax, e.g. `[1,10)`; an unbounded side is empty.
+fn range_text(cell: &Value) -> String {
+    if cell.get("empty").and_then(Value::as_bool) == Some(true) {
+        return "empty".to_string();
+    }
+    let flag = |key: &str| cell.get(key).and_then(Value::as_bool) == Some(true);
+    let bound = |key: &str| cell.get(key).and_then(cell_text).unwrap_or_default();
+    format!(
+        "{}{},{}{}",
+        if flag("lowerInclusive") { '[' } else { '(' },
+        bound("lower"),
+        bound("upper"),
+        if flag("upperInclusive") { ']' } else { ')' }
+    )
+}
+
+fn json_cell(cell: &Value, out: &mut String) {
+    let string = |s: &str, out: &mut String| out.push_str(&Value::from(s).to_string());
+    match cell_type(cell) {
+        _ if !cell.is_object() => out.push_str(&cell.to_string()),
+        Some("bigint") | Some("decimal") if tagged_value(cell).is_some_and(is_json_number) => {
+            out.push_str(tagged_value(cell).unwrap_or("0"))
+        }
+        Some("json") => {
+            let source = tagged_value(cell).unwrap_or("null");
+            match serde_json::from_str::<Value>(source) {
+                // The source text as sent, unless it would break a line.
+                Ok(_) if !source.contains(['\r', '\n']) => out.push_str(source.trim()),
+                Ok(doc) => out.push_str(&doc.to_string()),
+                Err(_) => string(source, out),
+            }
+        }
+        Some("array") => {
+            out.push('[');
+            let items = cell.get("items").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                json_cell(item, out);
+            }
+            out.push(']');
+        }
+        Some("record") => {
+            out.push('{');
+            let fields = cell.get("fields").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                string(field.get("name").and_then(Value::as_str).unwrap_or(""), out);
+                out.push(':');
+                json_cell(field.get("value").unwrap_or(&Value::Null), out);
+            }
+            out.push('}');
+        }
+        _ => string(&cell_text(cell).unwrap_or_default(), out),
+    }
+}
+
+/// Whether `s` is a number in JSON's grammar.
+fn is_json_number(s: &str) -> bool {
+    let digits = |s: &str| s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let int = digits(s);
+    if int == 0 || (int > 1 && s.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &s[int..];
+    if let Some(frac) = rest.strip_prefix('.') {
+        let n = digits(frac);
+        if n == 0 {
+            return false;
+        }
+        rest = &frac[n..];
+    }
+    if let Some(exp) = rest.strip_prefix(['e', 'E']) {
+        let exp = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+        let n = digits(exp);
+        return n > 0 && n == exp.len();
+    }
+    rest.is_empty()
+}
+
+/// A cell as a literal of `db`'s SQL.
+fn sql_literal(db: DbType, cell: &Value, out: &mut String) {
+    match cell {
+        Value::Null => out.push_str("NULL"),
+        Value::Bool(b) => out.push_str(db.dialect().bool_literal(*b)),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => out.push_str(&db.dialect().string_literal(s)),
+        _ => match cell_type(cell) {
+            Some("bigint") | Some("decimal") if tagged_value(cell).is_some_and(is_json_number) => {
+                out.push_str(tagged_value(cell).unwrap_or("0"))
+            }
+            Some("bytes") => bytes_literal(db, &cell_bytes(cell), out),
+            Some("array") if db == DbType::Postgres => {
+                let items = cell.get("items").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+                if items.is_empty() {
+                    out.push_str("'{}'");
+                    return;
+                }
+                out.push_str("ARRAY[");
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    sql_literal(db, item, out);
+                }
+                out.push(']');
+            }
+            _ => out.push_str(&db.dialect().string_literal(&cell_text(cell).unwrap_or_default())),
+        },
+    }
+}
+
+fn bytes_literal(db: DbType, bytes: &[u8], out: &mut String) {
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    match db {
+        DbType::Mysql | DbType::Sqlite => out.push_str(&format!("X'{}'", hex)),
+        DbType::Mssql => out.push_str(&format!("0x{}", hex)),
+        DbType::Postgres => out.push_str(&format!("'\\x{}'::bytea", hex)),
+    }
+}
+
+struct RunningExport {
+    session_id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The exports in progress, by the id their caller gave them.
+#[derive(Default)]
+pub struct ExportService {
+    running: RwLock<HashMap<String, RunningExport>>,
+}
+
+impl ExportService {
+    /// Register an export; the flag is raised when it is cancelled.
+    pub async fn start(&self, export_id: &str, session_id: &str) -> AppResult<Arc<AtomicBool>> {
+        if export_id.trim().is_empty() {
+            return Err(AppError::validation("exportId is empty"));
+        }
+        let mut running = self.running.write().await;
+        if running.contains_key(export_id) {
+            return Err(AppError::state(format!("Export {} is already running", export_id)));
+        }
+        let cancelled = Arc::new(AtomicBool::new(false));
+        running.insert(
+            export_id.to_string(),
+            RunningExport { session_id: session_id.to_string(), cancelled: cancelled.clone() },
+        );
+        Ok(cancelled)
+    }
+
+    pub async fn finish(&self, export_id: &str) {
+        self.running.write().await.remove(export_id);
+    }
+
+    /// Raise an export's cancel flag; returns its session.
+    pub async fn cancel(&self, export_id: &str) -> AppResult<String> {
+        let running = self.running.read().await;
+        let export = running
+            .get(export_id)
+            .ok_or_else(|| AppError::state(format!("No export {} is running", export_id)))?;
+        export.cancelled.store(true, Ordering::SeqCst);
+        Ok(export.session_id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn export(format: ExportFormat, dialect: DbType, rows: &[Vec<Value>]) -> String {
+        let options = ExportOptions { format, batch_size: 2, ..ExportOptions::default() };
+        let mut writer = ExportWriter::new(options, dialect, "t").unwrap();
+        let mut out = writer.begin(vec!["id".into(), "name".into(), "data".into()]);
+        for page in rows.chunks(2) {
+            out += &writer.write_rows(page).unwrap();
+        }
+        out + &writer.finish()
+    }
+
+    #[test]
+    fn writes_each_format() {
+        let bytes = json!({ "type": "bytes", "length": 2, "preview": "AP8=", "truncated": false });
+        let rows = vec![
+            vec![json!(1), json!("a,\"b\""), Value::Null],
+            vec![json!({ "type": "bigint", "value": "9007199254740993" }), json!(""), bytes],
+            vec![json!({ "type": "decimal", "value": "NaN" }), json!("it's\\"), json!(true)],
+        ];
+
+        assert_eq!(
+            export(ExportFormat::Csv, DbType::Mysql, &rows),
+            "id,name,data\n1,\"a,\"\"b\"\"\",\n9007199254740993,\"\",AP8=\nNaN,it's\\,true\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Json, DbType::Mysql, &rows),
+            "[\n  {\"id\":1,\"name\":\"a,\\\"b\\\"\",\"data\":null},\n  \
+             {\"id\":9007199254740993,\"name\":\"\",\"data\":\"AP8=\"},\n  \
+             {\"id\":\"NaN\",\"name\":\"it's\\\\\",\"data\":true}\n]\n"
+        );
+        assert_eq!(export(ExportFormat::Json, DbType::Mysql, &[]), "[]\n");
+        assert_eq!(
+            export(ExportFormat::SqlInsert, DbType::Mysql, &rows),
+            "INSERT INTO `t` (`id`, `name`, `data`) VALUES\n  (1, 'a,\"b\"', NULL),\n  \
+             (9007199254740993, '', X'00FF');\n\
+             INSERT INTO `t` (`id`, `name`, `data`) VALUES\n  ('NaN', 'it''s\\\\', TRUE);\n"
+        );
+        let mssql = export(ExportFormat::SqlInsert, DbType::Mssql, &rows);
+        assert!(mssql.contains("(9007199254740993, N'', 0x00FF);") && mssql.contains("N'it''s\\', 1)"));
+        let pg = export(ExportFormat::SqlInsert, DbType::Postgres, &rows[1..2]);
+        assert!(pg.contains("'\\x00FF'::bytea"));
+        assert_eq!(
+            export(ExportFormat::Markdown, DbType::Mysql, &rows[..1]),
+            "| id | name | data |\n| --- | --- | --- |\n| 1 | a,\"b\" |  |\n"
+        );
+
+        let mut writer = ExportWriter::new(ExportOptions::default(), DbType::Mysql, "t").unwrap();
+        writer.begin(vec!["a".into(), "b".into()]);
+        let cut = json!({ "type": "bytes", "length": 300, "preview": "AP8=", "truncated": true });
+        let nested = json!({ "type": "array", "items": [cut] });
+        let err = writer.write_rows(&[rows[0].clone(), vec![json!(1), nested]]).unwrap_err();
+        assert!(err.message.starts_with("Row 2, column b: "));
+        let options = ExportOptions { delimiter: Some('"'), ..ExportOptions::default() };
+        assert!(ExportWriter::new(options, DbType::Mysql, "t").is_err());
+
+        let mut utf16 = Encoder::new(ExportEncoding::Utf16Le);
+        assert_eq!(utf16.encode("a"), vec![0xFF, 0xFE, b'a', 0]);
+        assert_eq!(utf16.encode("b"), vec![b'b', 0]);
+        assert!(is_json_number("-12.5e+3") && !is_json_number("012") && !is_json_number("1."));
+    }
+}
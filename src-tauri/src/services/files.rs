@@ -33,3 +33,33 @@ pub fn data_file_path(filename: &str) -> AppResult<PathBuf> {
 pub fn secret_key_path() -> AppResult<PathBuf> {
     data_file_path("secret.key")
 }
+
+/// Points HOME at a fresh directory for one test and removes it afterwards.
+/// HOME is process-wide, so every test that touches app data holds the same
+/// lock while it runs.
+#[cfg(test)]
+pub(crate) struct TempHome {
+    dir: PathBuf,
+    _guard: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+static HOME_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+impl TempHome {
+    pub(crate) fn new() -> Self {
+        let guard = HOME_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+        let dir = std::env::temp_dir().join(format!("workgrid-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        std::env::set_var("HOME", &dir);
+        Self { dir, _guard: guard }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempHome {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
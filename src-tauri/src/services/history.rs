@@ -0,0 +1,425 @@
+// Query history: every script a session runs through `db_query`,
+// `db_query_params` or `db_execute`, with where and when it ran, how long it
+// took, the rows it returned or affected, and how it ended. Statements the
+// guardrails hold back never reach the server and are not recorded.
+//
+// Entries carry the `sql::fingerprint` of their SQL so runs of the same
+// statement with other literals group together. The store persists with the
+// same AES-256-GCM whole-file envelope as the registries (SQL can hold
+// secrets), and is pruned to the `HistoryRetention` limits on every write.
+//
+// Recording a run only changes the store in memory; the file is rewritten
+// at most once per `FLUSH_DELAY`, on a blocking thread, so a large history
+// adds nothing to a query's latency. Deletes and retention changes are
+// written before they return, and `flush` writes what is pending (the app
+// calls it on exit).
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::drivers::DbType;
+use crate::models::{
+    HistoryEntry, HistoryGroup, HistoryPage, HistoryQuery, HistoryRetention, HistoryStatus, QueryResultSet,
+};
+use crate::services::crypto::{decrypt_password, encrypt_password};
+use crate::services::files::data_file_path;
+use crate::sql::fingerprint;
+use crate::{AppError, AppResult};
+
+const HISTORY_FILE: &str = "query_history.json";
+
+/// Longer scripts (bulk inserts, dumps) are cut to this many characters.
+const MAX_SQL_CHARS: usize = 100_000;
+
+/// Upper bound for `HistoryRetention::max_entries`.
+const MAX_ENTRIES_LIMIT: usize = 100_000;
+
+const DEFAULT_PAGE: usize = 200;
+
+/// How long recorded runs wait to be written, so a burst of queries costs
+/// one write.
+const FLUSH_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    #[serde(default)]
+    retention: HistoryRetention,
+    #[serde(default)]
+    entries: Vec<HistoryEntry>,
+}
+
+/// A script on its way to the server, timed for the history.
+pub struct QueryRun {
+    profile_id: String,
+    server_id: Option<String>,
+    db: DbType,
+    database: Option<String>,
+    sql: String,
+    executed_at: DateTime<Utc>,
+    started: Instant,
+}
+
+impl QueryRun {
+    /// `server_id` is the saved server the session's profile connected
+    /// from, if any.
+    pub fn start(
+        session_id: &str,
+        server_id: Option<String>,
+        db: DbType,
+        database: Option<String>,
+        sql: &str,
+    ) -> Self {
+        Self {
+            profile_id: session_id.split(':').next().unwrap_or("").to_string(),
+            server_id,
+            db,
+            database,
+            sql: sql.to_string(),
+            executed_at: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// The rows a `db_query` call returned or affected, over all its results.
+pub fn row_count(results: &[QueryResultSet]) -> u64 {
+    results.iter().map(|r| r.affected_rows).sum()
+}
+
+// ---------------------------------------------------------------- - service
+
+/// Entries are kept oldest first.
+#[derive(Debug, Default)]
+pub struct HistoryService {
+    store: Arc<RwLock<HistoryFile>>,
+    /// A delayed flush is on its way.
+    scheduled: Arc<AtomicBool>,
+    /// Held while the file is written, so an older snapshot never lands
+    /// after a newer one.
+    writing: Arc<Mutex<()>>,
+}
+
+impl HistoryService {
+    pub async fn new() -> AppResult<Self> {
+        let service = Self::default();
+        service.load().await?;
+        Ok(service)
+    }
+
+    async fn load(&self) -> AppResult<()> {
+        let path = data_file_path(HISTORY_FILE)?;
+        if !path.exists() {
+            return Ok(());
+        }
+        let bytes = std::fs::read(&path)?;
+        let json = match String::from_utf8(bytes) {
+            Ok(text) if text.starts_with("wkgrd:") => decrypt_password(&text)?,
+            Ok(text) => text,
+            Err(e) => return Err(AppError::io(e.to_string())),
+        };
+        let file: HistoryFile = serde_json::from_str(&json).map_err(|e| AppError::io(e.to_string()))?;
+        *self.store.write().await = file;
+        Ok(())
+    }
+
+    /// Write the store now.
+    pub async fn flush(&self) -> AppResult<()> {
+        write(&self.store, &self.writing).await
+    }
+
+    /// Write the store after `FLUSH_DELAY`, unless a write is already
+    /// waiting; it will pick up this change too.
+    fn schedule_flush(&self) {
+        if self.scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let (store, scheduled, writing) = (self.store.clone(), self.scheduled.clone(), self.writing.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(FLUSH_DELAY).await;
+            // Cleared before the snapshot: a run recorded from here on
+            // schedules a write of its own.
+            scheduled.store(false, Ordering::SeqCst);
+            if let Err(e) = write(&store, &writing).await {
+                eprintln!("workgrid: failed to write query history: {e}");
+            }
+        });
+    }
+
+    /// Record how a run ended: the rows it returned or affected, or its
+    /// error.
+    pub async fn record(&self, run: QueryRun, outcome: Result<u64, &AppError>) -> AppResult<()> {
+        let mut store = self.store.write().await;
+        if store.retention.max_entries == 0 {
+            return Ok(());
+        }
+        let (status, row_count, error) = match outcome {
+            Ok(rows) => (HistoryStatus::Success, Some(rows), None),
+            Err(e) if e.kind == "cancelled" => (HistoryStatus::Cancelled, None, Some(e.message.clone())),
+            Err(e) => (HistoryStatus::Error, None, Some(e.message.clone())),
+        };
+        let mut sql = run.sql;
+        if let Some((cut, _)) = sql.char_indices().nth(MAX_SQL_CHARS) {
+            sql.truncate(cut);
+        }
+        store.entries.push(HistoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            server_id: run.server_id,
+            fingerprint: fingerprint::fingerprint(run.db, &sql),
+            profile_id: run.profile_id,
+            db_type: run.db.as_str().to_string(),
+            database: run.database,
+            sql,
+            executed_at: run.executed_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            duration_ms: run.started.elapsed().as_millis() as u64,
+            row_count,
+            status,
+            error,
+        });
+        let retention = store.retention.clone();
+        prune(&mut store.entries, &retention, Utc::now());
+        drop(store);
+        self.schedule_flush();
+        Ok(())
+    }
+
+    /// Matching entries, newest first.
+    pub async fn search(&self, query: &HistoryQuery) -> AppResult<HistoryPage> {
+        let filter = Filter::new(query)?;
+        let store = self.store.read().await;
+        let matching: Vec<&HistoryEntry> = store.entries.iter().rev().filter(|e| filter.matches(e)).collect();
+        let entries = matching
+            .iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.limit.unwrap_or(DEFAULT_PAGE))
+            .map(|e| (*e).clone())
+            .collect();
+        Ok(HistoryPage { entries, total: matching.len() })
+    }
+
+    /// Matching entries grouped by fingerprint, most recently run first.
+    pub async fn groups(&self, query: &HistoryQuery) -> AppResult<Vec<HistoryGroup>> {
+        let filter = Filter::new(query)?;
+        let store = self.store.read().await;
+        let mut groups: Vec<(HistoryGroup, u64)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for entry in store.entries.iter().rev().filter(|e| filter.matches(e)) {
+            let at = *index.entry(entry.fingerprint.as_str()).or_insert_with(|| {
+                let group = HistoryGroup {
+                    fingerprint: entry.fingerprint.clone(),
+                    sql: entry.sql.clone(),
+                    runs: 0,
+                    errors: 0,
+                    avg_duration_ms: 0,
+                    last_executed_at: entry.executed_at.clone(),
+                };
+                groups.push((group, 0));
+                groups.len() - 1
+            });
+            let (group, total_ms) = &mut groups[at];
+            group.runs += 1;
+            if entry.status == HistoryStatus::Error {
+                group.errors += 1;
+            }
+            *total_ms += entry.duration_ms;
+        }
+        Ok(groups
+            .into_iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.limit.unwrap_or(DEFAULT_PAGE))
+            .map(|(group, total_ms)| HistoryGroup { avg_duration_ms: total_ms / group.runs as u64, ..group })
+            .collect())
+    }
+
+    /// Delete entries by id; returns how many were found.
+    pub async fn delete(&self, ids: &[String]) -> AppResult<usize> {
+        let mut store = self.store.write().await;
+        let before = store.entries.len();
+        store.entries.retain(|e| !ids.contains(&e.id));
+        let removed = before - store.entries.len();
+        drop(store);
+        if removed > 0 {
+            self.flush().await?;
+        }
+        Ok(removed)
+    }
+
+    /// Delete every entry, or those run before `before` (RFC 3339).
+    pub async fn clear(&self, before: Option<&str>) -> AppResult<usize> {
+        let cutoff = before.map(parse_time).transpose()?;
+        let mut store = self.store.write().await;
+        let count = store.entries.len();
+        match cutoff {
+            Some(cutoff) => store.entries.retain(|e| executed_at(e).is_none_or(|t| t >= cutoff)),
+            None => store.entries.clear(),
+        }
+        let removed = count - store.entries.len();
+        drop(store);
+        self.flush().await?;
+        Ok(removed)
+    }
+
+    pub async fn retention(&self) -> HistoryRetention {
+        self.store.read().await.retention.clone()
+    }
+
+    /// Change the limits and prune to them now.
+    pub async fn set_retention(&self, retention: HistoryRetention) -> AppResult<HistoryRetention> {
+        if retention.max_entries > MAX_ENTRIES_LIMIT {
+            return Err(AppError::validation(format!(
+                "maxEntries must be at most {}",
+                MAX_ENTRIES_LIMIT
+            )));
+        }
+        if retention.max_age_days == Some(0) {
+            return Err(AppError::validation("maxAgeDays must be at least 1"));
+        }
+        let mut store = self.store.write().await;
+        prune(&mut store.entries, &retention, Utc::now());
+        store.retention = retention.clone();
+        drop(store);
+        self.flush().await?;
+        Ok(retention)
+    }
+}
+
+/// Snapshot the store and write it on a blocking thread.
+async fn write(store: &RwLock<HistoryFile>, writing: &Mutex<()>) -> AppResult<()> {
+    let _writing = writing.lock().await;
+    let snapshot = store.read().await.clone();
+    tokio::task::spawn_blocking(move || persist(&snapshot))
+        .await
+        .map_err(|e| AppError::io(format!("History write failed: {}", e)))?
+}
+
+fn persist(store: &HistoryFile) -> AppResult<()> {
+    let json = serde_json::to_string(store)?;
+    let sealed = encrypt_password(&json)?;
+    let path = data_file_path(HISTORY_FILE)?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, sealed.into_bytes())?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Drop entries past the age limit, then the oldest beyond the count limit.
+fn prune(entries: &mut Vec<HistoryEntry>, retention: &HistoryRetention, now: DateTime<Utc>) {
+    if let Some(days) = retention.max_age_days {
+        let cutoff = now - chrono::Duration::days(days as i64);
+        entries.retain(|e| executed_at(e).is_none_or(|t| t >= cutoff));
+    }
+    let excess = entries.len().saturating_sub(retention.max_entries);
+    entries.drain(..excess);
+}
+
+fn executed_at(entry: &HistoryEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.executed_at).ok().map(|t| t.with_timezone(&Utc))
+}
+
+fn parse_time(value: &str) -> AppResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| AppError::validation(format!("Invalid timestamp {}: {}", value, e)))
+}
+
+/// A `HistoryQuery` with its times parsed and its words lower-cased.
+struct Filter<'a> {
+    query: &'a HistoryQuery,
+    words: Vec<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+impl<'a> Filter<'a> {
+    fn new(query: &'a HistoryQuery) -> AppResult<Self> {
+        Ok(Self {
+            query,
+            words: query.text.as_deref().unwrap_or("").split_whitespace().map(str::to_lowercase).collect(),
+            from: query.from.as_deref().map(parse_time).transpose()?,
+            to: query.to.as_deref().map(parse_time).transpose()?,
+        })
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        let q = self.query;
+        if q.server_id.as_ref().is_some_and(|id| entry.server_id.as_ref() != Some(id))
+            || q.profile_id.as_ref().is_some_and(|id| *id != entry.profile_id)
+            || q.fingerprint.as_ref().is_some_and(|f| *f != entry.fingerprint)
+            || q.status.is_some_and(|s| s != entry.status)
+        {
+            return false;
+        }
+        if self.from.is_some() || self.to.is_some() {
+            let Some(at) = executed_at(entry) else {
+                return false;
+            };
+            if self.from.is_some_and(|from| at < from) || self.to.is_some_and(|to| at > to) {
+                return false;
+            }
+        }
+        if !self.words.is_empty() {
+            let sql = entry.sql.to_lowercase();
+            return self.words.iter().all(|w| sql.contains(w.as_str()));
+        }
+        true
+    }
+}
+
+// ------------------------------------------------------------------- - tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::files::TempHome;
+
+    #[tokio::test]
+    async fn records_searches_and_prunes_history() {
+        let _home = TempHome::new();
+        let service = HistoryService::default();
+        let run = |sql: &str| {
+            QueryRun::start("srv-abc:1", Some("abc".into()), DbType::Mysql, Some("shop".into()), sql)
+        };
+        service.record(run("SELECT * FROM orders WHERE id = 1"), Ok(1)).await.unwrap();
+        service.record(run("select * from orders where id = 2"), Ok(0)).await.unwrap();
+        let failed = AppError::database("Table 'shop.order' doesn't exist");
+        service.record(run("DELETE FROM `order`"), Err(&failed)).await.unwrap();
+
+        // Written once, after the runs settle; encrypted on disk, and loads back.
+        let path = data_file_path(HISTORY_FILE).unwrap();
+        assert!(!path.exists());
+        tokio::time::sleep(FLUSH_DELAY * 3).await;
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(raw.starts_with("wkgrd:") && !raw.contains("orders"));
+        let service = HistoryService::new().await.unwrap();
+
+        let page = service.search(&HistoryQuery { text: Some("ORDERS id".into()), ..Default::default() }).await;
+        let page = page.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.entries[0].sql, "select * from orders where id = 2");
+        assert_eq!(page.entries[0].server_id.as_deref(), Some("abc"));
+        assert_eq!(page.entries[0].database.as_deref(), Some("shop"));
+        assert_eq!(page.entries[0].fingerprint, page.entries[1].fingerprint);
+
+        let errors = HistoryQuery { status: Some(HistoryStatus::Error), ..Default::default() };
+        let page = service.search(&errors).await.unwrap();
+        assert_eq!(page.entries[0].error.as_deref(), Some("Table 'shop.order' doesn't exist"));
+        assert_eq!(page.entries[0].row_count, None);
+        let later = HistoryQuery { from: Some("2999-01-01T00:00:00Z".into()), ..Default::default() };
+        assert_eq!(service.search(&later).await.unwrap().total, 0);
+
+        let groups = service.groups(&HistoryQuery::default()).await.unwrap();
+        assert_eq!(groups.iter().map(|g| g.runs).collect::<Vec<_>>(), vec![1, 2]);
+
+        let retention = HistoryRetention { max_entries: 1, max_age_days: None };
+        service.set_retention(retention).await.unwrap();
+        let page = service.search(&HistoryQuery::default()).await.unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.entries[0].sql, "DELETE FROM `order`");
+        assert_eq!(service.clear(None).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,603 @@
+// File import: CSV, TSV and NDJSON read a record at a time, column types
+// inferred from a sample, values checked and turned into bind parameters,
+// and the INSERT / CREATE TABLE statements for the session's dialect.
+//
+// This is the pure part; `commands::import` runs the statements in batched
+// transactions. Files are read as a stream, so only the batch in progress is
+// held in memory (UTF-16 files, which are rare, are decoded whole).
+//
+// CSV follows RFC 4180: a field is quoted when it starts with the quote
+// character, a doubled quote inside one is a quote, and quoted fields may
+// span lines. An unquoted field equal to the null text is NULL, so with the
+// default empty null text `a,,b` has a NULL and `a,"",b` an empty string.
+// Blank lines are skipped.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::drivers::DbType;
+use crate::models::{ImportColumn, ImportFileOptions, ImportFormat, ImportTarget, ImportType, QueryParam};
+use crate::{AppError, AppResult};
+
+/// Rows the column types are inferred from.
+pub const INFER_ROWS: usize = 1_000;
+/// Bind values in one INSERT; SQLite's lowest default limit.
+const MAX_STATEMENT_PARAMS: usize = 999;
+
+/// One row of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// The line the row starts on, 1-based.
+    pub line: u64,
+    /// By column index; `None` is NULL. CSV rows may be short or long.
+    pub fields: Vec<Option<String>>,
+}
+
+/// Reads a file a record at a time.
+pub struct RecordReader {
+    input: Box<dyn BufRead + Send>,
+    options: ImportFileOptions,
+    delimiter: char,
+    columns: Vec<String>,
+    /// Lines read so far.
+    line: u64,
+    /// A CSV row read to name the columns of a file without a header.
+    pending: Option<Record>,
+}
+
+impl RecordReader {
+    pub fn open(path: &str, options: ImportFileOptions) -> AppResult<Self> {
+        let mut file = File::open(path).map_err(|e| AppError::io(format!("Cannot open {}: {}", path, e)))?;
+        let mut bom = [0u8; 2];
+        let read = file.read(&mut bom)?;
+        if read == 2 && bom == [0xFF, 0xFE] {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            let text = String::from_utf16(&units)
+                .map_err(|_| AppError::validation("The file is not valid UTF-16"))?;
+            return Self::new(Box::new(Cursor::new(text.into_bytes())), options);
+        }
+        let head = Cursor::new(bom[..read].to_vec());
+        Self::new(Box::new(BufReader::new(head.chain(file))), options)
+    }
+
+    /// Reads the header, if any; a UTF-8 byte order mark is skipped.
+    pub fn new(input: Box<dyn BufRead + Send>, options: ImportFileOptions) -> AppResult<Self> {
+        let delimiter = options.delimiter.unwrap_or(match options.format {
+            ImportFormat::Tsv => '\t',
+            _ => ',',
+        });
+        if matches!(delimiter, '\r' | '\n') || delimiter == options.quote {
+            return Err(AppError::validation("The delimiter must differ from the quote and line breaks"));
+        }
+        let mut reader =
+            Self { input, options, delimiter, columns: Vec::new(), line: 0, pending: None };
+        if reader.options.format != ImportFormat::Ndjson {
+            let first = reader.next_csv()?;
+            if reader.options.header {
+                let names = first.map(|r| r.fields).unwrap_or_default();
+                reader.columns = names.into_iter().map(Option::unwrap_or_default).collect();
+                for (i, name) in reader.columns.iter_mut().enumerate() {
+                    if name.trim().is_empty() {
+                        *name = format!("column{}", i + 1);
+                    }
+                }
+            } else {
+                let count = first.as_ref().map_or(0, |r| r.fields.len());
+                reader.columns = (1..=count).map(|i| format!("column{}", i)).collect();
+                reader.pending = first;
+            }
+        }
+        Ok(reader)
+    }
+
+    /// The column names. NDJSON columns are the keys seen so far, so the
+    /// list grows as records are read.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn next_record(&mut self) -> AppResult<Option<Record>> {
+        if let Some(record) = self.pending.take() {
+            return Ok(Some(record));
+        }
+        match self.options.format {
+            ImportFormat::Ndjson => self.next_ndjson(),
+            _ => self.next_csv(),
+        }
+    }
+
+    fn read_line(&mut self) -> AppResult<Option<String>> {
+        let mut bytes = Vec::new();
+        if self.input.read_until(b'\n', &mut bytes)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        if self.line == 1 && bytes.starts_with(b"\xEF\xBB\xBF") {
+            bytes.drain(..3);
+        }
+        String::from_utf8(bytes).map(Some).map_err(|_| {
+            AppError::validation(format!(
+                "Line {} is not UTF-8; save the file as UTF-8 or UTF-16 with a byte order mark",
+                self.line
+            ))
+        })
+    }
+
+    fn next_csv(&mut self) -> AppResult<Option<Record>> {
+        let quote = self.options.quote;
+        loop {
+            let start = self.line + 1;
+            let mut fields = Vec::new();
+            let mut field = String::new();
+            let mut quoted = false;
+            let mut in_quotes = false;
+            loop {
+                let Some(line) = self.read_line()? else {
+                    if in_quotes {
+                        return Err(AppError::validation(format!(
+                            "The quoted field starting on line {} is never closed",
+                            start
+                        )));
+                    }
+                    if start > self.line {
+                        return Ok(None);
+                    }
+                    break;
+                };
+                let mut chars = line.chars().peekable();
+                while let Some(c) = chars.next() {
+                    if in_quotes {
+                        if c != quote {
+                            field.push(c);
+                        } else if chars.peek() == Some(&quote) {
+                            field.push(quote);
+                            chars.next();
+                        } else {
+                            in_quotes = false;
+                        }
+                    } else if c == quote && field.is_empty() && !quoted {
+                        in_quotes = true;
+                        quoted = true;
+                    } else if c == self.delimiter {
+                        fields.push(self.field(std::mem::take(&mut field), quoted));
+                        quoted = false;
+                    } else if c != '\r' && c != '\n' {
+                        field.push(c);
+                    }
+                }
+                if !in_quotes {
+                    break;
+                }
+            }
+            if fields.is_empty() && field.is_empty() && !quoted {
+                continue;
+            }
+            fields.push(self.field(field, quoted));
+            return Ok(Some(Record { line: start, fields }));
+        }
+    }
+
+    fn field(&self, text: String, quoted: bool) -> Option<String> {
+        (quoted || text != self.options.null_text).then_some(text)
+    }
+
+    fn next_ndjson(&mut self) -> AppResult<Option<Record>> {
+        loop {
+            let Some(line) = self.read_line()? else {
+                return Ok(None);
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let object = match serde_json::from_str::<serde_json::Value>(&line) {
+                Ok(serde_json::Value::Object(object)) => object,
+                _ => return Err(AppError::validation(format!("Line {} is not a JSON object", self.line))),
+            };
+            let mut fields = vec![None; self.columns.len()];
+            for (key, value) in object {
+                let index = match self.columns.iter().position(|c| *c == key) {
+                    Some(i) => i,
+                    None => {
+                        self.columns.push(key);
+                        fields.push(None);
+                        self.columns.len() - 1
+                    }
+                };
+                fields[index] = match value {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(s) => Some(s),
+                    other => Some(other.to_string()),
+                };
+            }
+            return Ok(Some(Record { line: self.line, fields }));
+        }
+    }
+}
+
+/// What a column's values looked like so far.
+#[derive(Debug, Clone, Default)]
+struct ColumnStats {
+    column_type: Option<ImportType>,
+    nullable: bool,
+    length: u32,
+    int_digits: u32,
+    scale: u32,
+}
+
+impl ColumnStats {
+    fn add(&mut self, value: Option<&str>) {
+        let Some(value) = value else {
+            self.nullable = true;
+            return;
+        };
+        self.length = self.length.max(value.chars().count() as u32);
+        let seen = classify(value);
+        if let ImportType::Int | ImportType::Decimal = seen {
+            let digits = value.trim_start_matches(['-', '+']);
+            let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+            self.int_digits = self.int_digits.max(int.len() as u32);
+            self.scale = self.scale.max(frac.len() as u32);
+        }
+        self.column_type = Some(match (self.column_type, seen) {
+            (None, seen) => seen,
+            (Some(a), b) if a == b => a,
+            (Some(ImportType::Int | ImportType::Decimal), ImportType::Int | ImportType::Decimal) => {
+                ImportType::Decimal
+            }
+            (Some(ImportType::Int | ImportType::Decimal | ImportType::Float), ImportType::Float)
+            | (Some(ImportType::Float), ImportType::Int | ImportType::Decimal) => ImportType::Float,
+            (Some(ImportType::Date | ImportType::DateTime), ImportType::Date | ImportType::DateTime) => {
+                ImportType::DateTime
+            }
+            _ => ImportType::Text,
+        });
+    }
+
+    fn column(&self, name: &str) -> ImportColumn {
+        let column_type = self.column_type.unwrap_or(ImportType::Text);
+        let decimal = column_type == ImportType::Decimal;
+        ImportColumn {
+            name: name.to_string(),
+            column_type,
+            nullable: self.nullable,
+            length: (column_type == ImportType::Text).then_some(self.length),
+            precision: decimal.then_some(self.int_digits.max(1) + self.scale),
+            scale: decimal.then_some(self.scale),
+        }
+    }
+}
+
+/// The narrowest type that reads `value` without loss. Numbers with a
+/// leading zero (`007`, zip codes) stay text.
+fn classify(value: &str) -> ImportType {
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let int_part = unsigned.split(['.', 'e', 'E']).next().unwrap_or("");
+    let numeric = is_number(value) && !(int_part.len() > 1 && int_part.starts_with('0'));
+    if numeric && value.parse::<i64>().is_ok() {
+        ImportType::Int
+    } else if numeric && !value.contains(['e', 'E']) && unsigned.len() <= 39 {
+        ImportType::Decimal
+    } else if numeric {
+        ImportType::Float
+    } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        ImportType::Bool
+    } else if parse_date(value).is_some() {
+        ImportType::Date
+    } else if parse_time(value).is_some() {
+        ImportType::Time
+    } else if parse_date_time(value).is_some() {
+        ImportType::DateTime
+    } else if value.starts_with(['{', '[']) && serde_json::from_str::<serde_json::Value>(value).is_ok() {
+        ImportType::Json
+    } else {
+        ImportType::Text
+    }
+}
+
+/// `-?digits[.digits][e[+-]digits]`.
+fn is_number(s: &str) -> bool {
+    let digits = |s: &str| s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let int = digits(s);
+    let mut rest = &s[int..];
+    if let Some(frac) = rest.strip_prefix('.') {
+        let n = digits(frac);
+        if n == 0 {
+            return false;
+        }
+        rest = &frac[n..];
+    }
+    if int == 0 {
+        return false;
+    }
+    if let Some(exp) = rest.strip_prefix(['e', 'E']) {
+        let exp = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+        return !exp.is_empty() && digits(exp) == exp.len();
+    }
+    rest.is_empty()
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M:%S%.f").ok()
+}
+
+fn parse_date_time(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f"))
+        .ok()
+}
+
+/// Infers column types from up to `sample` records; returns the columns and
+/// the first `keep` records.
+pub fn infer(
+    reader: &mut RecordReader,
+    sample: usize,
+    keep: usize,
+) -> AppResult<(Vec<ImportColumn>, Vec<Record>, u64)> {
+    let mut stats: Vec<ColumnStats> = Vec::new();
+    let mut kept = Vec::new();
+    let mut sampled = 0u64;
+    while (sampled as usize) < sample {
+        let Some(record) = reader.next_record()? else {
+            break;
+        };
+        if stats.len() < record.fields.len() {
+            stats.resize(record.fields.len(), ColumnStats::default());
+        }
+        for (column, value) in stats.iter_mut().zip(&record.fields) {
+            column.add(value.as_deref());
+        }
+        sampled += 1;
+        if kept.len() < keep {
+            kept.push(record);
+        }
+    }
+    stats.resize(reader.columns().len().max(stats.len()), ColumnStats::default());
+    let columns = stats
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let name = reader.columns().get(i).cloned().unwrap_or_else(|| format!("column{}", i + 1));
+            column.column(&name)
+        })
+        .collect();
+    Ok((columns, kept, sampled))
+}
+
+/// A field as a bind value of `value_type`; the error says why it does not
+/// fit.
+pub fn to_param(value_type: ImportType, value: Option<&str>) -> Result<QueryParam, String> {
+    let Some(text) = value else {
+        return Ok(QueryParam::Null);
+    };
+    let invalid = |what: &str| format!("{:?} is not {}", truncate(text), what);
+    let trimmed = text.trim();
+    Ok(match value_type {
+        ImportType::Text => QueryParam::Text(text.to_string()),
+        ImportType::Bool => match trimmed.to_ascii_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => QueryParam::Bool(true),
+            "false" | "f" | "no" | "n" | "0" => QueryParam::Bool(false),
+            _ => return Err(invalid("a boolean")),
+        },
+        ImportType::Int => QueryParam::Int(trimmed.parse().map_err(|_| invalid("an integer"))?),
+        ImportType::Decimal if is_number(trimmed) => QueryParam::Decimal(trimmed.to_string()),
+        ImportType::Decimal => return Err(invalid("a number")),
+        ImportType::Float => QueryParam::Float(trimmed.parse().map_err(|_| invalid("a number"))?),
+        ImportType::Date => match parse_date(trimmed) {
+            Some(_) => QueryParam::Date(trimmed.to_string()),
+            None => return Err(invalid("a date (YYYY-MM-DD)")),
+        },
+        ImportType::Time => match parse_time(trimmed) {
+            Some(_) => QueryParam::Time(trimmed.to_string()),
+            None => return Err(invalid("a time (HH:MM:SS)")),
+        },
+        ImportType::DateTime => match parse_date_time(trimmed) {
+            Some(_) => QueryParam::DateTime(trimmed.replacen('T', " ", 1)),
+            None if parse_date(trimmed).is_some() => QueryParam::DateTime(format!("{} 00:00:00", trimmed)),
+            None => return Err(invalid("a date and time (YYYY-MM-DD HH:MM:SS)")),
+        },
+        ImportType::Json => QueryParam::Json(serde_json::from_str(text).map_err(|_| invalid("JSON"))?),
+    })
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(40) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// The target's name, qualified by its schema when it has one.
+pub fn table_name(db: DbType, target: &ImportTarget) -> String {
+    db.dialect().qualify(target.schema.as_deref(), &target.table)
+}
+
+/// Rows per INSERT statement for `columns` bind values each.
+pub fn rows_per_statement(columns: usize) -> usize {
+    (MAX_STATEMENT_PARAMS / columns.max(1)).max(1)
+}
+
+/// A multi-row INSERT with `:r{row}_{column}` parameters, and its values.
+pub fn insert_statement(
+    db: DbType,
+    target: &ImportTarget,
+    columns: &[String],
+    rows: &[Vec<QueryParam>],
+) -> (String, BTreeMap<String, QueryParam>) {
+    let names: Vec<String> = columns.iter().map(|c| db.dialect().quote_ident(c)).collect();
+    let mut values = BTreeMap::new();
+    let tuples: Vec<String> = rows
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let params: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(c, value)| {
+                    let name = format!("r{}_{}", r, c);
+                    values.insert(name.clone(), value.clone());
+                    format!(":{}", name)
+                })
+                .collect();
+            format!("({})", params.join(", "))
+        })
+        .collect();
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES {}",
+        table_name(db, target),
+        names.join(", "),
+        tuples.join(", ")
+    );
+    (sql, values)
+}
+
+/// CREATE TABLE for `columns` in `db`'s types. Columns are nullable; a
+/// sample is no proof that a column never holds NULL.
+pub fn create_table_sql(db: DbType, target: &ImportTarget, columns: &[ImportColumn]) -> AppResult<String> {
+    if target.table.trim().is_empty() {
+        return Err(AppError::validation("The table name is empty"));
+    }
+    if columns.is_empty() {
+        return Err(AppError::validation("A table needs at least one column"));
+    }
+    let mut lines = Vec::with_capacity(columns.len());
+    for (i, column) in columns.iter().enumerate() {
+        if column.name.trim().is_empty() {
+            return Err(AppError::validation(format!("Column {} has no name", i + 1)));
+        }
+        if columns[..i].iter().any(|c| c.name.eq_ignore_ascii_case(&column.name)) {
+            return Err(AppError::validation(format!("Column {} appears twice", column.name)));
+        }
+        lines.push(format!("  {} {}", db.dialect().quote_ident(&column.name), column_type_sql(db, column)));
+    }
+    Ok(format!("CREATE TABLE {} (\n{}\n)", table_name(db, target), lines.join(",\n")))
+}
+
+fn column_type_sql(db: DbType, column: &ImportColumn) -> String {
+    match (column.column_type, db) {
+        (ImportType::Bool, DbType::Mysql | DbType::Postgres) => "BOOLEAN".into(),
+        (ImportType::Bool, DbType::Mssql) => "BIT".into(),
+        (ImportType::Bool | ImportType::Int, DbType::Sqlite) => "INTEGER".into(),
+        (ImportType::Int, _) => "BIGINT".into(),
+        (ImportType::Decimal, DbType::Sqlite) => "NUMERIC".into(),
+        (ImportType::Decimal, _) => {
+            let max = if db == DbType::Mysql { 65 } else { 38 };
+            let precision = column.precision.unwrap_or(18).clamp(1, max);
+            let scale = column.scale.unwrap_or(0).min(precision).min(30);
+            format!("DECIMAL({}, {})", precision, scale)
+        }
+        (ImportType::Float, DbType::Mysql) => "DOUBLE".into(),
+        (ImportType::Float, DbType::Postgres) => "DOUBLE PRECISION".into(),
+        (ImportType::Float, DbType::Sqlite) => "REAL".into(),
+        (ImportType::Float, DbType::Mssql) => "FLOAT".into(),
+        (ImportType::Date | ImportType::Time | ImportType::DateTime | ImportType::Json, DbType::Sqlite) => {
+            "TEXT".into()
+        }
+        (ImportType::Date, _) => "DATE".into(),
+        (ImportType::Time, _) => "TIME".into(),
+        (ImportType::DateTime, DbType::Mysql) => "DATETIME(6)".into(),
+        (ImportType::DateTime, DbType::Postgres) => "TIMESTAMP".into(),
+        (ImportType::DateTime, DbType::Mssql) => "DATETIME2".into(),
+        (ImportType::Json, DbType::Mysql) => "JSON".into(),
+        (ImportType::Json, DbType::Postgres) => "JSONB".into(),
+        (ImportType::Json, DbType::Mssql) => "NVARCHAR(MAX)".into(),
+        (ImportType::Text, DbType::Postgres | DbType::Sqlite) => "TEXT".into(),
+        (ImportType::Text, DbType::Mysql | DbType::Mssql) => {
+            // Room to grow over the sample, within an indexable size.
+            let fit = [32, 64, 128, 255, 1_000, 4_000]
+                .into_iter()
+                .find(|n| *n >= column.length.unwrap_or(255));
+            match (db, fit) {
+                (DbType::Mysql, Some(n)) => format!("VARCHAR({})", n),
+                (DbType::Mysql, None) => "LONGTEXT".into(),
+                (_, Some(n)) => format!("NVARCHAR({})", n),
+                (_, None) => "NVARCHAR(MAX)".into(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reader(text: &str, options: ImportFileOptions) -> RecordReader {
+        RecordReader::new(Box::new(Cursor::new(text.as_bytes().to_vec())), options).unwrap()
+    }
+
+    fn fields(reader: &mut RecordReader) -> Vec<Vec<Option<String>>> {
+        std::iter::from_fn(|| reader.next_record().unwrap()).map(|r| r.fields).collect()
+    }
+
+    #[test]
+    fn reads_csv_and_ndjson() {
+        let text = "\u{feff}id,name,note\r\n1,\"a, \"\"b\"\"\",\n\n2,\"\",\"two\nlines\"\n";
+        let mut csv = reader(text, ImportFileOptions::default());
+        assert_eq!(csv.columns(), ["id", "name", "note"]);
+        let first = csv.next_record().unwrap().unwrap();
+        assert_eq!(first.line, 2);
+        assert_eq!(first.fields, vec![Some("1".into()), Some("a, \"b\"".into()), None]);
+        let second = csv.next_record().unwrap().unwrap();
+        assert_eq!((second.line, second.fields[1].as_deref()), (4, Some("")));
+        assert_eq!(second.fields[2].as_deref(), Some("two\nlines"));
+        assert!(csv.next_record().unwrap().is_none());
+
+        let options = ImportFileOptions { format: ImportFormat::Tsv, header: false, ..Default::default() };
+        let mut tsv = reader("x\ty\n", options);
+        assert_eq!(tsv.columns(), ["column1", "column2"]);
+        assert_eq!(fields(&mut tsv), vec![vec![Some("x".into()), Some("y".into())]]);
+
+        let mut unclosed = reader("a\n\"open\n", ImportFileOptions::default());
+        assert_eq!(unclosed.next_record().unwrap_err().kind, "validation");
+
+        let options = ImportFileOptions { format: ImportFormat::Ndjson, ..Default::default() };
+        let mut ndjson = reader("{\"b\":1,\"a\":\"x\"}\n\n{\"c\":{\"k\":[1]},\"a\":null}\n", options);
+        let rows = fields(&mut ndjson);
+        assert_eq!(ndjson.columns(), ["a", "b", "c"]);
+        assert_eq!(rows[1], vec![None, None, Some("{\"k\":[1]}".into())]);
+    }
+
+    #[test]
+    fn infers_types_and_builds_statements() {
+        let text = "id,price,zip,day,at,flag,doc\n\
+                    1,2.50,01234,2024-02-29,2024-02-29T10:00:00,true,{\"a\":1}\n\
+                    20,,98765,2024-03-01,2024-03-01,false,[]\n\
+                    3,100.125,12345,2024-03-02,2024-03-02 08:30:00.5,TRUE,{}\n";
+        let mut csv = reader(text, ImportFileOptions::default());
+        let (columns, kept, sampled) = infer(&mut csv, INFER_ROWS, 1).unwrap();
+        assert_eq!((kept.len(), sampled), (1, 3));
+        let types: Vec<ImportType> = columns.iter().map(|c| c.column_type).collect();
+        use ImportType::*;
+        assert_eq!(types, vec![Int, Decimal, Text, Date, DateTime, Bool, Json]);
+        assert_eq!((columns[1].precision, columns[1].scale, columns[1].nullable), (Some(6), Some(3), true));
+
+        let target = ImportTarget { schema: Some("s".into()), table: "t".into() };
+        let ddl = create_table_sql(DbType::Postgres, &target, &columns[..3]).unwrap();
+        assert_eq!(
+            ddl,
+            "CREATE TABLE \"s\".\"t\" (\n  \"id\" BIGINT,\n  \"price\" DECIMAL(6, 3),\n  \"zip\" TEXT\n)"
+        );
+        let ddl = create_table_sql(DbType::Mssql, &target, &columns[2..3]).unwrap();
+        assert!(ddl.ends_with("[zip] NVARCHAR(32)\n)"));
+
+        let rows = vec![vec![QueryParam::Int(1), QueryParam::Null], vec![QueryParam::Int(2), QueryParam::Null]];
+        let (sql, values) = insert_statement(DbType::Mysql, &target, &["a".into(), "b".into()], &rows);
+        assert_eq!(sql, "INSERT INTO `s`.`t` (`a`, `b`) VALUES (:r0_0, :r0_1), (:r1_0, :r1_1)");
+        assert_eq!(values.len(), 4);
+        assert_eq!(rows_per_statement(10), 99);
+
+        assert!(matches!(to_param(Int, Some("x")), Err(e) if e.contains("not an integer")));
+        let midnight = to_param(DateTime, Some("2024-01-02"));
+        assert!(matches!(midnight, Ok(QueryParam::DateTime(s)) if s == "2024-01-02 00:00:00"));
+        assert!(matches!(to_param(Bool, None), Ok(QueryParam::Null)));
+    }
+}
@@ -5,5 +5,9 @@ pub mod connection;
 pub mod crypto;
 pub mod credentials;
 pub mod db_servers;
+pub mod export;
 pub mod files;
+pub mod history;
+pub mod import;
+pub mod snippets;
 pub mod ssh_servers;
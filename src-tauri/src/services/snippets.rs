@@ -0,0 +1,557 @@
+// Saved query snippets: named SQL with a description, tags, an optional
+// target database type and `${name}` placeholders (see `sql::placeholders`),
+// kept in folders with the same semantics as the credentials vault: create,
+// rename, move, reorder before a sibling, deep copy and recursive delete,
+// with "root" standing for the top level.
+//
+// Nodes live in a flat map plus ordered child lists; on disk they are one
+// list in tree order, sealed in the same AES-256-GCM whole-file envelope as
+// the registries.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::drivers::DbType;
+use crate::models::{PlaceholderSpan, SnippetInput, SnippetNode, SnippetNodeType, SnippetQuery, SnippetText};
+use crate::services::crypto::{decrypt_password, encrypt_password};
+use crate::services::files::data_file_path;
+use crate::sql::placeholders;
+use crate::{AppError, AppResult};
+
+const SNIPPETS_FILE: &str = "snippets.json";
+
+/// The top level of the tree, as a parent id.
+const ROOT: &str = "root";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnippetRecord {
+    id: String,
+    #[serde(rename = "type")]
+    node_type: SnippetNodeType,
+    /// `None` at the top level.
+    #[serde(default)]
+    parent_id: Option<String>,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    db_type: Option<String>,
+    #[serde(default)]
+    sql: String,
+    #[serde(default)]
+    expanded: bool,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+}
+
+// -------------------------------------------------------------- - flat store
+
+#[derive(Debug, Default)]
+struct SnippetStore {
+    nodes: HashMap<String, SnippetRecord>,
+    /// Child ids in order, by parent id (`ROOT` for the top level).
+    children: HashMap<String, Vec<String>>,
+}
+
+impl SnippetStore {
+    fn from_records(records: Vec<SnippetRecord>) -> Self {
+        let mut store = Self::default();
+        for mut record in records {
+            // A parent that did not load leaves its children at the top.
+            if record.parent_id.as_ref().is_some_and(|p| !store.nodes.contains_key(p)) {
+                record.parent_id = None;
+            }
+            let parent = record.parent_id.clone().unwrap_or_else(|| ROOT.to_string());
+            store.children.entry(parent).or_default().push(record.id.clone());
+            store.nodes.insert(record.id.clone(), record);
+        }
+        store
+    }
+
+    /// Records in tree order, parents before their children.
+    fn to_records(&self) -> Vec<SnippetRecord> {
+        let mut out = Vec::new();
+        let mut stack = vec![ROOT.to_string()];
+        while let Some(parent) = stack.pop() {
+            let ids = self.children.get(&parent).cloned().unwrap_or_default();
+            for id in ids.iter() {
+                if let Some(node) = self.nodes.get(id) {
+                    out.push(node.clone());
+                }
+            }
+            stack.extend(ids.into_iter().rev());
+        }
+        out
+    }
+
+    /// `ROOT` or the id of a folder.
+    fn folder(&self, id: &str) -> AppResult<String> {
+        match self.nodes.get(id) {
+            _ if id == ROOT => Ok(ROOT.to_string()),
+            Some(node) if node.node_type == SnippetNodeType::Folder => Ok(id.to_string()),
+            Some(_) => Err(AppError::validation("target is a snippet, not a folder")),
+            None => Err(AppError::validation("target folder not found")),
+        }
+    }
+
+    fn upsert_snippet(&mut self, input: SnippetInput) -> AppResult<SnippetNode> {
+        let name = input.name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::validation("snippet name is required"));
+        }
+        if input.sql.trim().is_empty() {
+            return Err(AppError::validation("snippet SQL is required"));
+        }
+        let mut tags: Vec<String> = Vec::new();
+        for tag in input.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+        }
+        let db_type = input
+            .db_type
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(|t| DbType::from_str(t).as_str().to_string());
+        let description = input.description.filter(|d| !d.trim().is_empty());
+        let now = Some(chrono::Utc::now().to_rfc3339());
+
+        let existing = input.id.filter(|id| !id.is_empty() && id.as_str() != "new");
+        if let Some(id) = existing {
+            let node = self.nodes.get_mut(&id).ok_or_else(|| AppError::validation("snippet not found"))?;
+            if node.node_type != SnippetNodeType::Snippet {
+                return Err(AppError::validation("id refers to a folder, not a snippet"));
+            }
+            node.name = name;
+            node.description = description;
+            node.tags = tags;
+            node.db_type = db_type;
+            node.sql = input.sql;
+            node.updated_at = now;
+            return self.node(&id);
+        }
+
+        let parent = self.folder(input.parent_id.as_deref().unwrap_or(ROOT))?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let record = SnippetRecord {
+            id: id.clone(),
+            node_type: SnippetNodeType::Snippet,
+            parent_id: Some(parent.clone()).filter(|p| p != ROOT),
+            name,
+            description,
+            tags,
+            db_type,
+            sql: input.sql,
+            expanded: false,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        self.nodes.insert(id.clone(), record);
+        self.children.entry(parent).or_default().push(id.clone());
+        self.node(&id)
+    }
+
+    fn create_folder(&mut self, parent_id: Option<&str>, name: &str) -> AppResult<SnippetNode> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::validation("folder name is required"));
+        }
+        let parent = self.folder(parent_id.unwrap_or(ROOT))?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Some(chrono::Utc::now().to_rfc3339());
+        let record = SnippetRecord {
+            id: id.clone(),
+            node_type: SnippetNodeType::Folder,
+            parent_id: Some(parent.clone()).filter(|p| p != ROOT),
+            name: name.to_string(),
+            description: None,
+            tags: Vec::new(),
+            db_type: None,
+            sql: String::new(),
+            expanded: true,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        self.nodes.insert(id.clone(), record);
+        self.children.entry(parent).or_default().push(id.clone());
+        self.node(&id)
+    }
+
+    fn set_expanded(&mut self, id: &str, expanded: bool) -> AppResult<()> {
+        match self.nodes.get_mut(id) {
+            Some(node) if node.node_type == SnippetNodeType::Folder => {
+                node.expanded = expanded;
+                Ok(())
+            }
+            Some(_) => Err(AppError::validation("node is not a folder")),
+            None => Err(AppError::validation("node not found")),
+        }
+    }
+
+    /// Move a node under `target_parent`, before `before_id` when given or
+    /// else at the end.
+    fn reorder_node(&mut self, id: &str, target_parent: &str, before_id: Option<&str>) -> AppResult<()> {
+        if !self.nodes.contains_key(id) {
+            return Err(AppError::validation("node not found"));
+        }
+        let target = self.folder(target_parent)?;
+        if id == target || self.is_descendant(&target, id) {
+            return Err(AppError::validation("cannot move a folder into itself or its descendant"));
+        }
+        if let Some(before) = before_id {
+            if !self.children.get(&target).is_some_and(|list| list.iter().any(|x| x == before)) {
+                return Err(AppError::validation("before_id is not a child of the target parent"));
+            }
+        }
+        for list in self.children.values_mut() {
+            list.retain(|x| x != id);
+        }
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.parent_id = Some(target.clone()).filter(|p| p != ROOT);
+        }
+        let list = self.children.entry(target).or_default();
+        let at = before_id.and_then(|before| list.iter().position(|x| x == before)).unwrap_or(list.len());
+        list.insert(at, id.to_string());
+        Ok(())
+    }
+
+    /// True when `candidate` is a descendant of `ancestor` (transitive).
+    fn is_descendant(&self, candidate: &str, ancestor: &str) -> bool {
+        let mut stack = vec![ancestor.to_string()];
+        while let Some(current) = stack.pop() {
+            for child_id in self.children.get(&current).into_iter().flatten() {
+                if child_id == candidate {
+                    return true;
+                }
+                stack.push(child_id.clone());
+            }
+        }
+        false
+    }
+
+    fn copy_node(&mut self, id: &str, target_parent: &str) -> AppResult<SnippetNode> {
+        if !self.nodes.contains_key(id) {
+            return Err(AppError::validation("node not found"));
+        }
+        let target = self.folder(target_parent)?;
+        let copy_id = self.copy_subtree(id, &target);
+        if let Some(node) = self.nodes.get_mut(&copy_id) {
+            node.name = format!("{} (Copy)", node.name);
+        }
+        self.node(&copy_id)
+    }
+
+    /// Deep-copy `source_id` under `new_parent` with fresh ids; returns the
+    /// new top-level id.
+    fn copy_subtree(&mut self, source_id: &str, new_parent: &str) -> String {
+        let mut copy = self.nodes[source_id].clone();
+        copy.id = uuid::Uuid::new_v4().to_string();
+        copy.parent_id = Some(new_parent.to_string()).filter(|p| p != ROOT);
+        let now = Some(chrono::Utc::now().to_rfc3339());
+        copy.created_at = now.clone();
+        copy.updated_at = now;
+        let new_id = copy.id.clone();
+        self.nodes.insert(new_id.clone(), copy);
+        self.children.entry(new_parent.to_string()).or_default().push(new_id.clone());
+        for child_id in self.children.get(source_id).cloned().unwrap_or_default() {
+            self.copy_subtree(&child_id, &new_id);
+        }
+        new_id
+    }
+
+    fn rename_node(&mut self, id: &str, new_name: &str) -> AppResult<SnippetNode> {
+        let trimmed = new_name.trim();
+        if trimmed.is_empty() {
+            return Err(AppError::validation("name is required"));
+        }
+        let node = self.nodes.get_mut(id).ok_or_else(|| AppError::validation("node not found"))?;
+        node.name = trimmed.to_string();
+        node.updated_at = Some(chrono::Utc::now().to_rfc3339());
+        self.node(id)
+    }
+
+    fn delete_node(&mut self, id: &str) -> AppResult<()> {
+        if !self.nodes.contains_key(id) {
+            return Err(AppError::validation("node not found"));
+        }
+        let mut stack = vec![id.to_string()];
+        while let Some(current) = stack.pop() {
+            stack.extend(self.children.remove(&current).unwrap_or_default());
+            self.nodes.remove(&current);
+        }
+        for list in self.children.values_mut() {
+            list.retain(|x| x != id);
+        }
+        Ok(())
+    }
+
+    fn snippet(&self, id: &str) -> AppResult<&SnippetRecord> {
+        match self.nodes.get(id) {
+            Some(node) if node.node_type == SnippetNodeType::Snippet => Ok(node),
+            Some(_) => Err(AppError::validation("node is a folder, not a snippet")),
+            None => Err(AppError::validation("snippet not found")),
+        }
+    }
+
+    /// A node with its subtree.
+    fn node(&self, id: &str) -> AppResult<SnippetNode> {
+        let record = self.nodes.get(id).ok_or_else(|| AppError::validation("node not found"))?;
+        let mut node = to_node(record);
+        node.children = self.nodes_under(id);
+        Ok(node)
+    }
+
+    fn nodes_under(&self, parent: &str) -> Vec<SnippetNode> {
+        let ids = self.children.get(parent).map(Vec::as_slice).unwrap_or_default();
+        ids.iter().filter_map(|id| self.node(id).ok()).collect()
+    }
+
+    /// Matching snippets in tree order, without children.
+    fn search(&self, query: &SnippetQuery) -> Vec<SnippetNode> {
+        let words: Vec<String> =
+            query.text.as_deref().unwrap_or("").split_whitespace().map(str::to_lowercase).collect();
+        let tag = query.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+        let db_type = query.db_type.as_deref().map(|t| DbType::from_str(t).as_str());
+        self.to_records()
+            .iter()
+            .filter(|r| r.node_type == SnippetNodeType::Snippet)
+            .filter(|r| tag.is_none_or(|tag| r.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))))
+            .filter(|r| db_type.is_none_or(|db| r.db_type.as_deref().is_none_or(|t| t == db)))
+            .filter(|r| {
+                let haystack = format!(
+                    "{}\n{}\n{}\n{}",
+                    r.name,
+                    r.description.as_deref().unwrap_or(""),
+                    r.tags.join(" "),
+                    r.sql
+                )
+                .to_lowercase();
+                words.iter().all(|w| haystack.contains(w.as_str()))
+            })
+            .map(to_node)
+            .collect()
+    }
+}
+
+fn to_node(record: &SnippetRecord) -> SnippetNode {
+    let db = DbType::from_str(record.db_type.as_deref().unwrap_or(""));
+    SnippetNode {
+        id: record.id.clone(),
+        node_type: record.node_type,
+        parent_id: record.parent_id.clone(),
+        name: record.name.clone(),
+        description: record.description.clone(),
+        tags: record.tags.clone(),
+        db_type: record.db_type.clone(),
+        sql: record.sql.clone(),
+        placeholders: placeholders::names(db, &record.sql),
+        expanded: record.expanded,
+        children: Vec::new(),
+        created_at: record.created_at.clone(),
+        updated_at: record.updated_at.clone(),
+    }
+}
+
+// ---------------------------------------------------------------- - service
+
+#[derive(Debug, Default)]
+pub struct SnippetService {
+    store: RwLock<SnippetStore>,
+}
+
+impl SnippetService {
+    pub async fn new() -> AppResult<Self> {
+        let service = Self::default();
+        service.load().await?;
+        Ok(service)
+    }
+
+    async fn load(&self) -> AppResult<()> {
+        let path = data_file_path(SNIPPETS_FILE)?;
+        if !path.exists() {
+            return Ok(());
+        }
+        let bytes = std::fs::read(&path)?;
+        let json = match String::from_utf8(bytes) {
+            Ok(text) if text.starts_with("wkgrd:") => decrypt_password(&text)?,
+            Ok(text) => text,
+            Err(e) => return Err(AppError::io(e.to_string())),
+        };
+        let records: Vec<SnippetRecord> = serde_json::from_str(&json).map_err(|e| AppError::io(e.to_string()))?;
+        *self.store.write().await = SnippetStore::from_records(records);
+        Ok(())
+    }
+
+    async fn persist(&self, store: &SnippetStore) -> AppResult<()> {
+        let json = serde_json::to_string_pretty(&store.to_records())?;
+        let sealed = encrypt_password(&json)?;
+        let path = data_file_path(SNIPPETS_FILE)?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, sealed.into_bytes())?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    pub async fn tree(&self) -> Vec<SnippetNode> {
+        self.store.read().await.nodes_under(ROOT)
+    }
+
+    pub async fn get(&self, id: &str) -> AppResult<SnippetNode> {
+        self.store.read().await.node(id)
+    }
+
+    pub async fn search(&self, query: &SnippetQuery) -> Vec<SnippetNode> {
+        self.store.read().await.search(query)
+    }
+
+    pub async fn upsert_snippet(&self, input: SnippetInput) -> AppResult<SnippetNode> {
+        let mut store = self.store.write().await;
+        let out = store.upsert_snippet(input)?;
+        self.persist(&store).await?;
+        Ok(out)
+    }
+
+    pub async fn create_folder(&self, parent_id: Option<&str>, name: &str) -> AppResult<SnippetNode> {
+        let mut store = self.store.write().await;
+        let out = store.create_folder(parent_id, name)?;
+        self.persist(&store).await?;
+        Ok(out)
+    }
+
+    pub async fn move_node(&self, id: &str, target_parent: &str) -> AppResult<()> {
+        self.reorder_node(id, target_parent, None).await
+    }
+
+    /// Reorder a node under `target_parent`, inserting before `before_id`
+    /// (when `Some`) or appending at the end (when `None`).
+    pub async fn reorder_node(&self, id: &str, target_parent: &str, before_id: Option<&str>) -> AppResult<()> {
+        let mut store = self.store.write().await;
+        store.reorder_node(id, target_parent, before_id)?;
+        self.persist(&store).await
+    }
+
+    pub async fn copy_node(&self, id: &str, target_parent: &str) -> AppResult<SnippetNode> {
+        let mut store = self.store.write().await;
+        let out = store.copy_node(id, target_parent)?;
+        self.persist(&store).await?;
+        Ok(out)
+    }
+
+    pub async fn rename_node(&self, id: &str, new_name: &str) -> AppResult<SnippetNode> {
+        let mut store = self.store.write().await;
+        let out = store.rename_node(id, new_name)?;
+        self.persist(&store).await?;
+        Ok(out)
+    }
+
+    pub async fn set_expanded(&self, id: &str, expanded: bool) -> AppResult<()> {
+        let mut store = self.store.write().await;
+        store.set_expanded(id, expanded)?;
+        self.persist(&store).await
+    }
+
+    pub async fn delete_node(&self, id: &str) -> AppResult<()> {
+        let mut store = self.store.write().await;
+        store.delete_node(id)?;
+        self.persist(&store).await
+    }
+
+    /// A snippet's SQL for an editor, placeholders marked.
+    pub async fn text(&self, id: &str) -> AppResult<SnippetText> {
+        let store = self.store.read().await;
+        let snippet = store.snippet(id)?;
+        let db = DbType::from_str(snippet.db_type.as_deref().unwrap_or(""));
+        let utf16 = |byte: usize| snippet.sql[..byte].encode_utf16().count();
+        let placeholders = placeholders::find(db, &snippet.sql)
+            .into_iter()
+            .map(|p| PlaceholderSpan { start: utf16(p.start), end: utf16(p.end), name: p.name })
+            .collect();
+        Ok(SnippetText { sql: snippet.sql.clone(), placeholders })
+    }
+
+    /// A snippet's SQL ready to run on a `db` session, placeholders turned
+    /// into `:name` parameters, and the names to bind.
+    pub async fn runnable(&self, id: &str, db: DbType) -> AppResult<(String, Vec<String>)> {
+        let store = self.store.read().await;
+        let snippet = store.snippet(id)?;
+        if let Some(target) = snippet.db_type.as_deref().filter(|t| *t != db.as_str()) {
+            return Err(AppError::validation(format!(
+                "The snippet is written for {}, not {}",
+                target,
+                db.as_str()
+            )));
+        }
+        Ok((placeholders::bind(db, &snippet.sql)?, placeholders::names(db, &snippet.sql)))
+    }
+}
+
+// ------------------------------------------------------------------- - tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::files::TempHome;
+
+    fn input(parent_id: Option<&str>, name: &str, sql: &str) -> SnippetInput {
+        SnippetInput {
+            id: None,
+            parent_id: parent_id.map(str::to_string),
+            name: name.to_string(),
+            description: Some("Orders placed since a date".to_string()),
+            tags: vec!["reports".to_string(), " Reports ".to_string()],
+            db_type: Some("postgresql".to_string()),
+            sql: sql.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn snippet_library_round_trip() {
+        let _home = TempHome::new();
+        let service = SnippetService::default();
+        let reports = service.create_folder(None, "Reports").await.unwrap();
+        let daily = service.create_folder(Some(&reports.id), "Daily").await.unwrap();
+        let recent = service
+            .upsert_snippet(input(Some(&daily.id), "Recent", "SELECT * FROM orders WHERE placed > ${since}"))
+            .await
+            .unwrap();
+        assert_eq!(recent.placeholders, vec!["since"]);
+        assert_eq!(recent.tags, vec!["reports"]);
+        assert_eq!(recent.db_type.as_deref(), Some("postgres"));
+
+        // Persisted in tree order and encrypted; reloads into the same tree.
+        let raw = std::fs::read_to_string(data_file_path(SNIPPETS_FILE).unwrap()).unwrap();
+        assert!(raw.starts_with("wkgrd:"));
+        let service = SnippetService::new().await.unwrap();
+        let tree = service.tree().await;
+        assert_eq!(tree[0].children[0].children[0].name, "Recent");
+
+        // Folder ops keep the tree consistent.
+        assert!(service.move_node(&reports.id, &daily.id).await.is_err());
+        service.copy_node(&daily.id, ROOT).await.unwrap();
+        service.reorder_node(&recent.id, ROOT, Some(&reports.id)).await.unwrap();
+        let names: Vec<String> = service.tree().await.into_iter().map(|n| n.name).collect();
+        assert_eq!(names, vec!["Recent", "Reports", "Daily (Copy)"]);
+        service.delete_node(&reports.id).await.unwrap();
+        assert_eq!(service.tree().await.len(), 2);
+
+        let query = SnippetQuery { text: Some("ORDERS since".into()), ..Default::default() };
+        assert_eq!(service.search(&query).await.len(), 2);
+        let query = SnippetQuery { db_type: Some("mysql".into()), ..Default::default() };
+        assert!(service.search(&query).await.is_empty());
+
+        let text = service.text(&recent.id).await.unwrap();
+        assert_eq!((text.placeholders[0].start, text.placeholders[0].end), (36, 44));
+        let (sql, names) = service.runnable(&recent.id, DbType::Postgres).await.unwrap();
+        assert_eq!(sql, "SELECT * FROM orders WHERE placed > :since");
+        assert_eq!(names, vec!["since"]);
+        assert_eq!(service.runnable(&recent.id, DbType::Mysql).await.unwrap_err().kind, "validation");
+    }
+}
@@ -646,24 +646,7 @@ pub(crate) fn parse_container_lines(text: &str) -> AppResult<Vec<crate::models::
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    // Redirect HOME for the whole test so persistence lands in a sandbox.
-    static HOME_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
-    struct TempHome(std::path::PathBuf, #[allow(dead_code)] Option<std::sync::MutexGuard<'static, ()>>);
-    impl TempHome {
-        fn new() -> Self {
-            let guard = HOME_LOCK.lock().unwrap_or_else(|p| p.into_inner());
-            let dir = std::env::temp_dir().join(format!("workgrid-ssh-test-{}", uuid::Uuid::new_v4()));
-            std::fs::create_dir_all(&dir).unwrap();
-            std::env::set_var("HOME", &dir);
-            Self(dir, Some(guard))
-        }
-    }
-    impl Drop for TempHome {
-        fn drop(&mut self) {
-            let _ = std::fs::remove_dir_all(&self.0);
-        }
-    }
+    use crate::services::files::TempHome;
 
     #[tokio::test]
     async fn server_registry_round_trip_encrypted() {
@@ -0,0 +1,144 @@
+// Destructive statements: changes that are hard to undo and easy to run by
+// accident. `classify` splits a script as the drivers do and flags:
+//
+//   UPDATE / DELETE   without a WHERE clause of its own (every row)
+//   TRUNCATE
+//   DROP              DATABASE, SCHEMA and TABLE
+//
+// A WHERE inside a subquery does not count. The `ConnectionManager` holds a
+// flagged script back as the profile's `DestructivePolicy` says: with
+// `confirm` the first call fails with kind `confirmation_required` and a
+// token, and the same SQL sent again with the token runs.
+
+use crate::drivers::DbType;
+use crate::models::{DestructiveKind, DestructiveStatement};
+use crate::sql::{statement_words, Word};
+
+/// How much of a statement `DestructiveStatement::statement` keeps.
+const PREVIEW_CHARS: usize = 200;
+
+/// The destructive statements of a script, in order.
+pub fn classify(db: DbType, sql: &str) -> Vec<DestructiveStatement> {
+    let statements = match db {
+        DbType::Postgres => super::split_postgres_statements(sql),
+        _ => super::split_sql_statements(sql),
+    };
+    statements
+        .iter()
+        .enumerate()
+        .filter_map(|(index, stmt)| classify_statement(db, index, stmt))
+        .collect()
+}
+
+fn classify_statement(db: DbType, index: usize, stmt: &str) -> Option<DestructiveStatement> {
+    let chars: Vec<char> = stmt.chars().collect();
+    let words: Vec<Word> = statement_words(db, stmt).into_iter().filter(|w| w.depth == 0).collect();
+    // The statement proper follows any CTEs (`WITH ... DELETE FROM t`).
+    let verb = match words.first() {
+        Some(w) if w.is("WITH") => words
+            .iter()
+            .position(|w| ["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE"].iter().any(|k| w.is(k)))?,
+        Some(_) => 0,
+        None => return None,
+    };
+    let rest = &words[verb + 1..];
+    let skip = |modifiers: &[&str]| {
+        rest.iter().position(|w| !modifiers.iter().any(|m| w.is(m))).unwrap_or(rest.len())
+    };
+    let verb_word = &words[verb];
+    let (kind, at) = if verb_word.is("UPDATE") {
+        if rest.iter().any(|w| w.is("WHERE")) {
+            return None;
+        }
+        (DestructiveKind::UpdateWithoutWhere, skip(&["ONLY", "LOW_PRIORITY", "IGNORE", "TOP"]))
+    } else if verb_word.is("DELETE") {
+        if rest.iter().any(|w| w.is("WHERE")) {
+            return None;
+        }
+        (DestructiveKind::DeleteWithoutWhere, skip(&["LOW_PRIORITY", "QUICK", "IGNORE", "TOP", "FROM", "ONLY"]))
+    } else if verb_word.is("TRUNCATE") {
+        (DestructiveKind::Truncate, skip(&["TABLE", "ONLY"]))
+    } else if verb_word.is("DROP") {
+        let kind = match rest.first() {
+            Some(w) if w.is("DATABASE") => DestructiveKind::DropDatabase,
+            Some(w) if w.is("SCHEMA") => DestructiveKind::DropSchema,
+            Some(w) if w.is("TABLE") => DestructiveKind::DropTable,
+            _ => return None,
+        };
+        (kind, skip(&["DATABASE", "SCHEMA", "TABLE", "IF", "EXISTS"]))
+    } else {
+        return None;
+    };
+    let object = rest.get(at).map(|_| object_name(&chars, &rest[at..]));
+    let mut statement: String = stmt.trim().chars().take(PREVIEW_CHARS).collect();
+    if stmt.trim().chars().count() > PREVIEW_CHARS {
+        statement.push('…');
+    }
+    Some(DestructiveStatement { index, kind, impact: impact(kind, object.as_deref()), object, statement })
+}
+
+/// A possibly qualified name (`sales.orders`, `[dbo].[t]`) starting at the
+/// first word, as written.
+fn object_name(chars: &[char], words: &[Word]) -> String {
+    let mut end = 0;
+    while let Some(next) = words.get(end + 1) {
+        let between: String = chars[words[end].end..next.start].iter().collect();
+        if between.trim() != "." {
+            break;
+        }
+        end += 1;
+    }
+    chars[words[0].start..words[end].end].iter().collect()
+}
+
+fn impact(kind: DestructiveKind, object: Option<&str>) -> String {
+    let object = object.unwrap_or("?");
+    match kind {
+        DestructiveKind::UpdateWithoutWhere => format!("Updates every row of {}", object),
+        DestructiveKind::DeleteWithoutWhere => format!("Deletes every row of {}", object),
+        DestructiveKind::Truncate => format!("Removes every row of {}", object),
+        DestructiveKind::DropDatabase => format!("Drops database {} and everything in it", object),
+        DestructiveKind::DropSchema => format!("Drops schema {}", object),
+        DestructiveKind::DropTable => format!("Drops table {} and its data", object),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_destructive_statements() {
+        let found = classify(
+            DbType::Mysql,
+            "UPDATE orders SET status = 'x'; UPDATE orders SET a = 1 WHERE id = 2; \
+             DELETE FROM `shop`.`orders`; DROP DATABASE IF EXISTS shop; SELECT 'DROP TABLE t'",
+        );
+        let kinds: Vec<(usize, DestructiveKind, Option<&str>)> =
+            found.iter().map(|s| (s.index, s.kind, s.object.as_deref())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (0, DestructiveKind::UpdateWithoutWhere, Some("orders")),
+                (2, DestructiveKind::DeleteWithoutWhere, Some("`shop`.`orders`")),
+                (3, DestructiveKind::DropDatabase, Some("shop")),
+            ]
+        );
+        assert_eq!(found[0].impact, "Updates every row of orders");
+
+        // A WHERE in a subquery is not the statement's own.
+        let found = classify(DbType::Postgres, "UPDATE t SET a = (SELECT max(b) FROM u WHERE u.id = 1)");
+        assert_eq!(found.len(), 1);
+        let found = classify(DbType::Postgres, "WITH old AS (SELECT 1) DELETE FROM ONLY audit.log; TRUNCATE t");
+        assert_eq!(found[0].object.as_deref(), Some("audit.log"));
+        assert_eq!(found[1].kind, DestructiveKind::Truncate);
+        assert!(classify(DbType::Postgres, "SELECT * FROM t FOR UPDATE; DROP INDEX i").is_empty());
+        let found = classify(DbType::Mysql, "SELECT 1 /*! ; DROP TABLE t */; /*!40000 TRUNCATE t2 */");
+        let kinds: Vec<DestructiveKind> = found.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec![DestructiveKind::DropTable, DestructiveKind::Truncate]);
+
+        let found = classify(DbType::Mssql, "DELETE TOP (10) FROM [dbo].[t]; DROP TABLE IF EXISTS #tmp");
+        assert_eq!(found[0].object.as_deref(), Some("[dbo].[t]"));
+        assert_eq!(found[1].object.as_deref(), Some("#tmp"));
+    }
+}
@@ -0,0 +1,132 @@
+// SQL dialects: how generated SQL spells names and values for each
+// `DbType`. Every statement the drivers and commands build goes through
+// here, so a name holding a quote character stays one name and a value
+// stays one literal.
+//
+//              identifier  string literal        booleans  limit/offset
+//   MySQL      `a``b`      'it''s \\ \0'         TRUE      LIMIT n OFFSET m
+//   Postgres   "a""b"      'it''s'               TRUE      LIMIT n OFFSET m
+//   SQLite     "a""b"      'it''s'               1         LIMIT n OFFSET m
+//   MSSQL      [a]]b]      N'it''s'              1         OFFSET m ROWS FETCH NEXT n ROWS ONLY
+//
+// Values a statement takes from the user are bound as parameters wherever
+// the statement allows them; literals are for the places it does not
+// (`SHOW`, `PRAGMA`, `USE`, exported SQL).
+
+use crate::drivers::DbType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqlDialect {
+    db: DbType,
+}
+
+impl SqlDialect {
+    pub fn new(db: DbType) -> Self {
+        Self { db }
+    }
+
+    /// Quote an identifier, doubling embedded quotes.
+    pub fn quote_ident(&self, ident: &str) -> String {
+        match self.db {
+            DbType::Mysql => format!("`{}`", ident.replace('`', "``")),
+            DbType::Mssql => format!("[{}]", ident.replace(']', "]]")),
+            DbType::Postgres | DbType::Sqlite => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    /// `name`, qualified by `schema` unless it is absent or blank.
+    pub fn qualify(&self, schema: Option<&str>, name: &str) -> String {
+        match schema.filter(|s| !s.is_empty()) {
+            Some(schema) => format!("{}.{}", self.quote_ident(schema), self.quote_ident(name)),
+            None => self.quote_ident(name),
+        }
+    }
+
+    /// A string literal. MySQL reads backslash escapes unless
+    /// NO_BACKSLASH_ESCAPES is set, so backslashes are escaped too (in that
+    /// mode they come back doubled); Postgres is taken to have
+    /// standard_conforming_strings (the default since 9.1). SQL Server
+    /// literals are N'' so characters outside the column's code page stay.
+    pub fn string_literal(&self, s: &str) -> String {
+        match self.db {
+            DbType::Mysql => {
+                let mut out = String::with_capacity(s.len() + 2);
+                out.push('\'');
+                for c in s.chars() {
+                    match c {
+                        '\'' => out.push_str("''"),
+                        '\\' => out.push_str("\\\\"),
+                        '\0' => out.push_str("\\0"),
+                        c => out.push(c),
+                    }
+                }
+                out.push('\'');
+                out
+            }
+            DbType::Mssql => format!("N'{}'", s.replace('\'', "''")),
+            DbType::Postgres | DbType::Sqlite => format!("'{}'", s.replace('\'', "''")),
+        }
+    }
+
+    pub fn bool_literal(&self, b: bool) -> &'static str {
+        match (self.db, b) {
+            (DbType::Mysql | DbType::Postgres, true) => "TRUE",
+            (DbType::Mysql | DbType::Postgres, false) => "FALSE",
+            (_, true) => "1",
+            (_, false) => "0",
+        }
+    }
+
+    /// The clause that skips `offset` rows and returns at most `limit`,
+    /// placed after ORDER BY. SQL Server requires the ORDER BY.
+    pub fn limit_offset(&self, limit: u64, offset: u64) -> String {
+        match self.db {
+            DbType::Mssql => format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, limit),
+            _ if offset == 0 => format!("LIMIT {}", limit),
+            _ => format!("LIMIT {} OFFSET {}", limit, offset),
+        }
+    }
+
+    /// An expression for the session's current database.
+    pub fn current_database(&self) -> &'static str {
+        match self.db {
+            DbType::Mysql => "DATABASE()",
+            DbType::Postgres => "current_database()",
+            DbType::Mssql => "DB_NAME()",
+            DbType::Sqlite => "NULL",
+        }
+    }
+
+    /// An expression for the schema unqualified names resolve in: the
+    /// database in MySQL, `main` in SQLite.
+    pub fn current_schema(&self) -> &'static str {
+        match self.db {
+            DbType::Mysql => "DATABASE()",
+            DbType::Postgres => "current_schema()",
+            DbType::Mssql => "SCHEMA_NAME()",
+            DbType::Sqlite => "'main'",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_names_and_values() {
+        let (mysql, pg, mssql) = (DbType::Mysql.dialect(), DbType::Postgres.dialect(), DbType::Mssql.dialect());
+        assert_eq!(mysql.qualify(Some("a`b"), "t"), "`a``b`.`t`");
+        assert_eq!(pg.qualify(Some(""), "say \"hi\""), r#""say ""hi""""#);
+        assert_eq!(mssql.qualify(None, "x]y"), "[x]]y]");
+
+        assert_eq!(mysql.string_literal(r"it's \ "), r"'it''s \\ '");
+        assert_eq!(pg.string_literal(r"it's \ "), r"'it''s \ '");
+        assert_eq!(mssql.string_literal("it's"), "N'it''s'");
+
+        assert_eq!(DbType::Sqlite.dialect().bool_literal(true), "1");
+        assert_eq!(pg.limit_offset(10, 0), "LIMIT 10");
+        assert_eq!(mysql.limit_offset(10, 20), "LIMIT 10 OFFSET 20");
+        assert_eq!(mssql.limit_offset(10, 20), "OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY");
+    }
+}
@@ -0,0 +1,177 @@
+// Statement fingerprints, for grouping the query history. `normalize` reduces
+// SQL to its shape: literals become `?`, lists of them one `?`, comments go,
+// bare words are upper-cased and the layout is rewritten, so
+//
+//   select * from t where id in (1, 2,3) -- recent
+//   SELECT *  FROM t WHERE id IN (42)
+//
+// both read `SELECT * FROM T WHERE ID IN(?)`. Quoted identifiers and bind
+// placeholders are kept as written. `fingerprint` is a stable hash of that.
+
+use crate::drivers::DbType;
+
+/// Characters that make up an operator token (`<=`, `::`, `||`).
+const OPERATOR: &str = "<>=!|&:+-*/%^~";
+
+/// The shape of a script; see the module comment.
+pub fn normalize(db: DbType, sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$');
+    let mut tokens: Vec<String> = Vec::new();
+    // Whether anything separated the next token from the previous one.
+    let mut spaced = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            spaced = true;
+            continue;
+        } else if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            spaced = true;
+            continue;
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+            spaced = true;
+            continue;
+        } else if matches!(c, '\'' | '"' | '`') || (c == '[' && db == DbType::Mssql) {
+            let close = if c == '[' { ']' } else { c };
+            i += 1;
+            while i < chars.len() {
+                let escaped = chars[i] == '\\' && db == DbType::Mysql;
+                if escaped || (chars[i] == close && chars.get(i + 1) == Some(&close)) {
+                    i += 2;
+                } else if chars[i] == close {
+                    break;
+                } else {
+                    i += 1;
+                }
+            }
+            i = (i + 1).min(chars.len());
+            if c == '\'' {
+                // The prefix of N'..', E'..', X'..' belongs to the literal.
+                let prefixed = !spaced
+                    && tokens.last().is_some_and(|t| matches!(t.as_str(), "N" | "E" | "X" | "B"));
+                if prefixed {
+                    tokens.pop();
+                }
+                push_literal(&mut tokens);
+            } else {
+                tokens.push(chars[start..i].iter().collect());
+            }
+        } else if c == '$' && db == DbType::Postgres && !next.is_some_and(|n| n.is_ascii_digit()) {
+            let tag_len = chars[i + 1..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+            if chars.get(i + 1 + tag_len) != Some(&'$') {
+                i += 1 + tag_len;
+                tokens.push(chars[start..i].iter().collect::<String>().to_ascii_uppercase());
+            } else {
+                let delimiter: Vec<char> = chars[i..i + 2 + tag_len].to_vec();
+                i += delimiter.len();
+                while i < chars.len() && !chars[i..].starts_with(&delimiter) {
+                    i += 1;
+                }
+                i = (i + delimiter.len()).min(chars.len());
+                push_literal(&mut tokens);
+            }
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            while i < chars.len() {
+                let exponent_sign = matches!(chars[i], '+' | '-') && matches!(chars[i - 1], 'e' | 'E');
+                if !(chars[i].is_alphanumeric() || chars[i] == '.' || exponent_sign) {
+                    break;
+                }
+                i += 1;
+            }
+            push_literal(&mut tokens);
+        } else if is_word(c) {
+            while i < chars.len() && is_word(chars[i]) {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect::<String>().to_ascii_uppercase());
+        } else if OPERATOR.contains(c) {
+            while i < chars.len() && OPERATOR.contains(chars[i]) {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else {
+            i += 1;
+            tokens.push(c.to_string());
+        }
+        spaced = false;
+    }
+    while tokens.last().is_some_and(|t| t == ";") {
+        tokens.pop();
+    }
+
+    let mut out = String::new();
+    for (n, token) in tokens.iter().enumerate() {
+        let prev = n.checked_sub(1).map(|p| tokens[p].as_str());
+        let glued = matches!(prev, None | Some("(" | "."))
+            || matches!(token.as_str(), "(" | ")" | "," | "." | ";");
+        if !glued {
+            out.push(' ');
+        }
+        out.push_str(token);
+    }
+    // Rows of literals: `VALUES (?), (?)`.
+    while out.contains("(?),(?)") {
+        out = out.replace("(?),(?)", "(?)");
+    }
+    out
+}
+
+/// A stable hash of `normalize`, as 16 hex digits.
+pub fn fingerprint(db: DbType, sql: &str) -> String {
+    // FNV-1a: std's hashers may change between releases, and fingerprints
+    // are stored.
+    let hash = normalize(db, sql)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// Add a `?`, folding it into a list of them.
+fn push_literal(tokens: &mut Vec<String>) {
+    let len = tokens.len();
+    if len >= 2 && tokens[len - 1] == "," && tokens[len - 2] == "?" {
+        tokens.pop();
+    } else {
+        tokens.push("?".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_literals_and_layout() {
+        let a = normalize(DbType::Mysql, "select * from t where id in (1, 2,3) -- recent\n;");
+        let b = normalize(DbType::Mysql, "SELECT *  FROM t\n WHERE id IN (42);");
+        assert_eq!(a, "SELECT * FROM T WHERE ID IN(?)");
+        assert_eq!(a, b);
+        assert_eq!(fingerprint(DbType::Mysql, "select 1"), fingerprint(DbType::Mysql, "SELECT  2.5e-3"));
+        assert_ne!(fingerprint(DbType::Mysql, "select 1"), fingerprint(DbType::Mysql, "select 1 from t"));
+
+        assert_eq!(
+            normalize(DbType::Mysql, "INSERT INTO `t` (a, b) VALUES (1, 'x\\'y'), (2, 'z')"),
+            "INSERT INTO `t`(A, B) VALUES(?)"
+        );
+        assert_eq!(
+            normalize(DbType::Postgres, "select $1::int, $$body$$, e'a' /* c */ from \"T\" where x <> -1"),
+            "SELECT $1 :: INT, ? FROM \"T\" WHERE X <> - ?"
+        );
+        assert_eq!(
+            normalize(DbType::Mssql, "SELECT [a b] FROM t WHERE n = N'x' AND d = @P1"),
+            "SELECT [a b] FROM T WHERE N = ? AND D = @P1"
+        );
+    }
+}
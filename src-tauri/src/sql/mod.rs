@@ -1,9 +1,33 @@
-// SQL utilities ported from the legacy backend. Two concerns:
-//   1. split_sql_statements — quote/backtick-aware statement splitter.
+// SQL utilities ported from the legacy backend. Four concerns:
+//   1. split_sql_statements — quote/backtick-aware statement splitter, plus
+//      split_postgres_statements for PostgreSQL's lexical rules.
 //   2. Query timeout constants + normalization (clamped to a sane range).
+//   3. Bind parameter helpers shared by the drivers (named placeholder
+//      rewriting, base64 bytes).
+//   4. statement_words — the words of a statement outside literals and
+//      comments, for the checks that look at what SQL does (`destructive`,
+//      `drivers::read_only`).
+//
+// Submodules: `dialect` (quoting names and values for each database),
+// `destructive` (statements held back for confirmation),
+// `fingerprint` (grouping the query history), `placeholders` (`${name}`
+// in saved snippets) and `row_edits` (keyed INSERT/UPDATE/DELETE for grid
+// edits).
+
+pub mod destructive;
+pub mod dialect;
+pub mod fingerprint;
+pub mod placeholders;
+pub mod row_edits;
 
 use std::time::Duration;
 
+use base64::Engine;
+
+use crate::drivers::DbType;
+use crate::models::{QueryParam, QueryParams};
+use crate::AppError;
+
 /// Split a SQL string into individual statements, respecting single-quotes,
 /// double-quotes, backticks, and backslash escapes. Empty statements are
 /// skipped. Ported verbatim from the legacy db.rs.
@@ -49,11 +73,127 @@ pub fn split_sql_statements(sql: &str) -> Vec<String> {
     stmts
 }
 
+/// Split a PostgreSQL script into statements. Unlike `split_sql_statements`
+/// this follows PostgreSQL's lexical rules: backslashes only escape inside
+/// `E'...'` strings, `$tag$...$tag$` bodies and comments (`--`, nested
+/// `/* */`) are opaque. Pieces holding only whitespace and comments are
+/// skipped.
+pub fn split_postgres_statements(sql: &str) -> Vec<String> {
+    fn is_ident(b: u8) -> bool {
+        b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+    }
+
+    // All delimiters are ASCII, so byte offsets are valid char boundaries.
+    let bytes = sql.as_bytes();
+    let len = bytes.len();
+    let mut stmts = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut i = 0;
+    while i < len {
+        match bytes[i] {
+            b'\'' => {
+                let escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !is_ident(bytes[i - 2]));
+                i += 1;
+                while i < len {
+                    if escapes && bytes[i] == b'\\' {
+                        i += 2;
+                        continue;
+                    }
+                    if bytes[i] == b'\'' {
+                        if bytes.get(i + 1) == Some(&b'\'') {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                has_code = true;
+            }
+            b'"' => {
+                // A doubled `""` closes and reopens, which is the same thing.
+                i += 1;
+                while i < len && bytes[i] != b'"' {
+                    i += 1;
+                }
+                i += 1;
+                has_code = true;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < len && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let mut depth = 0;
+                while i < len {
+                    if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            // `$tag$` opens a dollar-quoted body; `$1` is a parameter and
+            // `a$b` part of an identifier.
+            b'$' if i == 0 || !is_ident(bytes[i - 1]) => {
+                let tag_len = bytes[i + 1..]
+                    .iter()
+                    .position(|&b| !is_ident(b))
+                    .unwrap_or(len - i - 1);
+                let opens = bytes.get(i + 1 + tag_len) == Some(&b'$')
+                    && !bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+                if opens {
+                    let tag = &sql[i..i + tag_len + 2];
+                    let body = i + tag.len();
+                    i = match sql[body..].find(tag) {
+                        Some(end) => body + end + tag.len(),
+                        None => len,
+                    };
+                } else {
+                    i += 1;
+                }
+                has_code = true;
+            }
+            b';' => {
+                if has_code {
+                    stmts.push(sql[start..i].trim().to_string());
+                }
+                start = i + 1;
+                has_code = false;
+                i += 1;
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                has_code = true;
+                i += 1;
+            }
+        }
+    }
+    if has_code {
+        stmts.push(sql[start.min(len)..].trim().to_string());
+    }
+    stmts
+}
+
 //  ------ Query timeout
 
 pub const DEFAULT_QUERY_TIMEOUT_MS: u64 = 30_000;
 pub const MIN_QUERY_TIMEOUT_MS: u64 = 5_000;
 pub const MAX_QUERY_TIMEOUT_MS: u64 = 300_000;
+/// Pass as `timeout_ms` to run without a timeout (maintenance jobs).
+pub const NO_QUERY_TIMEOUT_MS: u64 = 0;
 
 /// Clamp a requested timeout to the allowed range, defaulting if None.
 pub fn normalized_query_timeout_ms(timeout_ms: Option<u64>) -> u64 {
@@ -62,6 +202,14 @@ pub fn normalized_query_timeout_ms(timeout_ms: Option<u64>) -> u64 {
         .clamp(MIN_QUERY_TIMEOUT_MS, MAX_QUERY_TIMEOUT_MS)
 }
 
+/// The timeout to enforce, or None when the caller asked for no timeout.
+pub fn effective_query_timeout_ms(timeout_ms: Option<u64>) -> Option<u64> {
+    match timeout_ms {
+        Some(NO_QUERY_TIMEOUT_MS) => None,
+        other => Some(normalized_query_timeout_ms(other)),
+    }
+}
+
 /// Human-readable timeout label for error messages.
 pub fn format_timeout_label(timeout_ms: u64) -> String {
     if timeout_ms % 1000 == 0 {
@@ -73,6 +221,9 @@ pub fn format_timeout_label(timeout_ms: u64) -> String {
 
 /// Wrap a future in a tokio timeout, returning an AppError on expiry.
 /// `label` is included in the timeout error message for diagnostics.
+///
+/// This only stops waiting; statements use the drivers' watchdog instead so
+/// the server stops too.
 pub async fn run_with_timeout<T, F>(
     timeout_ms: Option<u64>,
     label: &str,
@@ -81,7 +232,9 @@ pub async fn run_with_timeout<T, F>(
 where
     F: std::future::Future<Output = Result<T, crate::AppError>>,
 {
-    let effective = normalized_query_timeout_ms(timeout_ms);
+    let Some(effective) = effective_query_timeout_ms(timeout_ms) else {
+        return future.await;
+    };
     match tokio::time::timeout(Duration::from_millis(effective), future).await {
         Ok(result) => result,
         Err(_) => Err(crate::AppError::database(format!(
@@ -92,6 +245,204 @@ where
     }
 }
 
+//  ------ Bind parameters
+
+/// Rewrite `:name` placeholders to a driver's positional form (`$1`, `@P1`)
+/// and return the distinct names in placeholder order. Quotes, backticks and
+/// escapes are respected as in `split_sql_statements`; `::` casts are left
+/// alone.
+pub fn rewrite_named_params(sql: &str, placeholder: impl Fn(usize) -> String) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(sql.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut in_str_single = false;
+    let mut in_str_double = false;
+    let mut in_backtick = false;
+    let mut prev: Option<char> = None;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            out.push(c);
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+            prev = None;
+            continue;
+        }
+        let quoted = in_str_single || in_str_double || in_backtick;
+        match c {
+            '\'' if !in_str_double && !in_backtick => in_str_single = !in_str_single,
+            '"' if !in_str_single && !in_backtick => in_str_double = !in_str_double,
+            '`' if !in_str_single && !in_str_double => in_backtick = !in_backtick,
+            ':' if !quoted
+                && prev != Some(':')
+                && chars.peek().is_some_and(|n| n.is_ascii_alphabetic() || *n == '_') =>
+            {
+                let mut name = String::new();
+                while let Some(&n) = chars.peek() {
+                    if !(n.is_ascii_alphanumeric() || n == '_') {
+                        break;
+                    }
+                    name.push(n);
+                    chars.next();
+                }
+                let index = match names.iter().position(|n| *n == name) {
+                    Some(i) => i,
+                    None => {
+                        names.push(name);
+                        names.len() - 1
+                    }
+                };
+                out.push_str(&placeholder(index + 1));
+                prev = None;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+
+    (out, names)
+}
+
+/// Resolve params to positional order for drivers without native named
+/// binding: named params are rewritten with `placeholder`, positional ones
+/// pass through untouched.
+pub fn positional_params<'a>(
+    sql: &str,
+    params: &'a QueryParams,
+    placeholder: impl Fn(usize) -> String,
+) -> Result<(String, Vec<&'a QueryParam>), AppError> {
+    match params {
+        QueryParams::Positional(values) => Ok((sql.to_string(), values.iter().collect())),
+        QueryParams::Named(values) => {
+            let (sql, names) = rewrite_named_params(sql, placeholder);
+            let ordered = names
+                .iter()
+                .map(|name| {
+                    values.get(name).ok_or_else(|| {
+                        AppError::validation(format!("No value for parameter :{}", name))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((sql, ordered))
+        }
+    }
+}
+
+/// Decode a `bytes` param (base64).
+pub fn decode_bytes_param(encoded: &str) -> Result<Vec<u8>, AppError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| AppError::validation(format!("Invalid base64 in bytes parameter: {}", e)))
+}
+
+//  ------ Statement words
+
+/// A word of a statement: a bare word, upper-cased, or a quoted identifier
+/// without its delimiters. `depth` counts the parentheses around it; `start`
+/// and `end` are char offsets into the statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    pub text: String,
+    pub quoted: bool,
+    pub depth: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Word {
+    /// Whether this is the bare keyword `keyword` (given in upper case).
+    pub fn is(&self, keyword: &str) -> bool {
+        !self.quoted && self.text == keyword
+    }
+}
+
+/// The words of one statement, in order. String literals, comments and
+/// Postgres dollar-quoted bodies are skipped, except MySQL's executable
+/// `/*! ... */` comments, whose bodies are code; `db` picks the quoting
+/// rules (`[name]` in SQL Server, backslash escapes in MySQL).
+pub fn statement_words(db: DbType, stmt: &str) -> Vec<Word> {
+    let chars: Vec<char> = stmt.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$');
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') && db == DbType::Mysql && chars.get(i + 2) == Some(&'!') {
+            // MySQL runs the body of `/*! ... */` and `/*!50000 ... */`; the
+            // closing `*/` is punctuation to the scan below.
+            i += 3;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if matches!(c, '\'' | '"' | '`') || (c == '[' && db == DbType::Mssql) {
+            let close = if c == '[' { ']' } else { c };
+            let start = i;
+            let mut text = String::new();
+            i += 1;
+            while i < chars.len() {
+                let escaped = chars[i] == '\\' && db == DbType::Mysql;
+                if escaped || (chars[i] == close && chars.get(i + 1) == Some(&close)) {
+                    text.push(chars[i + 1..].first().copied().unwrap_or(close));
+                    i += 2;
+                } else if chars[i] == close {
+                    break;
+                } else {
+                    text.push(chars[i]);
+                    i += 1;
+                }
+            }
+            i += 1;
+            if c != '\'' {
+                words.push(Word { text, quoted: true, depth, start, end: i.min(chars.len()) });
+            }
+        } else if c == '$' && db == DbType::Postgres {
+            let tag_len = chars[i + 1..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+            let tag: String = chars[i..i + 1 + tag_len].iter().collect();
+            let opens = chars.get(i + 1 + tag_len) == Some(&'$') && !next.is_some_and(|n| n.is_ascii_digit());
+            if !opens {
+                i += 1 + tag_len;
+                continue;
+            }
+            let delimiter: Vec<char> = format!("{}$", tag).chars().collect();
+            i += delimiter.len();
+            while i < chars.len() && !chars[i..].starts_with(&delimiter) {
+                i += 1;
+            }
+            i += delimiter.len();
+        } else if is_word(c) {
+            let start = i;
+            while i < chars.len() && is_word(chars[i]) {
+                i += 1;
+            }
+            let text = chars[start..i].iter().collect::<String>().to_ascii_uppercase();
+            words.push(Word { text, quoted: false, depth, start, end: i });
+        } else {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    words
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,10 +477,74 @@ mod tests {
         assert_eq!(stmts, vec!["SELECT 1"]);
     }
 
+    #[test]
+    fn splits_postgres_scripts() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $body$ LANGUAGE sql;\n\
+                   SELECT 'C:\\'; SELECT E'it\\'s;'; -- don't; split\n\
+                   SELECT \"a;b\", $1 /* x /* y; */ z; */ ;\n\
+                   -- trailing comment";
+        let stmts = split_postgres_statements(sql);
+        assert_eq!(stmts.len(), 4);
+        assert!(stmts[0].ends_with("LANGUAGE sql"));
+        assert_eq!(stmts[1], "SELECT 'C:\\'");
+        assert_eq!(stmts[2], "SELECT E'it\\'s;'");
+        assert!(stmts[3].starts_with("-- don't; split\nSELECT \"a;b\", $1"));
+        assert_eq!(split_postgres_statements("SELECT $$a;b$$; SELECT 2"), vec!["SELECT $$a;b$$", "SELECT 2"]);
+    }
+
     #[test]
     fn clamps_timeout() {
         assert_eq!(normalized_query_timeout_ms(None), 30_000);
         assert_eq!(normalized_query_timeout_ms(Some(1)), 5_000);
         assert_eq!(normalized_query_timeout_ms(Some(999_999)), 300_000);
     }
+
+    #[test]
+    fn zero_disables_timeout() {
+        assert_eq!(effective_query_timeout_ms(Some(NO_QUERY_TIMEOUT_MS)), None);
+        assert_eq!(effective_query_timeout_ms(None), Some(30_000));
+        assert_eq!(effective_query_timeout_ms(Some(1)), Some(5_000));
+    }
+
+    #[test]
+    fn rewrites_named_params() {
+        let (sql, names) = rewrite_named_params(
+            "SELECT :a::text, ':skip', :b_2 FROM t WHERE x = :a",
+            |i| format!("${}", i),
+        );
+        assert_eq!(sql, "SELECT $1::text, ':skip', $2 FROM t WHERE x = $1");
+        assert_eq!(names, vec!["a", "b_2"]);
+    }
+
+    #[test]
+    fn named_params_must_all_be_bound() {
+        let mut named = std::collections::BTreeMap::new();
+        named.insert("id".to_string(), QueryParam::Int(1));
+        let params = QueryParams::Named(named);
+        let (sql, values) = positional_params("SELECT :id", &params, |i| format!("@P{}", i)).unwrap();
+        assert_eq!(sql, "SELECT @P1");
+        assert_eq!(values.len(), 1);
+        let err = positional_params("SELECT :id, :name", &params, |i| format!("@P{}", i)).unwrap_err();
+        assert_eq!(err.kind, "validation");
+    }
+
+    #[test]
+    fn finds_statement_words() {
+        let words = statement_words(DbType::Mssql, "select [a]]b] -- drop\n, 'it''s' FROM (t)");
+        let texts: Vec<(&str, bool, usize)> =
+            words.iter().map(|w| (w.text.as_str(), w.quoted, w.depth)).collect();
+        assert_eq!(texts, vec![("SELECT", false, 0), ("a]b", true, 0), ("FROM", false, 0), ("T", false, 1)]);
+
+        let words = statement_words(DbType::Postgres, "SELECT $body$ DELETE $body$, $1, '\\' FROM \"t\"");
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["SELECT", "FROM", "t"]);
+        assert!(words[1].is("FROM") && words[2].quoted);
+
+        // MySQL runs executable comments; elsewhere they are comments.
+        let texts = |db| -> Vec<String> {
+            statement_words(db, "SELECT 1 /*!50000 FOR UPDATE */ /* x */").into_iter().map(|w| w.text).collect()
+        };
+        assert_eq!(texts(DbType::Mysql), vec!["SELECT", "1", "FOR", "UPDATE"]);
+        assert_eq!(texts(DbType::Postgres), vec!["SELECT", "1"]);
+    }
 }
@@ -0,0 +1,153 @@
+// `${name}` placeholders in saved snippets (see `services::snippets`).
+//
+// A placeholder stands for a value. Running a snippet turns each one into a
+// named `:name` parameter, so values are bound by the driver and never
+// spliced into the SQL. A placeholder inside a string literal or quoted
+// identifier cannot be bound and is refused; one inside a comment is not a
+// placeholder at all.
+
+use crate::drivers::DbType;
+use crate::{AppError, AppResult};
+
+/// One `${name}`; `start` and `end` are byte offsets, braces included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    /// Inside a string literal, quoted identifier or dollar-quoted body.
+    pub quoted: bool,
+}
+
+/// The placeholders of `sql`, in order.
+pub fn find(db: DbType, sql: &str) -> Vec<Placeholder> {
+    let chars: Vec<(usize, char)> = sql.char_indices().collect();
+    let at = |i: usize| chars.get(i).map(|c| c.1);
+    let offset = |i: usize| chars.get(i).map_or(sql.len(), |c| c.0);
+    let mut found = Vec::new();
+    // The delimiter that closes the quoted run we are in.
+    let mut quote: Option<Vec<char>> = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        if c == '$' && at(i + 1) == Some('{') {
+            let len = name_len(&chars[i + 2..]);
+            if len > 0 && at(i + 2 + len) == Some('}') {
+                found.push(Placeholder {
+                    name: chars[i + 2..i + 2 + len].iter().map(|c| c.1).collect(),
+                    start: offset(i),
+                    end: offset(i + 3 + len),
+                    quoted: quote.is_some(),
+                });
+                i += 3 + len;
+                continue;
+            }
+        }
+        if let Some(close) = &quote {
+            let single = close.len() == 1;
+            let escaped = single && c == '\\' && db == DbType::Mysql && close[0] != '`';
+            if escaped || (single && c == close[0] && at(i + 1) == Some(c)) {
+                i += 2;
+            } else if chars[i..].iter().map(|c| c.1).take(close.len()).eq(close.iter().copied()) {
+                i += close.len();
+                quote = None;
+            } else {
+                i += 1;
+            }
+            continue;
+        }
+        let next = at(i + 1);
+        if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i].1 != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i].1 == '*' && at(i + 1) == Some('/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if matches!(c, '\'' | '"' | '`') || (c == '[' && db == DbType::Mssql) {
+            quote = Some(vec![if c == '[' { ']' } else { c }]);
+            i += 1;
+        } else if c == '$' && db == DbType::Postgres && !next.is_some_and(|n| n.is_ascii_digit()) {
+            let tag_len = chars[i + 1..].iter().take_while(|c| c.1.is_alphanumeric() || c.1 == '_').count();
+            if at(i + 1 + tag_len) == Some('$') {
+                quote = Some(chars[i..i + 2 + tag_len].iter().map(|c| c.1).collect());
+                i += 2 + tag_len;
+            } else {
+                i += 1 + tag_len;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    found
+}
+
+/// The distinct placeholder names of `sql`, in order of first use.
+pub fn names(db: DbType, sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for p in find(db, sql) {
+        if !names.contains(&p.name) {
+            names.push(p.name);
+        }
+    }
+    names
+}
+
+/// `sql` with each placeholder turned into a `:name` parameter.
+pub fn bind(db: DbType, sql: &str) -> AppResult<String> {
+    let found = find(db, sql);
+    if let Some(p) = found.iter().find(|p| p.quoted) {
+        return Err(AppError::validation(format!(
+            "${{{}}} is inside quotes and cannot be bound; use it in place of the whole literal",
+            p.name
+        )));
+    }
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0;
+    for p in &found {
+        out.push_str(&sql[last..p.start]);
+        out.push(':');
+        out.push_str(&p.name);
+        last = p.end;
+    }
+    out.push_str(&sql[last..]);
+    Ok(out)
+}
+
+fn name_len(chars: &[(usize, char)]) -> usize {
+    match chars.first() {
+        Some((_, c)) if c.is_ascii_alphabetic() || *c == '_' => {
+            chars.iter().take_while(|c| c.1.is_ascii_alphanumeric() || c.1 == '_').count()
+        }
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_and_binds_placeholders() {
+        let sql = "SELECT * FROM t WHERE a = ${id} AND b > ${since} -- ${note}\n OR a = ${id}";
+        assert_eq!(names(DbType::Mysql, sql), vec!["id", "since"]);
+        assert_eq!(
+            bind(DbType::Mysql, sql).unwrap(),
+            "SELECT * FROM t WHERE a = :id AND b > :since -- ${note}\n OR a = :id"
+        );
+        let first = &find(DbType::Mysql, sql)[0];
+        assert_eq!(&sql[first.start..first.end], "${id}");
+
+        // Not placeholders: `$` alone, `${}`, `${1x}`.
+        assert!(find(DbType::Mysql, "SELECT '$', ${}, ${1x}").is_empty());
+
+        let quoted = find(DbType::Postgres, "SELECT 'it''s ${a}', $f$ ${b} $f$, \"${c}\", ${d}");
+        let flags: Vec<(&str, bool)> = quoted.iter().map(|p| (p.name.as_str(), p.quoted)).collect();
+        assert_eq!(flags, vec![("a", true), ("b", true), ("c", true), ("d", false)]);
+        assert_eq!(bind(DbType::Postgres, "SELECT '${a}'").unwrap_err().kind, "validation");
+        assert_eq!(bind(DbType::Mssql, "SELECT [x]]${y}], ${z}").unwrap_err().kind, "validation");
+    }
+}
@@ -0,0 +1,179 @@
+// Statements for grid row edits (see `commands::row_edits`).
+//
+// Every value is a `:name` parameter: `:v0`, `:v1`... for the columns
+// written and `:k0`, `:k1`... for the row key, so column names never need
+// to be valid parameter names. A key must name exactly the table's key
+// columns (see `drivers::keys`) and none may be NULL; anything less could
+// match more than one row.
+
+use std::collections::BTreeMap;
+
+use crate::drivers::DbType;
+use crate::models::{QueryParam, QueryParams, RowChange, RowEditBatch, RowEditPlan, RowEditStatement};
+use crate::{AppError, AppResult};
+
+/// One statement per change, in order. Errors name the change (1-based).
+pub fn plan(db: DbType, batch: &RowEditBatch, key_columns: &[String]) -> AppResult<RowEditPlan> {
+    if batch.changes.is_empty() {
+        return Err(AppError::validation("There are no changes to apply"));
+    }
+    let statements = batch
+        .changes
+        .iter()
+        .enumerate()
+        .map(|(i, change)| {
+            statement(db, batch, key_columns, change)
+                .map_err(|e| AppError { message: format!("Change {}: {}", i + 1, e.message), ..e })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    Ok(RowEditPlan { key_columns: key_columns.to_vec(), statements })
+}
+
+/// The statement for one change.
+pub fn statement(
+    db: DbType,
+    batch: &RowEditBatch,
+    key_columns: &[String],
+    change: &RowChange,
+) -> AppResult<RowEditStatement> {
+    let table = qualified_table(db, batch);
+    let mut params = BTreeMap::new();
+    let sql = match change {
+        RowChange::Insert { values } if values.is_empty() => match db {
+            DbType::Mysql => format!("INSERT INTO {} () VALUES ()", table),
+            _ => format!("INSERT INTO {} DEFAULT VALUES", table),
+        },
+        RowChange::Insert { values } => {
+            let mut columns = Vec::with_capacity(values.len());
+            let mut placeholders = Vec::with_capacity(values.len());
+            for (i, (column, value)) in values.iter().enumerate() {
+                columns.push(db.dialect().quote_ident(column));
+                placeholders.push(format!(":v{}", i));
+                params.insert(format!("v{}", i), value.clone());
+            }
+            format!("INSERT INTO {} ({}) VALUES ({})", table, columns.join(", "), placeholders.join(", "))
+        }
+        RowChange::Update { key, values } => {
+            if values.is_empty() {
+                return Err(AppError::validation("An update needs at least one column to set"));
+            }
+            let set: Vec<String> = values
+                .iter()
+                .enumerate()
+                .map(|(i, (column, value))| {
+                    params.insert(format!("v{}", i), value.clone());
+                    format!("{} = :v{}", db.dialect().quote_ident(column), i)
+                })
+                .collect();
+            let filter = key_filter(db, key_columns, key, &mut params)?;
+            format!("UPDATE {} SET {} WHERE {}", table, set.join(", "), filter)
+        }
+        RowChange::Delete { key } => {
+            let filter = key_filter(db, key_columns, key, &mut params)?;
+            format!("DELETE FROM {} WHERE {}", table, filter)
+        }
+    };
+    Ok(RowEditStatement { sql, params: QueryParams::Named(params) })
+}
+
+/// `SELECT COUNT(*)` of the rows `key` matches. MySQL reports the rows an
+/// UPDATE changed, not the rows it matched, so an update that writes the
+/// values a row already holds is told apart from a missing row with this.
+pub fn count_statement(
+    db: DbType,
+    batch: &RowEditBatch,
+    key_columns: &[String],
+    key: &BTreeMap<String, QueryParam>,
+) -> AppResult<RowEditStatement> {
+    let mut params = BTreeMap::new();
+    let filter = key_filter(db, key_columns, key, &mut params)?;
+    let sql = format!("SELECT COUNT(*) FROM {} WHERE {}", qualified_table(db, batch), filter);
+    Ok(RowEditStatement { sql, params: QueryParams::Named(params) })
+}
+
+fn qualified_table(db: DbType, batch: &RowEditBatch) -> String {
+    db.dialect().qualify(batch.schema.as_deref(), &batch.table)
+}
+
+/// The WHERE clause for `key`, in key column order. Names match the key
+/// columns case-insensitively; the catalog's spelling is used.
+fn key_filter(
+    db: DbType,
+    key_columns: &[String],
+    key: &BTreeMap<String, QueryParam>,
+    params: &mut BTreeMap<String, QueryParam>,
+) -> AppResult<String> {
+    if let Some(extra) = key.keys().find(|k| !key_columns.iter().any(|c| c.eq_ignore_ascii_case(k))) {
+        return Err(AppError::validation(format!("{} is not a key column", extra)));
+    }
+    let mut terms = Vec::with_capacity(key_columns.len());
+    for (i, column) in key_columns.iter().enumerate() {
+        let value = key
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(column))
+            .map(|(_, v)| v)
+            .ok_or_else(|| AppError::validation(format!("The row key is missing column {}", column)))?;
+        if matches!(value, QueryParam::Null) {
+            return Err(AppError::validation(format!(
+                "Key column {} is NULL and cannot identify a row",
+                column
+            )));
+        }
+        terms.push(format!("{} = :k{}", db.dialect().quote_ident(column), i));
+        params.insert(format!("k{}", i), value.clone());
+    }
+    Ok(terms.join(" AND "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, QueryParam)]) -> BTreeMap<String, QueryParam> {
+        entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn builds_keyed_statements() {
+        let key_columns = vec!["id".to_string()];
+        let batch = RowEditBatch {
+            schema: Some("app".into()),
+            table: "users".into(),
+            changes: vec![
+                RowChange::Insert { values: map(&[("name", QueryParam::Text("a".into()))]) },
+                RowChange::Update {
+                    key: map(&[("ID", QueryParam::Int(7))]),
+                    values: map(&[("name", QueryParam::Text("b".into())), ("age", QueryParam::Null)]),
+                },
+                RowChange::Delete { key: map(&[("id", QueryParam::Int(8))]) },
+                RowChange::Insert { values: BTreeMap::new() },
+            ],
+        };
+        let edits = plan(DbType::Postgres, &batch, &key_columns).unwrap();
+        let sql: Vec<&str> = edits.statements.iter().map(|s| s.sql.as_str()).collect();
+        assert_eq!(
+            sql,
+            vec![
+                r#"INSERT INTO "app"."users" ("name") VALUES (:v0)"#,
+                r#"UPDATE "app"."users" SET "age" = :v0, "name" = :v1 WHERE "id" = :k0"#,
+                r#"DELETE FROM "app"."users" WHERE "id" = :k0"#,
+                r#"INSERT INTO "app"."users" DEFAULT VALUES"#,
+            ]
+        );
+        let QueryParams::Named(params) = &edits.statements[1].params else { panic!("named params") };
+        assert!(matches!(params["k0"], QueryParam::Int(7)));
+
+        let mysql = statement(DbType::Mysql, &batch, &key_columns, &batch.changes[3]).unwrap();
+        assert_eq!(mysql.sql, "INSERT INTO `app`.`users` () VALUES ()");
+
+        let partial = RowChange::Delete { key: BTreeMap::new() };
+        let null_key = RowChange::Delete { key: map(&[("id", QueryParam::Null)]) };
+        let other = RowChange::Delete { key: map(&[("id", QueryParam::Int(1)), ("name", QueryParam::Int(1))]) };
+        for change in [partial, null_key, other] {
+            assert!(statement(DbType::Sqlite, &batch, &key_columns, &change).is_err());
+        }
+        let empty_update = RowChange::Update { key: map(&[]), values: map(&[]) };
+        let bad = RowEditBatch { changes: vec![empty_update], ..batch };
+        assert!(plan(DbType::Mssql, &bad, &key_columns).unwrap_err().message.starts_with("Change 1: "));
+    }
+}
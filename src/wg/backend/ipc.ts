@@ -4,23 +4,57 @@
 // TreeBackend/EditorBackend shims build on.
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
+  CellRef,
   ColumnInfo,
   ConnectParams,
   ConnectionHandle,
   CredentialEntryDto,
   CredentialEntryInput,
   CredentialNodeDto,
+  CursorInfo,
+  CursorPage,
   DbServerDto,
   DbServerInput,
   DbServerTestResult,
   DockerContainerDto,
+  ExportOptions,
+  ExportProgress,
+  ExportSource,
+  ExportSummary,
+  HistoryGroup,
+  HistoryPage,
+  HistoryQuery,
+  HistoryRetention,
+  ImportColumn,
+  ImportFileOptions,
+  ImportMapping,
+  ImportOptions,
+  ImportPreview,
+  ImportSummary,
+  ImportTarget,
   SshServerDto,
   SshServerInput,
   SshTestResult,
   DatabaseInfo,
+  OpenTransactionPolicy,
+  PoolStats,
+  QueryParam,
+  QueryParams,
+  QueryPlan,
   QueryResultSet,
+  RowEditBatch,
+  RowEditPlan,
+  RowEditSummary,
+  SessionScope,
+  SnippetInput,
+  SnippetNode,
+  SnippetQuery,
+  SnippetText,
   TableInfo,
+  TransactionOptions,
+  TransactionState,
 } from './types.js';
 import type { TreeNode } from './BackendAdapter.js';
 
@@ -30,8 +64,9 @@ export function dbConnect(params: ConnectParams): Promise<ConnectionHandle> {
   return invoke<ConnectionHandle>('db_connect', { params });
 }
 
-export function dbDisconnect(profileId: string): Promise<void> {
-  return invoke<void>('db_disconnect', { profileId });
+/** Fails while a session has an open transaction unless `onOpenTransaction` is `'rollback'`. */
+export function dbDisconnect(profileId: string, onOpenTransaction?: OpenTransactionPolicy): Promise<void> {
+  return invoke<void>('db_disconnect', { profileId, onOpenTransaction: onOpenTransaction ?? null });
 }
 
 export function dbCancelConnect(profileId: string): Promise<void> {
@@ -46,22 +81,414 @@ export function dbPing(profileId: string): Promise<void> {
   return invoke<void>('db_ping', { profileId });
 }
 
+/** Pool statistics of one profile, or of every connected profile. */
+export function dbPoolStats(profileId?: string): Promise<PoolStats[]> {
+  return invoke<PoolStats[]>('db_pool_stats', { profileId: profileId ?? null });
+}
+
 //  ------ Sessions + queries
 
 export function dbBeginSession(profileId: string): Promise<string> {
   return invoke<string>('db_begin_session', { profileId });
 }
 
-export function dbEndSession(sessionId: string): Promise<void> {
-  return invoke<void>('db_end_session', { sessionId });
+/** Fails while the session has an open transaction unless `onOpenTransaction` is `'rollback'`. */
+export function dbEndSession(sessionId: string, onOpenTransaction?: OpenTransactionPolicy): Promise<void> {
+  return invoke<void>('db_end_session', { sessionId, onOpenTransaction: onOpenTransaction ?? null });
+}
+
+export function dbGetSessionScope(sessionId: string): Promise<SessionScope> {
+  return invoke<SessionScope>('db_get_session_scope', { sessionId });
+}
+
+/**
+ * Switch the session's default database and/or schema. A Postgres session
+ * switching database gets a new connection, so its settings are reset.
+ */
+export function dbSetSessionScope(
+  sessionId: string,
+  database?: string,
+  schema?: string,
+): Promise<SessionScope> {
+  return invoke<SessionScope>('db_set_session_scope', {
+    sessionId,
+    database: database ?? null,
+    schema: schema ?? null,
+  });
+}
+
+/** Pass as `timeoutMs` to run without a timeout (maintenance jobs). */
+export const NO_QUERY_TIMEOUT_MS = 0;
+
+/**
+ * A session whose connection dropped is reconnected, and the call run again
+ * when every statement reads; the first result set then carries a notice
+ * saying so. SQL that may write, or a transaction that was open, rejects with
+ * an error of kind `connection_lost` instead, since its outcome is unknown.
+ *
+ * Destructive statements (UPDATE/DELETE without WHERE, TRUNCATE, DROP) reject
+ * with kind `confirmation_required` and a `ConfirmationRequired` in
+ * `details`; send the same SQL again with its `token` as `confirmToken`.
+ *
+ * Runs that reach the server are recorded in the query history.
+ */
+export function dbQuery(
+  sessionId: string,
+  sql: string,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<QueryResultSet[]> {
+  return invoke<QueryResultSet[]>('db_query', {
+    sessionId,
+    sql,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+/** Run one statement with typed bind values instead of concatenating input into SQL. */
+export function dbQueryParams(
+  sessionId: string,
+  sql: string,
+  params: QueryParams,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<QueryResultSet[]> {
+  return invoke<QueryResultSet[]>('db_query_params', {
+    sessionId,
+    sql,
+    params,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+export function dbExecute(
+  sessionId: string,
+  sql: string,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<number> {
+  return invoke<number>('db_execute', {
+    sessionId,
+    sql,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+/**
+ * The plan of one statement as a driver-independent node tree. With
+ * `analyze` the statement is run and actual rows and times are included.
+ */
+export function dbExplain(
+  sessionId: string,
+  sql: string,
+  analyze: boolean,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<QueryPlan> {
+  return invoke<QueryPlan>('db_explain', {
+    sessionId,
+    sql,
+    analyze,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+/**
+ * Cancel the statement running on a session. The pending `dbQuery`/`dbExecute`
+ * rejects with an error of kind `cancelled`.
+ */
+export function dbCancelQuery(sessionId: string): Promise<void> {
+  return invoke<void>('db_cancel_query', { sessionId });
+}
+
+/**
+ * Open a server-side cursor for one statement; page through it with
+ * `dbFetchCursor`. The session is busy until the cursor is done or closed.
+ */
+export function dbOpenCursor(
+  sessionId: string,
+  sql: string,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<CursorInfo> {
+  return invoke<CursorInfo>('db_open_cursor', {
+    sessionId,
+    sql,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+export function dbFetchCursor(cursorId: string, maxRows: number): Promise<CursorPage> {
+  return invoke<CursorPage>('db_fetch_cursor', { cursorId, maxRows });
+}
+
+export function dbCloseCursor(cursorId: string): Promise<void> {
+  return invoke<void>('db_close_cursor', { cursorId });
+}
+
+//  ------ Binary cells
+
+/** Full value of one cell as base64 (`null` for SQL NULL); result sets only carry a preview. */
+export function dbFetchCell(sessionId: string, cell: CellRef, timeoutMs?: number): Promise<string | null> {
+  return invoke<string | null>('db_fetch_cell', { sessionId, cell, timeoutMs: timeoutMs ?? null });
+}
+
+/** Write one cell to a file; resolves to the bytes written. */
+export function dbSaveCell(sessionId: string, cell: CellRef, path: string, timeoutMs?: number): Promise<number> {
+  return invoke<number>('db_save_cell', { sessionId, cell, path, timeoutMs: timeoutMs ?? null });
+}
+
+/** Replace one cell with a file's contents; resolves to the rows updated. */
+export function dbLoadCell(sessionId: string, cell: CellRef, path: string, timeoutMs?: number): Promise<number> {
+  return invoke<number>('db_load_cell', { sessionId, cell, path, timeoutMs: timeoutMs ?? null });
+}
+
+//  ------ Transactions
+
+export function dbBeginTransaction(sessionId: string, options?: TransactionOptions): Promise<TransactionState> {
+  return invoke<TransactionState>('db_begin_transaction', { sessionId, options: options ?? null });
+}
+
+export function dbCommit(sessionId: string): Promise<TransactionState> {
+  return invoke<TransactionState>('db_commit', { sessionId });
+}
+
+export function dbRollback(sessionId: string): Promise<TransactionState> {
+  return invoke<TransactionState>('db_rollback', { sessionId });
+}
+
+export function dbSavepoint(sessionId: string, name: string): Promise<TransactionState> {
+  return invoke<TransactionState>('db_savepoint', { sessionId, name });
+}
+
+export function dbRollbackToSavepoint(sessionId: string, name: string): Promise<TransactionState> {
+  return invoke<TransactionState>('db_rollback_to_savepoint', { sessionId, name });
+}
+
+export function dbReleaseSavepoint(sessionId: string, name: string): Promise<TransactionState> {
+  return invoke<TransactionState>('db_release_savepoint', { sessionId, name });
+}
+
+/** Whether a session has uncommitted work. */
+export function dbTransactionState(sessionId: string): Promise<TransactionState> {
+  return invoke<TransactionState>('db_transaction_state', { sessionId });
+}
+
+//  ------ Query history
+
+/** Entries matching `query`, newest first, one page at a time. */
+export function historySearch(query?: HistoryQuery): Promise<HistoryPage> {
+  return invoke<HistoryPage>('history_search', { query: query ?? null });
+}
+
+/** Entries matching `query` grouped by statement fingerprint. */
+export function historyGroups(query?: HistoryQuery): Promise<HistoryGroup[]> {
+  return invoke<HistoryGroup[]>('history_groups', { query: query ?? null });
+}
+
+export function historyDelete(ids: string[]): Promise<number> {
+  return invoke<number>('history_delete', { ids });
+}
+
+/** Delete the whole history, or the entries run before `before` (RFC 3339). */
+export function historyClear(before?: string): Promise<number> {
+  return invoke<number>('history_clear', { before: before ?? null });
+}
+
+export function historyGetRetention(): Promise<HistoryRetention> {
+  return invoke<HistoryRetention>('history_get_retention');
+}
+
+/** Entries beyond the new limits are removed at once. */
+export function historySetRetention(retention: HistoryRetention): Promise<HistoryRetention> {
+  return invoke<HistoryRetention>('history_set_retention', { retention });
+}
+
+//  ------ Snippets
+
+export function snippetsGetTree(): Promise<SnippetNode[]> {
+  return invoke<SnippetNode[]>('snippets_get_tree');
+}
+
+export function snippetsGet(id: string): Promise<SnippetNode> {
+  return invoke<SnippetNode>('snippets_get', { id });
+}
+
+/** Snippets matching `query`, in tree order. */
+export function snippetsSearch(query?: SnippetQuery): Promise<SnippetNode[]> {
+  return invoke<SnippetNode[]>('snippets_search', { query: query ?? null });
+}
+
+export function snippetsUpsert(input: SnippetInput): Promise<SnippetNode> {
+  return invoke<SnippetNode>('snippets_upsert', { input });
+}
+
+export function snippetsCreateFolder(parentId: string | null, name: string): Promise<SnippetNode> {
+  return invoke<SnippetNode>('snippets_create_folder', { parentId: parentId ?? null, name });
 }
 
-export function dbQuery(sessionId: string, sql: string, timeoutMs?: number): Promise<QueryResultSet[]> {
-  return invoke<QueryResultSet[]>('db_query', { sessionId, sql, timeoutMs: timeoutMs ?? null });
+/** Move to the end of a folder; `root` is the top level. */
+export function snippetsMoveNode(id: string, targetParent: string): Promise<void> {
+  return invoke<void>('snippets_move_node', { id, targetParent });
 }
 
-export function dbExecute(sessionId: string, sql: string, timeoutMs?: number): Promise<number> {
-  return invoke<number>('db_execute', { sessionId, sql, timeoutMs: timeoutMs ?? null });
+/** Insert before `beforeId` under `targetParent`, or append when omitted. */
+export function snippetsReorderNode(id: string, targetParent: string, beforeId?: string | null): Promise<void> {
+  return invoke<void>('snippets_reorder_node', { id, targetParent, beforeId: beforeId ?? null });
+}
+
+export function snippetsCopyNode(id: string, targetParent: string): Promise<SnippetNode> {
+  return invoke<SnippetNode>('snippets_copy_node', { id, targetParent });
+}
+
+export function snippetsRenameNode(id: string, newName: string): Promise<SnippetNode> {
+  return invoke<SnippetNode>('snippets_rename_node', { id, newName });
+}
+
+export function snippetsSetExpanded(id: string, expanded: boolean): Promise<void> {
+  return invoke<void>('snippets_set_expanded', { id, expanded });
+}
+
+export function snippetsDeleteNode(id: string): Promise<void> {
+  return invoke<void>('snippets_delete_node', { id });
+}
+
+/** The snippet's SQL with its placeholder positions, for the editor. */
+export function snippetsInsert(id: string): Promise<SnippetText> {
+  return invoke<SnippetText>('snippets_insert', { id });
+}
+
+/**
+ * Run a snippet on a session with each `${name}` bound to `values[name]` as
+ * a parameter. Guarded and recorded like dbQuery.
+ */
+export function snippetsRun(
+  sessionId: string,
+  id: string,
+  values?: Record<string, QueryParam>,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<QueryResultSet[]> {
+  return invoke<QueryResultSet[]>('snippets_run', {
+    sessionId,
+    id,
+    values: values ?? null,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+//  ------ Export
+
+/**
+ * Stream `source` on a session to the file at `path`. `exportId` is chosen
+ * by the caller and names the export in progress events and
+ * dbCancelExport. A cancelled or failed export leaves no file behind.
+ */
+export function dbExport(
+  sessionId: string,
+  exportId: string,
+  source: ExportSource,
+  path: string,
+  options?: ExportOptions,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<ExportSummary> {
+  return invoke<ExportSummary>('db_export', {
+    sessionId,
+    exportId,
+    source,
+    path,
+    options: options ?? null,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
+}
+
+/** The running dbExport fails with kind `cancelled`. */
+export function dbCancelExport(exportId: string): Promise<void> {
+  return invoke<void>('db_cancel_export', { exportId });
+}
+
+/** Listen to the progress of every export. */
+export function onExportProgress(handler: (progress: ExportProgress) => void): Promise<UnlistenFn> {
+  return listen<ExportProgress>('export://progress', (event) => handler(event.payload));
+}
+
+//  ------ Import
+
+/** The file's columns with inferred types, and its first `rows` rows (50). */
+export function dbImportPreview(
+  path: string,
+  options?: ImportFileOptions,
+  rows?: number,
+): Promise<ImportPreview> {
+  return invoke<ImportPreview>('db_import_preview', { path, options: options ?? null, rows: rows ?? null });
+}
+
+/** A CREATE TABLE for `columns` in the session's dialect, for review. */
+export function dbImportCreateTableSql(
+  sessionId: string,
+  target: ImportTarget,
+  columns: ImportColumn[],
+): Promise<string> {
+  return invoke<string>('db_import_create_table_sql', { sessionId, target, columns });
+}
+
+/**
+ * Load a file into `target` in batched transactions. Committed batches stay
+ * when the import stops; the summary lists the rejected rows. The session
+ * must not have a transaction open.
+ */
+export function dbImport(
+  sessionId: string,
+  path: string,
+  fileOptions: ImportFileOptions | null,
+  target: ImportTarget,
+  mapping: ImportMapping[],
+  options?: ImportOptions,
+  timeoutMs?: number,
+): Promise<ImportSummary> {
+  return invoke<ImportSummary>('db_import', {
+    sessionId,
+    path,
+    fileOptions: fileOptions ?? null,
+    target,
+    mapping,
+    options: options ?? null,
+    timeoutMs: timeoutMs ?? null,
+  });
+}
+
+//  ------ Row edits
+
+/** The keyed INSERT/UPDATE/DELETE statements `dbApplyRowEdits` would run. */
+export function dbPreviewRowEdits(sessionId: string, batch: RowEditBatch): Promise<RowEditPlan> {
+  return invoke<RowEditPlan>('db_preview_row_edits', { sessionId, batch });
+}
+
+/**
+ * Apply a batch all or none: under a savepoint in the session's open
+ * transaction (left for the caller to commit), else in its own. An update
+ * or delete that does not touch exactly one row fails with kind `conflict`.
+ */
+export function dbApplyRowEdits(
+  sessionId: string,
+  batch: RowEditBatch,
+  timeoutMs?: number,
+  confirmToken?: string,
+): Promise<RowEditSummary> {
+  return invoke<RowEditSummary>('db_apply_row_edits', {
+    sessionId,
+    batch,
+    timeoutMs: timeoutMs ?? null,
+    confirmToken: confirmToken ?? null,
+  });
 }
 
 //  ------ Schema introspection
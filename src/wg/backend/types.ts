@@ -5,9 +5,545 @@
 
 export interface QueryResultSet {
   columns: string[];
-  rows: unknown[][];
+  /** Per-column metadata, parallel to `columns`. */
+  columnMeta: ResultColumn[];
+  rows: CellValue[][];
   affectedRows: number;
   info: string;
+  /** Warnings and notices the server raised while the statement ran. */
+  messages: QueryMessage[];
+}
+
+export type MessageSeverity = 'info' | 'notice' | 'warning' | 'error';
+
+/** A server message: MySQL warnings, PG notices, SQL Server PRINT output. */
+export interface QueryMessage {
+  severity: MessageSeverity;
+  /** MySQL error number or SQLSTATE, when the server sends one. */
+  code: string | null;
+  text: string;
+}
+
+/** Driver-independent type family of a result column. */
+export type TypeCategory =
+  | 'bool'
+  | 'int'
+  | 'decimal'
+  | 'float'
+  | 'text'
+  | 'bytes'
+  | 'date'
+  | 'time'
+  | 'dateTime'
+  | 'json'
+  | 'uuid'
+  | 'other';
+
+/** Result column metadata; fields the protocol does not report are null. */
+export interface ResultColumn {
+  name: string;
+  nativeType: string;
+  category: TypeCategory;
+  nullable: boolean | null;
+  precision: number | null;
+  /** Digits after the decimal point; fractional-second digits for temporal types. */
+  scale: number | null;
+  length: number | null;
+  schema: string | null;
+  table: string | null;
+  column: string | null;
+}
+
+export interface CursorInfo {
+  cursorId: string;
+  sessionId: string;
+  columns: string[];
+  columnMeta: ResultColumn[];
+}
+
+export interface CursorPage {
+  rows: CellValue[][];
+  /** The result is exhausted and the cursor has been released. */
+  done: boolean;
+  fetchedRows: number;
+}
+
+/**
+ * One result cell. Values JSON holds exactly are plain; integers outside
+ * ±(2^53 - 1), exact decimals, non-finite floats and typed values are tagged
+ * with their text form so nothing is rounded on the way to the grid.
+ */
+export type CellValue = null | boolean | number | string | TaggedCell | BinaryValue;
+
+export type TaggedCell =
+  | { type: 'bigint'; value: string }
+  | { type: 'decimal'; value: string }
+  /** `NaN`, `Infinity` or `-Infinity`. */
+  | { type: 'float'; value: string }
+  | { type: 'date'; value: string }
+  /** `HH:MM:SS[.fff]`, with an offset when zoned. */
+  | { type: 'time'; value: string }
+  /** ISO 8601; the offset is present only for zoned timestamps. */
+  | { type: 'dateTime'; value: string }
+  /** ISO 8601 duration, e.g. `P1Y2M3DT4H5M6.5S`. */
+  | { type: 'interval'; value: string }
+  | { type: 'uuid'; value: string }
+  /** The document's source text. */
+  | { type: 'json'; value: string }
+  | { type: 'array'; items: CellValue[] }
+  | {
+      type: 'range';
+      lower: CellValue;
+      upper: CellValue;
+      lowerInclusive: boolean;
+      upperInclusive: boolean;
+      empty: boolean;
+    }
+  | { type: 'record'; fields: { name: string; value: CellValue }[] };
+
+//  ------ Bind parameters (dbQueryParams)
+
+/** A typed bind value. `bytes` is base64; temporal values are ISO-like strings. */
+export type QueryParam =
+  | { type: 'null' }
+  | { type: 'bool'; value: boolean }
+  | { type: 'int'; value: number }
+  | { type: 'decimal'; value: string }
+  | { type: 'float'; value: number }
+  | { type: 'text'; value: string }
+  | { type: 'bytes'; value: string }
+  | { type: 'date'; value: string }
+  | { type: 'time'; value: string }
+  | { type: 'dateTime'; value: string }
+  | { type: 'json'; value: unknown };
+
+/** Positional params use the driver's placeholders (`?`, `$1`, `@P1`); named ones use `:name`. */
+export type QueryParams =
+  | { positional: QueryParam[] }
+  | { named: Record<string, QueryParam> };
+
+//  ------ Binary cells (dbFetchCell / dbSaveCell / dbLoadCell)
+
+/**
+ * A binary value in a result set. `preview` is base64 of at most the first
+ * 256 bytes; when `truncated` is false it is the whole value.
+ */
+export interface BinaryValue {
+  type: 'bytes';
+  length: number;
+  preview: string;
+  truncated: boolean;
+}
+
+/** One cell of a table, addressed by its row key (normally the primary key). */
+export interface CellRef {
+  schema?: string | null;
+  table: string;
+  column: string;
+  key: Record<string, QueryParam>;
+}
+
+//  ------ Transactions
+
+export type IsolationLevel =
+  | 'readUncommitted'
+  | 'readCommitted'
+  | 'repeatableRead'
+  | 'snapshot'
+  | 'serializable';
+
+export interface TransactionOptions {
+  isolation?: IsolationLevel | null;
+  readOnly?: boolean;
+}
+
+/** A session's transaction as tracked by the backend. */
+export interface TransactionState {
+  active: boolean;
+  /** Only known for transactions started with `dbBeginTransaction`. */
+  isolation: IsolationLevel | null;
+  readOnly: boolean;
+  /** Open savepoints, oldest first. */
+  savepoints: string[];
+}
+
+/** What ending a session or disconnecting does with an open transaction. */
+export type OpenTransactionPolicy = 'refuse' | 'rollback';
+
+/**
+ * The database and schema a session resolves unqualified names against. In
+ * MySQL a schema is a database; SQLite has no database name.
+ */
+export interface SessionScope {
+  database: string | null;
+  schema: string | null;
+}
+
+//  ------ Destructive statements
+
+/** What a server's connections do with destructive statements. */
+export type DestructivePolicy = 'confirm' | 'allow' | 'refuse';
+
+export type DestructiveKind =
+  | 'updateWithoutWhere'
+  | 'deleteWithoutWhere'
+  | 'truncate'
+  | 'dropDatabase'
+  | 'dropSchema'
+  | 'dropTable';
+
+export interface DestructiveStatement {
+  /** Position in the script, from 0. */
+  index: number;
+  kind: DestructiveKind;
+  object: string | null;
+  /** What running it does, e.g. "Deletes every row of orders". */
+  impact: string;
+  statement: string;
+}
+
+/** `details` of a `confirmation_required` error; resend the SQL with `token`. */
+export interface ConfirmationRequired {
+  token: string;
+  statements: DestructiveStatement[];
+  expiresInSecs: number;
+}
+
+//  ------ Query history
+
+export type HistoryStatus = 'success' | 'error' | 'cancelled';
+
+/** One script run through dbQuery / dbQueryParams / dbExecute. */
+export interface HistoryEntry {
+  id: string;
+  /** The registered server, for connections made from the registry. */
+  serverId: string | null;
+  profileId: string;
+  dbType: string;
+  database: string | null;
+  sql: string;
+  /** Shared by scripts that differ only in literals and layout. */
+  fingerprint: string;
+  /** RFC 3339, UTC. */
+  executedAt: string;
+  durationMs: number;
+  /** Rows returned or affected; null when it failed. */
+  rowCount: number | null;
+  status: HistoryStatus;
+  error: string | null;
+}
+
+/** Filters for historySearch / historyGroups, combined with AND. */
+export interface HistoryQuery {
+  /** Words that must all appear in the SQL, in any case. */
+  text?: string | null;
+  serverId?: string | null;
+  profileId?: string | null;
+  fingerprint?: string | null;
+  status?: HistoryStatus | null;
+  /** RFC 3339 bounds on `executedAt`, both inclusive. */
+  from?: string | null;
+  to?: string | null;
+  /** Page size (default 200) and start, newest first. */
+  limit?: number | null;
+  offset?: number | null;
+}
+
+export interface HistoryPage {
+  entries: HistoryEntry[];
+  /** Matches before paging. */
+  total: number;
+}
+
+/** The runs of one fingerprint; `sql` is the latest run's. */
+export interface HistoryGroup {
+  fingerprint: string;
+  sql: string;
+  runs: number;
+  errors: number;
+  avgDurationMs: number;
+  lastExecutedAt: string;
+}
+
+export interface HistoryRetention {
+  /** 0 turns recording off. */
+  maxEntries: number;
+  /** null keeps entries until `maxEntries` pushes them out. */
+  maxAgeDays: number | null;
+}
+
+//  ------ Query snippets
+
+export type SnippetNodeType = 'folder' | 'snippet';
+
+/** A folder or a saved query in the snippet tree. */
+export interface SnippetNode {
+  id: string;
+  type: SnippetNodeType;
+  /** null at the top level. */
+  parentId: string | null;
+  name: string;
+  description: string | null;
+  tags: string[];
+  /** The database type the SQL is written for; null runs anywhere. */
+  dbType: string | null;
+  sql: string;
+  /** The `${name}` placeholders of `sql`, in order of first use. */
+  placeholders: string[];
+  /** Folders only: UI expansion state. */
+  expanded: boolean;
+  children: SnippetNode[];
+  createdAt: string | null;
+  updatedAt: string | null;
+}
+
+export interface SnippetInput {
+  /** Existing snippet to update in place; omit to create one. */
+  id?: string | null;
+  /** Folder to create it in; the top level when omitted. */
+  parentId?: string | null;
+  name: string;
+  description?: string | null;
+  tags?: string[];
+  dbType?: string | null;
+  sql: string;
+}
+
+/** Filters for snippetsSearch, combined with AND. */
+export interface SnippetQuery {
+  /** Words that must all appear in the name, description, tags or SQL. */
+  text?: string | null;
+  tag?: string | null;
+  /** Snippets for this database type, and those for any. */
+  dbType?: string | null;
+}
+
+/** Where a `${name}` sits in the SQL, in UTF-16 code units, braces included. */
+export interface PlaceholderSpan {
+  name: string;
+  start: number;
+  end: number;
+}
+
+/** A snippet's SQL for inserting into an editor. */
+export interface SnippetText {
+  sql: string;
+  placeholders: PlaceholderSpan[];
+}
+
+//  ------ Export (dbExport)
+
+export type ExportFormat = 'csv' | 'tsv' | 'json' | 'ndjson' | 'sqlInsert' | 'markdown';
+
+/** What to export: the rows of one statement, or a whole table. */
+export type ExportSource =
+  | { type: 'query'; sql: string }
+  | { type: 'table'; schema?: string | null; table: string };
+
+/** When CSV/TSV fields are quoted; `all` still leaves NULLs bare. */
+export type ExportQuoting = 'minimal' | 'all' | 'never';
+
+/** `utf8Bom` and `utf16le` start with a byte order mark. */
+export type ExportEncoding = 'utf8' | 'utf8Bom' | 'utf16le';
+
+/** Every field is optional; the defaults are in the comments. */
+export interface ExportOptions {
+  /** `csv`. */
+  format?: ExportFormat;
+  /** CSV: `,`; TSV: a tab. One character. */
+  delimiter?: string | null;
+  /** `"`. One character. */
+  quote?: string;
+  /** `minimal`. */
+  quoting?: ExportQuoting;
+  /** Column names as the first line (CSV, TSV). `true`. */
+  header?: boolean;
+  /** How NULL is written in CSV, TSV and Markdown. Empty. */
+  nullText?: string;
+  /** `utf8`. */
+  encoding?: ExportEncoding;
+  /** Rows per INSERT statement. 100. */
+  batchSize?: number;
+  /** The database type INSERT statements are written for; the session's. */
+  dialect?: string | null;
+  /** The INSERT target; the source table's name, else `export`. */
+  tableName?: string | null;
+}
+
+/** Payload of the `export://progress` event, sent after each batch. */
+export interface ExportProgress {
+  exportId: string;
+  rows: number;
+  bytes: number;
+}
+
+export interface ExportSummary {
+  exportId: string;
+  path: string;
+  rows: number;
+  bytes: number;
+}
+
+//  ------ Import (dbImport)
+
+export type ImportFormat = 'csv' | 'tsv' | 'ndjson';
+
+/** UTF-8, or UTF-16 with a byte order mark. Defaults in the comments. */
+export interface ImportFileOptions {
+  /** `csv`. NDJSON holds one object per line; its keys are the columns. */
+  format?: ImportFormat;
+  /** CSV: `,`; TSV: a tab. One character. */
+  delimiter?: string | null;
+  /** `"`. One character. */
+  quote?: string;
+  /** The first line holds the column names. `true`. */
+  header?: boolean;
+  /** An unquoted field with this text is NULL. Empty. */
+  nullText?: string;
+}
+
+export type ImportType =
+  | 'bool'
+  | 'int'
+  | 'decimal'
+  | 'float'
+  | 'date'
+  | 'time'
+  | 'dateTime'
+  | 'text'
+  | 'json';
+
+/** A file column with its inferred type; also a CREATE TABLE column. */
+export interface ImportColumn {
+  name: string;
+  type: ImportType;
+  /** Whether NULLs (empty fields) were seen. */
+  nullable?: boolean;
+  /** Text: the longest value, in characters. */
+  length?: number | null;
+  /** Decimal: total digits and digits after the point. */
+  precision?: number | null;
+  scale?: number | null;
+}
+
+export interface ImportPreview {
+  columns: ImportColumn[];
+  /** The first rows, aligned with `columns`; null is NULL. */
+  rows: (string | null)[][];
+  /** Rows the types were inferred from. */
+  sampledRows: number;
+}
+
+export interface ImportTarget {
+  schema?: string | null;
+  table: string;
+}
+
+/** Loads file column `source` into table column `target`. */
+export interface ImportMapping {
+  source: string;
+  target: string;
+  /** How values are checked and bound; text, converted by the server, when omitted. */
+  type?: ImportType | null;
+}
+
+/** `abort` rolls back the batch in progress and stops; `skip` leaves the row out. */
+export type ImportErrorPolicy = 'abort' | 'skip';
+
+export interface ImportOptions {
+  /** Rows per transaction. 1000. */
+  batchSize?: number;
+  /** `abort`. */
+  onError?: ImportErrorPolicy;
+  /** With `skip`: stop as `abort` does after this many rejected rows. */
+  maxErrors?: number | null;
+  /** Create the target table with these columns first. */
+  createTable?: ImportColumn[] | null;
+}
+
+export interface ImportRowError {
+  /** The line the row starts on, 1-based. */
+  line: number;
+  message: string;
+}
+
+export interface ImportSummary {
+  rowsRead: number;
+  /** Rows committed. */
+  inserted: number;
+  /** Rows rejected and left out. */
+  skipped: number;
+  /** Stopped on a rejected row; the batch in progress was rolled back. */
+  aborted: boolean;
+  /** The first rejected rows (up to 1000). */
+  errors: ImportRowError[];
+}
+
+//  ------ Row edits (dbPreviewRowEdits / dbApplyRowEdits)
+
+/**
+ * One row change. `key` holds the row's key columns as loaded (the primary
+ * key, or a NOT NULL unique key); `values` the columns to write.
+ */
+export type RowChange =
+  | { op: 'insert'; values: Record<string, QueryParam> }
+  | { op: 'update'; key: Record<string, QueryParam>; values: Record<string, QueryParam> }
+  | { op: 'delete'; key: Record<string, QueryParam> };
+
+/** Changes to one table's rows, applied together. */
+export interface RowEditBatch {
+  schema?: string | null;
+  table: string;
+  changes: RowChange[];
+}
+
+export interface RowEditStatement {
+  sql: string;
+  params: QueryParams;
+}
+
+/** The statements a batch runs, one per change in order. */
+export interface RowEditPlan {
+  /** The columns rows are matched on. */
+  keyColumns: string[];
+  statements: RowEditStatement[];
+}
+
+export interface RowEditSummary {
+  inserted: number;
+  updated: number;
+  deleted: number;
+}
+
+/** `details` of a `conflict` error: the change (0-based) that matched no row or several. */
+export interface RowEditConflict {
+  index: number;
+  affected: number;
+}
+
+//  ------ Query plans (dbExplain)
+
+/**
+ * One plan operation. Costs are in the server's own units; actual rows and
+ * time (ms, including children) are totals over all loops.
+ */
+export interface PlanNode {
+  operation: string;
+  object: string | null;
+  estimatedRows: number | null;
+  actualRows: number | null;
+  cost: number | null;
+  timeMs: number | null;
+  details: Record<string, string>;
+  children: PlanNode[];
+}
+
+export interface QueryPlan {
+  analyzed: boolean;
+  /** One root per statement. */
+  nodes: PlanNode[];
+  /** The server's own plan output (JSON, XML or text). */
+  raw: string;
 }
 
 //  ------ Schema introspection
@@ -43,6 +579,9 @@ export interface DatabaseInfo {
 
 //  ------ Connection
 
+/** libpq-style sslmode; unset follows `ssl` / `sslRejectUnauthorized`. */
+export type SslMode = 'disable' | 'prefer' | 'require' | 'verify-ca' | 'verify-full';
+
 export interface ConnectParams {
   profileId: string;
   host: string;
@@ -56,6 +595,7 @@ export interface ConnectParams {
   sslCertFile?: string;
   sslKeyFile?: string;
   sslRejectUnauthorized?: boolean;
+  sslMode?: SslMode;
   dbType?: string;
   ssh?: boolean;
   sshHost?: string;
@@ -70,12 +610,56 @@ export interface ConnectParams {
   useDocker?: boolean;
   dockerContainer?: string;
   connectionVerboseLogging?: boolean;
+  pool?: PoolSettings;
+  /** Open connections read-only; SQL that may write fails with kind `read_only`. */
+  readOnly?: boolean;
+  destructive?: DestructivePolicy;
+}
+
+/** Connection pool settings; unset fields use the driver defaults (max 5). Ignored by SQLite. */
+export interface PoolSettings {
+  minConnections?: number;
+  maxConnections?: number;
+  /** Idle connections above the minimum are closed after this long. */
+  idleTimeoutSecs?: number;
+  /** Connections are closed once this old, when next idle. */
+  maxLifetimeSecs?: number;
+  /** How long taking a connection from the pool may wait. */
+  acquireTimeoutMs?: number;
+  /** A single statement run on an idle connection before it is handed out. */
+  healthCheckQuery?: string;
+}
+
+/** Live statistics of one connected profile's pool. */
+export interface PoolStats {
+  profileId: string;
+  dbType: string;
+  minConnections: number;
+  maxConnections: number;
+  /** Open connections, in use or idle. */
+  open: number;
+  inUse: number;
+  idle: number;
+  /** Requests waiting for a connection. */
+  waiting: number;
+  /** Connections pinned by sessions (counted in `inUse`). */
+  sessions: number;
+}
+
+/** TLS state negotiated on connect; protocol and cipher when the driver can see them. */
+export interface TlsInfo {
+  mode: SslMode;
+  encrypted: boolean;
+  certificateVerified: boolean;
+  protocol: string | null;
+  cipher: string | null;
 }
 
 export interface ConnectionHandle {
   profileId: string;
   dbType: string;
   serverVersion: string;
+  tls?: TlsInfo;
 }
 
 //  ------ Credentials (SSH identities)
@@ -228,6 +812,11 @@ export interface DbServerRecord {
   /** ssh / sshDocker: registered SSH server id. */
   sshServerId?: string | null;
   notes?: string | null;
+  pool?: PoolSettings | null;
+  /** Connect read-only: the backend refuses writes (e.g. production). */
+  readOnly?: boolean;
+  /** Destructive statements need confirming (the default), run, or are refused. */
+  destructive?: DestructivePolicy;
   createdAt?: string | null;
   updatedAt?: string | null;
 }
@@ -249,6 +838,9 @@ export interface DbServerInput {
   dockerContainer?: string | null;
   sshServerId?: string | null;
   notes?: string | null;
+  pool?: PoolSettings | null;
+  readOnly?: boolean;
+  destructive?: DestructivePolicy;
 }
 
 export interface DbServerTestResult {
@@ -134,11 +134,74 @@ export type {
 
 export type {
 	QueryResultSet,
+	QueryMessage,
+	MessageSeverity,
+	ResultColumn,
+	TypeCategory,
+	CursorInfo,
+	CursorPage,
+	CellValue,
+	TaggedCell,
+	QueryParam,
+	QueryParams,
+	BinaryValue,
+	CellRef,
+	IsolationLevel,
+	TransactionOptions,
+	TransactionState,
+	OpenTransactionPolicy,
+	SessionScope,
+	DestructivePolicy,
+	DestructiveKind,
+	DestructiveStatement,
+	ConfirmationRequired,
+	HistoryStatus,
+	HistoryEntry,
+	HistoryQuery,
+	HistoryPage,
+	HistoryGroup,
+	HistoryRetention,
+	SnippetNodeType,
+	SnippetNode,
+	SnippetInput,
+	SnippetQuery,
+	SnippetText,
+	PlaceholderSpan,
+	ExportFormat,
+	ExportSource,
+	ExportQuoting,
+	ExportEncoding,
+	ExportOptions,
+	ExportProgress,
+	ExportSummary,
+	ImportFormat,
+	ImportFileOptions,
+	ImportType,
+	ImportColumn,
+	ImportPreview,
+	ImportTarget,
+	ImportMapping,
+	ImportErrorPolicy,
+	ImportOptions,
+	ImportRowError,
+	ImportSummary,
+	RowChange,
+	RowEditBatch,
+	RowEditStatement,
+	RowEditPlan,
+	RowEditSummary,
+	RowEditConflict,
+	QueryPlan,
+	PlanNode,
 	ColumnInfo,
 	TableInfo,
 	DatabaseInfo,
 	ConnectParams,
+	SslMode,
+	PoolSettings,
+	PoolStats,
 	ConnectionHandle,
+	TlsInfo,
 } from './backend/types.js';
 
 export {